@@ -0,0 +1,61 @@
+//! `actions_at` on nested `BoxProduct`s and `CartesianProduct`s, recomputed versus read from an
+//! `ActionCache`
+
+use std::hint::black_box;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use ctmdp_rust::dense::{ActionCache, ActionIndex, StateIndex};
+use ctmdp_rust::mdp::MDP;
+use ctmdp_rust::pathmdp::{PathAction, PathState, PathWorld};
+use ctmdp_rust::products::{BoxProduct, CartesianProduct};
+
+fn path_world(length: usize) -> PathWorld {
+    PathWorld::new(
+        (0..length).map(PathState::new).collect(),
+        vec![PathAction::Next, PathAction::Prev],
+    )
+}
+
+/// Benchmarks every state's `actions_at` on `product`, recomputed and cached
+fn bench_product<M>(c: &mut Criterion, name: &str, product: &M)
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let states = StateIndex::from_sampler(product.all_states());
+    let actions = ActionIndex::new(product.all_actions());
+    let cache = ActionCache::new(product, &states, &actions);
+
+    let mut group = c.benchmark_group(format!("actions_at/{name}"));
+    group.bench_function("recomputed", |b| {
+        b.iter(|| {
+            states
+                .items()
+                .iter()
+                .map(|s| black_box(product.actions_at(s)).len())
+                .sum::<usize>()
+        })
+    });
+    group.bench_function("cached", |b| {
+        b.iter(|| {
+            (0..states.len())
+                .map(|i| black_box(cache.actions_at(i)).len())
+                .sum::<usize>()
+        })
+    });
+    group.finish();
+}
+
+fn actions_at(c: &mut Criterion) {
+    let box_product = BoxProduct::new(BoxProduct::new(path_world(8), path_world(8)), path_world(8));
+    bench_product(c, "box_3x8", &box_product);
+    let cartesian = CartesianProduct::new(
+        CartesianProduct::new(path_world(8), path_world(8)),
+        path_world(8),
+    );
+    bench_product(c, "cartesian_3x8", &cartesian);
+}
+
+criterion_group!(benches, actions_at);
+criterion_main!(benches);
@@ -0,0 +1,36 @@
+//! Sampling from a `Measure`: the thread-local `sample`, `sample_with` on a caller's RNG, and a
+//! prebuilt alias table
+
+use std::hint::black_box;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use ctmdp_rust::measure::MeasureBuilder;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+fn sample(c: &mut Criterion) {
+    let mut group = c.benchmark_group("measure/sample");
+    for size in [2usize, 16, 256] {
+        let measure = (0..size)
+            .map(|i| (i, (i + 1) as f64))
+            .collect::<MeasureBuilder<usize>>()
+            .normalize()
+            .unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        let sampler = measure.sampler();
+
+        group.bench_with_input(BenchmarkId::new("sample", size), &measure, |b, m| {
+            b.iter(|| black_box(m.sample()).copied())
+        });
+        group.bench_with_input(BenchmarkId::new("sample_with", size), &measure, |b, m| {
+            b.iter(|| black_box(m.sample_with(&mut rng)).copied())
+        });
+        group.bench_function(BenchmarkId::new("alias", size), |b| {
+            b.iter(|| black_box(sampler.sample(&mut rng)).copied())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, sample);
+criterion_main!(benches);
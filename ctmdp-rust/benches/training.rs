@@ -0,0 +1,59 @@
+//! Learning and planning on a nested `CartesianProduct` of path worlds: one Q-learning episode
+//! (including setting up and handing back the Q-table), on the product itself, which the learner
+//! compiles first, and on an already `compile`d form, and value-iteration sweeps
+
+use std::hint::black_box;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use ctmdp_rust::compiled::compile;
+use ctmdp_rust::config::TrainingConfig;
+use ctmdp_rust::pathmdp::{PathAction, PathState, PathWorld};
+use ctmdp_rust::planning::value_iteration;
+use ctmdp_rust::products::CartesianProduct;
+use ctmdp_rust::q_learning::q_learning;
+
+fn path_world(length: usize) -> PathWorld {
+    PathWorld::new(
+        (0..length).map(PathState::new).collect(),
+        vec![PathAction::Next, PathAction::Prev],
+    )
+}
+
+fn q_learning_episode(c: &mut Criterion) {
+    let product = CartesianProduct::new(
+        CartesianProduct::new(path_world(6), path_world(6)),
+        path_world(6),
+    );
+    let mut config = TrainingConfig::default();
+    config.num_episodes = 1;
+    config.max_num_steps = 24;
+
+    let mut group = c.benchmark_group("q_learning/episode/cartesian_3x6");
+    group.bench_function("product", |b| {
+        b.iter(|| black_box(q_learning(&product, &config).unwrap()))
+    });
+    let compiled = compile(&product).unwrap();
+    group.bench_function("compiled", |b| {
+        b.iter(|| black_box(q_learning(&compiled, &config).unwrap()))
+    });
+    group.finish();
+}
+
+fn value_iteration_sweeps(c: &mut Criterion) {
+    let product = CartesianProduct::new(
+        CartesianProduct::new(path_world(8), path_world(8)),
+        path_world(8),
+    );
+    let mut group = c.benchmark_group("value_iteration/cartesian_3x8");
+    // A fixed number of sweeps (the tolerance is never reached); the difference between the two
+    // is the cost of the sweeps themselves, the rest is compiling the product
+    for sweeps in [1usize, 100] {
+        group.bench_function(format!("{sweeps}_sweeps"), |b| {
+            b.iter(|| black_box(value_iteration(&product, 0.95, 0.0, sweeps).unwrap()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, q_learning_episode, value_iteration_sweeps);
+criterion_main!(benches);
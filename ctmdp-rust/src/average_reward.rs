@@ -0,0 +1,133 @@
+//! # Average-Reward Learning
+//!
+//! Continuing tasks (e.g. `PathWorld` chains that are restarted instead of ending) are better
+//! described by the long-run reward per step than by a discounted return. This module implements
+//! differential Q-learning (R-learning), which learns relative action values together with an
+//! estimate of that gain.
+
+use madepro::models::{Action, ActionValue, State};
+use rand::Rng;
+use rand::seq::IteratorRandom;
+use crate::config::TrainingConfig;
+use crate::mdp::MDP;
+use crate::error::Error;
+use crate::q_learning::{action_union, random_start};
+
+/// Differential action values together with the learned average reward per step
+pub struct AverageRewardValues<S: State, A: Action> {
+    pub action_value: ActionValue<S, A>,
+    pub gain: f64,
+}
+
+/// # R-Learning
+///
+/// This function implements differential Q-learning, the average-reward counterpart of Q-Learning.
+/// Instead of discounting, each target subtracts the current gain estimate `ρ`:
+///
+/// `δ = r - ρ + max_a' Q(s', a') - Q(s, a)`
+///
+/// `Q(s, a)` moves by `learning_rate * δ` and `ρ` by `average_reward_rate * δ`.
+///
+/// The task is treated as continuing: each episode is a segment of `max_num_steps` steps from a
+/// random state, and reaching a final state restarts from another random state within the segment.
+///
+/// # Arguments
+/// * `mdp` - The MDP to learn from
+/// * `config` - Configuration parameters (learning rate, average reward rate, exploration rate, etc.)
+///
+/// # Returns
+/// The differential `ActionValue` table and the estimated gain
+pub fn r_learning<M>(
+    mdp: &M,
+    config: &TrainingConfig,
+) -> Result<AverageRewardValues<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let states = mdp.all_states();
+    let actions = action_union(mdp);
+
+    let mut action_value = ActionValue::new(states, &actions);
+    let mut gain = 0.0;
+    let mut rng = config.rng();
+
+    for _ in 0..config.num_episodes {
+        let mut state = random_start(states, &mut rng)?.clone();
+
+        for _ in 0..config.max_num_steps {
+            if mdp.is_final_state(&state) || mdp.actions_at(&state).is_empty() {
+                state = random_start(states, &mut rng)?.clone();
+                continue;
+            }
+
+            let action = if rng.random::<f64>() < config.exploration_rate {
+                actions.iter().choose(&mut rng).ok_or(Error::EmptyActionSet)?.clone()
+            } else {
+                action_value.greedy(&state).clone()
+            };
+            let (measure, reward) = mdp.stochastic_transition(&state, &action)?;
+            let next_state = measure
+                .sample_with(&mut rng)
+                .cloned()
+                .unwrap_or_else(|| state.clone());
+
+            let greedy_next = action_value.greedy(&next_state).clone();
+            let current_q = action_value.get(&state, &action);
+            let delta = reward - gain + action_value.get(&next_state, &greedy_next) - current_q;
+
+            action_value.insert(&state, &action, current_q + config.learning_rate * delta);
+            gain += config.average_reward_rate * delta;
+
+            state = next_state;
+        }
+    }
+
+    Ok(AverageRewardValues { action_value, gain })
+}
+
+/// Estimates the gain (average reward per step) of `policy` by simulating it for `num_steps`
+/// steps from a random start state, restarting from a random state whenever a final state is reached.
+pub fn evaluate_gain<M, F>(mdp: &M, policy: &F, num_steps: usize) -> Result<f64, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    F: Fn(&M::State) -> M::Action,
+{
+    evaluate_gain_with(mdp, policy, num_steps, &mut rand::rng())
+}
+
+/// Like `evaluate_gain`, drawing start and next states from the given random number generator
+pub fn evaluate_gain_with<M, F, R>(
+    mdp: &M,
+    policy: &F,
+    num_steps: usize,
+    rng: &mut R,
+) -> Result<f64, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    F: Fn(&M::State) -> M::Action,
+    R: Rng + ?Sized,
+{
+    let states = mdp.all_states();
+    let mut state = random_start(states, rng)?.clone();
+    let mut total_reward = 0.0;
+
+    for _ in 0..num_steps {
+        if mdp.is_final_state(&state) {
+            state = random_start(states, rng)?.clone();
+        }
+        let action = policy(&state);
+        let (measure, reward) = mdp.stochastic_transition(&state, &action)?;
+        total_reward += reward;
+        state = measure.sample_with(rng).cloned().unwrap_or_else(|| state.clone());
+    }
+
+    if num_steps == 0 {
+        Ok(0.0)
+    } else {
+        Ok(total_reward / num_steps as f64)
+    }
+}
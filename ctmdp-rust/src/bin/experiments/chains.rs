@@ -0,0 +1,73 @@
+//! Box against Cartesian products of two branched chains, whose detours exist only at some
+//! states
+
+use clap::Args;
+use ctmdp_rust::chains::BranchedChain;
+use ctmdp_rust::error::Error;
+use ctmdp_rust::products::{BoxProduct, CartesianProduct};
+use ctmdp_rust::report::Report;
+
+use crate::common::{Defaults, TrialArgs, compare_box_cartesian};
+
+#[derive(Args, Debug, Clone)]
+pub struct ChainsArgs {
+    /// Chain lengths, one experiment per value
+    #[arg(long, value_delimiter = ',', default_values_t = [6, 8])]
+    pub lengths: Vec<usize>,
+    /// Branch states of every chain [default: a third and two thirds of the way along]
+    #[arg(long, value_delimiter = ',')]
+    pub branches: Option<Vec<usize>>,
+    /// Penalty of every detour
+    #[arg(long, default_value_t = BranchedChain::DEFAULT_DETOUR_PENALTY)]
+    pub detour_penalty: f64,
+    /// Probability that a detour reaches the goal instead of the start
+    #[arg(long, default_value_t = 0.0)]
+    pub detour_shortcut: f64,
+    #[command(flatten)]
+    pub trial: TrialArgs,
+}
+
+impl ChainsArgs {
+    fn chain(&self, length: usize, branches: &[usize]) -> Result<BranchedChain, Error> {
+        BranchedChain::new(length, branches.to_vec())
+            .with_detour_penalty(self.detour_penalty)
+            .with_detour_shortcut(self.detour_shortcut)
+    }
+}
+
+pub fn run(args: &ChainsArgs, report: &mut Report) -> Result<(), Error> {
+    println!("Chain with branches: Box vs Cartesian products");
+
+    for &length in &args.lengths {
+        let branches = args.branches.clone().unwrap_or_else(|| {
+            vec![
+                (length as f64 / 3.0).round() as usize,
+                (2.0 * length as f64 / 3.0).round() as usize,
+            ]
+        });
+        println!();
+        println!("Chain length {}, branches at {:?}", length, branches);
+
+        let bp = BoxProduct::new(
+            args.chain(length, &branches)?,
+            args.chain(length, &branches)?,
+        );
+        let cp = CartesianProduct::new(
+            args.chain(length, &branches)?,
+            args.chain(length, &branches)?,
+        );
+        let defaults = Defaults {
+            episodes: 1000,
+            runs: 10,
+            max_steps: (length * 3) as u32,
+            discount_factor: 0.9,
+            exploration_rate: 0.1,
+        };
+        let setting = format!("length {length}");
+        compare_box_cartesian(&bp, &cp, &args.trial, &defaults, &setting, report)?;
+    }
+
+    println!();
+    println!("Heterogeneous action spaces: detours exist only at some states.");
+    Ok(())
+}
@@ -0,0 +1,375 @@
+//! Options and helpers shared by the experiments
+
+use std::hash::Hash;
+use std::time::Instant;
+
+use clap::{Args, ValueEnum};
+use ctmdp_rust::chains::ChainAction;
+use ctmdp_rust::config::TrainingConfig;
+use ctmdp_rust::env::Environment;
+use ctmdp_rust::error::Error;
+use ctmdp_rust::mdp::MDP;
+use ctmdp_rust::pathmdp::{PathAction, PathState, PathWorld};
+use ctmdp_rust::policy::{DeterministicPolicy, greedy_policy};
+use ctmdp_rust::products::{BoxAction, Product};
+use ctmdp_rust::q_learning::{q_learning, sarsa};
+use ctmdp_rust::report::{Report, Trial};
+use madepro::models::ActionValue;
+
+/// The learner that trains the product Q-tables
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    QLearning,
+    Sarsa,
+}
+
+impl Algorithm {
+    /// The name the command line uses
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::QLearning => "q-learning",
+            Algorithm::Sarsa => "sarsa",
+        }
+    }
+}
+
+/// Which of the two products an experiment trains
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Products {
+    Box,
+    Cartesian,
+    Both,
+}
+
+impl Products {
+    pub fn includes_box(&self) -> bool {
+        *self != Products::Cartesian
+    }
+
+    pub fn includes_cartesian(&self) -> bool {
+        *self != Products::Box
+    }
+}
+
+/// Training options every experiment accepts. Options left out fall back to the experiment's
+/// `Defaults`.
+#[derive(Args, Debug, Clone)]
+pub struct TrialArgs {
+    /// Training episodes per run [default: depends on the experiment]
+    #[arg(long)]
+    pub episodes: Option<u32>,
+    /// Independent training runs to average over [default: depends on the experiment]
+    #[arg(long, conflicts_with = "seeds")]
+    pub runs: Option<usize>,
+    /// Step limit of every training episode [default: depends on the experiment]
+    #[arg(long)]
+    pub max_steps: Option<u32>,
+    /// Seed of the first run; run `k` uses `seed + k` [default: unseeded]
+    #[arg(long, conflicts_with = "seeds")]
+    pub seed: Option<u64>,
+    /// One seed per run, instead of `--runs` and `--seed`
+    #[arg(long, value_delimiter = ',')]
+    pub seeds: Option<Vec<u64>>,
+    /// Products to train
+    #[arg(long, value_enum, default_value_t = Products::Both)]
+    pub products: Products,
+    /// Learner of the product Q-tables
+    #[arg(long, value_enum, default_value_t = Algorithm::QLearning)]
+    pub algorithm: Algorithm,
+    /// Step size of the Q-value updates
+    #[arg(long, default_value_t = 0.1)]
+    pub learning_rate: f64,
+    /// Discount factor γ [default: depends on the experiment]
+    #[arg(long)]
+    pub discount_factor: Option<f64>,
+    /// Probability ε of exploring with a random action [default: depends on the experiment]
+    #[arg(long)]
+    pub exploration_rate: Option<f64>,
+}
+
+/// What an experiment uses for the options left out of `TrialArgs`
+#[derive(Debug, Clone, Copy)]
+pub struct Defaults {
+    pub episodes: u32,
+    pub runs: usize,
+    pub max_steps: u32,
+    pub discount_factor: f64,
+    pub exploration_rate: f64,
+}
+
+impl TrialArgs {
+    pub fn runs(&self, defaults: &Defaults) -> usize {
+        match &self.seeds {
+            Some(seeds) if !seeds.is_empty() => seeds.len(),
+            _ => self.runs.unwrap_or(defaults.runs).max(1),
+        }
+    }
+
+    /// The seed of run `run` (counting from 0), if seeded
+    pub fn seed(&self, run: usize) -> Option<u64> {
+        match &self.seeds {
+            Some(seeds) if !seeds.is_empty() => seeds.get(run).copied(),
+            _ => self.seed.map(|seed| seed.wrapping_add(run as u64)),
+        }
+    }
+
+    /// The training configuration of run `run` (counting from 0)
+    pub fn config(&self, defaults: &Defaults, run: usize) -> TrainingConfig {
+        let mut config = TrainingConfig::default();
+        config.num_episodes = self.episodes.unwrap_or(defaults.episodes);
+        config.max_num_steps = self.max_steps.unwrap_or(defaults.max_steps);
+        config.learning_rate = self.learning_rate;
+        config.discount_factor = self.discount_factor.unwrap_or(defaults.discount_factor);
+        config.exploration_rate = self.exploration_rate.unwrap_or(defaults.exploration_rate);
+        config.seed = self.seed(run);
+        config
+    }
+}
+
+/// Trains `mdp` with `algorithm`
+pub fn train<M>(
+    mdp: &M,
+    config: &TrainingConfig,
+    algorithm: Algorithm,
+) -> Result<ActionValue<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    match algorithm {
+        Algorithm::QLearning => q_learning(mdp, config),
+        Algorithm::Sarsa => sarsa(mdp, config),
+    }
+}
+
+/// Trains `mdp` and returns the greedy policy of the learned Q-values
+pub fn learn_policy<M>(
+    mdp: &M,
+    config: &TrainingConfig,
+    algorithm: Algorithm,
+) -> Result<DeterministicPolicy<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    Ok(greedy_policy(mdp, &train(mdp, config, algorithm)?))
+}
+
+/// An environment for evaluation rollouts, seeded if `seed` is set
+pub fn environment<M>(mdp: &M, seed: Option<u64>) -> Result<Environment<&M>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+{
+    match seed {
+        Some(seed) => Environment::with_seed(mdp, seed),
+        None => Environment::new(mdp),
+    }
+}
+
+pub fn path_world(length: usize) -> PathWorld {
+    let states: Vec<PathState> = (0..length).map(PathState::new).collect();
+    let actions = vec![PathAction::Next, PathAction::Prev];
+    PathWorld::new(states, actions)
+}
+
+/// Fraction of the states of `optimal` where `learned` picks a different action, among those
+/// `learned` covers (1 if it covers none)
+pub fn policy_distance<S, A>(
+    learned: &DeterministicPolicy<S, A>,
+    optimal: &DeterministicPolicy<S, A>,
+) -> f64
+where
+    S: Eq + Hash,
+    A: Eq,
+{
+    let mut total = 0.0;
+    let mut count = 0.0;
+
+    for (state, opt_action) in optimal.iter() {
+        if let Some(learned_action) = learned.get(state) {
+            count += 1.0;
+            if learned_action != opt_action {
+                total += 1.0;
+            }
+        }
+    }
+
+    if count == 0.0 { 1.0 } else { total / count }
+}
+
+pub fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len().max(1) as f64
+}
+
+pub fn analyze_action_space<M>(mdp: &M, label: &str)
+where
+    M: MDP,
+{
+    let states: Vec<_> = mdp.all_states().iter().collect();
+    let total_actions: usize = states.iter().map(|s| mdp.actions_at(s).len()).sum();
+    let avg = total_actions as f64 / states.len() as f64;
+
+    println!("  {} states: {}", label, states.len());
+    println!("  {} avg actions/state: {:.1}", label, avg);
+}
+
+/// Actions that move towards the end of a chain. A Box action advances if the component it
+/// moves does, a Cartesian action if every component does.
+pub trait Advances {
+    fn advances(&self) -> bool;
+}
+
+impl Advances for PathAction {
+    fn advances(&self) -> bool {
+        *self == PathAction::Next
+    }
+}
+
+impl Advances for ChainAction {
+    fn advances(&self) -> bool {
+        *self == ChainAction::Next
+    }
+}
+
+impl<A: Advances, B: Advances> Advances for BoxAction<A, B> {
+    fn advances(&self) -> bool {
+        match self {
+            BoxAction::Left(a) => a.advances(),
+            BoxAction::Right(b) => b.advances(),
+        }
+    }
+}
+
+impl<A: Advances, B: Advances> Advances for Product<A, B> {
+    fn advances(&self) -> bool {
+        self.first().advances() && self.second().advances()
+    }
+}
+
+/// The heuristic optimum of a product of chains: in every state, the first available action
+/// that advances, or the first available action if none does
+pub fn forward_policy<M>(mdp: &M) -> DeterministicPolicy<M::State, M::Action>
+where
+    M: MDP,
+    M::Action: Advances,
+{
+    let mut policy = DeterministicPolicy::new();
+
+    for state in mdp.all_states().iter() {
+        let actions = mdp.actions_at(state);
+        let chosen = actions
+            .iter()
+            .find(|action| action.advances())
+            .or_else(|| actions.first());
+        if let Some(action) = chosen {
+            policy.insert(state.clone(), action.clone());
+        }
+    }
+
+    policy
+}
+
+/// Trains `mdp` and records the learned policy's distance to `optimal` in `report`, returning
+/// the distance
+pub fn record_distance<M>(
+    mdp: &M,
+    optimal: &DeterministicPolicy<M::State, M::Action>,
+    config: &TrainingConfig,
+    algorithm: Algorithm,
+    trial: Trial,
+    report: &mut Report,
+) -> Result<f64, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let start = Instant::now();
+    let distance = policy_distance(&learn_policy(mdp, config, algorithm)?, optimal);
+    report.push(
+        trial
+            .with_policy_distance(distance)
+            .with_wall_clock(start.elapsed()),
+    );
+    Ok(distance)
+}
+
+/// Learns the products `trial` selects `runs` times, prints the mean distance of the learned
+/// policies to their `forward_policy` and records every run in `report` under `setting`
+pub fn compare_box_cartesian<B, C>(
+    bp: &B,
+    cp: &C,
+    trial: &TrialArgs,
+    defaults: &Defaults,
+    setting: &str,
+    report: &mut Report,
+) -> Result<(), Error>
+where
+    B: MDP,
+    B::State: Clone,
+    B::Action: Clone + Advances,
+    C: MDP,
+    C::State: Clone,
+    C::Action: Clone + Advances,
+{
+    let products = trial.products;
+    if products.includes_box() {
+        analyze_action_space(bp, "BP");
+    }
+    if products.includes_cartesian() {
+        analyze_action_space(cp, "CP");
+    }
+
+    let opt_bp = forward_policy(bp);
+    let opt_cp = forward_policy(cp);
+
+    let runs = trial.runs(defaults);
+    let episodes = trial.episodes.unwrap_or(defaults.episodes);
+    println!();
+    println!("Running {} trials ({} episodes each)...", runs, episodes);
+
+    let mut bp_dists = Vec::with_capacity(runs);
+    let mut cp_dists = Vec::with_capacity(runs);
+
+    for run in 0..runs {
+        let config = trial.config(defaults, run);
+        if products.includes_box() {
+            bp_dists.push(record_distance(
+                bp,
+                &opt_bp,
+                &config,
+                trial.algorithm,
+                Trial::new(setting, "BP", run, &config),
+                report,
+            )?);
+        }
+        if products.includes_cartesian() {
+            cp_dists.push(record_distance(
+                cp,
+                &opt_cp,
+                &config,
+                trial.algorithm,
+                Trial::new(setting, "CP", run, &config),
+                report,
+            )?);
+        }
+    }
+
+    let bp_mean = mean(&bp_dists);
+    let cp_mean = mean(&cp_dists);
+
+    println!("\nResults:");
+    if products.includes_box() {
+        println!("  BP policy distance: {:.4}", bp_mean);
+    }
+    if products.includes_cartesian() {
+        println!("  CP policy distance: {:.4}", cp_mean);
+    }
+    if products == Products::Both {
+        println!("  Ratio (CP/BP): {:.2}x", cp_mean / bp_mean);
+    }
+    Ok(())
+}
@@ -0,0 +1,124 @@
+//! Experiments defined in TOML files, so a setup can be versioned next to its results. A file
+//! names its `experiment` (any subcommand) and sets that subcommand's options: every key is the
+//! flag of the same name with `_` for `-`, alone or grouped in tables such as `[environment]`
+//! and `[training]`, and arrays are the comma-separated lists the flags take. Files therefore
+//! accept exactly what the command line does and fall back to the same defaults.
+//!
+//! ```toml
+//! experiment = "chains"
+//! output = "results/chains.csv"
+//!
+//! [environment]
+//! lengths = [6, 8, 10]
+//! detour_penalty = -2.0
+//!
+//! [training]
+//! products = "both"
+//! algorithm = "sarsa"
+//! episodes = 1500
+//! seeds = [1, 2, 3, 4, 5]
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Parser};
+use ctmdp_rust::error::Error;
+use toml::{Table, Value};
+
+use crate::{Cli, Experiment};
+
+#[derive(Args, Debug, Clone)]
+pub struct RunArgs {
+    /// TOML file defining the experiment
+    pub path: PathBuf,
+}
+
+/// The command-line form of a scalar or an array of scalars
+fn flag_value(key: &str, value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Integer(i) => Ok(i.to_string()),
+        Value::Float(f) => Ok(f.to_string()),
+        Value::Boolean(b) => Ok(b.to_string()),
+        Value::Datetime(d) => Ok(d.to_string()),
+        Value::Array(values) => values
+            .iter()
+            .map(|value| match value {
+                Value::Array(_) | Value::Table(_) => {
+                    Err(format!("`{key}` must be a list of plain values"))
+                }
+                value => flag_value(key, value),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(|values| values.join(",")),
+        Value::Table(_) => Err(format!(
+            "`{key}` is nested too deeply; tables hold options directly"
+        )),
+    }
+}
+
+/// Appends the flags of `key = value`: `--key=value`, or just `--key` for `true`. Switches take
+/// no value, so `false` is rejected rather than dropped; leave the key out instead.
+fn push_flag(key: &str, value: &Value, flags: &mut Vec<String>) -> Result<(), String> {
+    let flag = format!("--{}", key.replace('_', "-"));
+    match value {
+        Value::Boolean(true) => flags.push(flag),
+        Value::Boolean(false) => {
+            return Err(format!("`{key} = false` has no flag; leave `{key}` out instead"));
+        }
+        value => flags.push(format!("{flag}={}", flag_value(key, value)?)),
+    }
+    Ok(())
+}
+
+/// The command line `table` stands for: the program name, the experiment, then one flag per
+/// option
+fn command_line(table: &Table) -> Result<Vec<String>, String> {
+    let experiment = match table.get("experiment") {
+        Some(Value::String(experiment)) => experiment.clone(),
+        Some(_) => return Err("`experiment` must be a string".to_string()),
+        None => return Err("missing `experiment`, the subcommand to run".to_string()),
+    };
+
+    let mut flags = Vec::new();
+    for (key, value) in table.iter().filter(|(key, _)| *key != "experiment") {
+        match value {
+            Value::Table(options) => {
+                for (key, value) in options {
+                    push_flag(key, value, &mut flags)?;
+                }
+            }
+            value => push_flag(key, value, &mut flags)?,
+        }
+    }
+
+    let mut args = vec!["ctmdp-experiments".to_string(), experiment];
+    args.extend(flags);
+    Ok(args)
+}
+
+/// # Load
+///
+/// Reads the experiment defined in the TOML file at `path`. Fails with `Error::Io` if the file
+/// cannot be read and with `Error::Specification` if it is not valid TOML, names no experiment
+/// or sets options the experiment does not take.
+pub fn load(path: &Path) -> Result<Cli, Error> {
+    let in_file = |message: String| Error::Specification(format!("{}: {message}", path.display()));
+
+    let text =
+        fs::read_to_string(path).map_err(|e| Error::Io(format!("{}: {e}", path.display())))?;
+    let table: Table = text
+        .parse()
+        .map_err(|e: toml::de::Error| in_file(e.to_string()))?;
+    let args = command_line(&table).map_err(in_file)?;
+    let cli = Cli::try_parse_from(args)
+        .map_err(|e| in_file(e.render().to_string().trim_end().to_string()))?;
+
+    if let Experiment::Run(_) = cli.experiment {
+        return Err(in_file(
+            "an experiment file cannot run another file".to_string(),
+        ));
+    }
+    Ok(cli)
+}
@@ -0,0 +1,126 @@
+//! Joint against factored Q-learning on products of two path worlds: factored learners keep one
+//! table per component, so what they learn in one joint state carries over to every other state
+//! that shares a component. The factored product policy is the lift of the component greedy
+//! policies; box products finish the first component before moving the second.
+
+use std::time::Instant;
+
+use clap::Args;
+use ctmdp_rust::config::TrainingConfig;
+use ctmdp_rust::error::Error;
+use ctmdp_rust::factored::{QCombination, factored_q_learning};
+use ctmdp_rust::mdp::MDP;
+use ctmdp_rust::planning::value_iteration;
+use ctmdp_rust::policy::{DeterministicPolicy, tabulate};
+use ctmdp_rust::products::{BoxProduct, CartesianProduct, Scheduler};
+use ctmdp_rust::report::{Report, Trial};
+
+use crate::common::{Defaults, TrialArgs, mean, path_world, policy_distance, record_distance};
+
+#[derive(Args, Debug, Clone)]
+pub struct GeneralizationArgs {
+    /// Length of both component paths, one experiment per value
+    #[arg(long, value_delimiter = ',', default_values_t = [4, 6, 8])]
+    pub sizes: Vec<usize>,
+    #[command(flatten)]
+    pub trial: TrialArgs,
+}
+
+const DEFAULTS: Defaults = Defaults {
+    episodes: 500,
+    runs: 1,
+    max_steps: 50,
+    discount_factor: 0.9,
+    exploration_rate: 0.2,
+};
+
+/// Prints the mean distance of joint (`trial.algorithm`) and factored policies to the optimum
+/// found by value iteration, and records every run in `report` as `<name> joint` and
+/// `<name> factored`
+fn compare<M>(
+    name: &str,
+    product: &M,
+    trial: &TrialArgs,
+    setting: &str,
+    report: &mut Report,
+    factored: impl Fn(&TrainingConfig) -> Result<DeterministicPolicy<M::State, M::Action>, Error>,
+) -> Result<(), Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let planning = trial.config(&DEFAULTS, 0);
+    let optimal = value_iteration(
+        product,
+        planning.discount_factor,
+        planning.planning_tolerance,
+        planning.max_planning_iterations,
+    )?
+    .policy;
+
+    let mut joint_dists = Vec::new();
+    let mut factored_dists = Vec::new();
+    for run in 0..trial.runs(&DEFAULTS) {
+        let config = trial.config(&DEFAULTS, run);
+        joint_dists.push(record_distance(
+            product,
+            &optimal,
+            &config,
+            trial.algorithm,
+            Trial::new(setting, format!("{name} joint"), run, &config),
+            report,
+        )?);
+
+        let start = Instant::now();
+        let distance = policy_distance(&factored(&config)?, &optimal);
+        report.push(
+            Trial::new(setting, format!("{name} factored"), run, &config)
+                .with_policy_distance(distance)
+                .with_wall_clock(start.elapsed()),
+        );
+        factored_dists.push(distance);
+    }
+
+    let joint_label = format!("joint {} distance:", trial.algorithm.name());
+    println!("  {name}:");
+    println!("    {joint_label:<30}{:.4}", mean(&joint_dists));
+    println!(
+        "    {:<30}{:.4}",
+        "factored Q-learning distance:",
+        mean(&factored_dists)
+    );
+    Ok(())
+}
+
+pub fn run(args: &GeneralizationArgs, report: &mut Report) -> Result<(), Error> {
+    for &size in &args.sizes {
+        let a = path_world(size);
+        let b = path_world(size);
+        println!(
+            "Two PathWorlds of length {size}: joint tables have {} states, factored ones {} per component",
+            size * size,
+            size
+        );
+        let setting = format!("length {size}");
+
+        if args.trial.products.includes_box() {
+            let bp = BoxProduct::new(&a, &b);
+            compare("BP", &bp, &args.trial, &setting, report, |config| {
+                let values = factored_q_learning(&bp, config, QCombination::Sum)?;
+                let (first, second) = values.component_policies(&bp);
+                Ok(tabulate(&bp, &bp.lift_policies(first, second, Scheduler::FinishFirst)))
+            })?;
+        }
+
+        if args.trial.products.includes_cartesian() {
+            let cp = CartesianProduct::new(&a, &b);
+            compare("CP", &cp, &args.trial, &setting, report, |config| {
+                let values = factored_q_learning(&cp, config, QCombination::Sum)?;
+                let (first, second) = values.component_policies(&cp);
+                Ok(tabulate(&cp, &cp.lift_policies(first, second)))
+            })?;
+        }
+    }
+    Ok(())
+}
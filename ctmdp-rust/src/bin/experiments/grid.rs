@@ -0,0 +1,569 @@
+//! Box against Cartesian products of gridworlds with heterogeneous goals and rewards
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use clap::Args;
+use ctmdp_rust::error::Error;
+use ctmdp_rust::graph::graph_statistics;
+use ctmdp_rust::gridworld::{Goal, GridworldWithGoals};
+use ctmdp_rust::mdp::MDP;
+use ctmdp_rust::policy::{DeterministicPolicy, to_stochastic};
+use ctmdp_rust::products::{BoxAction, BoxProduct, CartesianProduct, Product};
+use ctmdp_rust::reachability::{expected_hitting_time, reach_probability};
+use ctmdp_rust::report::{Report, Trial};
+use ctmdp_rust::wrappers::TransformReward;
+use madepro::environments::gridworld::{GridworldAction, GridworldState};
+
+use crate::common::{
+    Defaults, TrialArgs, analyze_action_space, environment, learn_policy, mean, policy_distance,
+};
+
+/// Gridworld with its rewards scaled
+type WeightedGridworld = TransformReward<GridworldWithGoals>;
+
+/// `(row, col)` of every state of a component
+type Coordinates = HashMap<GridworldState, (usize, usize)>;
+
+#[derive(Args, Debug, Clone)]
+pub struct GridArgs {
+    /// Side length of the open square grids
+    #[arg(long, default_value_t = 3)]
+    pub size: usize,
+    /// Reward of the goal of component A, in the top-right corner
+    #[arg(long, default_value_t = 40.0)]
+    pub goal_reward_a: f64,
+    /// Reward of the goal of component B, in the bottom-left corner
+    #[arg(long, default_value_t = 10.0)]
+    pub goal_reward_b: f64,
+    /// Evaluation rollouts of every learned policy
+    #[arg(long, default_value_t = 200)]
+    pub eval_runs: usize,
+    #[command(flatten)]
+    pub trial: TrialArgs,
+}
+
+const TWO_GOAL_DEFAULTS: Defaults = Defaults {
+    episodes: 2000,
+    runs: 1,
+    max_steps: 20,
+    discount_factor: 0.95,
+    exploration_rate: 0.1,
+};
+
+const THREE_GRID_DEFAULTS: Defaults = Defaults {
+    episodes: 3000,
+    runs: 1,
+    max_steps: 25,
+    discount_factor: 0.95,
+    exploration_rate: 0.1,
+};
+
+/// An open `size`×`size` map, with a goal at `goal` if given
+fn open_map(size: usize, goal: Option<(usize, usize)>) -> String {
+    (0..size)
+        .map(|row| {
+            (0..size)
+                .map(|col| if goal == Some((row, col)) { 'G' } else { '.' })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A component whose goals pay `goal_reward` and end the episode, with every reward (goal
+/// rewards included) scaled by `scale`
+fn build_component(
+    map: &str,
+    goal_reward: f64,
+    scale: f64,
+) -> Result<(WeightedGridworld, Coordinates), Error> {
+    let gw = GridworldWithGoals::from_ascii(map)?.with_all_goals(Goal::terminal(goal_reward));
+    let coords = gw
+        .all_states()
+        .iter()
+        .filter_map(|s| gw.position(s).map(|p| (s.clone(), (p.row(), p.col()))))
+        .collect();
+    Ok((TransformReward::new(gw).scale(scale), coords))
+}
+
+fn manhattan(pos: (usize, usize), goal: (usize, usize)) -> usize {
+    pos.0.abs_diff(goal.0) + pos.1.abs_diff(goal.1)
+}
+
+fn action_toward(pos: (usize, usize), goal: (usize, usize)) -> GridworldAction {
+    if pos.0 < goal.0 {
+        GridworldAction::Down
+    } else if pos.0 > goal.0 {
+        GridworldAction::Up
+    } else if pos.1 < goal.1 {
+        GridworldAction::Right
+    } else if pos.1 > goal.1 {
+        GridworldAction::Left
+    } else {
+        GridworldAction::Up
+    }
+}
+
+/// The two components, their coordinates and their goals
+struct TwoGoals {
+    coords_a: Coordinates,
+    coords_b: Coordinates,
+    goal_a: (usize, usize),
+    goal_b: (usize, usize),
+}
+
+impl TwoGoals {
+    fn positions(&self, state: &Product<GridworldState, GridworldState>) -> [(usize, usize); 2] {
+        [
+            self.coords_a.get(state.first()).copied().unwrap_or((0, 0)),
+            self.coords_b.get(state.second()).copied().unwrap_or((0, 0)),
+        ]
+    }
+
+    fn reached_a(&self, state: &Product<GridworldState, GridworldState>) -> bool {
+        self.coords_a.get(state.first()) == Some(&self.goal_a)
+    }
+
+    fn reached_b(&self, state: &Product<GridworldState, GridworldState>) -> bool {
+        self.coords_b.get(state.second()) == Some(&self.goal_b)
+    }
+
+    fn optimal_policy_bp(
+        &self,
+        bp: &BoxProduct<&WeightedGridworld, &WeightedGridworld>,
+        weights: (f64, f64),
+    ) -> DeterministicPolicy<
+        Product<GridworldState, GridworldState>,
+        BoxAction<GridworldAction, GridworldAction>,
+    > {
+        let mut policy = HashMap::new();
+        for state in bp.all_states().iter() {
+            let [pos_a, pos_b] = self.positions(state);
+            let dist_a = manhattan(pos_a, self.goal_a);
+            let dist_b = manhattan(pos_b, self.goal_b);
+            let action = if dist_a > 0
+                && (weights.0 * dist_a as f64 >= weights.1 * dist_b as f64 || dist_b == 0)
+            {
+                BoxAction::Left(action_toward(pos_a, self.goal_a))
+            } else if dist_b > 0 {
+                BoxAction::Right(action_toward(pos_b, self.goal_b))
+            } else {
+                BoxAction::Left(GridworldAction::Up)
+            };
+            policy.insert(state.clone(), action);
+        }
+        policy
+    }
+
+    fn optimal_policy_cp(
+        &self,
+        cp: &CartesianProduct<&WeightedGridworld, &WeightedGridworld>,
+    ) -> DeterministicPolicy<
+        Product<GridworldState, GridworldState>,
+        Product<GridworldAction, GridworldAction>,
+    > {
+        let mut policy = HashMap::new();
+        for state in cp.all_states().iter() {
+            let [pos_a, pos_b] = self.positions(state);
+            let act_a = action_toward(pos_a, self.goal_a);
+            let act_b = action_toward(pos_b, self.goal_b);
+            policy.insert(state.clone(), Product::new(act_a, act_b));
+        }
+        policy
+    }
+
+    /// Fractions of `episodes` rollouts that reach goal A, goal B and both
+    fn evaluate_goal_hits<M>(
+        &self,
+        mdp: &M,
+        policy: &DeterministicPolicy<M::State, M::Action>,
+        fallback: M::Action,
+        episodes: usize,
+        max_steps: usize,
+        seed: Option<u64>,
+    ) -> Result<[f64; 3], Error>
+    where
+        M: MDP<State = Product<GridworldState, GridworldState>>,
+        M::Action: Clone,
+    {
+        let mut hits = [0.0; 3];
+        let mut env = environment(mdp, seed)?.with_max_steps(max_steps);
+        for _ in 0..episodes {
+            let mut state = env.reset();
+            let mut reached_a = false;
+            let mut reached_b = false;
+            for _ in 0..max_steps {
+                let action = policy
+                    .get(&state)
+                    .cloned()
+                    .unwrap_or_else(|| fallback.clone());
+                let step = env.step(&action)?;
+                reached_a |= self.reached_a(&step.next_state);
+                reached_b |= self.reached_b(&step.next_state);
+                state = step.next_state.clone();
+                if step.done() {
+                    break;
+                }
+            }
+            for (hit, reached) in
+                hits.iter_mut()
+                    .zip([reached_a, reached_b, reached_a && reached_b])
+            {
+                if reached {
+                    *hit += 1.0;
+                }
+            }
+        }
+        Ok(hits.map(|hit| hit / episodes as f64))
+    }
+
+    /// Exact probability of eventually reaching goal A and goal B under `policy`, averaged over
+    /// uniformly random start states (the distribution `Environment::reset` draws from)
+    fn exact_goal_probabilities<M>(
+        &self,
+        mdp: &M,
+        policy: &DeterministicPolicy<M::State, M::Action>,
+    ) -> Result<[f64; 2], Error>
+    where
+        M: MDP<State = Product<GridworldState, GridworldState>>,
+        M::Action: Clone,
+    {
+        let policy = to_stochastic(policy);
+        let to_a = reach_probability(mdp, &policy, |s| self.reached_a(s))?;
+        let to_b = reach_probability(mdp, &policy, |s| self.reached_b(s))?;
+        let n = to_a.len().max(1) as f64;
+        Ok([
+            to_a.values().sum::<f64>() / n,
+            to_b.values().sum::<f64>() / n,
+        ])
+    }
+
+    /// Mean expected number of steps to goal A and to goal B under `policy`, over the start
+    /// states that hit the goal with probability one, with the fraction of such states
+    fn exact_goal_steps<M>(
+        &self,
+        mdp: &M,
+        policy: &DeterministicPolicy<M::State, M::Action>,
+    ) -> Result<[(f64, f64); 2], Error>
+    where
+        M: MDP<State = Product<GridworldState, GridworldState>>,
+        M::Action: Clone,
+    {
+        let policy = to_stochastic(policy);
+        let to_a = expected_hitting_time(mdp, &policy, |s| self.reached_a(s))?;
+        let to_b = expected_hitting_time(mdp, &policy, |s| self.reached_b(s))?;
+        let summarize = |times: &HashMap<_, f64>| {
+            let finite: Vec<f64> = times.values().copied().filter(|t| t.is_finite()).collect();
+            (
+                mean(&finite),
+                finite.len() as f64 / times.len().max(1) as f64,
+            )
+        };
+        Ok([summarize(&to_a), summarize(&to_b)])
+    }
+}
+
+/// What one training run of a product scores
+struct Evaluation {
+    distance: f64,
+    /// Goal A, goal B, both
+    hits: [f64; 3],
+    /// Goal A, goal B
+    reach: [f64; 2],
+    /// `(mean steps, fraction of starts)` to goal A and goal B
+    steps: [(f64, f64); 2],
+}
+
+impl Evaluation {
+    /// `trial` with this run's policy distance and goal-hit rates
+    fn record(&self, trial: Trial) -> Trial {
+        trial
+            .with_policy_distance(self.distance)
+            .with_goal_hit_rate("A", self.hits[0])
+            .with_goal_hit_rate("B", self.hits[1])
+            .with_goal_hit_rate("both", self.hits[2])
+    }
+}
+
+/// The mean of `metric` over `evaluations`
+fn mean_of(evaluations: &[Evaluation], metric: impl Fn(&Evaluation) -> f64) -> f64 {
+    mean(&evaluations.iter().map(metric).collect::<Vec<_>>())
+}
+
+fn evaluate<M>(
+    mdp: &M,
+    goals: &TwoGoals,
+    learned: &DeterministicPolicy<M::State, M::Action>,
+    optimal: &DeterministicPolicy<M::State, M::Action>,
+    fallback: M::Action,
+    args: &GridArgs,
+    run: usize,
+) -> Result<Evaluation, Error>
+where
+    M: MDP<State = Product<GridworldState, GridworldState>>,
+    M::Action: Clone,
+{
+    let max_steps = args.trial.max_steps.unwrap_or(TWO_GOAL_DEFAULTS.max_steps) as usize;
+    Ok(Evaluation {
+        distance: policy_distance(learned, optimal),
+        hits: goals.evaluate_goal_hits(
+            mdp,
+            learned,
+            fallback,
+            args.eval_runs,
+            max_steps,
+            args.trial.seed(run),
+        )?,
+        reach: goals.exact_goal_probabilities(mdp, learned)?,
+        steps: goals.exact_goal_steps(mdp, learned)?,
+    })
+}
+
+fn evaluate_average_return<M>(
+    mdp: &M,
+    policy: &DeterministicPolicy<M::State, M::Action>,
+    episodes: usize,
+    max_steps: usize,
+    seed: Option<u64>,
+) -> Result<f64, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let mut env = environment(mdp, seed)?.with_max_steps(max_steps);
+    let mut total_return = 0.0;
+    for _ in 0..episodes {
+        let mut state = env.reset();
+        let mut episode_return = 0.0;
+        for _ in 0..max_steps {
+            let action = policy
+                .get(&state)
+                .cloned()
+                .unwrap_or_else(|| mdp.actions_at(&state)[0].clone());
+            let step = env.step(&action)?;
+            episode_return += step.reward;
+            state = step.next_state.clone();
+            if step.done() {
+                break;
+            }
+        }
+        total_return += episode_return;
+    }
+    Ok(total_return / episodes as f64)
+}
+
+fn run_three_gridworld_experiment(args: &GridArgs, report: &mut Report) -> Result<(), Error> {
+    println!("\nThree-gridworld product with penalty dimensions");
+
+    // High-value A, penalty-only B and C, shared by both products
+    let size = args.size;
+    let (a, _) = build_component(
+        &open_map(size, Some((0, size - 1))),
+        args.goal_reward_a,
+        1.0,
+    )?;
+    let (b, _) = build_component(&open_map(size, None), 0.0, 3.0)?;
+    let (c, _) = build_component(&open_map(size, None), 0.0, 3.0)?;
+
+    let bp3 = BoxProduct::new(BoxProduct::new(&a, &b), &c);
+
+    // Cartesian product: same components, but rewards sum across all three
+    let cp3 = CartesianProduct::new(CartesianProduct::new(&a, &b), &c);
+
+    let products = args.trial.products;
+    // Penalty-only components have no goal, so neither product can ever reach a final state
+    let mut statistics = Vec::new();
+    if products.includes_box() {
+        analyze_action_space(&bp3, "BP3");
+        statistics.push(("BP3", graph_statistics(&bp3)?));
+    }
+    if products.includes_cartesian() {
+        analyze_action_space(&cp3, "CP3");
+        statistics.push(("CP3", graph_statistics(&cp3)?));
+    }
+    for (name, stats) in statistics {
+        println!(
+            "  {} dead-end states: {} of {} ({} final)",
+            name, stats.num_dead_ends, stats.num_states, stats.num_final_states
+        );
+    }
+
+    let mut bp3_returns = Vec::new();
+    let mut cp3_returns = Vec::new();
+    for run in 0..args.trial.runs(&THREE_GRID_DEFAULTS) {
+        let config = args.trial.config(&THREE_GRID_DEFAULTS, run);
+        let max_steps = config.max_num_steps as usize;
+        let seed = args.trial.seed(run);
+
+        if products.includes_box() {
+            let start = Instant::now();
+            let policy_bp3 = learn_policy(&bp3, &config, args.trial.algorithm)?;
+            let bp3_return =
+                evaluate_average_return(&bp3, &policy_bp3, args.eval_runs, max_steps, seed)?;
+            report.push(
+                Trial::new("three gridworlds", "BP3", run, &config)
+                    .with_mean_return(bp3_return)
+                    .with_wall_clock(start.elapsed()),
+            );
+            bp3_returns.push(bp3_return);
+        }
+
+        if products.includes_cartesian() {
+            let start = Instant::now();
+            let policy_cp3 = learn_policy(&cp3, &config, args.trial.algorithm)?;
+            let cp3_return =
+                evaluate_average_return(&cp3, &policy_cp3, args.eval_runs, max_steps, seed)?;
+            report.push(
+                Trial::new("three gridworlds", "CP3", run, &config)
+                    .with_mean_return(cp3_return)
+                    .with_wall_clock(start.elapsed()),
+            );
+            cp3_returns.push(cp3_return);
+        }
+    }
+
+    println!(
+        "\nAverage episodic returns over {} evaluations (three-gridworld product):",
+        args.eval_runs
+    );
+    if products.includes_box() {
+        println!("  BP3 average return: {:.2}", mean(&bp3_returns));
+    }
+    if products.includes_cartesian() {
+        println!("  CP3 average return: {:.2}", mean(&cp3_returns));
+    }
+
+    Ok(())
+}
+
+pub fn run(args: &GridArgs, report: &mut Report) -> Result<(), Error> {
+    if args.size < 2 {
+        return Err(Error::Specification(format!(
+            "grids need at least 2 cells per side, got {}",
+            args.size
+        )));
+    }
+    println!("Gridworld components with heterogeneous goals/rewards");
+
+    let size = args.size;
+    let goal_a = (0, size - 1);
+    let goal_b = (size - 1, 0);
+
+    let (a, coords_a) = build_component(&open_map(size, Some(goal_a)), args.goal_reward_a, 1.0)?;
+    let (b, coords_b) = build_component(&open_map(size, Some(goal_b)), args.goal_reward_b, 1.0)?;
+    let goals = TwoGoals {
+        coords_a,
+        coords_b,
+        goal_a,
+        goal_b,
+    };
+    let bp = BoxProduct::new(&a, &b);
+    let cp = CartesianProduct::new(&a, &b);
+
+    let products = args.trial.products;
+    if products.includes_box() {
+        analyze_action_space(&bp, "BP");
+    }
+    if products.includes_cartesian() {
+        analyze_action_space(&cp, "CP");
+    }
+
+    let opt_bp = goals.optimal_policy_bp(&bp, (1.0, 0.5));
+    let opt_cp = goals.optimal_policy_cp(&cp);
+
+    let mut bp_runs = Vec::new();
+    let mut cp_runs = Vec::new();
+    for run in 0..args.trial.runs(&TWO_GOAL_DEFAULTS) {
+        let config = args.trial.config(&TWO_GOAL_DEFAULTS, run);
+        let up = GridworldAction::Up;
+
+        if products.includes_box() {
+            let start = Instant::now();
+            let learned_bp = learn_policy(&bp, &config, args.trial.algorithm)?;
+            let evaluation = evaluate(
+                &bp,
+                &goals,
+                &learned_bp,
+                &opt_bp,
+                BoxAction::Left(up.clone()),
+                args,
+                run,
+            )?;
+            report.push(
+                evaluation
+                    .record(Trial::new("two goals", "BP", run, &config))
+                    .with_wall_clock(start.elapsed()),
+            );
+            bp_runs.push(evaluation);
+        }
+
+        if products.includes_cartesian() {
+            let start = Instant::now();
+            let learned_cp = learn_policy(&cp, &config, args.trial.algorithm)?;
+            let evaluation = evaluate(
+                &cp,
+                &goals,
+                &learned_cp,
+                &opt_cp,
+                Product::new(up.clone(), up),
+                args,
+                run,
+            )?;
+            report.push(
+                evaluation
+                    .record(Trial::new("two goals", "CP", run, &config))
+                    .with_wall_clock(start.elapsed()),
+            );
+            cp_runs.push(evaluation);
+        }
+    }
+    let results: Vec<(&str, &Vec<Evaluation>)> = [("BP", &bp_runs), ("CP", &cp_runs)]
+        .into_iter()
+        .filter(|(_, runs)| !runs.is_empty())
+        .collect();
+
+    println!("\nPolicy quality (L1/Hamming distance to heuristic optimum):");
+    for &(label, runs) in &results {
+        println!("  {} distance: {:.4}", label, mean_of(runs, |e| e.distance));
+    }
+
+    println!(
+        "\nGoal reach frequency over {} evaluation rollouts:",
+        args.eval_runs
+    );
+    for &(label, runs) in &results {
+        println!(
+            "  {}: goal A {:.1}% | goal B {:.1}% | both {:.1}%",
+            label,
+            mean_of(runs, |e| e.hits[0]) * 100.0,
+            mean_of(runs, |e| e.hits[1]) * 100.0,
+            mean_of(runs, |e| e.hits[2]) * 100.0
+        );
+    }
+
+    println!("\nExact reach probabilities (no step limit):");
+    for &(label, runs) in &results {
+        println!(
+            "  {}: goal A {:.1}% | goal B {:.1}%",
+            label,
+            mean_of(runs, |e| e.reach[0]) * 100.0,
+            mean_of(runs, |e| e.reach[1]) * 100.0
+        );
+    }
+
+    println!("\nExpected steps to goal (start states that surely reach it):");
+    for &(label, runs) in &results {
+        println!(
+            "  {}: goal A {:.1} steps ({:.0}% of starts) | goal B {:.1} steps ({:.0}% of starts)",
+            label,
+            mean_of(runs, |e| e.steps[0].0),
+            mean_of(runs, |e| e.steps[0].1) * 100.0,
+            mean_of(runs, |e| e.steps[1].0),
+            mean_of(runs, |e| e.steps[1].1) * 100.0
+        );
+    }
+
+    run_three_gridworld_experiment(args, report)
+}
@@ -0,0 +1,142 @@
+//! # ctmdp-experiments
+//!
+//! The Box-versus-Cartesian product experiments behind one command line: each subcommand builds
+//! a pair of products, trains both and reports how close the learned policies come to a
+//! heuristic (or planned) optimum. Sizes, episodes, runs, seeds, the learner and its
+//! hyperparameters are flags, and `--output` writes every trial's metrics to a JSON or CSV
+//! report next to the printed summary. `run` reads the same options from a TOML file instead,
+//! e.g.
+//!
+//! ```text
+//! ctmdp-experiments chains --lengths 6,8,10 --runs 20 --seed 7 --output chains.csv
+//! ctmdp-experiments 3d --sizes 3,4 --algorithm sarsa --episodes 3000
+//! ctmdp-experiments run experiments/chains.toml
+//! ```
+
+mod chains;
+mod common;
+mod file;
+mod generalization;
+mod grid;
+mod paths;
+
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use ctmdp_rust::error::Error;
+use ctmdp_rust::report::{Report, ReportFormat, git_hash};
+
+use crate::common::TrialArgs;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "ctmdp-experiments",
+    about = "Box versus Cartesian product experiments"
+)]
+struct Cli {
+    /// File to write the per-trial report to
+    #[arg(long, global = true)]
+    output: Option<PathBuf>,
+    /// Encoding of the report [default: csv for `.csv` files, json otherwise]
+    #[arg(long, global = true, value_enum)]
+    format: Option<OutputFormat>,
+    #[command(subcommand)]
+    experiment: Experiment,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum OutputFormat {
+    Json,
+    Csv,
+}
+
+#[derive(Subcommand, Debug)]
+enum Experiment {
+    /// Two branched chains, with detours only at some states
+    Chains(chains::ChainsArgs),
+    /// Two gridworlds with goals in opposite corners, then three with penalty dimensions
+    Grid(grid::GridArgs),
+    /// Two path worlds
+    #[command(name = "2d")]
+    TwoD(paths::PathArgs),
+    /// Three path worlds
+    #[command(name = "3d")]
+    ThreeD(paths::PathArgs),
+    /// Four path worlds
+    #[command(name = "4d")]
+    FourD(paths::PathArgs),
+    /// Six path worlds
+    #[command(name = "6d")]
+    SixD(paths::PathArgs),
+    /// Joint against factored Q-learning on two path worlds
+    Generalization(generalization::GeneralizationArgs),
+    /// The experiment defined in a TOML file
+    Run(file::RunArgs),
+}
+
+impl Experiment {
+    fn name(&self) -> &'static str {
+        match self {
+            Experiment::Chains(_) => "chains",
+            Experiment::Grid(_) => "grid",
+            Experiment::TwoD(_) => "2d",
+            Experiment::ThreeD(_) => "3d",
+            Experiment::FourD(_) => "4d",
+            Experiment::SixD(_) => "6d",
+            Experiment::Generalization(_) => "generalization",
+            Experiment::Run(_) => "run",
+        }
+    }
+
+    fn trial(&self) -> &TrialArgs {
+        match self {
+            Experiment::Chains(args) => &args.trial,
+            Experiment::Grid(args) => &args.trial,
+            Experiment::TwoD(args)
+            | Experiment::ThreeD(args)
+            | Experiment::FourD(args)
+            | Experiment::SixD(args) => &args.trial,
+            Experiment::Generalization(args) => &args.trial,
+            Experiment::Run(_) => unreachable!("experiment files are loaded before running"),
+        }
+    }
+}
+
+fn main() -> Result<(), Error> {
+    let mut cli = Cli::parse();
+    if let Experiment::Run(args) = &cli.experiment {
+        let file = file::load(&args.path)?;
+        // `--output` and `--format` on the command line override the file's
+        cli = Cli {
+            output: cli.output.or(file.output),
+            format: cli.format.or(file.format),
+            experiment: file.experiment,
+        };
+    }
+
+    let trial = cli.experiment.trial();
+    let mut report = Report::new(cli.experiment.name(), trial.algorithm.name())
+        .with_seed(trial.seed(0))
+        .with_git_hash(git_hash());
+
+    match &cli.experiment {
+        Experiment::Chains(args) => chains::run(args, &mut report),
+        Experiment::Grid(args) => grid::run(args, &mut report),
+        Experiment::TwoD(args) => paths::run_2d(args, &mut report),
+        Experiment::ThreeD(args) => paths::run_3d(args, &mut report),
+        Experiment::FourD(args) => paths::run_4d(args, &mut report),
+        Experiment::SixD(args) => paths::run_6d(args, &mut report),
+        Experiment::Generalization(args) => generalization::run(args, &mut report),
+        Experiment::Run(_) => unreachable!("experiment files are loaded before running"),
+    }?;
+
+    if let Some(path) = &cli.output {
+        let format = match cli.format {
+            Some(OutputFormat::Json) => ReportFormat::Json,
+            Some(OutputFormat::Csv) => ReportFormat::Csv,
+            None => ReportFormat::from_path(path),
+        };
+        report.save(path, format)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,133 @@
+//! Box against Cartesian products of two, three, four and six path worlds
+
+use clap::Args;
+use ctmdp_rust::diagnostics::{DEFAULT_MEMORY_BUDGET, state_space_report};
+use ctmdp_rust::error::Error;
+use ctmdp_rust::products::{BoxProduct, CartesianProduct};
+use ctmdp_rust::report::Report;
+
+use crate::common::{Defaults, TrialArgs, compare_box_cartesian, path_world};
+
+#[derive(Args, Debug, Clone)]
+pub struct PathArgs {
+    /// Length of every component path, one experiment per value [default: 4,6 in 2D, 4 in 3D,
+    /// 3 in 4D and 6D]
+    #[arg(long, value_delimiter = ',')]
+    pub sizes: Option<Vec<usize>>,
+    #[command(flatten)]
+    pub trial: TrialArgs,
+}
+
+/// Training defaults of a product of `dimensions` paths of length `size`
+fn defaults(dimensions: usize, size: usize) -> Defaults {
+    let episodes = match dimensions {
+        // 1500 episodes at length 4, 2000 at length 6
+        2 => 500 + 250 * size as u32,
+        3 => 1500,
+        4 => 2000,
+        _ => 3000,
+    };
+    Defaults {
+        episodes,
+        runs: 10,
+        max_steps: (size * (dimensions + 1)) as u32,
+        discount_factor: 0.9,
+        exploration_rate: 0.1,
+    }
+}
+
+/// Runs `experiment` for every size in `args`, or for `default_sizes`, with the setting the
+/// size's trials are reported under
+fn for_each_size(
+    args: &PathArgs,
+    default_sizes: &[usize],
+    report: &mut Report,
+    mut experiment: impl FnMut(usize, &str, &mut Report) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let sizes = args.sizes.as_deref().unwrap_or(default_sizes);
+    for &size in sizes {
+        experiment(size, &format!("length {size}"), report)?;
+    }
+    Ok(())
+}
+
+pub fn run_2d(args: &PathArgs, report: &mut Report) -> Result<(), Error> {
+    println!("2D chain: Box vs Cartesian products");
+    for_each_size(args, &[4, 6], report, |size, setting, report| {
+        println!();
+        println!("Box vs Cartesian Product on {}x{} chain", size, size);
+        let bp = BoxProduct::new(path_world(size), path_world(size));
+        let cp = CartesianProduct::new(path_world(size), path_world(size));
+        compare_box_cartesian(&bp, &cp, &args.trial, &defaults(2, size), setting, report)
+    })
+}
+
+pub fn run_3d(args: &PathArgs, report: &mut Report) -> Result<(), Error> {
+    println!("3D path-product: Box vs Cartesian");
+    for_each_size(args, &[4], report, |size, setting, report| {
+        println!();
+        println!("Paths of length {}", size);
+        let bp = BoxProduct::new(
+            BoxProduct::new(path_world(size), path_world(size)),
+            path_world(size),
+        );
+        let cp = CartesianProduct::new(
+            CartesianProduct::new(path_world(size), path_world(size)),
+            path_world(size),
+        );
+        compare_box_cartesian(&bp, &cp, &args.trial, &defaults(3, size), setting, report)
+    })
+}
+
+pub fn run_4d(args: &PathArgs, report: &mut Report) -> Result<(), Error> {
+    println!("4D path-product: Box vs Cartesian");
+    for_each_size(args, &[3], report, |size, setting, report| {
+        println!();
+        println!("Paths of length {}", size);
+        let bp = BoxProduct::new(
+            BoxProduct::new(
+                BoxProduct::new(path_world(size), path_world(size)),
+                path_world(size),
+            ),
+            path_world(size),
+        );
+        let cp = CartesianProduct::new(
+            CartesianProduct::new(
+                CartesianProduct::new(path_world(size), path_world(size)),
+                path_world(size),
+            ),
+            path_world(size),
+        );
+        compare_box_cartesian(&bp, &cp, &args.trial, &defaults(4, size), setting, report)
+    })
+}
+
+pub fn run_6d(args: &PathArgs, report: &mut Report) -> Result<(), Error> {
+    println!("6D path-product: Box vs Cartesian");
+    for_each_size(args, &[3], report, |size, setting, report| {
+        println!();
+        println!("Paths of length {}", size);
+
+        let component = state_space_report(&path_world(size));
+        let cp_estimate = (1..6).fold(component, |report, _| report.cartesian_product(&component));
+        if let Some(warning) = cp_estimate.warning(DEFAULT_MEMORY_BUDGET) {
+            eprintln!("warning: {warning}");
+        }
+
+        let bp2 = BoxProduct::new(path_world(size), path_world(size));
+        let bp4 = BoxProduct::new(BoxProduct::new(bp2, path_world(size)), path_world(size));
+        let bp = BoxProduct::new(BoxProduct::new(bp4, path_world(size)), path_world(size));
+
+        let cp2 = CartesianProduct::new(path_world(size), path_world(size));
+        let cp4 = CartesianProduct::new(
+            CartesianProduct::new(cp2, path_world(size)),
+            path_world(size),
+        );
+        let cp = CartesianProduct::new(
+            CartesianProduct::new(cp4, path_world(size)),
+            path_world(size),
+        );
+
+        compare_box_cartesian(&bp, &cp, &args.trial, &defaults(6, size), setting, report)
+    })
+}
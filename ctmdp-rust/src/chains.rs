@@ -0,0 +1,379 @@
+//! # Chain Benchmarks
+//!
+//! Hard-exploration chains for evaluating exploration strategies such as R-MAX and UCB-VI in
+//! `model_based`. In `RiverSwim` and `NChain`, a small reward is easy to find near the start of
+//! the chain, while the large one sits at the far end behind a long run of unrewarded (and, in
+//! RiverSwim, mostly unsuccessful) moves, so dithering exploration like ε-greedy settles for the
+//! small reward.
+//!
+//! Their states are `PathState` indices and their actions `PathAction`s: `Next` moves towards
+//! the far end and `Prev` back towards `0`. Neither has final states; learners run them as
+//! continuing tasks cut into episodes of `max_num_steps` steps.
+//!
+//! `BranchedChain`, the chain of the Box-versus-Cartesian comparisons, is an episodic chain on
+//! `PathState` with a `Detour` action at some states, so its action sets differ between states.
+
+use std::collections::BTreeMap;
+
+use madepro::models::{Action, Sampler};
+
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::measure::{Measure, MeasureBuilder};
+use crate::pathmdp::{PathAction, PathState};
+
+/// Probability that swimming upstream (`Next`) makes progress from an interior state
+const UPSTREAM_SUCCESS: f64 = 0.35;
+/// Probability that swimming upstream from an interior state is swept back a state
+const UPSTREAM_SWEPT_BACK: f64 = 0.05;
+/// Probability that swimming upstream from either bank gets anywhere
+const BANK_SUCCESS: f64 = 0.6;
+
+/// The `0..length` states of a chain, or `Error::Specification` if it is too short
+fn chain_states(length: usize) -> Result<Sampler<PathState>, Error> {
+    if length < 2 {
+        return Err(Error::Specification(format!(
+            "a chain needs at least 2 states, got {length}"
+        )));
+    }
+    Ok((0..length).map(PathState::new).collect::<Vec<_>>().into())
+}
+
+/// RiverSwim (Strehl & Littman, 2008), with the transition probabilities of Osband, Russo &
+/// Van Roy (2013). Swimming downstream (`Prev`) always succeeds, and doing so in state `0`
+/// earns `LEFT_REWARD`. Swimming upstream (`Next`) against the current succeeds with
+/// probability 0.35 and gets swept back with probability 0.05 (from state `0`: 0.6 to advance,
+/// 0.4 to stay); in the last state it stays with probability 0.6, earning `RIGHT_REWARD`.
+#[derive(Debug, Clone)]
+pub struct RiverSwim {
+    states: Sampler<PathState>,
+    length: usize,
+}
+
+impl RiverSwim {
+    /// Reward for swimming downstream in state `0`
+    pub const LEFT_REWARD: f64 = 0.005;
+    /// Reward for swimming upstream in the last state
+    pub const RIGHT_REWARD: f64 = 1.0;
+
+    /// A river of `length` states; fails with `Error::Specification` for fewer than 2
+    pub fn new(length: usize) -> Result<Self, Error> {
+        Ok(RiverSwim {
+            states: chain_states(length)?,
+            length,
+        })
+    }
+
+    /// The usual six-state river
+    pub fn classic() -> Self {
+        Self::new(6).expect("six states make a valid river")
+    }
+
+    pub fn length(&self) -> usize {
+        self.length
+    }
+}
+
+impl MDP for RiverSwim {
+    type State = PathState;
+    type Action = PathAction;
+
+    fn all_states(&self) -> &Sampler<PathState> {
+        &self.states
+    }
+
+    fn all_actions(&self) -> Vec<PathAction> {
+        vec![PathAction::Next, PathAction::Prev]
+    }
+
+    fn is_final_state(&self, _state: &PathState) -> bool {
+        false
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &PathState,
+        action: &PathAction,
+    ) -> Result<(Measure<PathState>, f64), Error> {
+        let current = state.index();
+        let last = self.length - 1;
+        if current > last {
+            return Err(Error::StateNotFound);
+        }
+        match action {
+            PathAction::Prev => {
+                let reward = if current == 0 { Self::LEFT_REWARD } else { 0.0 };
+                let next = PathState::new(current.saturating_sub(1));
+                Ok((Measure::deterministic(next), reward))
+            }
+            PathAction::Next => {
+                let builder = MeasureBuilder::new();
+                let (builder, reward) = if current == 0 {
+                    let builder = builder
+                        .add(PathState::new(1), BANK_SUCCESS)
+                        .add(PathState::new(0), 1.0 - BANK_SUCCESS);
+                    (builder, 0.0)
+                } else if current == last {
+                    let builder = builder
+                        .add(PathState::new(last), BANK_SUCCESS)
+                        .add(PathState::new(last - 1), 1.0 - BANK_SUCCESS);
+                    (builder, Self::RIGHT_REWARD)
+                } else {
+                    let builder = builder
+                        .add(PathState::new(current + 1), UPSTREAM_SUCCESS)
+                        .add(PathState::new(current - 1), UPSTREAM_SWEPT_BACK)
+                        .add(
+                            PathState::new(current),
+                            1.0 - UPSTREAM_SUCCESS - UPSTREAM_SWEPT_BACK,
+                        );
+                    (builder, 0.0)
+                };
+                Ok((builder.normalize()?, reward))
+            }
+        }
+    }
+}
+
+/// NChain (Strens, 2000). Going forward (`Next`) advances a state for no reward, and in the
+/// last state stays there for `LARGE_REWARD`; going backward (`Prev`) returns to state `0` for
+/// `SMALL_REWARD`. With the slip probability the opposite action happens instead, and the
+/// reward is that of the action that happened.
+#[derive(Debug, Clone)]
+pub struct NChain {
+    states: Sampler<PathState>,
+    length: usize,
+    slip: f64,
+}
+
+impl NChain {
+    /// Reward for going back to the start
+    pub const SMALL_REWARD: f64 = 2.0;
+    /// Reward for going forward in the last state
+    pub const LARGE_REWARD: f64 = 10.0;
+
+    /// A chain of `length` states whose actions slip with probability `slip`. Fails with
+    /// `Error::Specification` for fewer than 2 states or `Error::ProbabilityOutOfRange` for a slip
+    /// outside `[0, 1]`.
+    pub fn new(length: usize, slip: f64) -> Result<Self, Error> {
+        if !(0.0..=1.0).contains(&slip) {
+            return Err(Error::ProbabilityOutOfRange);
+        }
+        Ok(NChain {
+            states: chain_states(length)?,
+            length,
+            slip,
+        })
+    }
+
+    /// The usual five-state chain with slip probability 0.2
+    pub fn classic() -> Self {
+        Self::new(5, 0.2).expect("five states and a slip of 0.2 make a valid chain")
+    }
+
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    pub fn slip(&self) -> f64 {
+        self.slip
+    }
+
+    /// Where `action` leads from `current` if it does not slip, and its reward
+    fn outcome(&self, current: usize, action: &PathAction) -> (PathState, f64) {
+        match action {
+            PathAction::Next if current == self.length - 1 => {
+                (PathState::new(current), Self::LARGE_REWARD)
+            }
+            PathAction::Next => (PathState::new(current + 1), 0.0),
+            PathAction::Prev => (PathState::new(0), Self::SMALL_REWARD),
+        }
+    }
+}
+
+impl MDP for NChain {
+    type State = PathState;
+    type Action = PathAction;
+
+    fn all_states(&self) -> &Sampler<PathState> {
+        &self.states
+    }
+
+    fn all_actions(&self) -> Vec<PathAction> {
+        vec![PathAction::Next, PathAction::Prev]
+    }
+
+    fn is_final_state(&self, _state: &PathState) -> bool {
+        false
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &PathState,
+        action: &PathAction,
+    ) -> Result<(Measure<PathState>, f64), Error> {
+        let current = state.index();
+        if current >= self.length {
+            return Err(Error::StateNotFound);
+        }
+        let opposite = match action {
+            PathAction::Next => PathAction::Prev,
+            PathAction::Prev => PathAction::Next,
+        };
+        let (intended, intended_reward) = self.outcome(current, action);
+        let (slipped, slipped_reward) = self.outcome(current, &opposite);
+        let measure = MeasureBuilder::new()
+            .add(intended, 1.0 - self.slip)
+            .add(slipped, self.slip)
+            .normalize()?;
+        let reward = (1.0 - self.slip) * intended_reward + self.slip * slipped_reward;
+        Ok((measure, reward))
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChainAction {
+    Next,
+    Prev,
+    Detour,
+}
+
+impl Action for ChainAction {}
+
+/// A chain whose last state is the goal, where `Next` and `Prev` are available everywhere and
+/// `Detour` only at branch states. A detour costs the branch's penalty and sends the agent back
+/// to state `0`, unless a shortcut probability is set: then it reaches the goal directly with
+/// that probability, which turns the detour into a gamble. Learners that explore over the whole
+/// action union may still take `Detour` elsewhere; it then costs `DEFAULT_DETOUR_PENALTY`.
+#[derive(Debug, Clone)]
+pub struct BranchedChain {
+    states: Sampler<PathState>,
+    length: usize,
+    /// Detour penalty of every branch state
+    branches: BTreeMap<usize, f64>,
+    shortcut_probability: f64,
+}
+
+impl BranchedChain {
+    /// Reward of a `Next` move
+    pub const NEXT_REWARD: f64 = 0.1;
+    /// Reward of a `Prev` move
+    pub const PREV_REWARD: f64 = -0.5;
+    /// Bonus for arriving at the goal
+    pub const GOAL_REWARD: f64 = 10.0;
+    /// Penalty of a detour unless the branch sets its own
+    pub const DEFAULT_DETOUR_PENALTY: f64 = 2.0;
+
+    /// A chain of `length` states with a detour of `DEFAULT_DETOUR_PENALTY` at each of
+    /// `branch_states`
+    pub fn new(length: usize, branch_states: Vec<usize>) -> Self {
+        let states: Vec<PathState> = (0..length).map(PathState::new).collect();
+        BranchedChain {
+            states: states.into(),
+            length,
+            branches: branch_states
+                .into_iter()
+                .map(|index| (index, Self::DEFAULT_DETOUR_PENALTY))
+                .collect(),
+            shortcut_probability: 0.0,
+        }
+    }
+
+    /// Adds a branch at `index` (or changes its penalty), where a detour costs `penalty`
+    pub fn with_branch(mut self, index: usize, penalty: f64) -> Self {
+        self.branches.insert(index, penalty);
+        self
+    }
+
+    /// Sets the detour penalty of every branch
+    pub fn with_detour_penalty(mut self, penalty: f64) -> Self {
+        for branch_penalty in self.branches.values_mut() {
+            *branch_penalty = penalty;
+        }
+        self
+    }
+
+    /// Detours reach the goal with probability `p` and state `0` otherwise; fails with
+    /// `Error::ProbabilityOutOfRange` outside `[0, 1]`
+    pub fn with_detour_shortcut(mut self, p: f64) -> Result<Self, Error> {
+        if !(0.0..=1.0).contains(&p) {
+            return Err(Error::ProbabilityOutOfRange);
+        }
+        self.shortcut_probability = p;
+        Ok(self)
+    }
+
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_branch_state(&self, index: usize) -> bool {
+        self.branches.contains_key(&index)
+    }
+
+    /// The branch states in increasing order
+    pub fn branch_states(&self) -> Vec<usize> {
+        self.branches.keys().copied().collect()
+    }
+
+    /// The penalty of a detour from `index`, if it is a branch state
+    pub fn detour_penalty(&self, index: usize) -> Option<f64> {
+        self.branches.get(&index).copied()
+    }
+
+    pub fn shortcut_probability(&self) -> f64 {
+        self.shortcut_probability
+    }
+}
+
+impl MDP for BranchedChain {
+    type State = PathState;
+    type Action = ChainAction;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        &self.states
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        let mut actions = vec![ChainAction::Prev, ChainAction::Next];
+        if self.is_branch_state(state.index()) {
+            actions.push(ChainAction::Detour);
+        }
+        actions
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        state.index() == self.length - 1
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        let current = state.index();
+        if current >= self.length {
+            return Err(Error::StateNotFound);
+        }
+        let goal = self.length - 1;
+        let (next, mut reward) = match action {
+            ChainAction::Next => (usize::min(current + 1, goal), Self::NEXT_REWARD),
+            ChainAction::Prev => (current.saturating_sub(1), Self::PREV_REWARD),
+            ChainAction::Detour => {
+                let penalty = self
+                    .detour_penalty(current)
+                    .unwrap_or(Self::DEFAULT_DETOUR_PENALTY);
+                let p = self.shortcut_probability;
+                let measure = MeasureBuilder::new()
+                    .add(PathState::new(0), 1.0 - p)
+                    .add(PathState::new(goal), p)
+                    .normalize()?;
+                return Ok((measure, p * Self::GOAL_REWARD - penalty));
+            }
+        };
+        if next == goal {
+            reward += Self::GOAL_REWARD;
+        }
+        Ok((Measure::deterministic(PathState::new(next)), reward))
+    }
+}
@@ -0,0 +1,245 @@
+//! # Checkpointing
+//!
+//! Long runs on large products (thousands of episodes over 6D state spaces) should survive an
+//! interruption. `checkpointed_q_learning` is one-step Q-learning that writes a `Checkpoint` (the
+//! Q-table, the number of finished episodes and the random state) to a file every few episodes,
+//! and `resume_from_checkpoint` picks a run up from such a file. Randomness comes from a seeded
+//! generator re-derived at the start of every episode, so the episode counter together with the
+//! seed is the complete random state.
+
+use std::fs;
+use std::path::PathBuf;
+
+use madepro::models::{ActionValue, Sampler};
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::{Rng, SeedableRng};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::config::TrainingConfig;
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::persistence::{Format, QTable, load, save};
+use crate::q_learning::{checked_action_union, checked_q_value};
+
+/// Everything needed to continue a run: the Q-table, the episodes finished so far and the seed
+/// from which the random state of every later episode is derived
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint<S, A> {
+    pub q_table: QTable<S, A>,
+    pub episode: usize,
+    pub seed: u64,
+}
+
+/// Where and how often `checkpointed_q_learning` saves its progress
+#[derive(Debug, Clone)]
+pub struct Checkpointing {
+    pub path: PathBuf,
+    /// Episodes between checkpoints; a final checkpoint is always written at the end of the run
+    pub interval: usize,
+    pub format: Format,
+    /// Seed of a fresh run (a resumed run keeps the seed stored in its checkpoint)
+    pub seed: u64,
+}
+
+impl Checkpointing {
+    /// Checkpoints to `path` every `interval` episodes, in the format implied by the extension
+    pub fn new(path: impl Into<PathBuf>, interval: usize) -> Self {
+        let path = path.into();
+        Checkpointing {
+            format: Format::from_path(&path),
+            path,
+            interval,
+            seed: 0,
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Writes `checkpoint` next to the target and renames it into place, so an interruption
+    /// mid-write leaves the previous checkpoint intact
+    fn write<S: Serialize, A: Serialize>(
+        &self,
+        checkpoint: &Checkpoint<S, A>,
+    ) -> Result<(), Error> {
+        let mut partial = self.path.clone().into_os_string();
+        partial.push(".partial");
+        let partial = PathBuf::from(partial);
+        save(checkpoint, &partial, self.format)?;
+        fs::rename(&partial, &self.path)
+            .map_err(|e| Error::Io(format!("{}: {e}", self.path.display())))
+    }
+}
+
+/// The generator for episode `episode` of the run seeded with `seed`
+fn episode_rng(seed: u64, episode: usize) -> StdRng {
+    StdRng::seed_from_u64(seed.wrapping_add(episode as u64))
+}
+
+/// `max_a Q(s, a)` over the actions available in `state` (zero if there are none)
+fn max_available<M: MDP>(
+    mdp: &M,
+    action_value: &ActionValue<M::State, M::Action>,
+    state: &M::State,
+) -> f64 {
+    mdp.actions_at(state)
+        .iter()
+        .map(|a| action_value.get(state, a))
+        .fold(None, |best: Option<f64>, q| {
+            Some(best.map_or(q, |b| b.max(q)))
+        })
+        .unwrap_or(0.0)
+}
+
+/// # Checkpointed Q-Learning
+///
+/// One-step Q-learning with epsilon-greedy exploration over the available actions, saving a
+/// `Checkpoint` to `checkpointing.path` every `checkpointing.interval` episodes and once more
+/// after the last one. Episodes start in uniformly random states and end in final states, dead
+/// ends or after `config.max_num_steps` steps.
+///
+/// # Arguments
+/// * `mdp` - The MDP to learn from
+/// * `config` - Configuration parameters (learning rate, discount factor, exploration rate, etc.)
+/// * `checkpointing` - Where, how often and in which format to save, and the run's seed
+///
+/// # Returns
+/// The learned `ActionValue` table
+pub fn checkpointed_q_learning<M>(
+    mdp: &M,
+    config: &TrainingConfig,
+    checkpointing: &Checkpointing,
+) -> Result<ActionValue<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone + Serialize,
+    M::Action: Clone + Serialize,
+{
+    let actions = checked_action_union(mdp)?;
+    let action_value = ActionValue::new(mdp.all_states(), &actions);
+    run_from(
+        mdp,
+        config,
+        checkpointing,
+        action_value,
+        &actions,
+        0,
+        checkpointing.seed,
+    )
+}
+
+/// # Resume From Checkpoint
+///
+/// Loads the checkpoint at `checkpointing.path` and continues `checkpointed_q_learning` from the
+/// episode it records until `config.num_episodes` episodes are done, with the seed stored in the
+/// checkpoint. Fails with `Error::Io` if there is no checkpoint and with `Error::StateNotFound`
+/// if it was written for a different state space.
+pub fn resume_from_checkpoint<M>(
+    mdp: &M,
+    config: &TrainingConfig,
+    checkpointing: &Checkpointing,
+) -> Result<ActionValue<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone + Serialize + DeserializeOwned,
+    M::Action: Clone + Serialize + DeserializeOwned,
+{
+    let checkpoint: Checkpoint<M::State, M::Action> =
+        load(&checkpointing.path, checkpointing.format)?;
+    let actions = checked_action_union(mdp)?;
+    let action_value = checkpoint
+        .q_table
+        .to_action_value(mdp.all_states(), &actions)?;
+    run_from(
+        mdp,
+        config,
+        checkpointing,
+        action_value,
+        &actions,
+        checkpoint.episode,
+        checkpoint.seed,
+    )
+}
+
+fn run_from<M>(
+    mdp: &M,
+    config: &TrainingConfig,
+    checkpointing: &Checkpointing,
+    mut action_value: ActionValue<M::State, M::Action>,
+    actions: &Sampler<M::Action>,
+    first_episode: usize,
+    seed: u64,
+) -> Result<ActionValue<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone + Serialize,
+    M::Action: Clone + Serialize,
+{
+    let states: Vec<M::State> = mdp.all_states().iter().cloned().collect();
+    let num_episodes = config.num_episodes as usize;
+    let checkpoint = |action_value: &ActionValue<M::State, M::Action>, episode: usize| {
+        checkpointing.write(&Checkpoint {
+            q_table: QTable::from_action_value(action_value, mdp.all_states(), actions),
+            episode,
+            seed,
+        })
+    };
+
+    for episode in first_episode..num_episodes {
+        let mut rng = episode_rng(seed, episode);
+        let Some(mut state) = states.choose(&mut rng).cloned() else {
+            return Err(Error::EmptyStateSpace);
+        };
+
+        for _ in 0..config.max_num_steps {
+            if mdp.is_final_state(&state) {
+                break;
+            }
+            let available = mdp.actions_at(&state);
+            let action = if rng.random::<f64>() < config.exploration_rate {
+                available.choose(&mut rng).cloned()
+            } else {
+                available
+                    .iter()
+                    .max_by(|a, b| {
+                        action_value
+                            .get(&state, a)
+                            .total_cmp(&action_value.get(&state, b))
+                    })
+                    .cloned()
+            };
+            let Some(action) = action else {
+                break;
+            };
+
+            let (measure, reward) = mdp.stochastic_transition(&state, &action)?;
+            let next_state = measure
+                .sample_with(&mut rng)
+                .cloned()
+                .unwrap_or_else(|| state.clone());
+            let next_value = if mdp.is_final_state(&next_state) {
+                0.0
+            } else {
+                max_available(mdp, &action_value, &next_state)
+            };
+            let current_q = action_value.get(&state, &action);
+            let target = reward + config.discount_factor * next_value;
+            let new_q = checked_q_value(current_q + config.learning_rate * (target - current_q))?;
+            action_value.insert(&state, &action, new_q);
+
+            state = next_state;
+        }
+
+        let finished = episode + 1;
+        if checkpointing.interval > 0 && finished % checkpointing.interval == 0 {
+            checkpoint(&action_value, finished)?;
+        }
+    }
+
+    checkpoint(&action_value, num_episodes.max(first_episode))?;
+    Ok(action_value)
+}
@@ -0,0 +1,252 @@
+//! # Combinators
+//!
+//! Ways of building one MDP out of others that are not products: chaining tasks one after another
+//! (states of the pieces are kept apart with `products::Either`) and cutting an MDP down to a
+//! subset of its states.
+
+use std::collections::HashSet;
+
+use indexmap::IndexMap;
+use madepro::models::{Sampler, State};
+
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::measure::{Measure, Probability};
+use crate::products::Either;
+
+/// Maps the state in which the first task was completed to a start state of the second
+type Handover<S1, S2> = Box<dyn Fn(&S1) -> S2>;
+
+/// Runs `M1` until it reaches a goal state (`MDP::is_goal`, i.e. a final state unless the MDP
+/// says otherwise) and then continues in `M2` from the state given by the handover function.
+///
+/// The switch happens on arrival: a transition of `M1` into a goal state `s` lands in
+/// `Right(handover(s))` instead. Final states of `M1` that are not goals still end the episode,
+/// and only goal states of `M2` count as goals of the whole chain. Goal states of `M1` are left
+/// out of `all_states`. Both tasks share one action type.
+pub struct Sequential<M1: MDP, M2: MDP> {
+    mdp1: M1,
+    mdp2: M2,
+    handover: Handover<M1::State, M2::State>,
+    states: Sampler<Either<M1::State, M2::State>>,
+}
+
+impl<M1, M2> Sequential<M1, M2>
+where
+    M1: MDP,
+    M2: MDP,
+    M1::State: Clone,
+    M2::State: Clone,
+{
+    pub fn new(mdp1: M1, mdp2: M2, handover: impl Fn(&M1::State) -> M2::State + 'static) -> Self {
+        let mut states = Vec::new();
+        for s1 in mdp1.all_states().iter() {
+            if !mdp1.is_goal(s1) {
+                states.push(Either::Left(s1.clone()));
+            }
+        }
+        for s2 in mdp2.all_states().iter() {
+            states.push(Either::Right(s2.clone()));
+        }
+
+        Sequential {
+            mdp1,
+            mdp2,
+            handover: Box::new(handover),
+            states: states.into(),
+        }
+    }
+
+    pub fn first(&self) -> &M1 {
+        &self.mdp1
+    }
+
+    pub fn second(&self) -> &M2 {
+        &self.mdp2
+    }
+
+    /// The state of the chain corresponding to a state of the first task
+    pub fn embed_first(&self, state: M1::State) -> Either<M1::State, M2::State> {
+        if self.mdp1.is_goal(&state) {
+            Either::Right((self.handover)(&state))
+        } else {
+            Either::Left(state)
+        }
+    }
+}
+
+impl<M1, M2> MDP for Sequential<M1, M2>
+where
+    M1: MDP,
+    M2: MDP<Action = M1::Action>,
+    M1::State: Clone,
+    M2::State: Clone,
+    M1::Action: Clone,
+{
+    type State = Either<M1::State, M2::State>;
+    type Action = M1::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        &self.states
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        match state {
+            Either::Left(s1) => self.mdp1.actions_at(s1),
+            Either::Right(s2) => self.mdp2.actions_at(s2),
+        }
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        match state {
+            Either::Left(s1) => self.mdp1.is_final_state(s1) && !self.mdp1.is_goal(s1),
+            Either::Right(s2) => self.mdp2.is_final_state(s2),
+        }
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        match state {
+            Either::Left(_) => false,
+            Either::Right(s2) => self.mdp2.is_goal(s2),
+        }
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        match state {
+            Either::Left(s1) => {
+                let (measure, reward) = self.mdp1.stochastic_transition(s1, action)?;
+                let mut dist = IndexMap::new();
+                for (s, p) in measure.dist() {
+                    let next = self.embed_first(s.clone());
+                    let entry = dist.entry(next).or_insert(Probability::ZERO);
+                    *entry = Probability::new((entry.value() + p.value()).min(1.0))?;
+                }
+                Ok((Measure::from_distribution(dist)?, reward))
+            }
+            Either::Right(s2) => {
+                let (measure, reward) = self.mdp2.stochastic_transition(s2, action)?;
+                let dist = measure
+                    .dist()
+                    .iter()
+                    .map(|(s, p)| (Either::Right(s.clone()), *p))
+                    .collect();
+                Ok((Measure::from_distribution(dist)?, reward))
+            }
+        }
+    }
+}
+
+/// A state of a `Restricted` MDP: one of the kept states, or the absorbing state standing in for
+/// everything outside the subset
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub enum RestrictedState<S> {
+    Inside(S),
+    Outside,
+}
+
+impl<S: State> State for RestrictedState<S> {}
+
+/// Limits an MDP to a subset of its states. Probability mass that would leave the subset goes to
+/// the final state `RestrictedState::Outside` instead; rewards are unchanged.
+pub struct Restricted<M: MDP> {
+    mdp: M,
+    subset: HashSet<M::State>,
+    states: Sampler<RestrictedState<M::State>>,
+}
+
+impl<M> Restricted<M>
+where
+    M: MDP,
+    M::State: Clone,
+{
+    /// Keeps the given states, in the order given
+    pub fn new(mdp: M, states: impl IntoIterator<Item = M::State>) -> Self {
+        let mut subset = HashSet::new();
+        let mut kept = Vec::new();
+        for s in states {
+            if subset.insert(s.clone()) {
+                kept.push(RestrictedState::Inside(s));
+            }
+        }
+        kept.push(RestrictedState::Outside);
+
+        Restricted {
+            mdp,
+            subset,
+            states: kept.into(),
+        }
+    }
+
+    pub fn contains(&self, state: &M::State) -> bool {
+        self.subset.contains(state)
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mdp
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mdp
+    }
+}
+
+impl<M> MDP for Restricted<M>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    type State = RestrictedState<M::State>;
+    type Action = M::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        &self.states
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        match state {
+            RestrictedState::Inside(s) => self.mdp.actions_at(s),
+            RestrictedState::Outside => Vec::new(),
+        }
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        match state {
+            RestrictedState::Inside(s) => self.mdp.is_final_state(s),
+            RestrictedState::Outside => true,
+        }
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        match state {
+            RestrictedState::Inside(s) => self.mdp.is_goal(s),
+            RestrictedState::Outside => false,
+        }
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        let RestrictedState::Inside(s) = state else {
+            return Err(Error::UndefinedTransition);
+        };
+        let (measure, reward) = self.mdp.stochastic_transition(s, action)?;
+        let mut dist: IndexMap<Self::State, Probability> = IndexMap::new();
+        for (next, p) in measure.dist() {
+            let next = if self.subset.contains(next) {
+                RestrictedState::Inside(next.clone())
+            } else {
+                RestrictedState::Outside
+            };
+            let entry = dist.entry(next).or_insert(Probability::ZERO);
+            *entry = Probability::new((entry.value() + p.value()).min(1.0))?;
+        }
+        Ok((Measure::from_distribution(dist)?, reward))
+    }
+}
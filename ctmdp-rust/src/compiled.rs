@@ -0,0 +1,218 @@
+//! # Compiled MDPs
+//!
+//! `compile` evaluates every transition of a finite MDP once and stores the result in flat
+//! arrays: for each state its available actions, and for each state-action pair (a *choice*) the
+//! reward and the `(next-state index, probability)` outcomes. A product built from nested
+//! wrappers re-derives its measures through every level on each call, which is wasted work when
+//! the model never changes. `CompiledMDP` implements `MDP` itself, so it can stand in for the
+//! original with any planner or learner, and it also exposes the arrays by index for code that
+//! works with `StateIndex`/`ActionIndex` numbering directly.
+
+use indexmap::IndexMap;
+use madepro::models::{Action, Sampler, State};
+use rand::Rng;
+
+use crate::dense::{ActionIndex, StateIndex};
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::measure::{Measure, Probability};
+
+/// A finite MDP with all transitions evaluated up front. Choices of state `s` are
+/// `choice_offsets[s]..choice_offsets[s + 1]`, and the outcomes of choice `c` are
+/// `outcome_offsets[c]..outcome_offsets[c + 1]`.
+#[derive(Debug, Clone)]
+pub struct CompiledMDP<S, A> {
+    states: StateIndex<S>,
+    actions: ActionIndex<A>,
+    all_states: Sampler<S>,
+    is_final: Vec<bool>,
+    is_goal: Vec<bool>,
+    choice_offsets: Vec<usize>,
+    choice_actions: Vec<usize>,
+    rewards: Vec<f64>,
+    outcome_offsets: Vec<usize>,
+    next_states: Vec<usize>,
+    probabilities: Vec<Probability>,
+}
+
+/// # Compile
+///
+/// Evaluates `stochastic_transition` for every state and every action available in it, in the
+/// order of `all_states()` and `actions_at`. Actions are numbered in the order of
+/// `all_actions()`, followed by any that only `actions_at` reports.
+///
+/// # Arguments
+/// * `mdp` - The finite MDP to compile
+///
+/// # Returns
+/// The compiled model, or `Error::StateNotFound` if a transition can reach a state outside
+/// `all_states()`
+pub fn compile<M>(mdp: &M) -> Result<CompiledMDP<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let states = StateIndex::from_sampler(mdp.all_states());
+    let actions = ActionIndex::new(mdp.all_actions());
+    let mut compiled = CompiledMDP {
+        all_states: Sampler::new(states.items().to_vec()),
+        is_final: states
+            .items()
+            .iter()
+            .map(|s| mdp.is_final_state(s))
+            .collect(),
+        is_goal: states.items().iter().map(|s| mdp.is_goal(s)).collect(),
+        choice_offsets: vec![0],
+        choice_actions: Vec::new(),
+        rewards: Vec::new(),
+        outcome_offsets: vec![0],
+        next_states: Vec::new(),
+        probabilities: Vec::new(),
+        states,
+        actions,
+    };
+
+    let states = compiled.states.items().to_vec();
+    for state in &states {
+        for action in mdp.actions_at(state) {
+            let action_index = compiled.actions.insert(action.clone());
+            let (measure, reward) = mdp.stochastic_transition(state, &action)?;
+            for (next_state, p) in measure.dist() {
+                let next_index = compiled
+                    .states
+                    .index_of(next_state)
+                    .ok_or(Error::StateNotFound)?;
+                compiled.next_states.push(next_index);
+                compiled.probabilities.push(*p);
+            }
+            compiled.choice_actions.push(action_index);
+            compiled.rewards.push(reward);
+            compiled.outcome_offsets.push(compiled.next_states.len());
+        }
+        compiled.choice_offsets.push(compiled.choice_actions.len());
+    }
+    Ok(compiled)
+}
+
+impl<S, A> CompiledMDP<S, A> {
+    pub fn state_index(&self) -> &StateIndex<S> {
+        &self.states
+    }
+
+    pub fn action_index(&self) -> &ActionIndex<A> {
+        &self.actions
+    }
+
+    pub fn num_states(&self) -> usize {
+        self.is_final.len()
+    }
+
+    /// Number of state-action pairs
+    pub fn num_choices(&self) -> usize {
+        self.choice_actions.len()
+    }
+
+    pub fn is_final_index(&self, state: usize) -> bool {
+        self.is_final[state]
+    }
+
+    /// The choices of the state with index `state`, as a range of choice indices
+    pub fn choices(&self, state: usize) -> std::ops::Range<usize> {
+        self.choice_offsets[state]..self.choice_offsets[state + 1]
+    }
+
+    /// The action index of `choice`
+    pub fn choice_action(&self, choice: usize) -> usize {
+        self.choice_actions[choice]
+    }
+
+    pub fn reward(&self, choice: usize) -> f64 {
+        self.rewards[choice]
+    }
+
+    /// The `(next-state index, probability)` outcomes of `choice`
+    pub fn outcomes(&self, choice: usize) -> impl Iterator<Item = (usize, f64)> + '_ {
+        let range = self.outcome_offsets[choice]..self.outcome_offsets[choice + 1];
+        self.next_states[range.clone()]
+            .iter()
+            .copied()
+            .zip(self.probabilities[range].iter().map(|p| p.value()))
+    }
+
+    /// `r + γ Σ_s' p(s') values[s']` for `choice`
+    pub fn backup(&self, choice: usize, values: &[f64], discount_factor: f64) -> f64 {
+        let expected_next: f64 = self.outcomes(choice).map(|(s, p)| p * values[s]).sum();
+        self.rewards[choice] + discount_factor * expected_next
+    }
+
+    /// Samples the index of the next state of `choice` by inverse transform (the last outcome
+    /// absorbs rounding error)
+    pub fn sample_next<R: Rng + ?Sized>(&self, choice: usize, rng: &mut R) -> usize {
+        let range = self.outcome_offsets[choice]..self.outcome_offsets[choice + 1];
+        let mut threshold = rng.random::<f64>();
+        for i in range.clone() {
+            threshold -= self.probabilities[i].value();
+            if threshold < 0.0 {
+                return self.next_states[i];
+            }
+        }
+        self.next_states[range.end - 1]
+    }
+
+    /// The choice of action index `action` in `state`, if it is available there
+    pub fn find_choice(&self, state: usize, action: usize) -> Option<usize> {
+        self.choices(state)
+            .find(|&choice| self.choice_actions[choice] == action)
+    }
+}
+
+impl<S: State, A: Action> MDP for CompiledMDP<S, A> {
+    type State = S;
+    type Action = A;
+
+    fn all_states(&self) -> &Sampler<S> {
+        &self.all_states
+    }
+
+    fn all_actions(&self) -> Vec<A> {
+        self.actions.items().to_vec()
+    }
+
+    fn actions_at(&self, state: &S) -> Vec<A> {
+        let Some(i) = self.states.index_of(state) else {
+            return Vec::new();
+        };
+        self.choices(i)
+            .map(|choice| self.actions.items()[self.choice_actions[choice]].clone())
+            .collect()
+    }
+
+    fn is_final_state(&self, state: &S) -> bool {
+        self.states
+            .index_of(state)
+            .is_some_and(|i| self.is_final[i])
+    }
+
+    fn is_goal(&self, state: &S) -> bool {
+        self.states.index_of(state).is_some_and(|i| self.is_goal[i])
+    }
+
+    fn stochastic_transition(&self, state: &S, action: &A) -> Result<(Measure<S>, f64), Error> {
+        let i = self.states.index_of(state).ok_or(Error::StateNotFound)?;
+        let choice = self
+            .actions
+            .index_of(action)
+            .and_then(|a| self.find_choice(i, a))
+            .ok_or(Error::UndefinedTransition)?;
+        let range = self.outcome_offsets[choice]..self.outcome_offsets[choice + 1];
+        let mut dist = IndexMap::with_capacity(range.len());
+        for k in range {
+            dist.insert(
+                self.states.items()[self.next_states[k]].clone(),
+                self.probabilities[k],
+            );
+        }
+        Ok((Measure::from_distribution(dist)?, self.rewards[choice]))
+    }
+}
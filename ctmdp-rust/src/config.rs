@@ -0,0 +1,98 @@
+//! # Training configuration
+//!
+//! `madepro`'s `Config` covers the hyperparameters shared by every tabular learner. The learners
+//! in this crate need a few more knobs, so `TrainingConfig` wraps the base config and derefs to it:
+//! existing code can keep writing `config.learning_rate = 0.1` unchanged.
+
+use madepro::models::Config;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::ops::{Deref, DerefMut};
+
+#[derive(Clone)]
+pub struct TrainingConfig {
+    base: Config,
+    /// Number of rewards accumulated before bootstrapping in TD targets (`1` is one-step TD).
+    pub n_step: usize,
+    /// Step size for the average-reward estimate in differential (R-)learning.
+    pub average_reward_rate: f64,
+    /// Replayed one-step updates performed after every environment step (`0` disables replay).
+    pub replay_updates: usize,
+    /// Maximum number of transitions kept in the replay buffer.
+    pub replay_capacity: usize,
+    /// Entropy temperature for soft Q-learning and Boltzmann policies.
+    pub temperature: f64,
+    /// Value-iteration stopping threshold for the model-based learners.
+    pub planning_tolerance: f64,
+    /// Maximum number of value-iteration sweeps per planning call.
+    pub max_planning_iterations: usize,
+    /// Visits after which a state-action pair counts as known (R-MAX).
+    pub known_threshold: usize,
+    /// Scale of the count-based exploration bonus `c / sqrt(N(s, a))` (UCB-VI).
+    pub bonus_coefficient: f64,
+    /// Gradient steps taken by MaxEnt inverse reinforcement learning.
+    pub irl_iterations: usize,
+    /// Step size of the Lagrange multiplier updates in constrained (primal-dual) learning.
+    pub multiplier_rate: f64,
+    /// Exponential-utility sensitivity `β` of risk-sensitive Q-learning (`< 0` risk-averse, `0` neutral).
+    pub risk_sensitivity: f64,
+    /// Continuous-time discount rate `β`; sojourn-time learners discount a dwell of length `τ` by `exp(-β τ)`.
+    pub discount_rate: f64,
+    /// Seed of the learners' random number generator; `None` seeds it from the thread RNG.
+    pub seed: Option<u64>,
+}
+
+impl Default for TrainingConfig {
+    fn default() -> Self {
+        TrainingConfig::from(Config::default())
+    }
+}
+
+impl From<Config> for TrainingConfig {
+    fn from(base: Config) -> Self {
+        TrainingConfig {
+            base,
+            n_step: 1,
+            average_reward_rate: 0.01,
+            replay_updates: 0,
+            replay_capacity: 10_000,
+            temperature: 1.0,
+            planning_tolerance: 1e-6,
+            max_planning_iterations: 1_000,
+            known_threshold: 5,
+            bonus_coefficient: 1.0,
+            irl_iterations: 100,
+            multiplier_rate: 0.01,
+            risk_sensitivity: 0.0,
+            discount_rate: 0.1,
+            seed: None,
+        }
+    }
+}
+
+impl TrainingConfig {
+    /// The random number generator a learner draws everything from (start states, exploration,
+    /// next states): seeded with `seed` if set, so runs with the same seed, configuration and
+    /// MDP repeat exactly. Wrappers with an RNG of their own, such as `NoisyReward`, are seeded
+    /// separately.
+    pub fn rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_rng(&mut rand::rng()),
+        }
+    }
+}
+
+impl Deref for TrainingConfig {
+    type Target = Config;
+
+    fn deref(&self) -> &Self::Target {
+        &self.base
+    }
+}
+
+impl DerefMut for TrainingConfig {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.base
+    }
+}
@@ -0,0 +1,294 @@
+//! # Constrained MDPs
+//!
+//! MDPs with cost signals next to the reward, and budgets on the expected discounted cost of an
+//! episode. `ConstrainedMDP` attaches cost functions to any MDP; `lagrangian_q_learning` learns a
+//! policy that maximizes reward subject to the budgets by primal-dual updates.
+
+use madepro::models::{Action, ActionValue, Sampler, State};
+use rand::Rng;
+use rand::seq::IndexedRandom;
+
+use crate::config::TrainingConfig;
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::measure::Measure;
+use crate::policy::DeterministicPolicy;
+use crate::q_learning::{action_union, random_start};
+
+/// Cost of a transition `(s, a, s')`
+type CostFn<S, A> = Box<dyn Fn(&S, &A, &S) -> f64>;
+
+/// An MDP with cost functions and a budget for each. Rewards and dynamics are the wrapped MDP's;
+/// costs are only visible through `costs`/`expected_costs`, so other learners ignore them.
+pub struct ConstrainedMDP<M: MDP> {
+    mdp: M,
+    costs: Vec<CostFn<M::State, M::Action>>,
+    budgets: Vec<f64>,
+}
+
+impl<M: MDP> ConstrainedMDP<M> {
+    /// Starts without constraints
+    pub fn new(mdp: M) -> Self {
+        ConstrainedMDP {
+            mdp,
+            costs: Vec::new(),
+            budgets: Vec::new(),
+        }
+    }
+
+    /// Adds a cost whose expected discounted sum per episode must stay within `budget`
+    pub fn with_cost(
+        mut self,
+        cost: impl Fn(&M::State, &M::Action, &M::State) -> f64 + 'static,
+        budget: f64,
+    ) -> Self {
+        self.costs.push(Box::new(cost));
+        self.budgets.push(budget);
+        self
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.costs.len()
+    }
+
+    pub fn budgets(&self) -> &[f64] {
+        &self.budgets
+    }
+
+    /// Every cost of the transition `(s, a, s')`
+    pub fn costs(&self, state: &M::State, action: &M::Action, next_state: &M::State) -> Vec<f64> {
+        self.costs
+            .iter()
+            .map(|cost| cost(state, action, next_state))
+            .collect()
+    }
+
+    /// Every cost of taking `action` in `state`, in expectation over the next state
+    pub fn expected_costs(&self, state: &M::State, action: &M::Action) -> Result<Vec<f64>, Error> {
+        let (measure, _) = self.mdp.stochastic_transition(state, action)?;
+        Ok(self
+            .costs
+            .iter()
+            .map(|cost| {
+                measure
+                    .dist()
+                    .iter()
+                    .map(|(next, p)| p.value() * cost(state, action, next))
+                    .sum()
+            })
+            .collect())
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mdp
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mdp
+    }
+}
+
+impl<M> MDP for ConstrainedMDP<M>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    type State = M::State;
+    type Action = M::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        self.mdp.all_states()
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        self.mdp.actions_at(state)
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.mdp.is_final_state(state)
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.mdp.is_goal(state)
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        self.mdp.stochastic_transition(state, action)
+    }
+}
+
+/// Reward and cost values learned by `lagrangian_q_learning`, with the final multipliers
+pub struct LagrangianValues<S: State, A: Action> {
+    pub reward_values: ActionValue<S, A>,
+    /// One table per constraint
+    pub cost_values: Vec<ActionValue<S, A>>,
+    /// `λ_k ≥ 0` per constraint
+    pub multipliers: Vec<f64>,
+    /// Discounted cost of every training episode, per constraint
+    pub episode_costs: Vec<Vec<f64>>,
+}
+
+impl<S: State, A: Action> LagrangianValues<S, A> {
+    /// `Q_r(s, a) - Σ_k λ_k Q_k(s, a)`
+    pub fn lagrangian(&self, state: &S, action: &A) -> f64 {
+        self.reward_values.get(state, action)
+            - self
+                .cost_values
+                .iter()
+                .zip(&self.multipliers)
+                .map(|(q, lambda)| lambda * q.get(state, action))
+                .sum::<f64>()
+    }
+
+    /// The available action with the highest Lagrangian value in every non-final state.
+    /// Ties go to the action listed first by `actions_at`.
+    pub fn greedy_policy<M>(&self, mdp: &M) -> DeterministicPolicy<S, A>
+    where
+        M: MDP<State = S, Action = A>,
+    {
+        let mut policy = DeterministicPolicy::new();
+        for state in mdp.all_states().iter() {
+            if mdp.is_final_state(state) {
+                continue;
+            }
+            if let Some(action) = best_action(&mdp.actions_at(state), |a| self.lagrangian(state, a))
+            {
+                policy.insert(state.clone(), action);
+            }
+        }
+        policy
+    }
+}
+
+fn best_action<A: Clone>(actions: &[A], score: impl Fn(&A) -> f64) -> Option<A> {
+    let mut best: Option<(&A, f64)> = None;
+    for action in actions {
+        let value = score(action);
+        if best.is_none_or(|(_, best_value)| value > best_value) {
+            best = Some((action, value));
+        }
+    }
+    best.map(|(a, _)| a.clone())
+}
+
+/// # Lagrangian Q-Learning
+///
+/// Primal-dual Q-learning for constrained MDPs. One Q-table is learned for the reward and one per
+/// cost, all with one-step Q-learning targets that bootstrap from the action maximizing the
+/// Lagrangian `Q_r - Σ_k λ_k Q_k` at the next state. Behavior is epsilon-greedy on the
+/// Lagrangian. After every episode each multiplier takes a projected gradient step on its
+/// constraint violation:
+///
+/// `λ_k ← max(0, λ_k + multiplier_rate · (C_k - budget_k))`
+///
+/// where `C_k` is the episode's discounted cost.
+///
+/// # Arguments
+/// * `cmdp` - The constrained MDP to learn from
+/// * `config` - Configuration parameters (learning rate, discount factor, exploration rate, multiplier rate, etc.)
+///
+/// # Returns
+/// The reward and cost tables, the final multipliers and the per-episode discounted costs
+pub fn lagrangian_q_learning<M>(
+    cmdp: &ConstrainedMDP<M>,
+    config: &TrainingConfig,
+) -> Result<LagrangianValues<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let states = cmdp.all_states();
+    if states.iter().next().is_none() {
+        return Err(Error::EmptyStateSpace);
+    }
+    let actions = action_union(cmdp);
+    let num_constraints = cmdp.num_constraints();
+
+    let mut values = LagrangianValues {
+        reward_values: ActionValue::new(states, &actions),
+        cost_values: (0..num_constraints)
+            .map(|_| ActionValue::new(states, &actions))
+            .collect(),
+        multipliers: vec![0.0; num_constraints],
+        episode_costs: vec![Vec::with_capacity(config.num_episodes as usize); num_constraints],
+    };
+    let mut rng = config.rng();
+
+    for _ in 0..config.num_episodes {
+        let mut state = random_start(states, &mut rng)?.clone();
+        let mut discounted_costs = vec![0.0; num_constraints];
+        let mut discount = 1.0;
+
+        for _ in 0..config.max_num_steps {
+            if cmdp.is_final_state(&state) {
+                break;
+            }
+            let available = cmdp.actions_at(&state);
+            let action = if rng.random::<f64>() < config.exploration_rate {
+                available.choose(&mut rng).cloned()
+            } else {
+                best_action(&available, |a| values.lagrangian(&state, a))
+            };
+            let Some(action) = action else {
+                break;
+            };
+
+            let (measure, reward) = cmdp.stochastic_transition(&state, &action)?;
+            let next_state = measure
+                .sample_with(&mut rng)
+                .cloned()
+                .unwrap_or_else(|| state.clone());
+            let costs = cmdp.costs(&state, &action, &next_state);
+            for (total, cost) in discounted_costs.iter_mut().zip(&costs) {
+                *total += discount * cost;
+            }
+            discount *= config.discount_factor;
+
+            let done = cmdp.is_final_state(&next_state);
+            let next_action = if done {
+                None
+            } else {
+                best_action(&cmdp.actions_at(&next_state), |a| {
+                    values.lagrangian(&next_state, a)
+                })
+            };
+            let bootstrap = |q: &ActionValue<M::State, M::Action>| {
+                next_action.as_ref().map_or(0.0, |a| q.get(&next_state, a))
+            };
+
+            let target = reward + config.discount_factor * bootstrap(&values.reward_values);
+            let current = values.reward_values.get(&state, &action);
+            let updated = current + config.learning_rate * (target - current);
+            values.reward_values.insert(&state, &action, updated);
+            for (q, cost) in values.cost_values.iter_mut().zip(&costs) {
+                let target = cost + config.discount_factor * bootstrap(q);
+                let current = q.get(&state, &action);
+                q.insert(
+                    &state,
+                    &action,
+                    current + config.learning_rate * (target - current),
+                );
+            }
+
+            state = next_state;
+        }
+
+        let constraints = values
+            .multipliers
+            .iter_mut()
+            .zip(&mut values.episode_costs)
+            .zip(cmdp.budgets());
+        for (((lambda, history), budget), cost) in constraints.zip(discounted_costs) {
+            *lambda = (*lambda + config.multiplier_rate * (cost - budget)).max(0.0);
+            history.push(cost);
+        }
+    }
+
+    Ok(values)
+}
@@ -0,0 +1,141 @@
+//! # Constructors
+//!
+//! Generic MDPs assembled from explicit data rather than a bespoke struct per environment.
+
+use std::collections::{HashMap, HashSet};
+
+use madepro::models::{Action, Sampler, State};
+
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::measure::Measure;
+
+/// An MDP given by an explicit table of transition measures and rewards.
+///
+/// Actions available at a state are exactly those with a transition registered for it, in
+/// registration order.
+#[derive(Debug)]
+pub struct TabularMDP<S: State, A: Action> {
+    states: Sampler<S>,
+    actions: HashMap<S, Vec<A>>,
+    transitions: HashMap<(S, A), (Measure<S>, f64)>,
+    final_states: HashSet<S>,
+}
+
+impl<S: State, A: Action> TabularMDP<S, A> {
+    pub fn new(states: Vec<S>) -> Self {
+        TabularMDP {
+            states: states.into(),
+            actions: HashMap::new(),
+            transitions: HashMap::new(),
+            final_states: HashSet::new(),
+        }
+    }
+
+    /// Registers (or replaces) the outcome of taking `action` in `state`
+    pub fn add_transition(&mut self, state: S, action: A, measure: Measure<S>, reward: f64) {
+        let key = (state.clone(), action.clone());
+        if !self.transitions.contains_key(&key) {
+            self.actions.entry(state).or_default().push(action);
+        }
+        self.transitions.insert(key, (measure, reward));
+    }
+
+    pub fn set_final(&mut self, state: S) {
+        self.final_states.insert(state);
+    }
+
+    /// Marks every state satisfying `is_final` as final
+    pub fn with_final(mut self, is_final: impl Fn(&S) -> bool) -> Self {
+        let finals: Vec<S> = self.states.iter().filter(|s| is_final(s)).cloned().collect();
+        self.final_states.extend(finals);
+        self
+    }
+
+    /// # From a Function
+    ///
+    /// Tabulates `transition` on every state and action: `Some((measure, reward))` registers the
+    /// outcome, `None` leaves the action unavailable in that state. Actions are available in the
+    /// order given. No state is final until marked with `set_final` or `with_final`.
+    ///
+    /// # Arguments
+    /// * `states` - The state space
+    /// * `actions` - Every action that may be available somewhere
+    /// * `transition` - The outcome of an action in a state, if it is available there
+    pub fn from_fn(
+        states: Vec<S>,
+        actions: Vec<A>,
+        transition: impl Fn(&S, &A) -> Option<(Measure<S>, f64)>,
+    ) -> Self {
+        let mut mdp = TabularMDP::new(states.clone());
+        for state in &states {
+            for action in &actions {
+                if let Some((measure, reward)) = transition(state, action) {
+                    mdp.add_transition(state.clone(), action.clone(), measure, reward);
+                }
+            }
+        }
+        mdp
+    }
+
+    /// # From Tables
+    ///
+    /// Builds the model from a transition table mapping `(state, action)` to `(next state,
+    /// probability)` lists and a reward table over the same pairs (pairs without a reward earn
+    /// zero). Actions are available in the order they appear in `transitions`.
+    ///
+    /// Fails with `Error::StateNotFound` if a table mentions a state outside `states`, with
+    /// `Error::UndefinedTransition` if a reward is given for a pair without transitions, and
+    /// with `Error::InvalidMeasure` or `Error::ProbabilityOutOfRange` if a list is not a
+    /// distribution.
+    pub fn from_tables(
+        states: Vec<S>,
+        transitions: impl IntoIterator<Item = ((S, A), Vec<(S, f64)>)>,
+        rewards: impl IntoIterator<Item = ((S, A), f64)>,
+    ) -> Result<Self, Error> {
+        let known: HashSet<S> = states.iter().cloned().collect();
+        let mut mdp = TabularMDP::new(states);
+        for ((state, action), outcomes) in transitions {
+            if !known.contains(&state) || outcomes.iter().any(|(s, _)| !known.contains(s)) {
+                return Err(Error::StateNotFound);
+            }
+            mdp.add_transition(state, action, Measure::categorical(outcomes)?, 0.0);
+        }
+        for (key, reward) in rewards {
+            let outcome = mdp
+                .transitions
+                .get_mut(&key)
+                .ok_or(Error::UndefinedTransition)?;
+            outcome.1 = reward;
+        }
+        Ok(mdp)
+    }
+}
+
+impl<S: State, A: Action> MDP for TabularMDP<S, A> {
+    type State = S;
+    type Action = A;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        &self.states
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        self.actions.get(state).cloned().unwrap_or_default()
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.final_states.contains(state)
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        self.transitions
+            .get(&(state.clone(), action.clone()))
+            .cloned()
+            .ok_or(Error::UndefinedTransition)
+    }
+}
@@ -0,0 +1,115 @@
+//! # Visit Counts
+//!
+//! Per state-action statistics of observed experience: visit counts, next-state counts and reward
+//! sums. This is the "known state" bookkeeping shared by the model-based learners and by
+//! count-based exploration bonuses.
+
+use std::collections::HashMap;
+
+use madepro::models::{Action, State};
+
+use crate::error::Error;
+use crate::measure::{Measure, MeasureBuilder};
+
+#[derive(Debug, Clone)]
+struct PairStatistics<S> {
+    visits: usize,
+    reward_sum: f64,
+    next_states: HashMap<S, usize>,
+}
+
+impl<S> Default for PairStatistics<S> {
+    fn default() -> Self {
+        PairStatistics {
+            visits: 0,
+            reward_sum: 0.0,
+            next_states: HashMap::new(),
+        }
+    }
+}
+
+/// Observed transition statistics keyed by state-action pair
+#[derive(Debug, Clone)]
+pub struct VisitCounts<S, A> {
+    pairs: HashMap<(S, A), PairStatistics<S>>,
+}
+
+impl<S, A> Default for VisitCounts<S, A> {
+    fn default() -> Self {
+        VisitCounts {
+            pairs: HashMap::new(),
+        }
+    }
+}
+
+impl<S: State, A: Action> VisitCounts<S, A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records one observed transition `(s, a, r, s')` and returns the new visit count of `(s, a)`
+    pub fn record(&mut self, state: &S, action: &A, reward: f64, next_state: &S) -> usize {
+        let stats = self
+            .pairs
+            .entry((state.clone(), action.clone()))
+            .or_default();
+        stats.visits += 1;
+        stats.reward_sum += reward;
+        *stats.next_states.entry(next_state.clone()).or_insert(0) += 1;
+        stats.visits
+    }
+
+    pub fn visits(&self, state: &S, action: &A) -> usize {
+        self.get(state, action).map_or(0, |stats| stats.visits)
+    }
+
+    pub fn reward_sum(&self, state: &S, action: &A) -> f64 {
+        self.get(state, action).map_or(0.0, |stats| stats.reward_sum)
+    }
+
+    /// Average observed reward, or `None` if the pair was never visited
+    pub fn mean_reward(&self, state: &S, action: &A) -> Option<f64> {
+        self.get(state, action)
+            .filter(|stats| stats.visits > 0)
+            .map(|stats| stats.reward_sum / stats.visits as f64)
+    }
+
+    /// The largest average reward over all visited pairs, or `None` if nothing was recorded
+    pub fn max_mean_reward(&self) -> Option<f64> {
+        self.pairs
+            .values()
+            .filter(|stats| stats.visits > 0)
+            .map(|stats| stats.reward_sum / stats.visits as f64)
+            .reduce(f64::max)
+    }
+
+    /// How often each next state followed `(s, a)`
+    pub fn next_state_counts(&self, state: &S, action: &A) -> Option<&HashMap<S, usize>> {
+        self.get(state, action).map(|stats| &stats.next_states)
+    }
+
+    /// Whether `(s, a)` has been visited at least `threshold` times
+    pub fn is_known(&self, state: &S, action: &A, threshold: usize) -> bool {
+        self.visits(state, action) >= threshold
+    }
+
+    /// The maximum-likelihood transition measure, or `None` if the pair was never visited
+    pub fn empirical_measure(&self, state: &S, action: &A) -> Option<Result<Measure<S>, Error>> {
+        let stats = self.get(state, action).filter(|stats| stats.visits > 0)?;
+        let counts: MeasureBuilder<S> = stats
+            .next_states
+            .iter()
+            .map(|(s, n)| (s.clone(), *n as f64))
+            .collect();
+        Some(counts.normalize())
+    }
+
+    /// Total number of recorded transitions
+    pub fn total_visits(&self) -> usize {
+        self.pairs.values().map(|stats| stats.visits).sum()
+    }
+
+    fn get(&self, state: &S, action: &A) -> Option<&PairStatistics<S>> {
+        self.pairs.get(&(state.clone(), action.clone()))
+    }
+}
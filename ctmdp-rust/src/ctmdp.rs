@@ -0,0 +1,444 @@
+//! # Continuous-Time MDPs
+//!
+//! In a continuous-time MDP the chosen action sets transition *rates* rather than probabilities:
+//! from `s` under `a` the process jumps to `s'` at rate `q(s' | s, a)`, so it stays in `s` for an
+//! exponentially distributed sojourn time with mean `1 / E(s, a)`, where the exit rate
+//! `E(s, a) = Σ_s' q(s' | s, a)`, and then jumps to `s'` with probability `q(s' | s, a) / E(s, a)`.
+//! Rewards accrue at a rate per unit of time spent in a state.
+//!
+//! `CTMDP` mirrors the `MDP` trait with `rates` in place of `stochastic_transition`. `TabularCTMDP`
+//! is the explicit-table model and `CtEnvironment` simulates sojourn times and dwell rewards.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use madepro::models::{Action, Sampler, State};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::error::Error;
+use crate::measure::{Measure, MeasureBuilder};
+
+/// Transition rates to next states. Rates are finite and non-negative; zero rates are dropped.
+#[derive(Debug, Clone)]
+pub struct RateMeasure<T> {
+    rates: HashMap<T, f64>,
+}
+
+impl<T: Eq + Hash> RateMeasure<T> {
+    /// Fails with `Error::NumericalError` on a negative or non-finite rate
+    pub fn new(rates: HashMap<T, f64>) -> Result<Self, Error> {
+        if let Some(rate) = rates.values().find(|r| !r.is_finite() || **r < 0.0) {
+            return Err(Error::NumericalError(format!(
+                "invalid transition rate {rate}"
+            )));
+        }
+        Ok(RateMeasure {
+            rates: rates.into_iter().filter(|(_, r)| *r > 0.0).collect(),
+        })
+    }
+
+    /// No outgoing transitions: the state is absorbing under this action
+    pub fn absorbing() -> Self {
+        RateMeasure {
+            rates: HashMap::new(),
+        }
+    }
+
+    pub fn rates(&self) -> &HashMap<T, f64> {
+        &self.rates
+    }
+
+    pub fn get_rate(&self, target: &T) -> f64 {
+        self.rates.get(target).copied().unwrap_or(0.0)
+    }
+
+    /// `E = Σ_s' q(s')`, the rate of leaving the current state
+    pub fn exit_rate(&self) -> f64 {
+        self.rates.values().sum()
+    }
+
+    pub fn is_absorbing(&self) -> bool {
+        self.rates.is_empty()
+    }
+
+    /// The jump distribution `q(s') / E`. Fails with `Error::InvalidMeasure` if there is no
+    /// outgoing rate.
+    pub fn jump_measure(&self) -> Result<Measure<T>, Error>
+    where
+        T: Clone,
+    {
+        let exit = self.exit_rate();
+        if exit <= 0.0 {
+            return Err(Error::InvalidMeasure);
+        }
+        self.rates
+            .iter()
+            .map(|(s, r)| (s.clone(), *r))
+            .collect::<MeasureBuilder<T>>()
+            .normalize()
+    }
+}
+
+/// A continuous-time Markov decision process.
+///
+/// As for `MDP`, `all_actions` and `actions_at` default to each other; implement one of them.
+pub trait CTMDP {
+    type State: State;
+    type Action: Action;
+
+    fn all_states(&self) -> &Sampler<Self::State>;
+
+    /// Every action available in some state, without duplicates
+    fn all_actions(&self) -> Vec<Self::Action> {
+        let mut seen = HashSet::new();
+        self.all_states()
+            .iter()
+            .flat_map(|s| self.actions_at(s))
+            .filter(|a| seen.insert(a.clone()))
+            .collect()
+    }
+
+    fn actions_at(&self, _state: &Self::State) -> Vec<Self::Action> {
+        self.all_actions()
+    }
+
+    fn is_final_state(&self, st: &Self::State) -> bool;
+
+    fn is_goal(&self, st: &Self::State) -> bool {
+        Self::is_final_state(self, st)
+    }
+
+    /// The transition rates out of `state` under `action`
+    fn rates(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<RateMeasure<Self::State>, Error>;
+
+    /// Reward earned per unit of time spent in `state` while `action` is chosen
+    fn reward_rate(&self, _state: &Self::State, _action: &Self::Action) -> f64 {
+        0.0
+    }
+
+    fn exit_rate(&self, state: &Self::State, action: &Self::Action) -> Result<f64, Error> {
+        Ok(self.rates(state, action)?.exit_rate())
+    }
+}
+
+impl<M: CTMDP + ?Sized> CTMDP for &M {
+    type State = M::State;
+    type Action = M::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        (**self).all_states()
+    }
+
+    fn all_actions(&self) -> Vec<Self::Action> {
+        (**self).all_actions()
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        (**self).actions_at(state)
+    }
+
+    fn is_final_state(&self, st: &Self::State) -> bool {
+        (**self).is_final_state(st)
+    }
+
+    fn is_goal(&self, st: &Self::State) -> bool {
+        (**self).is_goal(st)
+    }
+
+    fn rates(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<RateMeasure<Self::State>, Error> {
+        (**self).rates(state, action)
+    }
+
+    fn reward_rate(&self, state: &Self::State, action: &Self::Action) -> f64 {
+        (**self).reward_rate(state, action)
+    }
+
+    fn exit_rate(&self, state: &Self::State, action: &Self::Action) -> Result<f64, Error> {
+        (**self).exit_rate(state, action)
+    }
+}
+
+/// Draws a sojourn time `τ ~ Exp(exit_rate)` by inversion; infinite for a zero exit rate
+pub fn sample_sojourn<R: Rng + ?Sized>(exit_rate: f64, rng: &mut R) -> f64 {
+    if exit_rate <= 0.0 {
+        return f64::INFINITY;
+    }
+    let u: f64 = rng.random();
+    -(1.0 - u).ln() / exit_rate
+}
+
+/// Reward accumulated at `reward_rate` over a dwell of length `sojourn`, discounted continuously
+/// at `discount_rate` (`∫_0^τ e^{-βt} ρ dt`; `ρ τ` for `β = 0`)
+pub fn dwell_reward(reward_rate: f64, sojourn: f64, discount_rate: f64) -> f64 {
+    if reward_rate == 0.0 {
+        return 0.0;
+    }
+    if discount_rate > 0.0 {
+        reward_rate * (1.0 - (-discount_rate * sojourn).exp()) / discount_rate
+    } else {
+        reward_rate * sojourn
+    }
+}
+
+/// Expected dwell reward before the next jump, `ρ / (E + β)`
+pub fn expected_dwell_reward(reward_rate: f64, exit_rate: f64, discount_rate: f64) -> f64 {
+    if reward_rate == 0.0 {
+        return 0.0;
+    }
+    reward_rate / (exit_rate + discount_rate)
+}
+
+/// A CTMDP given by an explicit table of rates and reward rates.
+///
+/// Actions available at a state are exactly those with rates registered for it, in registration
+/// order.
+#[derive(Debug)]
+pub struct TabularCTMDP<S: State, A: Action> {
+    states: Sampler<S>,
+    actions: HashMap<S, Vec<A>>,
+    transitions: HashMap<(S, A), (RateMeasure<S>, f64)>,
+    final_states: HashSet<S>,
+}
+
+impl<S: State, A: Action> TabularCTMDP<S, A> {
+    pub fn new(states: Vec<S>) -> Self {
+        TabularCTMDP {
+            states: states.into(),
+            actions: HashMap::new(),
+            transitions: HashMap::new(),
+            final_states: HashSet::new(),
+        }
+    }
+
+    /// Registers (or replaces) the rates and reward rate of taking `action` in `state`
+    pub fn add_transition(&mut self, state: S, action: A, rates: RateMeasure<S>, reward_rate: f64) {
+        let key = (state.clone(), action.clone());
+        if !self.transitions.contains_key(&key) {
+            self.actions.entry(state).or_default().push(action);
+        }
+        self.transitions.insert(key, (rates, reward_rate));
+    }
+
+    pub fn set_final(&mut self, state: S) {
+        self.final_states.insert(state);
+    }
+}
+
+impl<S: State, A: Action> CTMDP for TabularCTMDP<S, A> {
+    type State = S;
+    type Action = A;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        &self.states
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        self.actions.get(state).cloned().unwrap_or_default()
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.final_states.contains(state)
+    }
+
+    fn rates(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<RateMeasure<Self::State>, Error> {
+        self.transitions
+            .get(&(state.clone(), action.clone()))
+            .map(|(rates, _)| rates.clone())
+            .ok_or(Error::UndefinedTransition)
+    }
+
+    fn reward_rate(&self, state: &Self::State, action: &Self::Action) -> f64 {
+        self.transitions
+            .get(&(state.clone(), action.clone()))
+            .map_or(0.0, |(_, reward_rate)| *reward_rate)
+    }
+}
+
+/// The outcome of one `CtEnvironment::step`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedStepResult<S> {
+    pub next_state: S,
+    /// Reward accumulated over the dwell, `reward_rate * sojourn`
+    pub reward: f64,
+    /// Time spent in the state before the jump (cut off at the time limit)
+    pub sojourn: f64,
+    /// Elapsed time in the episode after the step
+    pub time: f64,
+    /// `next_state` is final, or the state was absorbing under the action
+    pub terminated: bool,
+    /// The time limit was reached before a final state
+    pub truncated: bool,
+}
+
+impl<S> TimedStepResult<S> {
+    /// The episode is over, either way
+    pub fn done(&self) -> bool {
+        self.terminated || self.truncated
+    }
+}
+
+/// Simulates a CTMDP one jump at a time, like `Environment` does for an `MDP`.
+///
+/// Each step samples an exponential sojourn time from the exit rate of the chosen action, pays
+/// the reward rate over it and then jumps according to the rates. An optional time limit
+/// truncates the episode, paying only the part of the dwell before the limit.
+pub struct CtEnvironment<M: CTMDP> {
+    ctmdp: M,
+    start: Option<M::State>,
+    time_limit: Option<f64>,
+    state: M::State,
+    time: f64,
+    rng: StdRng,
+}
+
+impl<M> CtEnvironment<M>
+where
+    M: CTMDP,
+    M::State: Clone,
+{
+    /// Fails with `Error::EmptyStateSpace` if there is no state to start from
+    pub fn new(ctmdp: M) -> Result<Self, Error> {
+        Self::with_rng(ctmdp, StdRng::from_rng(&mut rand::rng()))
+    }
+
+    pub fn with_seed(ctmdp: M, seed: u64) -> Result<Self, Error> {
+        Self::with_rng(ctmdp, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(ctmdp: M, mut rng: StdRng) -> Result<Self, Error> {
+        let state = random_state(&ctmdp, &mut rng)?;
+        Ok(CtEnvironment {
+            ctmdp,
+            start: None,
+            time_limit: None,
+            state,
+            time: 0.0,
+            rng,
+        })
+    }
+
+    /// Starts every episode from `state` instead of a random one
+    pub fn with_start_state(mut self, state: M::State) -> Self {
+        self.state = state.clone();
+        self.start = Some(state);
+        self
+    }
+
+    /// Truncates episodes once `time_limit` units of time have elapsed
+    pub fn with_time_limit(mut self, time_limit: f64) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    /// Starts a new episode and returns its first state
+    pub fn reset(&mut self) -> M::State {
+        if let Some(start) = &self.start {
+            self.state = start.clone();
+        } else if let Ok(state) = random_state(&self.ctmdp, &mut self.rng) {
+            self.state = state;
+        }
+        self.time = 0.0;
+        self.state.clone()
+    }
+
+    /// Dwells in the current state under `action` and jumps
+    pub fn step(&mut self, action: &M::Action) -> Result<TimedStepResult<M::State>, Error> {
+        let rates = self.ctmdp.rates(&self.state, action)?;
+        let reward_rate = self.ctmdp.reward_rate(&self.state, action);
+        let mut sojourn = sample_sojourn(rates.exit_rate(), &mut self.rng);
+
+        let remaining = self.time_limit.map(|limit| (limit - self.time).max(0.0));
+        if let Some(remaining) = remaining.filter(|r| sojourn >= *r) {
+            sojourn = remaining;
+            self.time += sojourn;
+            return Ok(TimedStepResult {
+                next_state: self.state.clone(),
+                reward: dwell_reward(reward_rate, sojourn, 0.0),
+                sojourn,
+                time: self.time,
+                terminated: false,
+                truncated: true,
+            });
+        }
+        if rates.is_absorbing() {
+            // Without a time limit an absorbing state is never left
+            return Ok(TimedStepResult {
+                next_state: self.state.clone(),
+                reward: dwell_reward(reward_rate, sojourn, 0.0),
+                sojourn,
+                time: f64::INFINITY,
+                terminated: true,
+                truncated: false,
+            });
+        }
+
+        let next_state = rates
+            .jump_measure()?
+            .sample_with(&mut self.rng)
+            .cloned()
+            .unwrap_or_else(|| self.state.clone());
+        self.time += sojourn;
+        self.state = next_state.clone();
+        Ok(TimedStepResult {
+            terminated: self.ctmdp.is_final_state(&next_state),
+            next_state,
+            reward: dwell_reward(reward_rate, sojourn, 0.0),
+            sojourn,
+            time: self.time,
+            truncated: false,
+        })
+    }
+
+    pub fn state(&self) -> &M::State {
+        &self.state
+    }
+
+    /// Time elapsed in the current episode
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// The actions available in the current state
+    pub fn available_actions(&self) -> Vec<M::Action> {
+        self.ctmdp.actions_at(&self.state)
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.ctmdp
+    }
+
+    pub fn into_inner(self) -> M {
+        self.ctmdp
+    }
+}
+
+fn random_state<M>(ctmdp: &M, rng: &mut StdRng) -> Result<M::State, Error>
+where
+    M: CTMDP,
+    M::State: Clone,
+{
+    let num_states = ctmdp.all_states().iter().count();
+    if num_states == 0 {
+        return Err(Error::EmptyStateSpace);
+    }
+    let index = rng.random_range(0..num_states);
+    ctmdp
+        .all_states()
+        .iter()
+        .nth(index)
+        .cloned()
+        .ok_or(Error::EmptyStateSpace)
+}
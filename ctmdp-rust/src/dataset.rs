@@ -0,0 +1,228 @@
+//! # Trajectories and Datasets
+//!
+//! Logged episodes for offline learning, imitation and reporting. A `Trajectory` is the ordered
+//! `(s, a, r)` steps of one episode together with the state it ended in and whether that state
+//! was final; a `Dataset` is a collection of them.
+
+#[cfg(feature = "serde")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::env::Environment;
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::policy::Policy;
+use crate::replay::Transition;
+
+/// One `(s, a, r)` step of a trajectory
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Step<S, A> {
+    pub state: S,
+    pub action: A,
+    pub reward: f64,
+}
+
+/// The steps of one episode, in order
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Trajectory<S, A> {
+    pub steps: Vec<Step<S, A>>,
+    /// The state reached after the last step (the start state if there are no steps)
+    pub last_state: S,
+    /// `last_state` is final; otherwise the episode was truncated
+    pub terminal: bool,
+}
+
+impl<S, A> Trajectory<S, A> {
+    /// An empty trajectory starting in `state`
+    pub fn new(state: S) -> Self {
+        Trajectory {
+            steps: Vec::new(),
+            last_state: state,
+            terminal: false,
+        }
+    }
+
+    /// Records taking `action` in the current last state
+    pub fn push(&mut self, action: A, reward: f64, next_state: S, terminal: bool) {
+        let state = std::mem::replace(&mut self.last_state, next_state);
+        self.steps.push(Step {
+            state,
+            action,
+            reward,
+        });
+        self.terminal = terminal;
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn first_state(&self) -> &S {
+        self.steps
+            .first()
+            .map_or(&self.last_state, |step| &step.state)
+    }
+
+    /// `Σ_t γ^t r_t`
+    pub fn discounted_return(&self, discount_factor: f64) -> f64 {
+        self.steps
+            .iter()
+            .rev()
+            .fold(0.0, |acc, step| step.reward + discount_factor * acc)
+    }
+
+    pub fn total_reward(&self) -> f64 {
+        self.steps.iter().map(|step| step.reward).sum()
+    }
+}
+
+impl<S: Clone, A: Clone> Trajectory<S, A> {
+    /// The `(s, a, r, s', done)` transitions of the trajectory; only the last one can be done
+    pub fn transitions(&self) -> Vec<Transition<S, A>> {
+        let next_states = self
+            .steps
+            .iter()
+            .skip(1)
+            .map(|step| &step.state)
+            .chain(std::iter::once(&self.last_state));
+        let num_steps = self.steps.len();
+        self.steps
+            .iter()
+            .zip(next_states)
+            .enumerate()
+            .map(|(t, (step, next_state))| Transition {
+                state: step.state.clone(),
+                action: step.action.clone(),
+                reward: step.reward,
+                next_state: next_state.clone(),
+                done: self.terminal && t + 1 == num_steps,
+            })
+            .collect()
+    }
+}
+
+/// A collection of trajectories
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Dataset<S, A> {
+    pub trajectories: Vec<Trajectory<S, A>>,
+}
+
+impl<S, A> Default for Dataset<S, A> {
+    fn default() -> Self {
+        Dataset {
+            trajectories: Vec::new(),
+        }
+    }
+}
+
+impl<S, A> Dataset<S, A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, trajectory: Trajectory<S, A>) {
+        self.trajectories.push(trajectory);
+    }
+
+    /// Number of trajectories
+    pub fn len(&self) -> usize {
+        self.trajectories.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trajectories.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Trajectory<S, A>> {
+        self.trajectories.iter()
+    }
+
+    pub fn num_steps(&self) -> usize {
+        self.trajectories.iter().map(Trajectory::len).sum()
+    }
+
+    /// The discounted return of each trajectory
+    pub fn returns(&self, discount_factor: f64) -> Vec<f64> {
+        self.trajectories
+            .iter()
+            .map(|trajectory| trajectory.discounted_return(discount_factor))
+            .collect()
+    }
+
+    pub fn mean_return(&self, discount_factor: f64) -> Option<f64> {
+        if self.trajectories.is_empty() {
+            return None;
+        }
+        Some(self.returns(discount_factor).iter().sum::<f64>() / self.trajectories.len() as f64)
+    }
+}
+
+impl<S: Clone, A: Clone> Dataset<S, A> {
+    /// Every transition of every trajectory
+    pub fn transitions(&self) -> Vec<Transition<S, A>> {
+        self.trajectories
+            .iter()
+            .flat_map(Trajectory::transitions)
+            .collect()
+    }
+
+    /// # Collect
+    ///
+    /// Rolls out `policy` in `env` for `num_episodes` episodes. An episode ends when the
+    /// environment reports it done, after `max_steps` steps, or when the policy has no action
+    /// for the current state.
+    ///
+    /// # Returns
+    ///
+    /// The collected dataset, or the first transition error.
+    pub fn collect<M, P>(
+        env: &mut Environment<M>,
+        policy: &P,
+        num_episodes: usize,
+        max_steps: usize,
+    ) -> Result<Self, Error>
+    where
+        M: MDP<State = S, Action = A>,
+        P: Policy<S, A>,
+    {
+        let mut dataset = Dataset::new();
+        for _ in 0..num_episodes {
+            let mut trajectory = Trajectory::new(env.reset());
+            for _ in 0..max_steps {
+                let Some(action) = policy.action(&trajectory.last_state) else {
+                    break;
+                };
+                let step = env.step(&action)?;
+                let done = step.done();
+                trajectory.push(action, step.reward, step.next_state, step.terminated);
+                if done {
+                    break;
+                }
+            }
+            dataset.push(trajectory);
+        }
+        Ok(dataset)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<S: Serialize, A: Serialize> Dataset<S, A> {
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string(self).map_err(|e| Error::Serialization(e.to_string()))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<S: DeserializeOwned, A: DeserializeOwned> Dataset<S, A> {
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str(json).map_err(|e| Error::Serialization(e.to_string()))
+    }
+}
@@ -0,0 +1,273 @@
+//! # Dense Q-Tables
+//!
+//! Index-based storage for tabular learners. `StateIndex` and `ActionIndex` number the states and
+//! actions of a finite MDP contiguously, and `DenseQTable` keeps one value per pair in a flat
+//! array. An update then costs a single hash (of the sampled next state) instead of several hashes
+//! of keys that, in the product experiments, are deeply nested `Product<Product<...>>` values.
+//! `q_learning` and `sarsa` learn on a `DenseQTable` and hand back an `ActionValue` at the end.
+//!
+//! `ActionCache` does the same for `actions_at`, which allocates a fresh `Vec` on every call and,
+//! for products, concatenates and clones the component actions: it asks once per state and keeps
+//! the answer by state index.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use madepro::models::{Action, ActionValue, Sampler, State};
+use rand::Rng;
+
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::q_learning::checked_q_value;
+use crate::replay::Transition;
+
+/// Numbers distinct items `0..len()` in the order they were first seen
+#[derive(Debug, Clone)]
+pub struct ContiguousIndex<T> {
+    items: Vec<T>,
+    positions: HashMap<T, usize>,
+}
+
+/// Contiguous indices of the states of an MDP
+pub type StateIndex<S> = ContiguousIndex<S>;
+
+/// Contiguous indices of the actions of an MDP
+pub type ActionIndex<A> = ContiguousIndex<A>;
+
+impl<T: Eq + Hash + Clone> ContiguousIndex<T> {
+    /// Indexes `items`; repeated items keep the index of their first occurrence
+    pub fn new(items: impl IntoIterator<Item = T>) -> Self {
+        let mut index = ContiguousIndex {
+            items: Vec::new(),
+            positions: HashMap::new(),
+        };
+        for item in items {
+            index.insert(item);
+        }
+        index
+    }
+
+    /// The index of `item`, appending it first if it is new
+    pub fn insert(&mut self, item: T) -> usize {
+        if let Some(&index) = self.positions.get(&item) {
+            return index;
+        }
+        self.positions.insert(item.clone(), self.items.len());
+        self.items.push(item);
+        self.items.len() - 1
+    }
+
+    /// Indexes the items of a `Sampler` (e.g. `mdp.all_states()`)
+    pub fn from_sampler(sampler: &Sampler<T>) -> Self {
+        Self::new(sampler.iter().cloned())
+    }
+
+    pub fn index_of(&self, item: &T) -> Option<usize> {
+        self.positions.get(item).copied()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.items.get(index)
+    }
+
+    /// All items, item `i` at position `i`
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+impl<T: Eq + Hash + Clone> FromIterator<T> for ContiguousIndex<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::new(iter)
+    }
+}
+
+/// Q-values of `num_states × num_actions` index pairs in one row-major array, initialized to zero
+#[derive(Debug, Clone, PartialEq)]
+pub struct DenseQTable {
+    num_actions: usize,
+    values: Vec<f64>,
+}
+
+impl DenseQTable {
+    pub fn new(num_states: usize, num_actions: usize) -> Self {
+        DenseQTable {
+            num_actions,
+            values: vec![0.0; num_states * num_actions],
+        }
+    }
+
+    pub fn num_states(&self) -> usize {
+        self.values.len().checked_div(self.num_actions).unwrap_or(0)
+    }
+
+    pub fn num_actions(&self) -> usize {
+        self.num_actions
+    }
+
+    pub fn value(&self, state: usize, action: usize) -> f64 {
+        self.values[state * self.num_actions + action]
+    }
+
+    pub fn set_value(&mut self, state: usize, action: usize, value: f64) {
+        self.values[state * self.num_actions + action] = value;
+    }
+
+    /// The values of every action in `state`, in action-index order
+    pub fn row(&self, state: usize) -> &[f64] {
+        &self.values[state * self.num_actions..(state + 1) * self.num_actions]
+    }
+
+    /// The action among `actions` with the highest value in `state` (the first listed among
+    /// ties), or `None` if `actions` is empty
+    pub fn greedy(&self, state: usize, actions: &[usize]) -> Option<usize> {
+        let row = self.row(state);
+        let (&first, rest) = actions.split_first()?;
+        let mut best = first;
+        for &action in rest {
+            if row[action] > row[best] {
+                best = action;
+            }
+        }
+        Some(best)
+    }
+
+    /// `max_a Q(state, a)`
+    pub fn max_value(&self, state: usize) -> f64 {
+        self.row(state)
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    /// A uniformly random action among `actions` with probability `exploration_rate`, the greedy
+    /// one otherwise; `None` if `actions` is empty
+    pub fn epsilon_greedy<R: Rng + ?Sized>(
+        &self,
+        state: usize,
+        actions: &[usize],
+        exploration_rate: f64,
+        rng: &mut R,
+    ) -> Option<usize> {
+        if actions.is_empty() {
+            None
+        } else if rng.random::<f64>() < exploration_rate {
+            Some(actions[rng.random_range(0..actions.len())])
+        } else {
+            self.greedy(state, actions)
+        }
+    }
+
+    /// Applies one-step Q-Learning updates for a batch of replayed index transitions. Fails with
+    /// `Error::NumericalError` if an update produces a NaN or infinite value.
+    pub fn replay(
+        &mut self,
+        batch: &[&Transition<usize, usize>],
+        learning_rate: f64,
+        discount_factor: f64,
+    ) -> Result<(), Error> {
+        for transition in batch {
+            let bootstrap = if transition.done {
+                0.0
+            } else {
+                self.max_value(transition.next_state)
+            };
+            let target = transition.reward + discount_factor * bootstrap;
+            let current_q = self.value(transition.state, transition.action);
+            let new_q = checked_q_value(current_q + learning_rate * (target - current_q))?;
+            self.set_value(transition.state, transition.action, new_q);
+        }
+        Ok(())
+    }
+
+    /// Copies the values into an `ActionValue` keyed by the indexed states and actions
+    pub fn to_action_value<S, A>(
+        &self,
+        states: &StateIndex<S>,
+        actions: &ActionIndex<A>,
+    ) -> ActionValue<S, A>
+    where
+        S: State,
+        A: Action,
+    {
+        let mut action_value = ActionValue::new(
+            &Sampler::new(states.items().to_vec()),
+            &Sampler::new(actions.items().to_vec()),
+        );
+        for (i, state) in states.items().iter().enumerate() {
+            for (j, action) in actions.items().iter().enumerate() {
+                action_value.insert(state, action, self.value(i, j));
+            }
+        }
+        action_value
+    }
+
+    /// Reads the values of the indexed pairs out of an `ActionValue` that covers all of them
+    pub fn from_action_value<S, A>(
+        action_value: &ActionValue<S, A>,
+        states: &StateIndex<S>,
+        actions: &ActionIndex<A>,
+    ) -> Self
+    where
+        S: State,
+        A: Action,
+    {
+        let mut table = DenseQTable::new(states.len(), actions.len());
+        for (i, state) in states.items().iter().enumerate() {
+            for (j, action) in actions.items().iter().enumerate() {
+                table.set_value(i, j, action_value.get(state, action));
+            }
+        }
+        table
+    }
+}
+
+/// The actions available in every indexed state, memoized by state index
+#[derive(Debug, Clone)]
+pub struct ActionCache<A> {
+    actions: Vec<Vec<A>>,
+    indices: Vec<Vec<usize>>,
+}
+
+impl<A: Eq + Hash + Clone> ActionCache<A> {
+    /// Calls `mdp.actions_at` once per state in `states`. Actions missing from `actions` are kept
+    /// in `actions_at` but have no entry in `action_indices_at`.
+    pub fn new<M>(mdp: &M, states: &StateIndex<M::State>, actions: &ActionIndex<A>) -> Self
+    where
+        M: MDP<Action = A>,
+        M::State: Clone,
+    {
+        let available: Vec<Vec<A>> = states.items().iter().map(|s| mdp.actions_at(s)).collect();
+        let indices = available
+            .iter()
+            .map(|row| row.iter().filter_map(|a| actions.index_of(a)).collect())
+            .collect();
+        ActionCache {
+            actions: available,
+            indices,
+        }
+    }
+
+    /// The cached `actions_at` of the state with index `state`
+    pub fn actions_at(&self, state: usize) -> &[A] {
+        &self.actions[state]
+    }
+
+    /// The indices of the actions available in the state with index `state`
+    pub fn action_indices_at(&self, state: usize) -> &[usize] {
+        &self.indices[state]
+    }
+
+    /// Whether no action is available in the state with index `state`
+    pub fn is_dead_end(&self, state: usize) -> bool {
+        self.actions[state].is_empty()
+    }
+}
@@ -0,0 +1,197 @@
+//! # State-Space Diagnostics
+//!
+//! Size estimates for MDPs and their products. Products grow combinatorially (six path worlds of
+//! length ten already give a million states, each with up to 64 joint actions in the Cartesian
+//! case), and the first sign of trouble is usually the process running out of memory while a
+//! product or its Q-table is built. `state_space_report` measures a model by streaming its states,
+//! so it does not force a `LazyProduct` to materialize, and `box_product`/`cartesian_product`
+//! combine the reports of components to size a product before constructing it.
+
+use std::fmt;
+use std::mem::size_of;
+
+use crate::mdp::MDP;
+
+/// Memory above which `warning` complains by default (4 GiB)
+pub const DEFAULT_MEMORY_BUDGET: usize = 4 << 30;
+
+/// Per-entry overhead factor of a `HashMap` at its maximal load factor of 7/8
+const HASH_LOAD_FACTOR: f64 = 8.0 / 7.0;
+
+/// The size of an MDP's state and action spaces and of the tables learners keep for it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StateSpaceReport {
+    pub num_states: usize,
+    /// Size of the action union (`all_actions()`), the width of a Q-table row
+    pub num_actions: usize,
+    pub mean_actions_per_state: f64,
+    pub max_actions_per_state: usize,
+    /// `size_of` a state value (for combined reports, estimated from the components)
+    pub state_bytes: usize,
+    /// `size_of` an action value (for combined reports, estimated from the components)
+    pub action_bytes: usize,
+}
+
+/// # State-Space Report
+///
+/// Streams `mdp.states()` once, calling `actions_at` on every state, and measures the action
+/// union. Takes time linear in the number of states but stores none of them.
+///
+/// # Arguments
+/// * `mdp` - The model to measure
+///
+/// # Returns
+/// The sizes of the state and action spaces
+pub fn state_space_report<M>(mdp: &M) -> StateSpaceReport
+where
+    M: MDP,
+{
+    let mut num_states = 0;
+    let mut total_actions = 0;
+    let mut max_actions_per_state = 0;
+    for state in mdp.states() {
+        let available = mdp.actions_at(&state).len();
+        num_states += 1;
+        total_actions += available;
+        max_actions_per_state = max_actions_per_state.max(available);
+    }
+    StateSpaceReport {
+        num_states,
+        num_actions: mdp.all_actions().len(),
+        mean_actions_per_state: if num_states == 0 {
+            0.0
+        } else {
+            total_actions as f64 / num_states as f64
+        },
+        max_actions_per_state,
+        state_bytes: size_of::<M::State>(),
+        action_bytes: size_of::<M::Action>(),
+    }
+}
+
+impl StateSpaceReport {
+    /// The report of `BoxProduct::new(self, other)`: states multiply, available actions add up
+    pub fn box_product(&self, other: &StateSpaceReport) -> StateSpaceReport {
+        StateSpaceReport {
+            num_states: self.num_states.saturating_mul(other.num_states),
+            num_actions: self.num_actions.saturating_add(other.num_actions),
+            mean_actions_per_state: self.mean_actions_per_state + other.mean_actions_per_state,
+            max_actions_per_state: self
+                .max_actions_per_state
+                .saturating_add(other.max_actions_per_state),
+            state_bytes: self.state_bytes + other.state_bytes,
+            action_bytes: self.action_bytes.max(other.action_bytes),
+        }
+    }
+
+    /// The report of `CartesianProduct::new(self, other)` (or a `LazyProduct`): states and
+    /// available actions both multiply
+    pub fn cartesian_product(&self, other: &StateSpaceReport) -> StateSpaceReport {
+        StateSpaceReport {
+            num_states: self.num_states.saturating_mul(other.num_states),
+            num_actions: self.num_actions.saturating_mul(other.num_actions),
+            mean_actions_per_state: self.mean_actions_per_state * other.mean_actions_per_state,
+            max_actions_per_state: self
+                .max_actions_per_state
+                .saturating_mul(other.max_actions_per_state),
+            state_bytes: self.state_bytes + other.state_bytes,
+            action_bytes: self.action_bytes + other.action_bytes,
+        }
+    }
+
+    /// Expected number of available state-action pairs
+    pub fn num_state_action_pairs(&self) -> f64 {
+        self.num_states as f64 * self.mean_actions_per_state
+    }
+
+    /// Bytes of the materialized state space (`all_states()`)
+    pub fn state_space_bytes(&self) -> usize {
+        self.num_states.saturating_mul(self.state_bytes)
+    }
+
+    /// Bytes of a `DenseQTable` with its `StateIndex` and `ActionIndex`
+    pub fn dense_q_table_bytes(&self) -> usize {
+        let values = self
+            .num_states
+            .saturating_mul(self.num_actions)
+            .saturating_mul(size_of::<f64>());
+        let index = |count: usize, bytes: usize| {
+            let entries = count as f64 * (bytes + size_of::<usize>()) as f64;
+            count as f64 * bytes as f64 + entries * HASH_LOAD_FACTOR
+        };
+        values.saturating_add(
+            (index(self.num_states, self.state_bytes) + index(self.num_actions, self.action_bytes))
+                as usize,
+        )
+    }
+
+    /// Approximate bytes of an `ActionValue`, a hash map from every state to a hash map over
+    /// the action union
+    pub fn action_value_bytes(&self) -> usize {
+        const INNER_MAP_BYTES: usize = 48;
+        let row = self.num_actions as f64 * (self.action_bytes + size_of::<f64>() + 1) as f64;
+        let per_state = (self.state_bytes + INNER_MAP_BYTES + 1) as f64 + row * HASH_LOAD_FACTOR;
+        (self.num_states as f64 * per_state * HASH_LOAD_FACTOR) as usize
+    }
+
+    /// A description of the problem if materializing the states and an `ActionValue` would take
+    /// more than `budget` bytes
+    pub fn warning(&self, budget: usize) -> Option<String> {
+        let needed = self
+            .state_space_bytes()
+            .saturating_add(self.action_value_bytes());
+        (needed > budget).then(|| {
+            format!(
+                "{} states with {} actions would need about {} for the state space and Q-table, \
+                 over the budget of {}",
+                self.num_states,
+                self.num_actions,
+                format_bytes(needed),
+                format_bytes(budget)
+            )
+        })
+    }
+}
+
+impl fmt::Display for StateSpaceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "states:              {}", self.num_states)?;
+        writeln!(f, "action union:        {}", self.num_actions)?;
+        writeln!(
+            f,
+            "actions per state:   {:.1} mean, {} max",
+            self.mean_actions_per_state, self.max_actions_per_state
+        )?;
+        writeln!(
+            f,
+            "state space:         {}",
+            format_bytes(self.state_space_bytes())
+        )?;
+        writeln!(
+            f,
+            "ActionValue:         {} (approx)",
+            format_bytes(self.action_value_bytes())
+        )?;
+        write!(
+            f,
+            "DenseQTable:         {}",
+            format_bytes(self.dense_q_table_bytes())
+        )
+    }
+}
+
+/// `bytes` in the largest binary unit that keeps the number at least one
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
@@ -0,0 +1,253 @@
+//! # Distributional Q-Learning
+//!
+//! Tabular categorical (C51-style) Q-learning: instead of a single expected value, every
+//! state-action pair keeps a distribution over returns on a fixed grid of atoms. Greedy decisions
+//! are made on the expectation of those distributions.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use indexmap::IndexMap;
+use rand::Rng;
+
+use crate::config::TrainingConfig;
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::measure::{Measure, Probability};
+use crate::policy::DeterministicPolicy;
+use crate::risk::conditional_value_at_risk;
+use crate::q_learning::random_start;
+
+/// Evenly spaced return atoms `v_min, v_min + Δz, ..., v_max`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoricalSupport {
+    v_min: f64,
+    v_max: f64,
+    num_atoms: usize,
+}
+
+impl CategoricalSupport {
+    /// Returns `None` unless `v_min < v_max` and there are at least two atoms
+    pub fn new(v_min: f64, v_max: f64, num_atoms: usize) -> Option<Self> {
+        if num_atoms < 2 || v_min.partial_cmp(&v_max) != Some(std::cmp::Ordering::Less) {
+            return None;
+        }
+        Some(CategoricalSupport {
+            v_min,
+            v_max,
+            num_atoms,
+        })
+    }
+
+    pub fn num_atoms(&self) -> usize {
+        self.num_atoms
+    }
+
+    pub fn delta(&self) -> f64 {
+        (self.v_max - self.v_min) / (self.num_atoms - 1) as f64
+    }
+
+    pub fn atom(&self, index: usize) -> f64 {
+        self.v_min + index as f64 * self.delta()
+    }
+
+    pub fn atoms(&self) -> Vec<f64> {
+        (0..self.num_atoms).map(|i| self.atom(i)).collect()
+    }
+
+    /// Distributes the mass `weight` of a (clipped) return `value` over its two neighbouring atoms
+    fn project(&self, value: f64, weight: f64, target: &mut [f64]) {
+        let clipped = value.clamp(self.v_min, self.v_max);
+        let position = (clipped - self.v_min) / self.delta();
+        let lower = position.floor() as usize;
+        let upper = (position.ceil() as usize).min(self.num_atoms - 1);
+        if lower == upper {
+            target[lower] += weight;
+        } else {
+            target[lower] += weight * (upper as f64 - position);
+            target[upper] += weight * (position - lower as f64);
+        }
+    }
+
+    /// A point mass on the atom closest to zero
+    fn initial_distribution(&self) -> Vec<f64> {
+        let mut dist = vec![0.0; self.num_atoms];
+        self.project(0.0, 1.0, &mut dist);
+        dist
+    }
+}
+
+/// Learned categorical return distributions for every visited state-action pair
+pub struct ReturnDistributions<S, A> {
+    support: CategoricalSupport,
+    table: HashMap<(S, A), Vec<f64>>,
+}
+
+impl<S, A> ReturnDistributions<S, A>
+where
+    S: Eq + Hash + Clone,
+    A: Eq + Hash + Clone,
+{
+    pub fn support(&self) -> &CategoricalSupport {
+        &self.support
+    }
+
+    /// Atom probabilities of `Z(s, a)`; unvisited pairs are a point mass at zero
+    pub fn probabilities(&self, state: &S, action: &A) -> Vec<f64> {
+        self.table
+            .get(&(state.clone(), action.clone()))
+            .cloned()
+            .unwrap_or_else(|| self.support.initial_distribution())
+    }
+
+    /// `Z(s, a)` as a measure over atom indices (see `CategoricalSupport::atom`)
+    pub fn measure(&self, state: &S, action: &A) -> Result<Measure<usize>, Error> {
+        let dist = self
+            .probabilities(state, action)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, p)| *p > 0.0)
+            .map(|(i, p)| Ok((i, Probability::new(p.min(1.0))?)))
+            .collect::<Result<IndexMap<_, _>, Error>>()?;
+        Measure::from_distribution(dist)
+    }
+
+    /// `E[Z(s, a)]`
+    pub fn expected_value(&self, state: &S, action: &A) -> f64 {
+        self.probabilities(state, action)
+            .iter()
+            .enumerate()
+            .map(|(i, p)| p * self.support.atom(i))
+            .sum()
+    }
+
+    /// `CVaR_α` of `Z(s, a)`: the expected return over its worst `alpha` fraction (see
+    /// `risk::conditional_value_at_risk`)
+    pub fn cvar(&self, state: &S, action: &A, alpha: f64) -> Option<f64> {
+        let outcomes: Vec<(f64, f64)> = self
+            .probabilities(state, action)
+            .into_iter()
+            .enumerate()
+            .map(|(i, p)| (self.support.atom(i), p))
+            .collect();
+        conditional_value_at_risk(&outcomes, alpha)
+    }
+
+    /// The available action with the largest expected return (first listed wins ties)
+    pub fn greedy_action(&self, state: &S, actions: &[A]) -> Option<A> {
+        let mut best: Option<(&A, f64)> = None;
+        for action in actions {
+            let value = self.expected_value(state, action);
+            if best.is_none_or(|(_, best_value)| value > best_value) {
+                best = Some((action, value));
+            }
+        }
+        best.map(|(action, _)| action.clone())
+    }
+
+    /// Extracts the expectation-greedy policy over the actions available at each state
+    pub fn greedy_policy<M>(&self, mdp: &M) -> DeterministicPolicy<S, A>
+    where
+        M: MDP<State = S, Action = A>,
+    {
+        mdp.all_states()
+            .iter()
+            .filter_map(|s| {
+                self.greedy_action(s, &mdp.actions_at(s))
+                    .map(|a| (s.clone(), a))
+            })
+            .collect()
+    }
+}
+
+/// # Distributional Q-Learning
+///
+/// This function implements tabular categorical Q-Learning.
+/// Each step it:
+/// 1. Selects an action epsilon-greedily with respect to expected returns
+/// 2. Builds the target distribution `r + γ Z(s', a*)`, with `a*` the expectation-greedy action at `s'`
+///    (just `r` when `s'` is final), and projects it back onto the support
+/// 3. Mixes the target into `Z(s, a)` with weight `learning_rate`
+///
+/// # Arguments
+/// * `mdp` - The MDP to learn from
+/// * `config` - Configuration parameters (learning rate, discount factor, exploration rate, etc.)
+/// * `support` - The return atoms; returns outside `[v_min, v_max]` are clipped
+///
+/// # Returns
+/// The learned `ReturnDistributions`
+pub fn distributional_q_learning<M>(
+    mdp: &M,
+    config: &TrainingConfig,
+    support: CategoricalSupport,
+) -> Result<ReturnDistributions<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let states = mdp.all_states();
+    let mut distributions = ReturnDistributions {
+        support,
+        table: HashMap::new(),
+    };
+    let mut rng = config.rng();
+
+    for _ in 0..config.num_episodes {
+        let mut state = random_start(states, &mut rng)?.clone();
+
+        for _ in 0..config.max_num_steps {
+            let available_actions = mdp.actions_at(&state);
+            if available_actions.is_empty() {
+                break;
+            }
+            let action = if rng.random::<f64>() < config.exploration_rate {
+                available_actions[rng.random_range(0..available_actions.len())].clone()
+            } else {
+                match distributions.greedy_action(&state, &available_actions) {
+                    Some(a) => a,
+                    None => break,
+                }
+            };
+
+            let (measure, reward) = mdp.stochastic_transition(&state, &action)?;
+            let next_state = measure
+                .sample_with(&mut rng)
+                .cloned()
+                .unwrap_or_else(|| state.clone());
+
+            let support = &distributions.support;
+            let mut target = vec![0.0; support.num_atoms()];
+            let next_action = if mdp.is_final_state(&next_state) {
+                None
+            } else {
+                distributions.greedy_action(&next_state, &mdp.actions_at(&next_state))
+            };
+            match next_action {
+                Some(next_action) => {
+                    let next_dist = distributions.probabilities(&next_state, &next_action);
+                    for (i, p) in next_dist.iter().enumerate() {
+                        if *p > 0.0 {
+                            let value = reward + config.discount_factor * support.atom(i);
+                            support.project(value, *p, &mut target);
+                        }
+                    }
+                }
+                None => support.project(reward, 1.0, &mut target),
+            }
+
+            let mut current = distributions.probabilities(&state, &action);
+            for (p, t) in current.iter_mut().zip(target) {
+                *p += config.learning_rate * (t - *p);
+            }
+            distributions.table.insert((state, action), current);
+
+            state = next_state;
+            if mdp.is_final_state(&state) {
+                break;
+            }
+        }
+    }
+
+    Ok(distributions)
+}
@@ -0,0 +1,109 @@
+//! # Embedded Jump Chains
+//!
+//! The embedded (jump) chain of a CTMDP records only where the process jumps, not how long it
+//! waits: from `s` under `a` it moves to `s'` with probability `q(s' | s, a) / E(s, a)`. It is
+//! the natural discrete-time approximation to compare against uniformization, which keeps the
+//! timing. Rewards earned at a rate while holding in a state become lump sums per jump through
+//! `holding_to_transition_reward`, and `transition_to_holding_reward` goes back.
+
+use madepro::models::Sampler;
+
+use crate::ctmdp::{CTMDP, expected_dwell_reward};
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::measure::Measure;
+
+/// Lump-sum reward equivalent to earning `reward_rate` during an exponential holding time with
+/// rate `exit_rate`, discounted continuously at `discount_rate`: `ρ / (E + β)`
+pub fn holding_to_transition_reward(reward_rate: f64, exit_rate: f64, discount_rate: f64) -> f64 {
+    expected_dwell_reward(reward_rate, exit_rate, discount_rate)
+}
+
+/// The reward rate whose holding-time reward equals the lump sum `reward`: `r (E + β)`
+pub fn transition_to_holding_reward(reward: f64, exit_rate: f64, discount_rate: f64) -> f64 {
+    reward * (exit_rate + discount_rate)
+}
+
+/// Mean time spent in a state whose exit rate is `exit_rate`; infinite for absorbing states
+pub fn expected_holding_time(exit_rate: f64) -> f64 {
+    if exit_rate > 0.0 {
+        1.0 / exit_rate
+    } else {
+        f64::INFINITY
+    }
+}
+
+/// The jump-chain MDP of a CTMDP.
+///
+/// Each transition pays the expected holding-time reward `ρ / E` of the state it leaves, so
+/// undiscounted total rewards agree with the CTMDP's. Discounting per jump is only an
+/// approximation of continuous discounting (the exact per-jump discount `E / (E + β)` depends on
+/// the state). A state that is absorbing under an action loops on itself and pays nothing.
+pub struct EmbeddedMDP<M: CTMDP> {
+    ctmdp: M,
+}
+
+/// Builds the embedded jump-chain MDP of `ctmdp`
+pub fn embed<M: CTMDP>(ctmdp: M) -> EmbeddedMDP<M> {
+    EmbeddedMDP { ctmdp }
+}
+
+impl<M: CTMDP> EmbeddedMDP<M> {
+    /// Mean holding time in `state` under `action`
+    pub fn holding_time(&self, state: &M::State, action: &M::Action) -> Result<f64, Error> {
+        Ok(expected_holding_time(self.ctmdp.exit_rate(state, action)?))
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.ctmdp
+    }
+
+    pub fn into_inner(self) -> M {
+        self.ctmdp
+    }
+}
+
+impl<M> MDP for EmbeddedMDP<M>
+where
+    M: CTMDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    type State = M::State;
+    type Action = M::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        self.ctmdp.all_states()
+    }
+
+    fn all_actions(&self) -> Vec<Self::Action> {
+        self.ctmdp.all_actions()
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        self.ctmdp.actions_at(state)
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.ctmdp.is_final_state(state)
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.ctmdp.is_goal(state)
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        let rates = self.ctmdp.rates(state, action)?;
+        let exit_rate = rates.exit_rate();
+        if exit_rate <= 0.0 {
+            return Ok((Measure::deterministic(state.clone()), 0.0));
+        }
+        let reward =
+            holding_to_transition_reward(self.ctmdp.reward_rate(state, action), exit_rate, 0.0);
+        Ok((rates.jump_measure()?, reward))
+    }
+}
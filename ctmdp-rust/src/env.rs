@@ -0,0 +1,160 @@
+//! # Environments
+//!
+//! A stateful, Gym-style simulator over an `MDP`. `Environment` keeps the current state, samples
+//! next states from the transition measures and reports whether an episode ended because a final
+//! state was reached (`terminated`) or because the step limit ran out (`truncated`).
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::error::Error;
+use crate::mdp::MDP;
+
+/// The outcome of one `Environment::step`
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepResult<S> {
+    pub next_state: S,
+    pub reward: f64,
+    /// `next_state` is final
+    pub terminated: bool,
+    /// The step limit was reached before a final state
+    pub truncated: bool,
+}
+
+impl<S> StepResult<S> {
+    /// The episode is over, either way
+    pub fn done(&self) -> bool {
+        self.terminated || self.truncated
+    }
+}
+
+/// Simulates episodes of an MDP one step at a time.
+///
+/// Episodes start from a fixed state if one was given, otherwise from a state drawn uniformly
+/// from `all_states()`, as the learners do. Seeding fixes the start states and the draws used to
+/// sample transitions; outcomes of multi-state measures also depend on the measure's (hash map)
+/// iteration order, so only deterministic dynamics replay exactly across runs.
+pub struct Environment<M: MDP> {
+    mdp: M,
+    start: Option<M::State>,
+    max_steps: Option<usize>,
+    state: M::State,
+    steps: usize,
+    rng: StdRng,
+}
+
+impl<M> Environment<M>
+where
+    M: MDP,
+    M::State: Clone,
+{
+    /// Fails with `Error::EmptyStateSpace` if there is no state to start from
+    pub fn new(mdp: M) -> Result<Self, Error> {
+        Self::with_rng(mdp, StdRng::from_rng(&mut rand::rng()))
+    }
+
+    pub fn with_seed(mdp: M, seed: u64) -> Result<Self, Error> {
+        Self::with_rng(mdp, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(mdp: M, mut rng: StdRng) -> Result<Self, Error> {
+        let state = random_state(&mdp, &mut rng)?;
+        Ok(Environment {
+            mdp,
+            start: None,
+            max_steps: None,
+            state,
+            steps: 0,
+            rng,
+        })
+    }
+
+    /// Starts every episode from `state` instead of a random one
+    pub fn with_start_state(mut self, state: M::State) -> Self {
+        self.state = state.clone();
+        self.start = Some(state);
+        self
+    }
+
+    /// Truncates episodes after `max_steps` steps
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    /// Reseeds the generator; call `reset` afterwards to replay episodes from the seed
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// Starts a new episode and returns its first state
+    pub fn reset(&mut self) -> M::State {
+        if let Some(start) = &self.start {
+            self.state = start.clone();
+        } else if let Ok(state) = random_state(&self.mdp, &mut self.rng) {
+            self.state = state;
+        }
+        self.steps = 0;
+        self.state.clone()
+    }
+
+    /// Takes `action` in the current state. Stepping after the episode is done continues from the
+    /// last state; call `reset` to start over.
+    pub fn step(&mut self, action: &M::Action) -> Result<StepResult<M::State>, Error> {
+        let (measure, reward) = self.mdp.stochastic_transition(&self.state, action)?;
+        let next_state = measure
+            .sample_with(&mut self.rng)
+            .cloned()
+            .unwrap_or_else(|| self.state.clone());
+        self.steps += 1;
+        self.state = next_state.clone();
+
+        let terminated = self.mdp.is_final_state(&next_state);
+        let truncated = !terminated && self.max_steps.is_some_and(|max| self.steps >= max);
+        Ok(StepResult {
+            next_state,
+            reward,
+            terminated,
+            truncated,
+        })
+    }
+
+    pub fn state(&self) -> &M::State {
+        &self.state
+    }
+
+    /// Steps taken in the current episode
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// The actions available in the current state
+    pub fn available_actions(&self) -> Vec<M::Action> {
+        self.mdp.actions_at(&self.state)
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mdp
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mdp
+    }
+}
+
+fn random_state<M>(mdp: &M, rng: &mut StdRng) -> Result<M::State, Error>
+where
+    M: MDP,
+    M::State: Clone,
+{
+    let num_states = mdp.all_states().iter().count();
+    if num_states == 0 {
+        return Err(Error::EmptyStateSpace);
+    }
+    let index = rng.random_range(0..num_states);
+    mdp.all_states()
+        .iter()
+        .nth(index)
+        .cloned()
+        .ok_or(Error::EmptyStateSpace)
+}
@@ -1,3 +1,5 @@
+use std::fmt::Debug;
+
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -6,4 +8,36 @@ pub enum Error {
     ProbabilityOutOfRange,
     #[error("Measure must sum to one")]
     InvalidMeasure,
+    #[error("No transition defined for the given state-action pair")]
+    UndefinedTransition,
+    #[error("State is not part of the MDP")]
+    StateNotFound,
+    #[error("Action {action} is not available in state {state}")]
+    ActionNotAvailable { state: String, action: String },
+    #[error("MDP has no states")]
+    EmptyStateSpace,
+    #[error("MDP has no actions")]
+    EmptyActionSet,
+    #[error("Episodes can reach states from which no final state is reachable")]
+    NonTerminatingEpisode,
+    #[error("Numerical error: {0}")]
+    NumericalError(String),
+    #[error("Serialization failed: {0}")]
+    Serialization(String),
+    #[error("I/O error: {0}")]
+    Io(String),
+    #[error("Invalid specification: {0}")]
+    Specification(String),
+    #[error("Invariant violated: {0}")]
+    InvariantViolation(String),
+}
+
+impl Error {
+    /// `ActionNotAvailable` with the state and action rendered through `Debug`
+    pub fn action_not_available(state: &impl Debug, action: &impl Debug) -> Self {
+        Error::ActionNotAvailable {
+            state: format!("{state:?}"),
+            action: format!("{action:?}"),
+        }
+    }
 }
@@ -0,0 +1,436 @@
+//! # Model Export
+//!
+//! Writes an MDP out in formats other tools understand. `to_dot` renders the transition graph
+//! for Graphviz: one node per state and one edge per action and successor, labeled with the
+//! action, its probability and its reward, optionally highlighting the actions a policy takes.
+//! Looking at the picture is often the quickest way to see whether a product wired its
+//! components together as intended.
+//!
+//! For verification, `to_prism_explicit` produces PRISM's explicit-model files and `to_jani`
+//! (with the `serde` feature) a JANI model, so composed models can be checked with PRISM, Storm
+//! or the Modest Toolset. Both number the states in `all_states()` order and make final states
+//! absorbing, since model checkers treat states without choices as deadlocks.
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Write};
+use std::fs;
+use std::path::Path;
+
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::policy::DeterministicPolicy;
+
+/// Escapes a label for use inside a double-quoted DOT string
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// # Graphviz Export
+///
+/// The transition graph of `mdp` in DOT syntax. States are labeled with their `Debug` form;
+/// final states are drawn as double circles and goal states are filled. Every available action of
+/// a non-final state contributes one edge per successor with positive probability, labeled
+/// `action / p / r`. Successors outside `all_states()` are drawn dashed, since a well-formed
+/// model has none.
+///
+/// # Arguments
+/// * `mdp` - The model to draw
+///
+/// # Returns
+/// The `digraph` source, ready for `dot -Tsvg`
+pub fn to_dot<M>(mdp: &M) -> Result<String, Error>
+where
+    M: MDP,
+    M::State: Debug,
+    M::Action: Debug,
+{
+    write_dot(mdp, None)
+}
+
+/// Like `to_dot`, with the edges of the actions `policy` takes drawn bold and colored and all
+/// other edges greyed out
+pub fn to_dot_with_policy<M>(
+    mdp: &M,
+    policy: &DeterministicPolicy<M::State, M::Action>,
+) -> Result<String, Error>
+where
+    M: MDP,
+    M::State: Debug,
+    M::Action: Debug,
+{
+    write_dot(mdp, Some(policy))
+}
+
+fn write_dot<M>(
+    mdp: &M,
+    policy: Option<&DeterministicPolicy<M::State, M::Action>>,
+) -> Result<String, Error>
+where
+    M: MDP,
+    M::State: Debug,
+    M::Action: Debug,
+{
+    let mut nodes: Vec<M::State> = mdp.all_states().iter().cloned().collect();
+    let num_known = nodes.len();
+    let mut index: HashMap<M::State, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.clone(), i))
+        .collect();
+
+    // (from, to, action, probability, reward, taken by the policy)
+    let mut edges = Vec::new();
+    // Successors outside the state space are appended to `nodes` as they turn up
+    for i in 0..num_known {
+        let state = nodes[i].clone();
+        if mdp.is_final_state(&state) {
+            continue;
+        }
+        let chosen = policy.and_then(|p| p.get(&state));
+        for action in mdp.actions_at(&state) {
+            let (measure, reward) = mdp.stochastic_transition(&state, &action)?;
+            let mut successors: Vec<(usize, f64)> = Vec::new();
+            for (next, p) in measure.dist() {
+                if p.value() <= 0.0 {
+                    continue;
+                }
+                let j = *index.entry(next.clone()).or_insert_with(|| {
+                    nodes.push(next.clone());
+                    nodes.len() - 1
+                });
+                successors.push((j, p.value()));
+            }
+            successors.sort_by_key(|(j, _)| *j);
+            let taken = chosen == Some(&action);
+            let label = escape(&format!("{action:?}"));
+            for (j, p) in successors {
+                edges.push((i, j, label.clone(), p, reward, taken));
+            }
+        }
+    }
+
+    let mut dot = String::from("digraph mdp {\n    rankdir=LR;\n    node [shape=circle];\n");
+    for (i, state) in nodes.iter().enumerate() {
+        let mut attributes = vec![format!("label=\"{}\"", escape(&format!("{state:?}")))];
+        if i >= num_known {
+            attributes.push("style=dashed".to_string());
+        } else {
+            if mdp.is_final_state(state) {
+                attributes.push("shape=doublecircle".to_string());
+            }
+            if mdp.is_goal(state) {
+                attributes.push("style=filled fillcolor=lightgrey".to_string());
+            }
+        }
+        let _ = writeln!(dot, "    s{i} [{}];", attributes.join(" "));
+    }
+    for (from, to, label, p, reward, taken) in edges {
+        let style = match (policy.is_some(), taken) {
+            (false, _) => "",
+            (true, true) => " color=blue penwidth=2",
+            (true, false) => " color=grey fontcolor=grey",
+        };
+        let _ = writeln!(
+            dot,
+            "    s{from} -> s{to} [label=\"{label} / {p:.3} / {reward:.3}\"{style}];"
+        );
+    }
+    dot.push_str("}\n");
+    Ok(dot)
+}
+
+/// One nondeterministic choice of an explicit model: the action's identifier (`None` for the
+/// silent self-loop of a state without actions), its reward and its successors
+/// `(index, probability)`
+#[derive(Clone)]
+struct Choice {
+    action: Option<String>,
+    reward: f64,
+    successors: Vec<(usize, f64)>,
+}
+
+/// An MDP enumerated into numbered states and choices; final states have no choices
+struct ExplicitModel {
+    state_names: Vec<String>,
+    choices: Vec<Vec<Choice>>,
+    finals: Vec<bool>,
+    goals: Vec<bool>,
+}
+
+/// Turns a `Debug` rendering into an identifier: runs of other characters become `_`
+fn identifier(name: &str) -> String {
+    let mut id = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            id.push(c);
+        } else if !id.ends_with('_') {
+            id.push('_');
+        }
+    }
+    let id = id.trim_matches('_');
+    match id.chars().next() {
+        None => "a".to_string(),
+        Some(first) if first.is_ascii_digit() => format!("a_{id}"),
+        Some(_) => id.to_string(),
+    }
+}
+
+impl ExplicitModel {
+    /// Enumerates `mdp` through `all_state_action_pairs` and `stochastic_transition`. Fails with
+    /// `Error::StateNotFound` if a transition leaves `all_states()`.
+    fn from_mdp<M>(mdp: &M) -> Result<Self, Error>
+    where
+        M: MDP,
+        M::State: Debug,
+        M::Action: Debug,
+    {
+        let states: Vec<M::State> = mdp.all_states().iter().cloned().collect();
+        let index: HashMap<&M::State, usize> =
+            states.iter().enumerate().map(|(i, s)| (s, i)).collect();
+        let finals: Vec<bool> = states.iter().map(|s| mdp.is_final_state(s)).collect();
+
+        let mut choices: Vec<Vec<Choice>> = (0..states.len()).map(|_| Vec::new()).collect();
+        for (state, action) in mdp.all_state_action_pairs() {
+            let i = *index.get(&state).ok_or(Error::StateNotFound)?;
+            if finals[i] {
+                continue;
+            }
+            let (measure, reward) = mdp.stochastic_transition(&state, &action)?;
+            let mut successors = Vec::new();
+            for (next, p) in measure.dist() {
+                if p.value() > 0.0 {
+                    successors.push((*index.get(next).ok_or(Error::StateNotFound)?, p.value()));
+                }
+            }
+            successors.sort_by_key(|(j, _)| *j);
+            choices[i].push(Choice {
+                action: Some(identifier(&format!("{action:?}"))),
+                reward,
+                successors,
+            });
+        }
+
+        Ok(ExplicitModel {
+            state_names: states.iter().map(|s| format!("{s:?}")).collect(),
+            goals: states.iter().map(|s| mdp.is_goal(s)).collect(),
+            choices,
+            finals,
+        })
+    }
+
+    /// The choices of state `i`, with a single silent self-loop standing in for none
+    fn choices_or_loop(&self, i: usize) -> Vec<Choice> {
+        if self.choices[i].is_empty() {
+            return vec![Choice {
+                action: None,
+                reward: 0.0,
+                successors: vec![(i, 1.0)],
+            }];
+        }
+        self.choices[i].clone()
+    }
+}
+
+/// # PRISM Explicit Files
+///
+/// The contents of PRISM's explicit MDP files. State `i` is the `i`-th state of `all_states()`
+/// (its `Debug` form is in `state_names[i]`); every state is initial, matching the random starts
+/// of the learners. Final states and states without actions get a single unlabeled self-loop.
+/// Rewards are attached to every transition of a choice; labels `final` and `goal` mark the
+/// corresponding states. Import with
+/// `prism -importtrans m.tra -importstates m.sta -importlabels m.lab -importtransrewards m.trew -mdp`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrismExplicit {
+    /// `.sta`: the value of the single state variable `s`
+    pub states: String,
+    /// `.tra`: `source choice target probability action` rows
+    pub transitions: String,
+    /// `.lab`: `init`, `deadlock`, `final` and `goal`
+    pub labels: String,
+    /// `.trew`: `source choice target reward` rows for nonzero rewards
+    pub rewards: String,
+    pub state_names: Vec<String>,
+}
+
+impl PrismExplicit {
+    /// Writes `<base>.sta`, `<base>.tra`, `<base>.lab` and `<base>.trew`
+    pub fn write(&self, base: impl AsRef<Path>) -> Result<(), Error> {
+        let base = base.as_ref();
+        for (extension, contents) in [
+            ("sta", &self.states),
+            ("tra", &self.transitions),
+            ("lab", &self.labels),
+            ("trew", &self.rewards),
+        ] {
+            let path = base.with_extension(extension);
+            fs::write(&path, contents)
+                .map_err(|e| Error::Io(format!("{}: {e}", path.display())))?;
+        }
+        Ok(())
+    }
+}
+
+/// Exports `mdp` to PRISM's explicit format (see `PrismExplicit`)
+pub fn to_prism_explicit<M>(mdp: &M) -> Result<PrismExplicit, Error>
+where
+    M: MDP,
+    M::State: Debug,
+    M::Action: Debug,
+{
+    let model = ExplicitModel::from_mdp(mdp)?;
+    let n = model.state_names.len();
+
+    let mut states = String::from("(s)\n");
+    for i in 0..n {
+        let _ = writeln!(states, "{i}:({i})");
+    }
+
+    let mut rows = String::new();
+    let mut reward_rows = String::new();
+    let (mut num_choices, mut num_transitions, mut num_rewards) = (0, 0, 0);
+    for i in 0..n {
+        for (c, choice) in model.choices_or_loop(i).into_iter().enumerate() {
+            num_choices += 1;
+            for (j, p) in choice.successors {
+                num_transitions += 1;
+                match &choice.action {
+                    Some(action) => {
+                        let _ = writeln!(rows, "{i} {c} {j} {p} {action}");
+                    }
+                    None => {
+                        let _ = writeln!(rows, "{i} {c} {j} {p}");
+                    }
+                }
+                if choice.reward != 0.0 {
+                    num_rewards += 1;
+                    let _ = writeln!(reward_rows, "{i} {c} {j} {}", choice.reward);
+                }
+            }
+        }
+    }
+
+    let mut labels = String::from("0=\"init\" 1=\"deadlock\" 2=\"final\" 3=\"goal\"\n");
+    for i in 0..n {
+        let mut ids = vec!["0"];
+        if model.finals[i] {
+            ids.push("2");
+        }
+        if model.goals[i] {
+            ids.push("3");
+        }
+        let _ = writeln!(labels, "{i}: {}", ids.join(" "));
+    }
+
+    Ok(PrismExplicit {
+        states,
+        transitions: format!("{n} {num_choices} {num_transitions}\n{rows}"),
+        labels,
+        rewards: format!("{n} {num_choices} {num_rewards}\n{reward_rows}"),
+        state_names: model.state_names,
+    })
+}
+
+/// # JANI Export
+///
+/// `mdp` as a JANI model with one automaton over a bounded integer variable `s` (the index into
+/// `all_states()`). Every state is initial; each choice becomes an edge guarded by `s = i` that
+/// assigns the transient real variable `reward`, and final states get a silent self-loop. The
+/// transient booleans `final` and `goal` hold in the corresponding states, for use in
+/// properties.
+#[cfg(feature = "serde")]
+pub fn to_jani<M>(mdp: &M) -> Result<String, Error>
+where
+    M: MDP,
+    M::State: Debug,
+    M::Action: Debug,
+{
+    use serde_json::{Value, json};
+
+    let model = ExplicitModel::from_mdp(mdp)?;
+    let n = model.state_names.len();
+    if n == 0 {
+        return Err(Error::EmptyStateSpace);
+    }
+
+    let mut actions: Vec<&str> = model
+        .choices
+        .iter()
+        .flatten()
+        .filter_map(|c| c.action.as_deref())
+        .collect();
+    actions.sort_unstable();
+    actions.dedup();
+
+    let membership = |flags: &[bool]| -> Value {
+        let disjuncts: Vec<Value> = (0..n)
+            .filter(|&i| flags[i])
+            .map(|i| json!({"op": "=", "left": "s", "right": i}))
+            .collect();
+        disjuncts
+            .into_iter()
+            .reduce(|left, right| json!({"op": "∨", "left": left, "right": right}))
+            .unwrap_or(json!(false))
+    };
+
+    let mut edges = Vec::new();
+    for i in 0..n {
+        for choice in model.choices_or_loop(i) {
+            let reward = choice.reward;
+            let destinations: Vec<Value> = choice
+                .successors
+                .into_iter()
+                .map(|(j, p)| {
+                    json!({
+                        "location": "l",
+                        "probability": {"exp": p},
+                        "assignments": [
+                            {"ref": "s", "value": j},
+                            {"ref": "reward", "value": reward}
+                        ]
+                    })
+                })
+                .collect();
+            let mut edge = json!({
+                "location": "l",
+                "guard": {"exp": {"op": "=", "left": "s", "right": i}},
+                "destinations": destinations
+            });
+            if let Some(action) = choice.action {
+                edge["action"] = json!(action);
+            }
+            edges.push(edge);
+        }
+    }
+
+    let jani = json!({
+        "jani-version": 1,
+        "name": "mdp",
+        "type": "mdp",
+        "actions": actions.iter().map(|a| json!({"name": a})).collect::<Vec<_>>(),
+        "variables": [
+            {
+                "name": "s",
+                "type": {"kind": "bounded", "base": "int", "lower-bound": 0, "upper-bound": n - 1}
+            },
+            {"name": "reward", "type": "real", "transient": true, "initial-value": 0.0},
+            {"name": "final", "type": "bool", "transient": true, "initial-value": false},
+            {"name": "goal", "type": "bool", "transient": true, "initial-value": false}
+        ],
+        "restrict-initial": {"exp": true},
+        "properties": [],
+        "automata": [{
+            "name": "mdp",
+            "locations": [{
+                "name": "l",
+                "transient-values": [
+                    {"ref": "final", "value": membership(&model.finals)},
+                    {"ref": "goal", "value": membership(&model.goals)}
+                ]
+            }],
+            "initial-locations": ["l"],
+            "edges": edges
+        }],
+        "system": {"elements": [{"automaton": "mdp"}]}
+    });
+    serde_json::to_string_pretty(&jani).map_err(|e| Error::Serialization(e.to_string()))
+}
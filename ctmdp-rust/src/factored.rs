@@ -0,0 +1,315 @@
+//! # Factored Q-Learning
+//!
+//! Q-learning on product MDPs that keeps one Q-table per component instead of one over the joint
+//! state-action space. Product actions are scored by combining the component tables, and each
+//! step only updates the components that actually moved, using their own rewards.
+//!
+//! This assumes the product reward is the sum of the component rewards, which holds for
+//! `BoxProduct` and for `CartesianProduct` with `RewardCombiner::Sum`.
+
+use std::collections::HashMap;
+
+use madepro::models::{Action, ActionValue, State};
+use rand::Rng;
+
+use crate::config::TrainingConfig;
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::policy::{DeterministicPolicy, greedy_policy};
+use crate::products::{BoxAction, BoxProduct, CartesianProduct, Product};
+use crate::q_learning::{action_union, random_start};
+
+/// The component actions a product action is made of; `None` for a component that stays put
+pub type ComponentActions<'a, A1, A2> = (Option<&'a A1>, Option<&'a A2>);
+
+/// A product of two MDPs whose actions decompose into per-component actions
+pub trait FactoredProduct:
+    MDP<State = Product<<Self::First as MDP>::State, <Self::Second as MDP>::State>>
+{
+    type First: MDP;
+    type Second: MDP;
+
+    fn factors(&self) -> (&Self::First, &Self::Second);
+
+    fn split_action(
+        action: &Self::Action,
+    ) -> ComponentActions<'_, <Self::First as MDP>::Action, <Self::Second as MDP>::Action>;
+}
+
+impl<M1, M2> FactoredProduct for BoxProduct<M1, M2>
+where
+    M1: MDP,
+    M2: MDP,
+    M1::State: Clone,
+    M2::State: Clone,
+    M1::Action: Clone,
+    M2::Action: Clone,
+{
+    type First = M1;
+    type Second = M2;
+
+    fn factors(&self) -> (&M1, &M2) {
+        self.components()
+    }
+
+    fn split_action(action: &Self::Action) -> ComponentActions<'_, M1::Action, M2::Action> {
+        match action {
+            BoxAction::Left(a1) => (Some(a1), None),
+            BoxAction::Right(a2) => (None, Some(a2)),
+        }
+    }
+}
+
+impl<M1, M2> FactoredProduct for CartesianProduct<M1, M2>
+where
+    M1: MDP,
+    M2: MDP,
+    M1::State: Clone,
+    M2::State: Clone,
+    M1::Action: Clone,
+    M2::Action: Clone,
+{
+    type First = M1;
+    type Second = M2;
+
+    fn factors(&self) -> (&M1, &M2) {
+        self.components()
+    }
+
+    fn split_action(action: &Self::Action) -> ComponentActions<'_, M1::Action, M2::Action> {
+        (Some(action.first()), Some(action.second()))
+    }
+}
+
+/// How component values are combined into the score of a product action
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum QCombination {
+    /// `Σ_i term_i`, the additive decomposition of the product value
+    #[default]
+    Sum,
+    /// `max_i term_i`, an optimistic "best component" score
+    Max,
+}
+
+/// Per-component Q-tables learned by `factored_q_learning`
+pub struct FactoredActionValues<S1: State, A1: Action, S2: State, A2: Action> {
+    pub first: ActionValue<S1, A1>,
+    pub second: ActionValue<S2, A2>,
+    pub combination: QCombination,
+}
+
+/// The component tables `factored_q_learning` learns on a product `P`
+pub type FactoredValues<P> = FactoredActionValues<
+    <<P as FactoredProduct>::First as MDP>::State,
+    <<P as FactoredProduct>::First as MDP>::Action,
+    <<P as FactoredProduct>::Second as MDP>::State,
+    <<P as FactoredProduct>::Second as MDP>::Action,
+>;
+
+/// `max_a Q(s, a)` over the actions available at `state`; zero at final states and dead ends
+fn state_value<M>(mdp: &M, action_value: &ActionValue<M::State, M::Action>, state: &M::State) -> f64
+where
+    M: MDP,
+{
+    if mdp.is_final_state(state) {
+        return 0.0;
+    }
+    mdp.actions_at(state)
+        .iter()
+        .map(|a| action_value.get(state, a))
+        .fold(None, |best: Option<f64>, v| {
+            Some(best.map_or(v, |b| b.max(v)))
+        })
+        .unwrap_or(0.0)
+}
+
+impl<S1: State, A1: Action, S2: State, A2: Action> FactoredActionValues<S1, A1, S2, A2> {
+    /// Scores a product action: each component contributes `Q_i(s_i, a_i)` if the action moves it
+    /// and `V_i(s_i)` otherwise, and the contributions are combined per `combination`
+    pub fn score<P>(&self, product: &P, state: &P::State, action: &P::Action) -> f64
+    where
+        P: FactoredProduct,
+        P::First: MDP<State = S1, Action = A1>,
+        P::Second: MDP<State = S2, Action = A2>,
+    {
+        let (mdp1, mdp2) = product.factors();
+        let (a1, a2) = P::split_action(action);
+        let term1 = match a1 {
+            Some(a1) => self.first.get(state.first(), a1),
+            None => state_value(mdp1, &self.first, state.first()),
+        };
+        let term2 = match a2 {
+            Some(a2) => self.second.get(state.second(), a2),
+            None => state_value(mdp2, &self.second, state.second()),
+        };
+        match self.combination {
+            QCombination::Sum => term1 + term2,
+            QCombination::Max => term1.max(term2),
+        }
+    }
+
+    /// The best-scoring product action at `state`, first listed wins ties
+    pub fn greedy_action<P>(&self, product: &P, state: &P::State) -> Option<P::Action>
+    where
+        P: FactoredProduct,
+        P::First: MDP<State = S1, Action = A1>,
+        P::Second: MDP<State = S2, Action = A2>,
+    {
+        let mut best: Option<(P::Action, f64)> = None;
+        for action in product.actions_at(state) {
+            let value = self.score(product, state, &action);
+            if best
+                .as_ref()
+                .is_none_or(|(_, best_value)| value > *best_value)
+            {
+                best = Some((action, value));
+            }
+        }
+        best.map(|(action, _)| action)
+    }
+
+    /// The greedy policy of each component table on its own MDP, ready to be lifted to the
+    /// product with `lift_policies`
+    pub fn component_policies<P>(
+        &self,
+        product: &P,
+    ) -> (DeterministicPolicy<S1, A1>, DeterministicPolicy<S2, A2>)
+    where
+        P: FactoredProduct,
+        P::First: MDP<State = S1, Action = A1>,
+        P::Second: MDP<State = S2, Action = A2>,
+    {
+        let (mdp1, mdp2) = product.factors();
+        (greedy_policy(mdp1, &self.first), greedy_policy(mdp2, &self.second))
+    }
+
+    /// The greedy product policy over every non-final product state
+    pub fn greedy_policy<P>(&self, product: &P) -> DeterministicPolicy<P::State, P::Action>
+    where
+        P: FactoredProduct,
+        P::First: MDP<State = S1, Action = A1>,
+        P::Second: MDP<State = S2, Action = A2>,
+    {
+        let mut policy = HashMap::new();
+        for state in product.all_states().iter() {
+            if product.is_final_state(state) {
+                continue;
+            }
+            if let Some(action) = self.greedy_action(product, state) {
+                policy.insert(state.clone(), action);
+            }
+        }
+        policy
+    }
+}
+
+/// Samples one step of a component and moves its Q-value toward `r + γ V(s')`
+fn component_step<M, R>(
+    mdp: &M,
+    action_value: &mut ActionValue<M::State, M::Action>,
+    state: &M::State,
+    action: &M::Action,
+    config: &TrainingConfig,
+    rng: &mut R,
+) -> Result<M::State, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    R: Rng + ?Sized,
+{
+    let (measure, reward) = mdp.stochastic_transition(state, action)?;
+    let next_state = measure.sample_with(rng).cloned().unwrap_or_else(|| state.clone());
+    let target = reward + config.discount_factor * state_value(mdp, action_value, &next_state);
+    let current_q = action_value.get(state, action);
+    action_value.insert(
+        state,
+        action,
+        current_q + config.learning_rate * (target - current_q),
+    );
+    Ok(next_state)
+}
+
+/// # Factored Q-Learning
+///
+/// Learns one Q-table per component of a `BoxProduct` or `CartesianProduct`.
+///
+/// # Arguments
+///
+/// * `product` - The product MDP to learn on
+/// * `config` - Training configuration; episodes start from random product states
+/// * `combination` - How component values are combined when scoring product actions
+///
+/// # Returns
+///
+/// The component Q-tables. Behaviour is ε-greedy over the combined scores; each step simulates
+/// only the components the chosen action moves and updates only their tables, with one-step
+/// Q-learning targets computed from the component's own reward and next state.
+pub fn factored_q_learning<P>(
+    product: &P,
+    config: &TrainingConfig,
+    combination: QCombination,
+) -> Result<FactoredValues<P>, Error>
+where
+    P: FactoredProduct,
+    <P::First as MDP>::State: Clone,
+    <P::First as MDP>::Action: Clone,
+    <P::Second as MDP>::State: Clone,
+    <P::Second as MDP>::Action: Clone,
+{
+    let (mdp1, mdp2) = product.factors();
+    let mut values = FactoredActionValues {
+        first: ActionValue::new(mdp1.all_states(), &action_union(mdp1)),
+        second: ActionValue::new(mdp2.all_states(), &action_union(mdp2)),
+        combination,
+    };
+    let mut rng = config.rng();
+
+    for _ in 0..config.num_episodes {
+        let mut state = random_start(product.all_states(), &mut rng)?.clone();
+
+        for _ in 0..config.max_num_steps {
+            if product.is_final_state(&state) {
+                break;
+            }
+            let actions = product.actions_at(&state);
+            if actions.is_empty() {
+                break;
+            }
+            let action = if rng.random::<f64>() < config.exploration_rate {
+                actions[rng.random_range(0..actions.len())].clone()
+            } else {
+                match values.greedy_action(product, &state) {
+                    Some(action) => action,
+                    None => break,
+                }
+            };
+
+            let (a1, a2) = P::split_action(&action);
+            let next1 = match a1 {
+                Some(a1) => component_step(
+                    mdp1,
+                    &mut values.first,
+                    state.first(),
+                    a1,
+                    config,
+                    &mut rng,
+                )?,
+                None => state.first().clone(),
+            };
+            let next2 = match a2 {
+                Some(a2) => component_step(
+                    mdp2,
+                    &mut values.second,
+                    state.second(),
+                    a2,
+                    config,
+                    &mut rng,
+                )?,
+                None => state.second().clone(),
+            };
+            state = Product::new(next1, next2);
+        }
+    }
+
+    Ok(values)
+}
@@ -0,0 +1,217 @@
+//! # Transition Graph Analysis
+//!
+//! Structural checks on the directed graph with an edge `s → s'` whenever some action available
+//! at a non-final state `s` reaches `s'` with positive probability. Final states have no outgoing
+//! edges. Next states outside `all_states()` are ignored here (see the validator for those).
+
+use std::collections::HashMap;
+
+use madepro::models::State;
+
+use crate::error::Error;
+use crate::mdp::MDP;
+
+/// Summary of an MDP's transition graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphStatistics {
+    pub num_states: usize,
+    /// Distinct `s → s'` edges
+    pub num_edges: usize,
+    pub num_final_states: usize,
+    pub num_components: usize,
+    /// Size of the largest strongly connected component
+    pub largest_component: usize,
+    /// Non-final states from which no final state can be reached
+    pub num_dead_ends: usize,
+}
+
+struct TransitionGraph<S> {
+    states: Vec<S>,
+    is_final: Vec<bool>,
+    successors: Vec<Vec<usize>>,
+}
+
+impl<S: State> TransitionGraph<S> {
+    fn from_mdp<M>(mdp: &M) -> Result<Self, Error>
+    where
+        M: MDP<State = S>,
+    {
+        let states: Vec<S> = mdp.all_states().iter().cloned().collect();
+        let index: HashMap<&S, usize> = states.iter().enumerate().map(|(i, s)| (s, i)).collect();
+        let mut is_final = Vec::with_capacity(states.len());
+        let mut successors = Vec::with_capacity(states.len());
+
+        for state in &states {
+            let final_state = mdp.is_final_state(state);
+            let mut next: Vec<usize> = Vec::new();
+            if !final_state {
+                for action in mdp.actions_at(state) {
+                    let (measure, _) = mdp.stochastic_transition(state, &action)?;
+                    next.extend(
+                        measure
+                            .dist()
+                            .iter()
+                            .filter(|(_, p)| p.value() > 0.0)
+                            .filter_map(|(s, _)| index.get(s).copied()),
+                    );
+                }
+            }
+            next.sort_unstable();
+            next.dedup();
+            is_final.push(final_state);
+            successors.push(next);
+        }
+
+        Ok(TransitionGraph {
+            states,
+            is_final,
+            successors,
+        })
+    }
+
+    /// Tarjan's algorithm with an explicit stack, so deep chains don't overflow the call stack
+    fn components(&self) -> Vec<Vec<usize>> {
+        let n = self.states.len();
+        let mut index = vec![usize::MAX; n];
+        let mut low = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack = Vec::new();
+        let mut components = Vec::new();
+        let mut next_index = 0;
+
+        for root in 0..n {
+            if index[root] != usize::MAX {
+                continue;
+            }
+            // (node, position of the next successor to visit)
+            let mut call_stack = vec![(root, 0)];
+            while let Some(&mut (v, ref mut child)) = call_stack.last_mut() {
+                if *child == 0 && index[v] == usize::MAX {
+                    index[v] = next_index;
+                    low[v] = next_index;
+                    next_index += 1;
+                    stack.push(v);
+                    on_stack[v] = true;
+                }
+                if let Some(&w) = self.successors[v].get(*child) {
+                    *child += 1;
+                    if index[w] == usize::MAX {
+                        call_stack.push((w, 0));
+                    } else if on_stack[w] {
+                        low[v] = low[v].min(index[w]);
+                    }
+                    continue;
+                }
+
+                call_stack.pop();
+                if let Some(&(parent, _)) = call_stack.last() {
+                    low[parent] = low[parent].min(low[v]);
+                }
+                if low[v] == index[v] {
+                    let mut component = Vec::new();
+                    while let Some(w) = stack.pop() {
+                        on_stack[w] = false;
+                        component.push(w);
+                        if w == v {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+        components
+    }
+
+    /// Marks the states from which some final state is reachable
+    fn can_terminate(&self) -> Vec<bool> {
+        let n = self.states.len();
+        let mut predecessors = vec![Vec::new(); n];
+        for (v, next) in self.successors.iter().enumerate() {
+            for &w in next {
+                predecessors[w].push(v);
+            }
+        }
+        let mut reached = self.is_final.clone();
+        let mut frontier: Vec<usize> = (0..n).filter(|&v| reached[v]).collect();
+        while let Some(w) = frontier.pop() {
+            for &v in &predecessors[w] {
+                if !reached[v] {
+                    reached[v] = true;
+                    frontier.push(v);
+                }
+            }
+        }
+        reached
+    }
+}
+
+/// The strongly connected components of the transition graph, in reverse topological order
+/// (a component's successors come before it)
+pub fn strongly_connected_components<M>(mdp: &M) -> Result<Vec<Vec<M::State>>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+{
+    let graph = TransitionGraph::from_mdp(mdp)?;
+    Ok(graph
+        .components()
+        .into_iter()
+        .map(|component| {
+            component
+                .into_iter()
+                .map(|i| graph.states[i].clone())
+                .collect()
+        })
+        .collect())
+}
+
+/// Non-final states from which no final state is reachable under any sequence of actions.
+/// Episodes entering them can only end by truncation.
+pub fn dead_end_states<M>(mdp: &M) -> Result<Vec<M::State>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+{
+    let graph = TransitionGraph::from_mdp(mdp)?;
+    let can_terminate = graph.can_terminate();
+    Ok(graph
+        .states
+        .iter()
+        .zip(can_terminate)
+        .filter(|(_, reachable)| !reachable)
+        .map(|(s, _)| s.clone())
+        .collect())
+}
+
+/// Fails with `Error::NonTerminatingEpisode` if the MDP has dead-end states, i.e. if some
+/// episode can run forever whatever the agent does
+pub fn check_termination<M>(mdp: &M) -> Result<(), Error>
+where
+    M: MDP,
+    M::State: Clone,
+{
+    let graph = TransitionGraph::from_mdp(mdp)?;
+    if graph.can_terminate().iter().all(|r| *r) {
+        Ok(())
+    } else {
+        Err(Error::NonTerminatingEpisode)
+    }
+}
+
+pub fn graph_statistics<M>(mdp: &M) -> Result<GraphStatistics, Error>
+where
+    M: MDP,
+    M::State: Clone,
+{
+    let graph = TransitionGraph::from_mdp(mdp)?;
+    let components = graph.components();
+    Ok(GraphStatistics {
+        num_states: graph.states.len(),
+        num_edges: graph.successors.iter().map(Vec::len).sum(),
+        num_final_states: graph.is_final.iter().filter(|f| **f).count(),
+        num_components: components.len(),
+        largest_component: components.iter().map(Vec::len).max().unwrap_or(0),
+        num_dead_ends: graph.can_terminate().iter().filter(|r| !**r).count(),
+    })
+}
@@ -1,8 +1,9 @@
-use crate::{mdp, measure::Measure};
-use madepro::environments::gridworld::Gridworld;
+use crate::{mdp, measure::{Measure, MeasureBuilder}};
+use madepro::environments::gridworld::{Cell, Gridworld};
 use madepro::environments::gridworld::{GridworldAction, GridworldState};
-use madepro::models::{MDP, Sampler};
+use madepro::models::{Action, MDP, Sampler, State};
 use crate::error::Error;
+use std::collections::HashMap;
 use std::ops::Deref;
 
 impl mdp::MDP for Gridworld {
@@ -17,7 +18,7 @@ impl mdp::MDP for Gridworld {
         self.is_state_terminal(state)
     }
 
-    fn actions_at(&self, _state: &Self::State) -> Vec<Self::Action> {
+    fn all_actions(&self) -> Vec<Self::Action> {
         self.get_actions().iter().cloned().collect()
     }
 
@@ -32,9 +33,75 @@ impl mdp::MDP for Gridworld {
     }
 }
 
+/// Kinds of ground with a conventional reward for entering them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Terrain {
+    /// Slow going: `-2` on top of the step reward
+    Mud,
+    /// Dangerous: `-50` on top of the step reward
+    Lava,
+    /// A pickup worth `+5` every time the cell is entered
+    Bonus,
+    /// Any other extra reward
+    Custom(f64),
+}
+
+impl Terrain {
+    pub fn reward(&self) -> f64 {
+        match *self {
+            Terrain::Mud => -2.0,
+            Terrain::Lava => -50.0,
+            Terrain::Bonus => 5.0,
+            Terrain::Custom(reward) => reward,
+        }
+    }
+}
+
+/// What arriving at a goal pays and whether it ends the episode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Goal {
+    /// Added to the step reward of every move that arrives at the goal
+    pub reward: f64,
+    pub ends_episode: bool,
+}
+
+impl Goal {
+    /// A goal that pays `reward` and ends the episode
+    pub fn terminal(reward: f64) -> Self {
+        Goal {
+            reward,
+            ends_episode: true,
+        }
+    }
+
+    /// A goal that pays `reward` every time it is entered and lets the episode go on
+    pub fn non_terminal(reward: f64) -> Self {
+        Goal {
+            reward,
+            ends_episode: false,
+        }
+    }
+}
+
 pub struct GridworldWithGoals {
     gridworld: Gridworld,
     goal_states: Vec<GridworldState>,
+    /// Goals without an entry keep the underlying gridworld's semantics: no extra reward, and
+    /// final exactly when their cell is `Cell::End`
+    goal_configs: HashMap<GridworldState, Goal>,
+    /// Extra reward for arriving in a cell, added to the underlying gridworld's step reward
+    cell_rewards: HashMap<GridworldState, f64>,
+    /// Entrance → (exit, probability of being transported)
+    portals: HashMap<GridworldState, (GridworldState, f64)>,
+    start: Option<GridworldState>,
+    /// Known when built from a map; `GridworldState` does not expose its coordinates
+    layout: Option<Layout>,
+}
+
+/// The cells of a gridworld and the coordinates of its states
+struct Layout {
+    cells: Vec<Vec<Cell>>,
+    positions: HashMap<GridworldState, GridPosition>,
 }
 
 impl Deref for GridworldWithGoals {
@@ -50,6 +117,11 @@ impl From<Gridworld> for GridworldWithGoals {
         GridworldWithGoals {
             gridworld,
             goal_states: Vec::new(), // Empty by default
+            goal_configs: HashMap::new(),
+            cell_rewards: HashMap::new(),
+            portals: HashMap::new(),
+            start: None,
+            layout: None,
         }
     }
 }
@@ -59,11 +131,273 @@ impl GridworldWithGoals {
         GridworldWithGoals {
             gridworld,
             goal_states,
+            goal_configs: HashMap::new(),
+            cell_rewards: HashMap::new(),
+            portals: HashMap::new(),
+            start: None,
+            layout: None,
         }
     }
     pub fn get_goals(&self) -> &Vec<GridworldState> {
         &self.goal_states
     }
+
+    /// # From ASCII
+    ///
+    /// Builds a gridworld from a map with one line per row: `#` is a wall, `.` open ground, `G`
+    /// a goal (an end cell) and `S` the start, which is open ground too. Leading and trailing
+    /// whitespace and blank lines are ignored, so maps can be indented raw strings. Every
+    /// non-wall cell is a state, in row-major order, and all four moves are available.
+    ///
+    /// ```text
+    /// S..#
+    /// .#.#
+    /// ...G
+    /// ```
+    ///
+    /// # Arguments
+    /// * `map` - The map, one line per row
+    ///
+    /// # Returns
+    /// The gridworld, or `Error::Specification` naming the line and column of an unknown
+    /// character, a row whose length differs from the first, or a second `S`;
+    /// `Error::EmptyStateSpace` if the map has no open cells
+    pub fn from_ascii(map: &str) -> Result<Self, Error> {
+        let mut cells: Vec<Vec<Cell>> = Vec::new();
+        let mut goals = Vec::new();
+        let mut start: Option<GridPosition> = None;
+        let rows = map.lines().map(str::trim).filter(|line| !line.is_empty());
+        for (row, line) in rows.enumerate() {
+            let mut cell_row = Vec::new();
+            for (col, c) in line.chars().enumerate() {
+                let position = GridPosition::new(row, col);
+                let cell = match c {
+                    '#' => Cell::Wall,
+                    '.' => Cell::Air,
+                    'G' => {
+                        goals.push(position);
+                        Cell::End
+                    }
+                    'S' => {
+                        if let Some(first) = start {
+                            return Err(Error::Specification(format!(
+                                "line {}, column {}: second start 'S' (the first is at line {}, \
+                                 column {})",
+                                row + 1,
+                                col + 1,
+                                first.row + 1,
+                                first.col + 1
+                            )));
+                        }
+                        start = Some(position);
+                        Cell::Air
+                    }
+                    other => {
+                        return Err(Error::Specification(format!(
+                            "line {}, column {}: unexpected character '{other}', expected '#', \
+                             '.', 'G' or 'S'",
+                            row + 1,
+                            col + 1
+                        )));
+                    }
+                };
+                cell_row.push(cell);
+            }
+            if let Some(width) = cells.first().map(Vec::len)
+                && cell_row.len() != width
+            {
+                return Err(Error::Specification(format!(
+                    "line {} has {} cells, but the first line has {width}",
+                    row + 1,
+                    cell_row.len()
+                )));
+            }
+            cells.push(cell_row);
+        }
+        Self::from_layout(cells, goals, start)
+    }
+
+    /// A gridworld over `cells` with all four moves; `goals` and `start` must be open cells
+    pub(crate) fn from_layout(
+        cells: Vec<Vec<Cell>>,
+        goals: Vec<GridPosition>,
+        start: Option<GridPosition>,
+    ) -> Result<Self, Error> {
+        let mut states = Vec::new();
+        let mut positions = HashMap::new();
+        for (row, cell_row) in cells.iter().enumerate() {
+            for (col, cell) in cell_row.iter().enumerate() {
+                if *cell == Cell::Wall {
+                    continue;
+                }
+                let state = GridworldState::new(row, col);
+                positions.insert(state.clone(), GridPosition::new(row, col));
+                states.push(state);
+            }
+        }
+        if states.is_empty() {
+            return Err(Error::EmptyStateSpace);
+        }
+        let to_state = |p: GridPosition| GridworldState::new(p.row, p.col);
+        let gridworld = Gridworld::new(cells.clone(), states, compass_actions());
+        let goals = goals.into_iter().map(to_state).collect();
+        let mut gridworld = GridworldWithGoals::new(gridworld, goals);
+        gridworld.start = start.map(to_state);
+        gridworld.layout = Some(Layout { cells, positions });
+        Ok(gridworld)
+    }
+
+    /// The start cell, if the map marked one
+    pub fn get_start(&self) -> Option<&GridworldState> {
+        self.start.as_ref()
+    }
+
+    /// The coordinates of `state`, known for gridworlds built from a map
+    pub fn position(&self, state: &GridworldState) -> Option<GridPosition> {
+        self.layout.as_ref()?.positions.get(state).copied()
+    }
+
+    /// The cells, row by row, for gridworlds built from a map
+    pub fn cells(&self) -> Option<&[Vec<Cell>]> {
+        self.layout.as_ref().map(|layout| layout.cells.as_slice())
+    }
+
+    /// Makes the cell at `(row, col)` a goal (if it is not one already) with its own reward and
+    /// episode semantics. A terminal goal is final even on an open cell, and a non-terminal one
+    /// is not final even on a `Cell::End`.
+    pub fn with_goal(mut self, row: usize, col: usize, goal: Goal) -> Self {
+        let state = GridworldState::new(row, col);
+        if !self.goal_states.contains(&state) {
+            self.goal_states.push(state.clone());
+        }
+        self.goal_configs.insert(state, goal);
+        self
+    }
+
+    /// Gives every goal so far (e.g. every `G` of a map) the same configuration
+    pub fn with_all_goals(mut self, goal: Goal) -> Self {
+        for state in &self.goal_states {
+            self.goal_configs.insert(state.clone(), goal);
+        }
+        self
+    }
+
+    /// The configuration of the goal `state`, if it has one
+    pub fn goal_config(&self, state: &GridworldState) -> Option<&Goal> {
+        self.goal_configs.get(state)
+    }
+
+    /// Adds `reward` for arriving in the cell at `(row, col)`, on top of any reward it already
+    /// carries. Moves that bump into a wall stay in their cell and so pay its reward again.
+    pub fn with_cell_reward(mut self, row: usize, col: usize, reward: f64) -> Self {
+        *self
+            .cell_rewards
+            .entry(GridworldState::new(row, col))
+            .or_insert(0.0) += reward;
+        self
+    }
+
+    /// Adds the reward of `terrain` to the cell at `(row, col)`
+    pub fn with_terrain(self, row: usize, col: usize, terrain: Terrain) -> Self {
+        self.with_cell_reward(row, col, terrain.reward())
+    }
+
+    /// Adds `rewards[row][col]` to every cell; rows may be shorter than the grid, and zeros
+    /// are skipped
+    pub fn with_reward_grid(self, rewards: &[Vec<f64>]) -> Self {
+        let mut gridworld = self;
+        for (row, values) in rewards.iter().enumerate() {
+            for (col, &reward) in values.iter().enumerate() {
+                if reward != 0.0 {
+                    gridworld = gridworld.with_cell_reward(row, col, reward);
+                }
+            }
+        }
+        gridworld
+    }
+
+    /// The extra reward for arriving in `state` (zero for plain cells)
+    pub fn cell_reward(&self, state: &GridworldState) -> f64 {
+        self.cell_rewards.get(state).copied().unwrap_or(0.0)
+    }
+
+    /// # With Portal
+    ///
+    /// A one-way portal: a move that ends on `entrance` (bumping into a wall next to it
+    /// included) transports the agent on to `exit` with probability `probability` and leaves it
+    /// on the entrance otherwise. Arriving at `exit` by a portal does not trigger a portal
+    /// there. Cell and goal rewards are those of the cell the agent ends up in.
+    ///
+    /// # Arguments
+    /// * `entrance` - `(row, col)` of the entrance
+    /// * `exit` - `(row, col)` of the exit
+    /// * `probability` - Chance the portal works (`1.0` for a deterministic one)
+    ///
+    /// # Returns
+    /// The gridworld, `Error::ProbabilityOutOfRange` unless `0 ≤ probability ≤ 1`, or
+    /// `Error::StateNotFound` if either end is not a state
+    pub fn with_portal(
+        mut self,
+        entrance: (usize, usize),
+        exit: (usize, usize),
+        probability: f64,
+    ) -> Result<Self, Error> {
+        if !(0.0..=1.0).contains(&probability) {
+            return Err(Error::ProbabilityOutOfRange);
+        }
+        let entrance = GridworldState::new(entrance.0, entrance.1);
+        let exit = GridworldState::new(exit.0, exit.1);
+        let states = self.gridworld.get_states();
+        if !(states.iter().any(|s| *s == entrance) && states.iter().any(|s| *s == exit)) {
+            return Err(Error::StateNotFound);
+        }
+        self.portals.insert(entrance, (exit, probability));
+        Ok(self)
+    }
+
+    /// Portals both ways between `a` and `b`, each working with probability `probability`
+    pub fn with_portal_pair(
+        self,
+        a: (usize, usize),
+        b: (usize, usize),
+        probability: f64,
+    ) -> Result<Self, Error> {
+        self.with_portal(a, b, probability)?
+            .with_portal(b, a, probability)
+    }
+
+    /// The exit of the portal at `state` and the probability it works, if there is one
+    pub fn portal(&self, state: &GridworldState) -> Option<&(GridworldState, f64)> {
+        self.portals.get(state)
+    }
+
+    /// Sends the mass of `measure` on portal entrances through the portals
+    fn through_portals(
+        &self,
+        measure: Measure<GridworldState>,
+    ) -> Result<Measure<GridworldState>, Error> {
+        if self.portals.is_empty() {
+            return Ok(measure);
+        }
+        let mut builder = MeasureBuilder::new();
+        for (next, p) in measure.dist() {
+            match self.portals.get(next) {
+                Some((exit, works)) => {
+                    builder = builder
+                        .add(exit.clone(), p.value() * works)
+                        .add(next.clone(), p.value() * (1.0 - works));
+                }
+                None => builder = builder.add(next.clone(), p.value()),
+            }
+        }
+        builder.build()
+    }
+
+    /// Cell and goal rewards for arriving in `state`
+    fn arrival_reward(&self, state: &GridworldState) -> f64 {
+        let goal_reward = self.goal_configs.get(state).map_or(0.0, |goal| goal.reward);
+        self.cell_reward(state) + goal_reward
+    }
 }
 
 impl mdp::MDP for GridworldWithGoals {
@@ -74,23 +408,860 @@ impl mdp::MDP for GridworldWithGoals {
         self.get_states()
     }
 
-    fn actions_at(&self, _state: &Self::State) -> Vec<Self::Action> {
+    fn all_actions(&self) -> Vec<Self::Action> {
         self.get_actions().iter().cloned().collect()
     }
 
+    /// Configured goals are final when they end the episode; other states when their cell is
+    /// `Cell::End`
     fn is_final_state(&self, state: &Self::State) -> bool {
-        self.is_state_terminal(state)
+        match self.goal_configs.get(state) {
+            Some(goal) => goal.ends_episode,
+            None => self.is_state_terminal(state),
+        }
     }
 
     fn is_goal(&self, st: &Self::State) -> bool {
         self.goal_states.contains(st)
     }
 
+    /// The underlying step reward plus the expected cell and goal rewards of the state the
+    /// agent ends up in, after any portal
     fn stochastic_transition(
         &self,
         state: &Self::State,
         action: &Self::Action,
     ) -> Result<(Measure<GridworldState>, f64), Error> {
-        self.gridworld.stochastic_transition(state, action)
+        let (measure, reward) = self.gridworld.stochastic_transition(state, action)?;
+        let measure = self.through_portals(measure)?;
+        let extra = measure.expectation(|next| self.arrival_reward(next));
+        Ok((measure, reward + extra))
+    }
+}
+
+/// A move on a grid with diagonals, plus staying in place
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GridMove {
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+    Stay,
+}
+
+impl Action for GridMove {}
+
+impl GridMove {
+    /// `(row, column)` change of the move
+    pub fn offset(&self) -> (isize, isize) {
+        match self {
+            GridMove::Up => (-1, 0),
+            GridMove::Down => (1, 0),
+            GridMove::Left => (0, -1),
+            GridMove::Right => (0, 1),
+            GridMove::UpLeft => (-1, -1),
+            GridMove::UpRight => (-1, 1),
+            GridMove::DownLeft => (1, -1),
+            GridMove::DownRight => (1, 1),
+            GridMove::Stay => (0, 0),
+        }
+    }
+
+    pub fn is_diagonal(&self) -> bool {
+        let (dr, dc) = self.offset();
+        dr != 0 && dc != 0
+    }
+
+    /// The factor a move's step cost is scaled by: `√2` for diagonals, `1` otherwise (staying
+    /// costs a step too)
+    pub fn cost(&self) -> f64 {
+        if self.is_diagonal() {
+            std::f64::consts::SQRT_2
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Which moves a `GridworldWithMoves` offers besides the four compass moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveSet {
+    pub diagonals: bool,
+    pub stay: bool,
+}
+
+impl MoveSet {
+    /// The eight king moves, without `Stay`
+    pub fn eight_connected() -> Self {
+        MoveSet {
+            diagonals: true,
+            stay: false,
+        }
+    }
+
+    /// Adds `Stay`
+    pub fn with_stay(mut self) -> Self {
+        self.stay = true;
+        self
+    }
+
+    /// The moves in the set, compass moves first
+    pub fn moves(&self) -> Vec<GridMove> {
+        let mut moves = vec![GridMove::Up, GridMove::Down, GridMove::Left, GridMove::Right];
+        if self.diagonals {
+            moves.extend([
+                GridMove::UpLeft,
+                GridMove::UpRight,
+                GridMove::DownLeft,
+                GridMove::DownRight,
+            ]);
+        }
+        if self.stay {
+            moves.push(GridMove::Stay);
+        }
+        moves
+    }
+}
+
+/// # Gridworld With Moves
+///
+/// A `GridworldWithGoals` built from a map, with diagonal moves and/or `Stay` added. A move
+/// into a wall or off the grid leaves the agent in place (diagonals may cut corners between
+/// walls). Each move pays the step reward `-1` scaled by `GridMove::cost`, or `0` when it
+/// arrives in a `Cell::End` as in the underlying gridworld; portals, cell and goal rewards,
+/// goals and final states are those of the wrapped gridworld.
+pub struct GridworldWithMoves {
+    inner: GridworldWithGoals,
+    moves: Vec<GridMove>,
+}
+
+impl GridworldWithGoals {
+    /// Offers `moves` instead of the four compass moves; fails with `Error::Specification` if
+    /// the gridworld was not built from a map (`from_ascii`, `generate_maze`), since moves are
+    /// computed on its layout
+    pub fn with_moves(self, moves: MoveSet) -> Result<GridworldWithMoves, Error> {
+        if self.layout.is_none() {
+            return Err(Error::Specification(
+                "extra moves need a gridworld built from a map".to_string(),
+            ));
+        }
+        Ok(GridworldWithMoves {
+            inner: self,
+            moves: moves.moves(),
+        })
+    }
+}
+
+impl Deref for GridworldWithMoves {
+    type Target = GridworldWithGoals;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl GridworldWithMoves {
+    fn layout(&self) -> &Layout {
+        self.inner
+            .layout
+            .as_ref()
+            .expect("with_moves only wraps gridworlds with a layout")
+    }
+
+    /// The cell `action` leads to from `position`
+    fn target(&self, position: GridPosition, action: &GridMove) -> GridPosition {
+        let cells = &self.layout().cells;
+        let (dr, dc) = action.offset();
+        let is_open = |row: usize, col: usize| {
+            cells
+                .get(row)
+                .and_then(|r| r.get(col))
+                .is_some_and(|cell| *cell != Cell::Wall)
+        };
+        match (
+            position.row.checked_add_signed(dr),
+            position.col.checked_add_signed(dc),
+        ) {
+            (Some(row), Some(col)) if is_open(row, col) => GridPosition::new(row, col),
+            _ => position,
+        }
+    }
+}
+
+impl mdp::MDP for GridworldWithMoves {
+    type State = GridworldState;
+    type Action = GridMove;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        self.inner.all_states()
+    }
+
+    fn all_actions(&self) -> Vec<Self::Action> {
+        self.moves.clone()
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.inner.is_final_state(state)
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.inner.is_goal(state)
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        if !self.moves.contains(action) {
+            return Err(Error::action_not_available(state, action));
+        }
+        let position = self
+            .layout()
+            .positions
+            .get(state)
+            .copied()
+            .ok_or(Error::StateNotFound)?;
+        let target = self.target(position, action);
+        let next = GridworldState::new(target.row, target.col);
+        let step_reward = if self.layout().cells[target.row][target.col] == Cell::End {
+            0.0
+        } else {
+            -action.cost()
+        };
+        let measure = self
+            .inner
+            .through_portals(Measure::deterministic(next))?;
+        let extra = measure.expectation(|next| self.inner.arrival_reward(next));
+        Ok((measure, step_reward + extra))
+    }
+}
+
+/// A cell of the built-in grid environments below. Unlike `GridworldState`, it exposes its
+/// coordinates, which the environments need to apply wind and recognise special rows.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridPosition {
+    row: usize,
+    col: usize,
+}
+
+impl GridPosition {
+    pub const fn new(row: usize, col: usize) -> Self {
+        GridPosition { row, col }
+    }
+
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn col(&self) -> usize {
+        self.col
+    }
+}
+
+impl State for GridPosition {}
+
+fn compass_actions() -> Vec<GridworldAction> {
+    vec![
+        GridworldAction::Up,
+        GridworldAction::Down,
+        GridworldAction::Left,
+        GridworldAction::Right,
+    ]
+}
+
+/// The neighbour of `position` in the direction of `action`, staying put at the border
+pub(crate) fn move_within(
+    position: GridPosition,
+    action: &GridworldAction,
+    rows: usize,
+    cols: usize,
+) -> GridPosition {
+    let GridPosition { row, col } = position;
+    match action {
+        GridworldAction::Up => GridPosition::new(row.saturating_sub(1), col),
+        GridworldAction::Down => GridPosition::new((row + 1).min(rows - 1), col),
+        GridworldAction::Left => GridPosition::new(row, col.saturating_sub(1)),
+        GridworldAction::Right => GridPosition::new(row, (col + 1).min(cols - 1)),
+    }
+}
+
+fn check_in_grid(
+    name: &str,
+    position: GridPosition,
+    rows: usize,
+    cols: usize,
+) -> Result<(), Error> {
+    if position.row < rows && position.col < cols {
+        Ok(())
+    } else {
+        Err(Error::Specification(format!(
+            "{name} ({}, {}) lies outside the {rows}x{cols} grid",
+            position.row, position.col
+        )))
+    }
+}
+
+/// # Windy Gridworld
+///
+/// Sutton & Barto's windy gridworld (Example 6.5): an open grid where every column blows the
+/// agent `wind[col]` cells upwards after each move, measured in the column it leaves. Every step
+/// costs `-1` until the goal is reached. With stochastic wind the push is one weaker, as given,
+/// or one stronger with probability 1/3 each (Exercise 6.10).
+pub struct WindyGridworld {
+    rows: usize,
+    cols: usize,
+    wind: Vec<usize>,
+    start: GridPosition,
+    goal: GridPosition,
+    stochastic_wind: bool,
+    states: Sampler<GridPosition>,
+}
+
+impl WindyGridworld {
+    /// A `rows × cols` grid with one wind strength per column; fails with
+    /// `Error::Specification` if `wind` does not have `cols` entries or `start`/`goal` lie
+    /// outside the grid
+    pub fn new(
+        rows: usize,
+        cols: usize,
+        wind: Vec<usize>,
+        start: GridPosition,
+        goal: GridPosition,
+    ) -> Result<Self, Error> {
+        if rows == 0 || cols == 0 {
+            return Err(Error::EmptyStateSpace);
+        }
+        if wind.len() != cols {
+            return Err(Error::Specification(format!(
+                "expected {cols} wind strengths, one per column, got {}",
+                wind.len()
+            )));
+        }
+        check_in_grid("start", start, rows, cols)?;
+        check_in_grid("goal", goal, rows, cols)?;
+        let states = (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| GridPosition::new(row, col)))
+            .collect::<Vec<_>>();
+        Ok(WindyGridworld {
+            rows,
+            cols,
+            wind,
+            start,
+            goal,
+            stochastic_wind: false,
+            states: states.into(),
+        })
+    }
+
+    /// The 7×10 layout from the book: wind `0 0 0 1 1 1 2 2 1 0`, start `(3, 0)`, goal `(3, 7)`
+    pub fn classic() -> Self {
+        WindyGridworld::new(
+            7,
+            10,
+            vec![0, 0, 0, 1, 1, 1, 2, 2, 1, 0],
+            GridPosition::new(3, 0),
+            GridPosition::new(3, 7),
+        )
+        .expect("the classic windy gridworld is well formed")
+    }
+
+    /// Makes the wind in windy columns vary by one cell either way
+    pub fn with_stochastic_wind(mut self) -> Self {
+        self.stochastic_wind = true;
+        self
+    }
+
+    /// `(rows, cols)` of the grid
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    pub fn start(&self) -> GridPosition {
+        self.start
+    }
+
+    pub fn goal(&self) -> GridPosition {
+        self.goal
+    }
+
+    pub fn wind(&self) -> &[usize] {
+        &self.wind
+    }
+}
+
+impl mdp::MDP for WindyGridworld {
+    type State = GridPosition;
+    type Action = GridworldAction;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        &self.states
+    }
+
+    fn all_actions(&self) -> Vec<Self::Action> {
+        compass_actions()
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        *state == self.goal
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        if state.row >= self.rows || state.col >= self.cols {
+            return Err(Error::StateNotFound);
+        }
+        let moved = move_within(*state, action, self.rows, self.cols);
+        let blown =
+            |strength: usize| GridPosition::new(moved.row.saturating_sub(strength), moved.col);
+        let strength = self.wind[state.col];
+        let measure = if self.stochastic_wind && strength > 0 {
+            Measure::uniform([blown(strength - 1), blown(strength), blown(strength + 1)])?
+        } else {
+            Measure::deterministic(blown(strength))
+        };
+        Ok((measure, -1.0))
+    }
+}
+
+/// # Cliff Walking
+///
+/// Sutton & Barto's cliff walking task (Example 6.6): the agent starts in the bottom-left
+/// corner and must reach the bottom-right one. The cells between them form a cliff; stepping
+/// into it costs `-100` and sends the agent back to the start, every other step costs `-1`.
+/// Q-learning learns the optimal path along the edge while SARSA, which accounts for its own
+/// exploration, learns the safer path further up. Cliff cells are not states.
+pub struct CliffWalking {
+    rows: usize,
+    cols: usize,
+    states: Sampler<GridPosition>,
+}
+
+impl CliffWalking {
+    /// Reward for stepping into the cliff
+    pub const CLIFF_REWARD: f64 = -100.0;
+
+    /// A `rows × cols` grid whose bottom row holds start, cliff and goal; fails with
+    /// `Error::Specification` unless there are at least two rows and three columns
+    pub fn new(rows: usize, cols: usize) -> Result<Self, Error> {
+        if rows < 2 || cols < 3 {
+            return Err(Error::Specification(format!(
+                "cliff walking needs at least 2 rows and 3 columns, got {rows}x{cols}"
+            )));
+        }
+        let states = (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| GridPosition::new(row, col)))
+            .filter(|&position| !is_cliff(position, rows, cols))
+            .collect::<Vec<_>>();
+        Ok(CliffWalking {
+            rows,
+            cols,
+            states: states.into(),
+        })
+    }
+
+    /// The 4×12 layout from the book
+    pub fn classic() -> Self {
+        CliffWalking::new(4, 12).expect("the classic cliff walking grid is well formed")
+    }
+
+    /// `(rows, cols)` of the grid
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    pub fn start(&self) -> GridPosition {
+        GridPosition::new(self.rows - 1, 0)
+    }
+
+    pub fn goal(&self) -> GridPosition {
+        GridPosition::new(self.rows - 1, self.cols - 1)
+    }
+
+    pub fn is_cliff(&self, position: &GridPosition) -> bool {
+        is_cliff(*position, self.rows, self.cols)
+    }
+}
+
+fn is_cliff(position: GridPosition, rows: usize, cols: usize) -> bool {
+    position.row == rows - 1 && position.col > 0 && position.col < cols - 1
+}
+
+impl mdp::MDP for CliffWalking {
+    type State = GridPosition;
+    type Action = GridworldAction;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        &self.states
+    }
+
+    fn all_actions(&self) -> Vec<Self::Action> {
+        compass_actions()
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        *state == self.goal()
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        if state.row >= self.rows || state.col >= self.cols || self.is_cliff(state) {
+            return Err(Error::StateNotFound);
+        }
+        let next = move_within(*state, action, self.rows, self.cols);
+        if self.is_cliff(&next) {
+            Ok((Measure::deterministic(self.start()), Self::CLIFF_REWARD))
+        } else {
+            Ok((Measure::deterministic(next), -1.0))
+        }
+    }
+}
+
+/// The four-rooms layout inside its outer wall (`#` wall, `.` open)
+const FOUR_ROOMS_LAYOUT: [&str; 11] = [
+    ".....#.....",
+    ".....#.....",
+    "...........",
+    ".....#.....",
+    ".....#.....",
+    "#.####.....",
+    ".....###.##",
+    ".....#.....",
+    ".....#.....",
+    "...........",
+    ".....#.....",
+];
+
+/// # Four Rooms
+///
+/// The four-rooms domain of Sutton, Precup & Singh (1999), the standard testbed for options:
+/// an 11×11 grid split by walls into four rooms that are joined by single-cell hallways.
+/// Rooms are numbered `0` (top left), `1` (top right), `2` (bottom left) and `3` (bottom
+/// right). Moves into a wall or the border leave the agent in place, every step costs `-1`, and
+/// with a failure probability `p` the agent moves in one of the three other directions instead
+/// (uniformly, the paper uses `p = 1/3`).
+pub struct FourRooms {
+    open: Vec<Vec<bool>>,
+    goal: GridPosition,
+    failure_probability: f64,
+    states: Sampler<GridPosition>,
+}
+
+impl FourRooms {
+    pub const SIZE: usize = 11;
+
+    /// Deterministic four rooms with the given goal; fails with `Error::Specification` if the
+    /// goal is not an open cell
+    pub fn new(goal: GridPosition) -> Result<Self, Error> {
+        let open: Vec<Vec<bool>> = FOUR_ROOMS_LAYOUT
+            .iter()
+            .map(|row| row.chars().map(|c| c == '.').collect())
+            .collect();
+        check_in_grid("goal", goal, Self::SIZE, Self::SIZE)?;
+        if !open[goal.row][goal.col] {
+            return Err(Error::Specification(format!(
+                "goal ({}, {}) is a wall",
+                goal.row, goal.col
+            )));
+        }
+        let states = (0..Self::SIZE)
+            .flat_map(|row| (0..Self::SIZE).map(move |col| GridPosition::new(row, col)))
+            .filter(|p| open[p.row][p.col])
+            .collect::<Vec<_>>();
+        Ok(FourRooms {
+            open,
+            goal,
+            failure_probability: 0.0,
+            states: states.into(),
+        })
+    }
+
+    /// Moves fail with probability `p`; fails with `Error::ProbabilityOutOfRange` outside `[0, 1]`
+    pub fn with_failure_probability(mut self, p: f64) -> Result<Self, Error> {
+        if !(0.0..=1.0).contains(&p) {
+            return Err(Error::ProbabilityOutOfRange);
+        }
+        self.failure_probability = p;
+        Ok(self)
+    }
+
+    pub fn goal(&self) -> GridPosition {
+        self.goal
+    }
+
+    pub fn is_wall(&self, position: &GridPosition) -> bool {
+        position.row >= Self::SIZE
+            || position.col >= Self::SIZE
+            || !self.open[position.row][position.col]
+    }
+
+    /// The cells joining the rooms: `0`–`1`, `0`–`2`, `1`–`3` and `2`–`3`
+    pub fn hallways() -> [GridPosition; 4] {
+        [
+            GridPosition::new(2, 5),
+            GridPosition::new(5, 1),
+            GridPosition::new(6, 8),
+            GridPosition::new(9, 5),
+        ]
+    }
+
+    /// The room containing `position`; `None` for hallways and walls
+    pub fn room_of(&self, position: &GridPosition) -> Option<usize> {
+        if self.is_wall(position) || Self::hallways().contains(position) {
+            return None;
+        }
+        let right = position.col > 5;
+        // The hallway between the right-hand rooms sits one row lower than the left one
+        let last_top_row = if right { 5 } else { 4 };
+        let bottom = position.row > last_top_row;
+        Some(usize::from(right) + 2 * usize::from(bottom))
+    }
+
+    fn step(&self, position: GridPosition, action: &GridworldAction) -> GridPosition {
+        let next = move_within(position, action, Self::SIZE, Self::SIZE);
+        if self.is_wall(&next) { position } else { next }
+    }
+}
+
+impl mdp::MDP for FourRooms {
+    type State = GridPosition;
+    type Action = GridworldAction;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        &self.states
+    }
+
+    fn all_actions(&self) -> Vec<Self::Action> {
+        compass_actions()
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        *state == self.goal
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        if self.is_wall(state) {
+            return Err(Error::StateNotFound);
+        }
+        let slip = self.failure_probability / 3.0;
+        let measure = compass_actions()
+            .into_iter()
+            .fold(MeasureBuilder::new(), |builder, a| {
+                let p = if a == *action {
+                    1.0 - self.failure_probability
+                } else {
+                    slip
+                };
+                builder.add(self.step(*state, &a), p)
+            })
+            .build()?;
+        Ok((measure, -1.0))
+    }
+}
+
+/// A tile of a `FrozenLake` map
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
+    Start,
+    Frozen,
+    Hole,
+    Goal,
+}
+
+/// # Frozen Lake
+///
+/// The FrozenLake benchmark from Gym: walk from `S` to `G` across frozen tiles (`F`) without
+/// falling into a hole (`H`). Holes and the goal end the episode; reaching the goal pays `+1`
+/// and every other transition `0`. On slippery ice (the default) the agent moves in the
+/// intended direction or one of the two perpendicular ones, with probability 1/3 each, and a
+/// move off the map leaves it in place.
+pub struct FrozenLake {
+    tiles: Vec<Vec<Tile>>,
+    start: GridPosition,
+    slippery: bool,
+    states: Sampler<GridPosition>,
+}
+
+impl FrozenLake {
+    /// Parses one string per row; fails with `Error::Specification` if rows differ in length,
+    /// a character is not one of `S`, `F`, `H`, `G`, or there is not exactly one start and at
+    /// least one goal
+    pub fn new(map: &[&str]) -> Result<Self, Error> {
+        let mut tiles = Vec::with_capacity(map.len());
+        let mut starts = Vec::new();
+        let mut has_goal = false;
+        for (row, line) in map.iter().enumerate() {
+            let mut tile_row = Vec::with_capacity(line.len());
+            for (col, c) in line.chars().enumerate() {
+                let tile = match c {
+                    'S' => Tile::Start,
+                    'F' => Tile::Frozen,
+                    'H' => Tile::Hole,
+                    'G' => Tile::Goal,
+                    other => {
+                        return Err(Error::Specification(format!(
+                            "unknown tile '{other}' at ({row}, {col}), expected S, F, H or G"
+                        )));
+                    }
+                };
+                if tile == Tile::Start {
+                    starts.push(GridPosition::new(row, col));
+                }
+                has_goal |= tile == Tile::Goal;
+                tile_row.push(tile);
+            }
+            if let Some(first) = tiles.first().map(Vec::len)
+                && tile_row.len() != first
+            {
+                return Err(Error::Specification(format!(
+                    "row {row} has {} tiles, row 0 has {first}",
+                    tile_row.len()
+                )));
+            }
+            tiles.push(tile_row);
+        }
+        if tiles.first().is_none_or(Vec::is_empty) {
+            return Err(Error::EmptyStateSpace);
+        }
+        let [start] = starts[..] else {
+            return Err(Error::Specification(format!(
+                "expected exactly one start tile, found {}",
+                starts.len()
+            )));
+        };
+        if !has_goal {
+            return Err(Error::Specification("the map has no goal tile".to_string()));
+        }
+        let states = (0..tiles.len())
+            .flat_map(|row| (0..tiles[0].len()).map(move |col| GridPosition::new(row, col)))
+            .collect::<Vec<_>>();
+        Ok(FrozenLake {
+            tiles,
+            start,
+            slippery: true,
+            states: states.into(),
+        })
+    }
+
+    /// Gym's default 4×4 map
+    pub fn four_by_four() -> Self {
+        FrozenLake::new(&["SFFF", "FHFH", "FFFH", "HFFG"]).expect("the 4x4 map is well formed")
+    }
+
+    /// Gym's 8×8 map
+    pub fn eight_by_eight() -> Self {
+        FrozenLake::new(&[
+            "SFFFFFFF", "FFFFFFFF", "FFFHFFFF", "FFFFFHFF", "FFFHFFFF", "FHHFFFHF", "FHFFHFHF",
+            "FFFHFFFG",
+        ])
+        .expect("the 8x8 map is well formed")
+    }
+
+    /// Whether moves slip sideways (on by default)
+    pub fn with_slippery(mut self, slippery: bool) -> Self {
+        self.slippery = slippery;
+        self
+    }
+
+    pub fn start(&self) -> GridPosition {
+        self.start
+    }
+
+    /// `(rows, cols)` of the map
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.rows(), self.cols())
+    }
+
+    /// The tile at `position`, `None` off the map
+    pub fn tile(&self, position: &GridPosition) -> Option<Tile> {
+        self.tiles
+            .get(position.row)
+            .and_then(|row| row.get(position.col))
+            .copied()
+    }
+
+    fn rows(&self) -> usize {
+        self.tiles.len()
+    }
+
+    fn cols(&self) -> usize {
+        self.tiles[0].len()
+    }
+}
+
+/// The directions at right angles to `action`
+fn perpendicular(action: &GridworldAction) -> [GridworldAction; 2] {
+    match action {
+        GridworldAction::Up | GridworldAction::Down => {
+            [GridworldAction::Left, GridworldAction::Right]
+        }
+        GridworldAction::Left | GridworldAction::Right => {
+            [GridworldAction::Up, GridworldAction::Down]
+        }
+    }
+}
+
+impl mdp::MDP for FrozenLake {
+    type State = GridPosition;
+    type Action = GridworldAction;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        &self.states
+    }
+
+    fn all_actions(&self) -> Vec<Self::Action> {
+        compass_actions()
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        matches!(self.tile(state), Some(Tile::Hole | Tile::Goal))
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.tile(state) == Some(Tile::Goal)
+    }
+
+    /// Holes and goals are absorbing with reward `0`; elsewhere the reward is the probability of
+    /// arriving at a goal
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        if self.tile(state).is_none() {
+            return Err(Error::StateNotFound);
+        }
+        if self.is_final_state(state) {
+            return Ok((Measure::deterministic(*state), 0.0));
+        }
+        let (rows, cols) = (self.rows(), self.cols());
+        let measure = if self.slippery {
+            let [left, right] = perpendicular(action);
+            Measure::uniform([
+                move_within(*state, action, rows, cols),
+                move_within(*state, &left, rows, cols),
+                move_within(*state, &right, rows, cols),
+            ])?
+        } else {
+            Measure::deterministic(move_within(*state, action, rows, cols))
+        };
+        let reward = measure.expectation(|next| if self.is_goal(next) { 1.0 } else { 0.0 });
+        Ok((measure, reward))
     }
 }
@@ -0,0 +1,146 @@
+//! # Imitation Learning
+//!
+//! Behavioral cloning: policies fitted to the actions a demonstrator took in a logged `Dataset`.
+//! Clones of component demonstrators can be lifted to products with
+//! `BoxProduct::lift_policies`/`CartesianProduct::lift_policies`.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::dataset::Dataset;
+use crate::env::Environment;
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::policy::{DeterministicPolicy, Policy, StochasticPolicy, boltzmann_measure};
+
+/// Per state, each demonstrated action with how often it was taken, in order of first appearance
+type ActionCounts<S, A> = HashMap<S, Vec<(A, usize)>>;
+
+fn action_counts<S, A>(dataset: &Dataset<S, A>) -> ActionCounts<S, A>
+where
+    S: Eq + Hash + Clone,
+    A: Eq + Clone,
+{
+    let mut counts: ActionCounts<S, A> = HashMap::new();
+    for step in dataset.iter().flat_map(|trajectory| &trajectory.steps) {
+        let actions = counts.entry(step.state.clone()).or_default();
+        match actions.iter_mut().find(|(a, _)| *a == step.action) {
+            Some((_, count)) => *count += 1,
+            None => actions.push((step.action.clone(), 1)),
+        }
+    }
+    counts
+}
+
+/// # Clone Policy
+///
+/// Picks, in every demonstrated state, the action the demonstrator took most often. Ties go to
+/// the action demonstrated first. States never visited in the dataset are left out.
+pub fn clone_policy<S, A>(dataset: &Dataset<S, A>) -> DeterministicPolicy<S, A>
+where
+    S: Eq + Hash + Clone,
+    A: Eq + Clone,
+{
+    action_counts(dataset)
+        .into_iter()
+        .filter_map(|(state, actions)| {
+            let mut best: Option<(A, usize)> = None;
+            for (action, count) in actions {
+                if best
+                    .as_ref()
+                    .is_none_or(|(_, best_count)| count > *best_count)
+                {
+                    best = Some((action, count));
+                }
+            }
+            best.map(|(action, _)| (state, action))
+        })
+        .collect()
+}
+
+/// Softmax over demonstration counts, `π(a|s) ∝ exp(N(s, a) / temperature)`, over the actions
+/// demonstrated in `s`. Low temperatures approach `clone_policy`; high ones approach the uniform
+/// distribution over demonstrated actions.
+pub fn clone_softmax_policy<S, A>(
+    dataset: &Dataset<S, A>,
+    temperature: f64,
+) -> Result<StochasticPolicy<S, A>, Error>
+where
+    S: Eq + Hash + Clone,
+    A: Eq + Hash + Clone,
+{
+    action_counts(dataset)
+        .into_iter()
+        .map(|(state, actions)| {
+            let counts: HashMap<A, usize> = actions.iter().cloned().collect();
+            let actions: Vec<A> = actions.into_iter().map(|(a, _)| a).collect();
+            let measure = boltzmann_measure(actions, |a| counts[a] as f64, temperature)?;
+            Ok((state, measure))
+        })
+        .collect()
+}
+
+/// How a cloned policy compares with its demonstrator
+#[derive(Debug, Clone, PartialEq)]
+pub struct CloneEvaluation {
+    /// Fraction of demonstrated steps on which the clone picks the demonstrated action
+    pub accuracy: f64,
+    /// Mean undiscounted return of the clone
+    pub clone_return: f64,
+    /// Mean undiscounted return of the demonstrator
+    pub demonstrator_return: f64,
+}
+
+/// Fraction of the steps in `dataset` on which `policy` picks the logged action. `None` if the
+/// dataset has no steps. Stochastic policies are sampled once per step.
+pub fn imitation_accuracy<S, A, P>(dataset: &Dataset<S, A>, policy: &P) -> Option<f64>
+where
+    A: PartialEq,
+    P: Policy<S, A>,
+{
+    let steps: Vec<_> = dataset.iter().flat_map(|t| &t.steps).collect();
+    if steps.is_empty() {
+        return None;
+    }
+    let matches = steps
+        .iter()
+        .filter(|step| policy.action(&step.state).as_ref() == Some(&step.action))
+        .count();
+    Some(matches as f64 / steps.len() as f64)
+}
+
+/// # Evaluate Clone
+///
+/// Scores `clone` against `demonstrator`: its accuracy on the demonstrations and both policies'
+/// mean undiscounted returns over `num_episodes` rollouts of at most `max_steps` steps in `env`.
+///
+/// # Returns
+///
+/// The evaluation, or the first transition error. Accuracy is zero if `demonstrations` is empty.
+pub fn evaluate_clone<M, P, D>(
+    env: &mut Environment<M>,
+    clone: &P,
+    demonstrator: &D,
+    demonstrations: &Dataset<M::State, M::Action>,
+    num_episodes: usize,
+    max_steps: usize,
+) -> Result<CloneEvaluation, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+    P: Policy<M::State, M::Action>,
+    D: Policy<M::State, M::Action>,
+{
+    let clone_return = Dataset::collect(env, clone, num_episodes, max_steps)?
+        .mean_return(1.0)
+        .unwrap_or(0.0);
+    let demonstrator_return = Dataset::collect(env, demonstrator, num_episodes, max_steps)?
+        .mean_return(1.0)
+        .unwrap_or(0.0);
+    Ok(CloneEvaluation {
+        accuracy: imitation_accuracy(demonstrations, clone).unwrap_or(0.0),
+        clone_return,
+        demonstrator_return,
+    })
+}
@@ -0,0 +1,185 @@
+//! # Model Import
+//!
+//! Builds a `TabularMDP` from a list of transitions, so an externally specified model can be
+//! tried out without writing a Rust type for it. Each row reads `state, action, next_state,
+//! prob, reward`; states and actions are plain names (`NamedState`, `NamedAction`). Rows come
+//! from CSV text or, with the `serde` feature, a JSON array of objects with those five fields.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use madepro::models::{Action, State};
+
+use crate::constructors::TabularMDP;
+use crate::error::Error;
+use crate::measure::MeasureBuilder;
+
+/// How far the probabilities of one state-action pair may sum from one; text formats round
+pub const IMPORT_TOLERANCE: f64 = 1e-6;
+
+/// A state identified by its name in an imported model
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NamedState(pub String);
+
+impl State for NamedState {}
+
+/// An action identified by its name in an imported model
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NamedAction(pub String);
+
+impl Action for NamedAction {}
+
+/// The `(next_state, prob, reward)` rows of one state-action pair
+type Outcomes = Vec<(String, f64, f64)>;
+
+/// One `state, action, next_state, prob, reward` row
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransitionRow {
+    pub state: String,
+    pub action: String,
+    pub next_state: String,
+    pub prob: f64,
+    pub reward: f64,
+}
+
+/// # Tabular MDP From Rows
+///
+/// Groups the rows by `(state, action)`; the outcome probabilities of a pair must sum to one
+/// (within `IMPORT_TOLERANCE`) and its reward is the expected reward `Σ prob * reward` over its
+/// rows. States are numbered in order of first appearance, and states without outgoing rows
+/// are final.
+///
+/// # Arguments
+/// * `rows` - The transitions, in any order
+///
+/// # Returns
+/// The model, or `Error::InvalidMeasure` if some pair's probabilities do not sum to one
+pub fn tabular_mdp_from_rows(
+    rows: impl IntoIterator<Item = TransitionRow>,
+) -> Result<TabularMDP<NamedState, NamedAction>, Error> {
+    let mut states: Vec<NamedState> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut with_actions: HashSet<String> = HashSet::new();
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    let mut outcomes: HashMap<(String, String), Outcomes> = HashMap::new();
+
+    for row in rows {
+        if !(0.0..=1.0).contains(&row.prob) {
+            return Err(Error::ProbabilityOutOfRange);
+        }
+        for name in [&row.state, &row.next_state] {
+            if seen.insert(name.clone()) {
+                states.push(NamedState(name.clone()));
+            }
+        }
+        with_actions.insert(row.state.clone());
+        let key = (row.state, row.action);
+        let entry = outcomes.entry(key.clone()).or_insert_with(|| {
+            pairs.push(key);
+            Vec::new()
+        });
+        entry.push((row.next_state, row.prob, row.reward));
+    }
+
+    let mut mdp = TabularMDP::new(states.clone());
+    for (state, action) in pairs {
+        let rows = &outcomes[&(state.clone(), action.clone())];
+        let reward: f64 = rows.iter().map(|(_, p, r)| p * r).sum();
+        let measure = rows
+            .iter()
+            .fold(MeasureBuilder::new(), |builder, (next, p, _)| {
+                builder.add(NamedState(next.clone()), *p)
+            })
+            .tolerance(IMPORT_TOLERANCE)
+            .build()?;
+        mdp.add_transition(NamedState(state), NamedAction(action), measure, reward);
+    }
+    for state in states {
+        if !with_actions.contains(&state.0) {
+            mdp.set_final(state);
+        }
+    }
+    Ok(mdp)
+}
+
+/// # Parse CSV
+///
+/// Reads `state,action,next_state,prob,reward` rows. Blank lines and lines starting with `#`
+/// are skipped, as is a first row spelling out the column names; fields are trimmed and cannot
+/// be quoted, so names must not contain commas. Fails with `Error::Specification` naming the
+/// offending line.
+pub fn parse_csv(text: &str) -> Result<Vec<TransitionRow>, Error> {
+    let mut rows = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if rows.is_empty() && fields.first() == Some(&"state") {
+            continue;
+        }
+        let [state, action, next_state, prob, reward] = fields[..] else {
+            return Err(Error::Specification(format!(
+                "line {}: expected 5 fields, found {}",
+                number + 1,
+                fields.len()
+            )));
+        };
+        let number_field = |field: &str| {
+            field
+                .parse::<f64>()
+                .map_err(|e| Error::Specification(format!("line {}: {field:?}: {e}", number + 1)))
+        };
+        rows.push(TransitionRow {
+            state: state.to_string(),
+            action: action.to_string(),
+            next_state: next_state.to_string(),
+            prob: number_field(prob)?,
+            reward: number_field(reward)?,
+        });
+    }
+    Ok(rows)
+}
+
+/// Parses a JSON array of `{"state", "action", "next_state", "prob", "reward"}` objects
+#[cfg(feature = "serde")]
+pub fn parse_json(text: &str) -> Result<Vec<TransitionRow>, Error> {
+    serde_json::from_str(text).map_err(|e| Error::Serialization(e.to_string()))
+}
+
+/// # Load Transitions
+///
+/// Reads a transition list from `path` and builds the model with `tabular_mdp_from_rows`.
+/// Files ending in `.json` are parsed as JSON (with the `serde` feature), everything else as
+/// CSV.
+pub fn load_tabular_mdp(
+    path: impl AsRef<Path>,
+) -> Result<TabularMDP<NamedState, NamedAction>, Error> {
+    let path = path.as_ref();
+    let text =
+        fs::read_to_string(path).map_err(|e| Error::Io(format!("{}: {e}", path.display())))?;
+    let is_json = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let rows = if is_json {
+        #[cfg(feature = "serde")]
+        {
+            parse_json(&text)?
+        }
+        #[cfg(not(feature = "serde"))]
+        {
+            return Err(Error::Specification(
+                "JSON transition lists need the `serde` feature".to_string(),
+            ));
+        }
+    } else {
+        parse_csv(&text)?
+    };
+    tabular_mdp_from_rows(rows)
+}
@@ -0,0 +1,190 @@
+//! # Inverse Reinforcement Learning
+//!
+//! Recovering rewards from demonstrations. `max_ent_irl` fits a state-reward vector under which
+//! the maximum-entropy policy visits states as often as the expert did, using the exact dynamics
+//! from `stochastic_transition`.
+
+use std::collections::HashMap;
+
+use crate::config::TrainingConfig;
+use crate::dataset::Dataset;
+use crate::error::Error;
+use crate::mdp::MDP;
+
+/// Rewards recovered by `max_ent_irl`
+pub struct IrlResult<S> {
+    /// The learned reward of each state (for being in it, including a final state)
+    pub rewards: HashMap<S, f64>,
+    /// `Σ_s |μ_E(s) - μ_θ(s)|` between the expert's and the learned policy's expected visit counts
+    /// at the last iteration
+    pub visitation_gap: f64,
+    pub iterations: usize,
+}
+
+/// For every state, the next-state distribution `[(index, probability)]` of each available action;
+/// empty for final states
+type Dynamics = Vec<Vec<Vec<(usize, f64)>>>;
+
+fn log_sum_exp(values: &[f64]) -> f64 {
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY {
+        return max;
+    }
+    max + values.iter().map(|v| (v - max).exp()).sum::<f64>().ln()
+}
+
+/// # MaxEnt IRL
+///
+/// Tabular maximum-entropy inverse reinforcement learning (Ziebart et al., 2008) with one-hot
+/// state features, so the reward is a free parameter `θ(s)` per state. Each iteration
+///
+/// 1. runs soft value iteration over a horizon `T` equal to the longest demonstration:
+///    `Q_t(s, a) = θ(s) + Σ_s' p(s' | s, a) V_(t+1)(s')`, `V_t(s) = log Σ_a exp Q_t(s, a)`, with
+///    `V(s) = θ(s)` at final states and `V_T = θ`;
+/// 2. propagates the empirical start distribution for `T` steps under `π_t(a|s) = exp(Q_t - V_t)`
+///    to get expected visit counts `μ_θ` (final states are counted once and absorb);
+/// 3. takes the gradient step `θ ← θ + config.learning_rate · (μ_E - μ_θ)`.
+///
+/// It stops after `config.irl_iterations` iterations or once the visitation gap drops below
+/// `config.planning_tolerance`. Rewards are only identified up to the usual MaxEnt ambiguities
+/// (e.g. a constant shift on states from which every action leads to the same place).
+///
+/// # Arguments
+/// * `mdp` - The MDP whose dynamics the expert acted in
+/// * `demonstrations` - Expert trajectories
+/// * `config` - Configuration parameters (learning rate, IRL iterations, planning tolerance)
+///
+/// # Returns
+/// The recovered rewards, or `Error::EmptyStateSpace` if there are no states or demonstrations.
+pub fn max_ent_irl<M>(
+    mdp: &M,
+    demonstrations: &Dataset<M::State, M::Action>,
+    config: &TrainingConfig,
+) -> Result<IrlResult<M::State>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+{
+    let states: Vec<M::State> = mdp.all_states().iter().cloned().collect();
+    if states.is_empty() || demonstrations.is_empty() {
+        return Err(Error::EmptyStateSpace);
+    }
+    let index: HashMap<&M::State, usize> = states.iter().enumerate().map(|(i, s)| (s, i)).collect();
+    let n = states.len();
+
+    let mut dynamics: Dynamics = Vec::with_capacity(n);
+    for state in &states {
+        let mut actions = Vec::new();
+        if !mdp.is_final_state(state) {
+            for action in mdp.actions_at(state) {
+                let (measure, _) = mdp.stochastic_transition(state, &action)?;
+                let outcomes = measure
+                    .dist()
+                    .iter()
+                    .filter_map(|(s, p)| index.get(s).map(|&i| (i, p.value())))
+                    .collect();
+                actions.push(outcomes);
+            }
+        }
+        dynamics.push(actions);
+    }
+
+    // Expert visit counts and start distribution, per demonstration
+    let num_demonstrations = demonstrations.len() as f64;
+    let mut expert_visits = vec![0.0; n];
+    let mut start = vec![0.0; n];
+    let mut horizon = 0;
+    for trajectory in demonstrations.iter() {
+        horizon = horizon.max(trajectory.len());
+        let visited = trajectory
+            .steps
+            .iter()
+            .map(|step| &step.state)
+            .chain(std::iter::once(&trajectory.last_state));
+        for state in visited {
+            let i = *index.get(state).ok_or(Error::StateNotFound)?;
+            expert_visits[i] += 1.0 / num_demonstrations;
+        }
+        let first = index
+            .get(trajectory.first_state())
+            .ok_or(Error::StateNotFound)?;
+        start[*first] += 1.0 / num_demonstrations;
+    }
+
+    let mut theta = vec![0.0; n];
+    let mut visitation_gap = f64::INFINITY;
+    let mut iterations = 0;
+    while iterations < config.irl_iterations {
+        iterations += 1;
+
+        // Backward pass: soft values for t = T down to 0, keeping each step's policy
+        let mut values = theta.clone();
+        let mut policies: Vec<Vec<Vec<f64>>> = Vec::with_capacity(horizon);
+        for _ in 0..horizon {
+            let mut next_values = vec![0.0; n];
+            let mut policy = Vec::with_capacity(n);
+            for s in 0..n {
+                if dynamics[s].is_empty() {
+                    next_values[s] = theta[s];
+                    policy.push(Vec::new());
+                    continue;
+                }
+                let q: Vec<f64> = dynamics[s]
+                    .iter()
+                    .map(|outcomes| {
+                        theta[s] + outcomes.iter().map(|&(j, p)| p * values[j]).sum::<f64>()
+                    })
+                    .collect();
+                let v = log_sum_exp(&q);
+                next_values[s] = v;
+                policy.push(q.iter().map(|q| (q - v).exp()).collect());
+            }
+            values = next_values;
+            policies.push(policy);
+        }
+        policies.reverse();
+
+        // Forward pass: expected visit counts under the time-indexed policy
+        let mut learner_visits = vec![0.0; n];
+        let mut distribution = start.clone();
+        for policy in &policies {
+            let mut next_distribution = vec![0.0; n];
+            for s in 0..n {
+                if distribution[s] == 0.0 {
+                    continue;
+                }
+                learner_visits[s] += distribution[s];
+                for (outcomes, pi) in dynamics[s].iter().zip(&policy[s]) {
+                    for &(j, p) in outcomes {
+                        next_distribution[j] += distribution[s] * pi * p;
+                    }
+                }
+            }
+            distribution = next_distribution;
+        }
+        for s in 0..n {
+            learner_visits[s] += distribution[s];
+        }
+
+        visitation_gap = 0.0;
+        for s in 0..n {
+            let gradient = expert_visits[s] - learner_visits[s];
+            visitation_gap += gradient.abs();
+            theta[s] += config.learning_rate * gradient;
+        }
+        if !visitation_gap.is_finite() {
+            return Err(Error::NumericalError(format!(
+                "MaxEnt IRL diverged (visitation gap {visitation_gap})"
+            )));
+        }
+        if visitation_gap <= config.planning_tolerance {
+            break;
+        }
+    }
+
+    Ok(IrlResult {
+        rewards: states.into_iter().zip(theta).collect(),
+        visitation_gap,
+        iterations,
+    })
+}
@@ -0,0 +1,203 @@
+//! # Labeling
+//!
+//! Atomic propositions on MDP states. A labeled MDP maps every state to the set of proposition
+//! symbols true in it; reward machines, temporal-logic specifications and model checking all read
+//! states through these labels. `Labeled` is implemented for the built-in environments, and the
+//! `Labeling` wrapper attaches propositions defined by predicates to any MDP.
+
+use std::collections::BTreeSet;
+
+use madepro::environments::gridworld::Gridworld;
+use madepro::models::Sampler;
+
+use crate::error::Error;
+use crate::gridworld::GridworldWithGoals;
+use crate::mdp::MDP;
+use crate::measure::Measure;
+use crate::pathmdp::PathWorld;
+
+/// An atomic proposition symbol
+pub type Proposition = String;
+
+/// The propositions true in a state
+pub type Labels = BTreeSet<Proposition>;
+
+/// Holds in goal states
+pub const AT_GOAL: &str = "at_goal";
+/// Holds in final states
+pub const TERMINAL: &str = "terminal";
+/// Holds in gridworld states where some action bumps into a wall or the border
+pub const WALL_ADJACENT: &str = "wall_adjacent";
+/// Holds in the first state of a `PathWorld`
+pub const AT_START: &str = "at_start";
+
+/// An MDP whose states carry atomic propositions
+pub trait Labeled: MDP {
+    fn labels(&self, state: &Self::State) -> Labels;
+
+    fn holds(&self, state: &Self::State, proposition: &str) -> bool {
+        self.labels(state).contains(proposition)
+    }
+}
+
+impl<M: Labeled + ?Sized> Labeled for &M {
+    fn labels(&self, state: &Self::State) -> Labels {
+        (**self).labels(state)
+    }
+
+    fn holds(&self, state: &Self::State, proposition: &str) -> bool {
+        (**self).holds(state, proposition)
+    }
+}
+
+/// Labels shared by every MDP: `at_goal` and `terminal`
+fn goal_labels<M: MDP + ?Sized>(mdp: &M, state: &M::State) -> Labels {
+    let mut labels = Labels::new();
+    if mdp.is_goal(state) {
+        labels.insert(AT_GOAL.to_string());
+    }
+    if mdp.is_final_state(state) {
+        labels.insert(TERMINAL.to_string());
+    }
+    labels
+}
+
+/// Whether some action leaves `state` where it is. Gridworld moves into a wall or off the grid
+/// are the only self-loops, so this detects wall adjacency without access to the cell layout.
+fn bumps<M: MDP + ?Sized>(mdp: &M, state: &M::State) -> bool {
+    mdp.actions_at(state).iter().any(|action| {
+        mdp.stochastic_transition(state, action)
+            .map(|(measure, _)| measure.get_prob(state).is_some())
+            .unwrap_or(false)
+    })
+}
+
+/// `at_goal`, `terminal` and `wall_adjacent`
+impl Labeled for Gridworld {
+    fn labels(&self, state: &Self::State) -> Labels {
+        let mut labels = goal_labels(self, state);
+        if !self.is_final_state(state) && bumps(self, state) {
+            labels.insert(WALL_ADJACENT.to_string());
+        }
+        labels
+    }
+}
+
+/// `at_goal` (the explicit goals), `terminal` and `wall_adjacent`
+impl Labeled for GridworldWithGoals {
+    fn labels(&self, state: &Self::State) -> Labels {
+        let mut labels = goal_labels(self, state);
+        if !self.is_final_state(state) && bumps(self, state) {
+            labels.insert(WALL_ADJACENT.to_string());
+        }
+        labels
+    }
+}
+
+/// `at_start`, `at_goal` and `terminal`
+impl Labeled for PathWorld {
+    fn labels(&self, state: &Self::State) -> Labels {
+        let mut labels = goal_labels(self, state);
+        if state.index() == 0 {
+            labels.insert(AT_START.to_string());
+        }
+        labels
+    }
+}
+
+/// Decides whether a proposition holds in a state
+type Predicate<S> = Box<dyn Fn(&S) -> bool>;
+
+/// Attaches propositions to any MDP, each defined by a predicate on states. The dynamics and
+/// rewards are the wrapped MDP's.
+pub struct Labeling<M: MDP> {
+    mdp: M,
+    propositions: Vec<(Proposition, Predicate<M::State>)>,
+}
+
+impl<M: MDP> Labeling<M> {
+    pub fn new(mdp: M) -> Self {
+        Labeling {
+            mdp,
+            propositions: Vec::new(),
+        }
+    }
+
+    /// Adds `name`, true exactly in the states satisfying `predicate`
+    pub fn with_proposition(
+        mut self,
+        name: impl Into<Proposition>,
+        predicate: impl Fn(&M::State) -> bool + 'static,
+    ) -> Self {
+        self.propositions.push((name.into(), Box::new(predicate)));
+        self
+    }
+
+    /// Every proposition symbol this labeling can assign
+    pub fn propositions(&self) -> impl Iterator<Item = &str> {
+        self.propositions.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mdp
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mdp
+    }
+}
+
+impl<M> MDP for Labeling<M>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    type State = M::State;
+    type Action = M::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        self.mdp.all_states()
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        self.mdp.actions_at(state)
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.mdp.is_final_state(state)
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.mdp.is_goal(state)
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        self.mdp.stochastic_transition(state, action)
+    }
+}
+
+impl<M> Labeled for Labeling<M>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    fn labels(&self, state: &Self::State) -> Labels {
+        self.propositions
+            .iter()
+            .filter(|(_, predicate)| predicate(state))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    fn holds(&self, state: &Self::State, proposition: &str) -> bool {
+        self.propositions
+            .iter()
+            .any(|(name, predicate)| name == proposition && predicate(state))
+    }
+}
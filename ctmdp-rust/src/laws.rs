@@ -0,0 +1,273 @@
+//! # Product Laws
+//!
+//! Numerical checks of the algebraic laws products should satisfy: associativity up to
+//! re-bracketing the states, commutativity up to swapping the components, and the frame
+//! property of `BoxProduct` (a move of one component leaves the other where it was). A law is
+//! stated as a pair of mutually inverse `Morphism`s between the two sides, so the same
+//! `check_isomorphism` serves anyone writing a new combinator. Checkers return
+//! `Error::InvariantViolation` describing the first violation found.
+
+use std::fmt::Debug;
+
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::morphism::Morphism;
+use crate::products::{BoxAction, BoxProduct, CartesianProduct, Product};
+
+/// `((x1, x2), x3) ↦ (x1, (x2, x3))`
+fn reassociate<T1: Clone, T2: Clone, T3: Clone>(
+    nested: &Product<Product<T1, T2>, T3>,
+) -> Product<T1, Product<T2, T3>> {
+    let inner = nested.first();
+    Product::new(
+        inner.first().clone(),
+        Product::new(inner.second().clone(), nested.second().clone()),
+    )
+}
+
+/// `(x1, (x2, x3)) ↦ ((x1, x2), x3)`
+fn dissociate<T1: Clone, T2: Clone, T3: Clone>(
+    nested: &Product<T1, Product<T2, T3>>,
+) -> Product<Product<T1, T2>, T3> {
+    let inner = nested.second();
+    Product::new(
+        Product::new(nested.first().clone(), inner.first().clone()),
+        inner.second().clone(),
+    )
+}
+
+/// `(x1, x2) ↦ (x2, x1)`
+fn swap<T1: Clone, T2: Clone>(pair: &Product<T1, T2>) -> Product<T2, T1> {
+    Product::new(pair.second().clone(), pair.first().clone())
+}
+
+/// `Left(Left(a1)) ↦ Left(a1)`, `Left(Right(a2)) ↦ Right(Left(a2))`, `Right(a3) ↦ Right(Right(a3))`
+fn reassociate_box<A1: Clone, A2: Clone, A3: Clone>(
+    action: &BoxAction<BoxAction<A1, A2>, A3>,
+) -> BoxAction<A1, BoxAction<A2, A3>> {
+    match action {
+        BoxAction::Left(BoxAction::Left(a1)) => BoxAction::Left(a1.clone()),
+        BoxAction::Left(BoxAction::Right(a2)) => BoxAction::Right(BoxAction::Left(a2.clone())),
+        BoxAction::Right(a3) => BoxAction::Right(BoxAction::Right(a3.clone())),
+    }
+}
+
+/// The inverse of `reassociate_box`
+fn dissociate_box<A1: Clone, A2: Clone, A3: Clone>(
+    action: &BoxAction<A1, BoxAction<A2, A3>>,
+) -> BoxAction<BoxAction<A1, A2>, A3> {
+    match action {
+        BoxAction::Left(a1) => BoxAction::Left(BoxAction::Left(a1.clone())),
+        BoxAction::Right(BoxAction::Left(a2)) => BoxAction::Left(BoxAction::Right(a2.clone())),
+        BoxAction::Right(BoxAction::Right(a3)) => BoxAction::Right(a3.clone()),
+    }
+}
+
+/// `Left(a) ↦ Right(a)` and `Right(a) ↦ Left(a)`
+fn swap_box<A1: Clone, A2: Clone>(action: &BoxAction<A1, A2>) -> BoxAction<A2, A1> {
+    match action {
+        BoxAction::Left(a1) => BoxAction::Right(a1.clone()),
+        BoxAction::Right(a2) => BoxAction::Left(a2.clone()),
+    }
+}
+
+/// # Check Isomorphism
+///
+/// Checks that `forward` and `backward` are homomorphisms between `lhs` and `rhs` (see
+/// `Morphism::violations`) and that both sides have the same number of states, so that the two
+/// MDPs agree on final states, available actions, rewards and transition measures up to
+/// relabeling.
+///
+/// # Arguments
+/// * `lhs`, `rhs` - The two sides of the law
+/// * `forward`, `backward` - The relabelings in each direction
+/// * `tolerance` - Allowed reward difference and total-variation distance between measures
+///
+/// # Returns
+/// `Ok(())` if the law holds, otherwise `Error::InvariantViolation` with the first violation
+pub fn check_isomorphism<L, R>(
+    lhs: &L,
+    rhs: &R,
+    forward: &Morphism<L, R>,
+    backward: &Morphism<R, L>,
+    tolerance: f64,
+) -> Result<(), Error>
+where
+    L: MDP,
+    R: MDP,
+    L::State: Clone + Debug,
+    L::Action: Clone + Debug,
+    R::State: Clone + Debug,
+    R::Action: Clone + Debug,
+{
+    let lhs_count = lhs.all_states().iter().count();
+    let rhs_count = rhs.all_states().iter().count();
+    if lhs_count != rhs_count {
+        return Err(Error::InvariantViolation(format!(
+            "{lhs_count} states on the left, {rhs_count} on the right"
+        )));
+    }
+    if let Some(violation) = forward.violations(lhs, rhs, tolerance)?.first() {
+        return Err(Error::InvariantViolation(format!(
+            "left to right: {violation:?}"
+        )));
+    }
+    if let Some(violation) = backward.violations(rhs, lhs, tolerance)?.first() {
+        return Err(Error::InvariantViolation(format!(
+            "right to left: {violation:?}"
+        )));
+    }
+    Ok(())
+}
+
+/// `(M1 □ M2) □ M3 ≅ M1 □ (M2 □ M3)`, each move relabeled to act on the same component
+pub fn check_box_associativity<M1, M2, M3>(
+    mdp1: &M1,
+    mdp2: &M2,
+    mdp3: &M3,
+    tolerance: f64,
+) -> Result<(), Error>
+where
+    M1: MDP,
+    M2: MDP,
+    M3: MDP,
+    M1::State: Clone + Debug + 'static,
+    M2::State: Clone + Debug + 'static,
+    M3::State: Clone + Debug + 'static,
+    M1::Action: Clone + Debug + 'static,
+    M2::Action: Clone + Debug + 'static,
+    M3::Action: Clone + Debug + 'static,
+{
+    let lhs = BoxProduct::new(BoxProduct::new(mdp1, mdp2), mdp3);
+    let rhs = BoxProduct::new(mdp1, BoxProduct::new(mdp2, mdp3));
+    let forward = Morphism::new(reassociate, |_, action| reassociate_box(action));
+    let backward = Morphism::new(dissociate, |_, action| dissociate_box(action));
+    check_isomorphism(&lhs, &rhs, &forward, &backward, tolerance)
+}
+
+/// `M1 □ M2 ≅ M2 □ M1`: `Left` and `Right` trade places
+pub fn check_box_commutativity<M1, M2>(mdp1: &M1, mdp2: &M2, tolerance: f64) -> Result<(), Error>
+where
+    M1: MDP,
+    M2: MDP,
+    M1::State: Clone + Debug + 'static,
+    M2::State: Clone + Debug + 'static,
+    M1::Action: Clone + Debug + 'static,
+    M2::Action: Clone + Debug + 'static,
+{
+    let lhs = BoxProduct::new(mdp1, mdp2);
+    let rhs = BoxProduct::new(mdp2, mdp1);
+    let forward = Morphism::new(swap, |_, action| swap_box(action));
+    let backward = Morphism::new(swap, |_, action| swap_box(action));
+    check_isomorphism(&lhs, &rhs, &forward, &backward, tolerance)
+}
+
+/// `(M1 × M2) × M3 ≅ M1 × (M2 × M3)` with the summed rewards of `CartesianProduct::new`; joint
+/// actions are re-bracketed like the states
+pub fn check_cartesian_associativity<M1, M2, M3>(
+    mdp1: &M1,
+    mdp2: &M2,
+    mdp3: &M3,
+    tolerance: f64,
+) -> Result<(), Error>
+where
+    M1: MDP,
+    M2: MDP,
+    M3: MDP,
+    M1::State: Clone + Debug + 'static,
+    M2::State: Clone + Debug + 'static,
+    M3::State: Clone + Debug + 'static,
+    M1::Action: Clone + Debug + 'static,
+    M2::Action: Clone + Debug + 'static,
+    M3::Action: Clone + Debug + 'static,
+{
+    let lhs = CartesianProduct::new(CartesianProduct::new(mdp1, mdp2), mdp3);
+    let rhs = CartesianProduct::new(mdp1, CartesianProduct::new(mdp2, mdp3));
+    let forward = Morphism::new(reassociate, |_, action| reassociate(action));
+    let backward = Morphism::new(dissociate, |_, action| dissociate(action));
+    check_isomorphism(&lhs, &rhs, &forward, &backward, tolerance)
+}
+
+/// `M1 × M2 ≅ M2 × M1` with the summed rewards of `CartesianProduct::new`; joint actions are
+/// swapped like the states
+pub fn check_cartesian_commutativity<M1, M2>(
+    mdp1: &M1,
+    mdp2: &M2,
+    tolerance: f64,
+) -> Result<(), Error>
+where
+    M1: MDP,
+    M2: MDP,
+    M1::State: Clone + Debug + 'static,
+    M2::State: Clone + Debug + 'static,
+    M1::Action: Clone + Debug + 'static,
+    M2::Action: Clone + Debug + 'static,
+{
+    let lhs = CartesianProduct::new(mdp1, mdp2);
+    let rhs = CartesianProduct::new(mdp2, mdp1);
+    let forward = Morphism::new(swap, |_, action| swap(action));
+    let backward = Morphism::new(swap, |_, action| swap(action));
+    check_isomorphism(&lhs, &rhs, &forward, &backward, tolerance)
+}
+
+/// # Check Box Frame
+///
+/// Checks that every move of a `BoxProduct` acts on one component only: under `Left(a1)` the
+/// second component stays put with probability one while the first moves as `a1` moves it in
+/// the first MDP and earns the same reward, and symmetrically under `Right(a2)`.
+pub fn check_box_frame<M1, M2>(product: &BoxProduct<M1, M2>, tolerance: f64) -> Result<(), Error>
+where
+    M1: MDP,
+    M2: MDP,
+    M1::State: Clone + Debug,
+    M2::State: Clone + Debug,
+    M1::Action: Clone + Debug,
+    M2::Action: Clone + Debug,
+{
+    let (mdp1, mdp2) = product.components();
+    for (state, action) in product.all_state_action_pairs() {
+        let (joint, reward) = product.stochastic_transition(&state, &action)?;
+        let (frame_leak, marginal_distance, component_reward) = match &action {
+            BoxAction::Left(a1) => {
+                let (measure1, reward1) = mdp1.stochastic_transition(state.first(), a1)?;
+                let leak = joint
+                    .dist()
+                    .iter()
+                    .filter(|(s, _)| s.second() != state.second())
+                    .map(|(_, p)| p.value())
+                    .sum::<f64>();
+                let marginal = joint.clone().map(|s| s.into_parts().0);
+                (leak, marginal.l1_distance(&measure1), reward1)
+            }
+            BoxAction::Right(a2) => {
+                let (measure2, reward2) = mdp2.stochastic_transition(state.second(), a2)?;
+                let leak = joint
+                    .dist()
+                    .iter()
+                    .filter(|(s, _)| s.first() != state.first())
+                    .map(|(_, p)| p.value())
+                    .sum::<f64>();
+                let marginal = joint.clone().map(|s| s.into_parts().1);
+                (leak, marginal.l1_distance(&measure2), reward2)
+            }
+        };
+        if frame_leak > tolerance {
+            return Err(Error::InvariantViolation(format!(
+                "{state:?}, {action:?}: the inactive component moves with probability {frame_leak}"
+            )));
+        }
+        if marginal_distance > tolerance {
+            return Err(Error::InvariantViolation(format!(
+                "{state:?}, {action:?}: the active component's marginal is {marginal_distance} \
+                 away from its own transition"
+            )));
+        }
+        if (reward - component_reward).abs() > tolerance {
+            return Err(Error::InvariantViolation(format!(
+                "{state:?}, {action:?}: reward {reward}, but the active component earns \
+                 {component_reward}"
+            )));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,156 @@
+//! # Lexicographic Objectives
+//!
+//! Learning for multi-objective MDPs whose objectives are ranked in strict priority order: a
+//! lower-priority objective only breaks ties among actions that are (nearly) optimal for every
+//! objective above it. Slack tolerances say how far from optimal an action may be on a
+//! higher-priority objective and still count as a tie.
+
+use madepro::models::{Action, ActionValue, State};
+use rand::Rng;
+use rand::seq::IndexedRandom;
+
+use crate::config::TrainingConfig;
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::multi_objective::MultiObjectiveMDP;
+use crate::policy::DeterministicPolicy;
+use crate::q_learning::{action_union, random_start};
+
+/// One Q-table per objective, highest priority first, learned by `lexicographic_q_learning`
+pub struct LexicographicValues<S: State, A: Action> {
+    pub values: Vec<ActionValue<S, A>>,
+    /// Slack per objective; objectives without an entry get none
+    pub slacks: Vec<f64>,
+}
+
+impl<S: State, A: Action> LexicographicValues<S, A> {
+    /// The actions that survive lexicographic filtering in `state`: starting from `actions`, each
+    /// objective in priority order keeps the actions within its slack of the best remaining one.
+    /// Order is preserved.
+    pub fn admissible(&self, state: &S, actions: &[A]) -> Vec<A> {
+        let mut candidates = actions.to_vec();
+        for (k, q) in self.values.iter().enumerate() {
+            let Some(best) = candidates
+                .iter()
+                .map(|a| q.get(state, a))
+                .max_by(f64::total_cmp)
+            else {
+                break;
+            };
+            let slack = self.slacks.get(k).copied().unwrap_or(0.0);
+            candidates.retain(|a| q.get(state, a) >= best - slack);
+        }
+        candidates
+    }
+
+    /// The lexicographically greedy action: the first admissible action
+    pub fn best_action(&self, state: &S, actions: &[A]) -> Option<A> {
+        self.admissible(state, actions).into_iter().next()
+    }
+
+    /// The lexicographically greedy action in every non-final state
+    pub fn greedy_policy<M>(&self, mdp: &M) -> DeterministicPolicy<S, A>
+    where
+        M: MDP<State = S, Action = A>,
+    {
+        let mut policy = DeterministicPolicy::new();
+        for state in mdp.all_states().iter() {
+            if mdp.is_final_state(state) {
+                continue;
+            }
+            if let Some(action) = self.best_action(state, &mdp.actions_at(state)) {
+                policy.insert(state.clone(), action);
+            }
+        }
+        policy
+    }
+}
+
+/// # Lexicographic Q-Learning
+///
+/// Q-learning with one table per objective of a `MultiObjectiveMDP`, objective `0` having the
+/// highest priority. The greedy action at a state is found by filtering the available actions
+/// objective by objective, keeping those with `Q_k(s, a) ≥ max Q_k(s, ·) - slacks[k]` among the
+/// survivors of the previous objectives (see `LexicographicValues::admissible`). Every table
+/// bootstraps from that same greedy action at the next state, so all objectives are evaluated
+/// under the lexicographic policy. Behavior is epsilon-greedy around it.
+///
+/// With zero slack every objective after the first only breaks exact ties; a small slack lets
+/// later objectives trade a little of an earlier one (e.g. "reach goal A, then take the shortest
+/// route for B among routes that lose at most `slack` of A's value").
+///
+/// # Arguments
+/// * `mdp` - The multi-objective MDP, objectives in priority order
+/// * `slacks` - Tolerance per objective (missing entries are `0`)
+/// * `config` - Configuration parameters (learning rate, discount factor, exploration rate, etc.)
+///
+/// # Returns
+/// The learned Q-table of every objective
+pub fn lexicographic_q_learning<M>(
+    mdp: &M,
+    slacks: &[f64],
+    config: &TrainingConfig,
+) -> Result<LexicographicValues<M::State, M::Action>, Error>
+where
+    M: MultiObjectiveMDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let states = mdp.all_states();
+    if states.iter().next().is_none() {
+        return Err(Error::EmptyStateSpace);
+    }
+    let actions = action_union(mdp);
+    let mut learned = LexicographicValues {
+        values: (0..mdp.num_objectives())
+            .map(|_| ActionValue::new(states, &actions))
+            .collect(),
+        slacks: slacks.to_vec(),
+    };
+    let mut rng = config.rng();
+
+    for _ in 0..config.num_episodes {
+        let mut state = random_start(states, &mut rng)?.clone();
+
+        for _ in 0..config.max_num_steps {
+            if mdp.is_final_state(&state) {
+                break;
+            }
+            let available = mdp.actions_at(&state);
+            let action = if rng.random::<f64>() < config.exploration_rate {
+                available.choose(&mut rng).cloned()
+            } else {
+                learned.best_action(&state, &available)
+            };
+            let Some(action) = action else {
+                break;
+            };
+
+            let (measure, rewards) = mdp.vector_transition(&state, &action)?;
+            let next_state = measure
+                .sample_with(&mut rng)
+                .cloned()
+                .unwrap_or_else(|| state.clone());
+            let next_action = if mdp.is_final_state(&next_state) {
+                None
+            } else {
+                learned.best_action(&next_state, &mdp.actions_at(&next_state))
+            };
+
+            for (q, reward) in learned.values.iter_mut().zip(&rewards) {
+                let bootstrap = next_action.as_ref().map_or(0.0, |a| q.get(&next_state, a));
+                let target = reward + config.discount_factor * bootstrap;
+                let current = q.get(&state, &action);
+                q.insert(
+                    &state,
+                    &action,
+                    current + config.learning_rate * (target - current),
+                );
+            }
+
+            state = next_state;
+        }
+    }
+
+    Ok(learned)
+}
@@ -1,10 +1,71 @@
+pub mod average_reward;
+pub mod chains;
+#[cfg(feature = "serde")]
+pub mod checkpoint;
+pub mod combinators;
+pub mod compiled;
+pub mod config;
+pub mod constrained;
+pub mod constructors;
+pub mod counts;
+pub mod ctmdp;
+pub mod dataset;
+pub mod dense;
+pub mod diagnostics;
+pub mod distributional;
+pub mod embedded;
+pub mod env;
 pub mod error;
+pub mod export;
+pub mod factored;
+pub mod graph;
 pub mod gridworld;
+pub mod imitation;
+pub mod import;
+pub mod irl;
+pub mod labeling;
+#[cfg(feature = "testing")]
+pub mod laws;
+pub mod lexicographic;
+pub mod ltl;
+pub mod markov_chain;
+pub mod maxq;
+pub mod maze;
 pub mod mdp;
 pub mod measure;
+pub mod model_based;
+pub mod morphism;
+pub mod multi_objective;
+pub mod occupancy;
+pub mod offline;
+pub mod options;
+#[cfg(feature = "parallel")]
+pub mod parallel;
 pub mod pathmdp;
+#[cfg(feature = "serde")]
+pub mod persistence;
+#[cfg(feature = "plotting")]
+pub mod plotting;
+pub mod planning;
+pub mod policy;
 pub mod products;
 pub mod q_learning;
-
-const NO_OP_TRANSITION_REWARD: f64 = -1.0;
-const END_TRANSITION_REWARD: f64 = 10.0;
+pub mod quotient;
+pub mod race;
+pub mod reachability;
+pub mod render;
+pub mod reward_machine;
+pub mod replay;
+#[cfg(feature = "serde")]
+pub mod report;
+pub mod risk;
+pub mod robust;
+pub mod shield;
+pub mod smdp;
+pub mod taxi;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod time_bounded;
+pub mod uniformization;
+pub mod validation;
+pub mod wrappers;
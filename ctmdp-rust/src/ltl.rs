@@ -0,0 +1,485 @@
+//! # LTL Specifications
+//!
+//! Temporal-logic task specifications over the propositions of a `Labeled` MDP. A `Formula` of
+//! linear temporal logic (interpreted on finite traces: `X`, `F`, `G`, `U` and Boolean
+//! connectives) is compiled by formula progression into a deterministic finite automaton, which
+//! becomes a reward machine paying a sparse reward on the transition that satisfies the
+//! specification. Hand-built automata (`Dfa::new` + `add_transition`) go through the same product.
+//!
+//! The compilation is exact for the co-safety fragment (tasks that are satisfied after finitely
+//! many steps, such as `F a & F b` or `!hazard U goal`); safety formulas like `G !hazard` are never
+//! accepted, but violating them drops the automaton into a rejecting sink where no reward is left.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::error::Error;
+use crate::labeling::{Labeled, Labels, Proposition};
+use crate::reward_machine::{RewardMachine, RewardMachineProduct};
+
+/// Automaton states `Dfa::from_formula` explores before giving up
+const MAX_AUTOMATON_STATES: usize = 10_000;
+
+/// An LTL formula. `And`/`Or` operands are kept flattened, sorted and deduplicated, so equal
+/// formulas compare equal and progression reaches finitely many distinct states.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Formula {
+    True,
+    False,
+    Prop(Proposition),
+    Not(Box<Formula>),
+    And(Vec<Formula>),
+    Or(Vec<Formula>),
+    /// Holds if the operand holds from the next step on
+    Next(Box<Formula>),
+    /// Holds if the operand holds now or at some later step
+    Eventually(Box<Formula>),
+    /// Holds if the operand holds now and at every later step
+    Always(Box<Formula>),
+    /// `a U b`: `b` holds at some step, and `a` holds at every step before it
+    Until(Box<Formula>, Box<Formula>),
+}
+
+impl Formula {
+    pub fn prop(name: impl Into<Proposition>) -> Self {
+        Formula::Prop(name.into())
+    }
+
+    pub fn negate(self) -> Self {
+        match self {
+            Formula::True => Formula::False,
+            Formula::False => Formula::True,
+            Formula::Not(inner) => *inner,
+            other => Formula::Not(Box::new(other)),
+        }
+    }
+
+    pub fn and(operands: Vec<Formula>) -> Self {
+        let mut flat = Vec::with_capacity(operands.len());
+        for operand in operands {
+            match operand {
+                Formula::True => {}
+                Formula::False => return Formula::False,
+                Formula::And(inner) => flat.extend(inner),
+                other => flat.push(other),
+            }
+        }
+        flat.sort();
+        flat.dedup();
+        if flat.iter().any(|f| flat.contains(&f.clone().negate())) {
+            return Formula::False;
+        }
+        match flat.len() {
+            0 => Formula::True,
+            1 => flat.pop().unwrap_or(Formula::True),
+            _ => Formula::And(flat),
+        }
+    }
+
+    pub fn or(operands: Vec<Formula>) -> Self {
+        let mut flat = Vec::with_capacity(operands.len());
+        for operand in operands {
+            match operand {
+                Formula::False => {}
+                Formula::True => return Formula::True,
+                Formula::Or(inner) => flat.extend(inner),
+                other => flat.push(other),
+            }
+        }
+        flat.sort();
+        flat.dedup();
+        if flat.iter().any(|f| flat.contains(&f.clone().negate())) {
+            return Formula::True;
+        }
+        match flat.len() {
+            0 => Formula::False,
+            1 => flat.pop().unwrap_or(Formula::False),
+            _ => Formula::Or(flat),
+        }
+    }
+
+    /// # Parse
+    ///
+    /// Reads a formula written with propositions (alphanumeric words other than the keywords),
+    /// `true`, `false`, `!`, `&`, `|`, `->` and the temporal operators `X`, `F`, `G` (prefix) and
+    /// `U` (infix), with parentheses.
+    /// Binding is tightest for the prefix operators, then `U`, `&`, `|` and `->`; `U` and `->`
+    /// associate to the right.
+    ///
+    /// # Arguments
+    /// * `input` - The formula text, e.g. `"F a & (!hazard U goal)"`
+    ///
+    /// # Returns
+    /// The formula, or `Error::Specification` describing the first syntax error
+    pub fn parse(input: &str) -> Result<Formula, Error> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let formula = parser.implication()?;
+        match parser.peek() {
+            None => Ok(formula),
+            Some(token) => Err(Error::Specification(format!(
+                "unexpected {token:?} after complete formula"
+            ))),
+        }
+    }
+
+    /// The formula that must hold of the rest of the trace after a step whose state satisfies
+    /// exactly `labels` (Bacchus & Kabanza progression)
+    pub fn progress(&self, labels: &Labels) -> Formula {
+        match self {
+            Formula::True => Formula::True,
+            Formula::False => Formula::False,
+            Formula::Prop(name) => {
+                if labels.contains(name) {
+                    Formula::True
+                } else {
+                    Formula::False
+                }
+            }
+            Formula::Not(inner) => inner.progress(labels).negate(),
+            Formula::And(operands) => {
+                Formula::and(operands.iter().map(|f| f.progress(labels)).collect())
+            }
+            Formula::Or(operands) => {
+                Formula::or(operands.iter().map(|f| f.progress(labels)).collect())
+            }
+            Formula::Next(inner) => (**inner).clone(),
+            Formula::Eventually(inner) => Formula::or(vec![inner.progress(labels), self.clone()]),
+            Formula::Always(inner) => Formula::and(vec![inner.progress(labels), self.clone()]),
+            Formula::Until(hold, goal) => Formula::or(vec![
+                goal.progress(labels),
+                Formula::and(vec![hold.progress(labels), self.clone()]),
+            ]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Prop(String),
+    True,
+    False,
+    Not,
+    And,
+    Or,
+    Implies,
+    Next,
+    Eventually,
+    Always,
+    Until,
+    Open,
+    Close,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' | '!' | '&' | '|' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    '!' => Token::Not,
+                    '&' => Token::And,
+                    _ => Token::Or,
+                });
+            }
+            '-' => {
+                chars.next();
+                if chars.next() != Some('>') {
+                    return Err(Error::Specification("expected '>' after '-'".to_string()));
+                }
+                tokens.push(Token::Implies);
+            }
+            c if c.is_ascii_alphanumeric() || c == '_' => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(match word.as_str() {
+                    "X" => Token::Next,
+                    "F" => Token::Eventually,
+                    "G" => Token::Always,
+                    "U" => Token::Until,
+                    "true" => Token::True,
+                    "false" => Token::False,
+                    _ => Token::Prop(word),
+                });
+            }
+            other => {
+                return Err(Error::Specification(format!(
+                    "unexpected character {other:?}"
+                )));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive-descent parser, one method per precedence level
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn implication(&mut self) -> Result<Formula, Error> {
+        let premise = self.disjunction()?;
+        if self.eat(&Token::Implies) {
+            let conclusion = self.implication()?;
+            return Ok(Formula::or(vec![premise.negate(), conclusion]));
+        }
+        Ok(premise)
+    }
+
+    fn disjunction(&mut self) -> Result<Formula, Error> {
+        let mut operands = vec![self.conjunction()?];
+        while self.eat(&Token::Or) {
+            operands.push(self.conjunction()?);
+        }
+        Ok(Formula::or(operands))
+    }
+
+    fn conjunction(&mut self) -> Result<Formula, Error> {
+        let mut operands = vec![self.until()?];
+        while self.eat(&Token::And) {
+            operands.push(self.until()?);
+        }
+        Ok(Formula::and(operands))
+    }
+
+    fn until(&mut self) -> Result<Formula, Error> {
+        let hold = self.unary()?;
+        if self.eat(&Token::Until) {
+            let goal = self.until()?;
+            return Ok(Formula::Until(Box::new(hold), Box::new(goal)));
+        }
+        Ok(hold)
+    }
+
+    fn unary(&mut self) -> Result<Formula, Error> {
+        let Some(token) = self.peek().cloned() else {
+            return Err(Error::Specification(
+                "unexpected end of formula".to_string(),
+            ));
+        };
+        self.position += 1;
+        match token {
+            Token::Not => Ok(self.unary()?.negate()),
+            Token::Next => Ok(Formula::Next(Box::new(self.unary()?))),
+            Token::Eventually => Ok(Formula::Eventually(Box::new(self.unary()?))),
+            Token::Always => Ok(Formula::Always(Box::new(self.unary()?))),
+            Token::Prop(name) => Ok(Formula::Prop(name)),
+            Token::True => Ok(Formula::True),
+            Token::False => Ok(Formula::False),
+            Token::Open => {
+                let inner = self.implication()?;
+                if !self.eat(&Token::Close) {
+                    return Err(Error::Specification("missing ')'".to_string()));
+                }
+                Ok(inner)
+            }
+            other => Err(Error::Specification(format!("unexpected {other:?}"))),
+        }
+    }
+}
+
+/// A deterministic finite automaton over proposition sets, with states `0..num_states` and
+/// initial state `0`. A label set without an explicit transition leaves the state unchanged.
+#[derive(Debug, Clone)]
+pub struct Dfa {
+    num_states: usize,
+    accepting: HashSet<usize>,
+    rejecting: HashSet<usize>,
+    transitions: HashMap<(usize, Labels), usize>,
+}
+
+impl Dfa {
+    pub fn new(num_states: usize) -> Self {
+        Dfa {
+            num_states,
+            accepting: HashSet::new(),
+            rejecting: HashSet::new(),
+            transitions: HashMap::new(),
+        }
+    }
+
+    /// # From Formula
+    ///
+    /// Compiles a formula into an automaton by progression: each state is a formula still to be
+    /// satisfied, starting from `formula`, and reading `labels` moves to its progression. States
+    /// that progressed to `true` are accepting, those that progressed to `false` rejecting.
+    ///
+    /// # Arguments
+    /// * `formula` - The specification
+    /// * `alphabet` - Every label set the automaton may read (see `alphabet`)
+    ///
+    /// # Returns
+    /// The automaton, or `Error::Specification` if it would exceed 10 000 states
+    pub fn from_formula(formula: &Formula, alphabet: &[Labels]) -> Result<Dfa, Error> {
+        let mut index: HashMap<Formula, usize> = HashMap::from([(formula.clone(), 0)]);
+        let mut formulas = vec![formula.clone()];
+        let mut queue = VecDeque::from([0]);
+        let mut transitions = HashMap::new();
+        while let Some(state) = queue.pop_front() {
+            for labels in alphabet {
+                let next = formulas[state].progress(labels);
+                let target = match index.get(&next) {
+                    Some(&target) => target,
+                    None => {
+                        if formulas.len() >= MAX_AUTOMATON_STATES {
+                            return Err(Error::Specification(format!(
+                                "automaton exceeds {MAX_AUTOMATON_STATES} states"
+                            )));
+                        }
+                        let target = formulas.len();
+                        index.insert(next.clone(), target);
+                        formulas.push(next);
+                        queue.push_back(target);
+                        target
+                    }
+                };
+                transitions.insert((state, labels.clone()), target);
+            }
+        }
+        let states_where = |value: Formula| -> HashSet<usize> {
+            formulas
+                .iter()
+                .enumerate()
+                .filter(|(_, f)| **f == value)
+                .map(|(i, _)| i)
+                .collect()
+        };
+        Ok(Dfa {
+            num_states: formulas.len(),
+            accepting: states_where(Formula::True),
+            rejecting: states_where(Formula::False),
+            transitions,
+        })
+    }
+
+    pub fn num_states(&self) -> usize {
+        self.num_states
+    }
+
+    /// Adds `from --labels--> to`; fails with `Error::StateNotFound` if a state is out of range
+    pub fn add_transition(&mut self, from: usize, labels: Labels, to: usize) -> Result<(), Error> {
+        if from >= self.num_states || to >= self.num_states {
+            return Err(Error::StateNotFound);
+        }
+        self.transitions.insert((from, labels), to);
+        Ok(())
+    }
+
+    pub fn set_accepting(&mut self, state: usize) -> Result<(), Error> {
+        if state >= self.num_states {
+            return Err(Error::StateNotFound);
+        }
+        self.accepting.insert(state);
+        Ok(())
+    }
+
+    /// Marks a sink from which the specification can no longer be satisfied
+    pub fn set_rejecting(&mut self, state: usize) -> Result<(), Error> {
+        if state >= self.num_states {
+            return Err(Error::StateNotFound);
+        }
+        self.rejecting.insert(state);
+        Ok(())
+    }
+
+    pub fn is_accepting(&self, state: usize) -> bool {
+        self.accepting.contains(&state)
+    }
+
+    pub fn is_rejecting(&self, state: usize) -> bool {
+        self.rejecting.contains(&state)
+    }
+
+    pub fn step(&self, state: usize, labels: &Labels) -> usize {
+        self.transitions
+            .get(&(state, labels.clone()))
+            .copied()
+            .unwrap_or(state)
+    }
+
+    /// The reward machine with this automaton's transitions, paying `accept_reward` on every
+    /// transition into an accepting state from a non-accepting one. Accepting states are
+    /// terminal; rejecting states are absorbing sinks without reward.
+    pub fn to_reward_machine(&self, accept_reward: f64) -> Result<RewardMachine<Labels>, Error> {
+        let mut machine = RewardMachine::new(self.num_states);
+        for ((from, labels), &to) in &self.transitions {
+            let reward = if self.is_accepting(to) && !self.is_accepting(*from) {
+                accept_reward
+            } else {
+                0.0
+            };
+            machine.add_edge(*from, labels.clone(), to, reward)?;
+        }
+        for &state in &self.accepting {
+            machine.set_terminal(state)?;
+        }
+        Ok(machine)
+    }
+}
+
+/// The distinct label sets of a labeled MDP's states, in order of first appearance
+pub fn alphabet<M: Labeled>(mdp: &M) -> Vec<Labels> {
+    let mut seen = HashSet::new();
+    mdp.all_states()
+        .iter()
+        .map(|s| mdp.labels(s))
+        .filter(|labels| seen.insert(labels.clone()))
+        .collect()
+}
+
+/// # Specification Product
+///
+/// The product of a labeled MDP with an automaton: the automaton reads the labels of every state
+/// the MDP enters, and the transition that first reaches an accepting state pays `accept_reward`
+/// (all other rewards are zero). Start episodes from `RewardMachineProduct::start_state` so the
+/// automaton also reads the first state's labels.
+///
+/// # Arguments
+/// * `mdp` - The labeled MDP
+/// * `dfa` - The specification automaton, e.g. from `Dfa::from_formula(&formula, &alphabet(&mdp))`
+/// * `accept_reward` - Reward for satisfying the specification
+///
+/// # Returns
+/// The product MDP over `(state, automaton state)`
+pub fn specification_product<M>(
+    mdp: M,
+    dfa: &Dfa,
+    accept_reward: f64,
+) -> Result<RewardMachineProduct<M, Labels>, Error>
+where
+    M: Labeled,
+    M::State: Clone + 'static,
+{
+    let machine = dfa.to_reward_machine(accept_reward)?;
+    Ok(RewardMachineProduct::from_labeled(mdp, machine))
+}
@@ -0,0 +1,257 @@
+//! # Policy-Induced Markov Chains
+//!
+//! Fixing a policy turns an MDP into a Markov chain over its states. `MarkovChain` stores that
+//! chain explicitly and answers long-run questions about it: the stationary distribution (by
+//! power iteration or an exact linear solve) and how quickly the chain forgets where it started
+//! (mixing time). Average-reward methods assume such a distribution exists, and comparing the
+//! mixing of a product with that of its components shows how composition slows exploration.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::policy::StochasticPolicy;
+use crate::reachability::solve_linear_system;
+
+/// A finite Markov chain with sparse transition rows
+#[derive(Debug, Clone)]
+pub struct MarkovChain<S> {
+    states: Vec<S>,
+    index: HashMap<S, usize>,
+    /// `rows[i]` lists `(j, p(j | i))` with positive probabilities summing to one
+    rows: Vec<Vec<(usize, f64)>>,
+}
+
+impl<S: Eq + Hash + Clone> MarkovChain<S> {
+    /// # Induced Chain
+    ///
+    /// The chain `P(s' | s) = Σ_a π(a | s) p(s' | s, a)` of `policy` on `mdp`. Final states and
+    /// states where the policy is undefined are absorbing; mass sent to states outside
+    /// `all_states()` stays where it is.
+    pub fn induced<M>(mdp: &M, policy: &StochasticPolicy<S, M::Action>) -> Result<Self, Error>
+    where
+        M: MDP<State = S>,
+    {
+        let states: Vec<S> = mdp.all_states().iter().cloned().collect();
+        let index: HashMap<S, usize> = states
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.clone(), i))
+            .collect();
+
+        let mut rows = Vec::with_capacity(states.len());
+        for (i, state) in states.iter().enumerate() {
+            let measure = policy.get(state).filter(|_| !mdp.is_final_state(state));
+            let Some(measure) = measure else {
+                rows.push(vec![(i, 1.0)]);
+                continue;
+            };
+            let mut row: HashMap<usize, f64> = HashMap::new();
+            for (action, p_action) in measure.dist() {
+                if p_action.value() <= 0.0 {
+                    continue;
+                }
+                let (next, _) = mdp.stochastic_transition(state, action)?;
+                for (s, p) in next.dist() {
+                    let j = index.get(s).copied().unwrap_or(i);
+                    *row.entry(j).or_insert(0.0) += p_action.value() * p.value();
+                }
+            }
+            rows.push(normalized(row, i));
+        }
+        Ok(MarkovChain {
+            states,
+            index,
+            rows,
+        })
+    }
+
+    /// Like `induced`, but final states restart the episode in a uniformly random state (as
+    /// `evaluate_gain` does), which keeps episodic tasks recurrent
+    pub fn induced_with_restarts<M>(
+        mdp: &M,
+        policy: &StochasticPolicy<S, M::Action>,
+    ) -> Result<Self, Error>
+    where
+        M: MDP<State = S>,
+    {
+        let mut chain = Self::induced(mdp, policy)?;
+        let n = chain.states.len();
+        for (state, row) in chain.states.iter().zip(chain.rows.iter_mut()) {
+            if mdp.is_final_state(state) {
+                *row = (0..n).map(|j| (j, 1.0 / n as f64)).collect();
+            }
+        }
+        Ok(chain)
+    }
+
+    pub fn states(&self) -> &[S] {
+        &self.states
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// `P(to | from)`; zero for unknown states
+    pub fn transition_probability(&self, from: &S, to: &S) -> f64 {
+        let (Some(&i), Some(&j)) = (self.index.get(from), self.index.get(to)) else {
+            return 0.0;
+        };
+        self.rows[i]
+            .iter()
+            .find(|(k, _)| *k == j)
+            .map_or(0.0, |(_, p)| *p)
+    }
+
+    /// One step of the chain applied to a distribution over `states()` (in that order)
+    pub fn step(&self, distribution: &[f64]) -> Vec<f64> {
+        let mut next = vec![0.0; self.states.len()];
+        for (row, &mass) in self.rows.iter().zip(distribution) {
+            if mass == 0.0 {
+                continue;
+            }
+            for &(j, p) in row {
+                next[j] += mass * p;
+            }
+        }
+        next
+    }
+
+    /// # Stationary Distribution
+    ///
+    /// Power iteration on the lazy chain `(P + I) / 2`, which has the same stationary
+    /// distributions as `P` but converges for periodic chains too. Starts from the uniform
+    /// distribution and stops once no probability changes by more than `tolerance` or after
+    /// `max_iterations` steps. For a reducible chain the result is the limit from the uniform
+    /// start, one of several stationary distributions.
+    pub fn stationary_distribution(
+        &self,
+        tolerance: f64,
+        max_iterations: usize,
+    ) -> HashMap<S, f64> {
+        let distribution = self.lazy_power_iteration(tolerance, max_iterations);
+        self.states.iter().cloned().zip(distribution).collect()
+    }
+
+    /// # Exact Stationary Distribution
+    ///
+    /// Solves `π P = π`, `Σ π = 1` by Gaussian elimination (one balance equation is replaced by
+    /// the normalization). Fails with `Error::NumericalError` if the solution is not unique,
+    /// i.e. the chain has more than one closed class.
+    pub fn stationary_distribution_exact(&self) -> Result<HashMap<S, f64>, Error> {
+        let n = self.states.len();
+        if n == 0 {
+            return Err(Error::EmptyStateSpace);
+        }
+        // Row j of the system: Σ_i π(i) (P(j | i) - [i = j]) = 0
+        let mut a = vec![vec![0.0; n]; n];
+        for (i, row) in self.rows.iter().enumerate() {
+            a[i][i] -= 1.0;
+            for &(j, p) in row {
+                a[j][i] += p;
+            }
+        }
+        let mut b = vec![0.0; n];
+        a[n - 1] = vec![1.0; n];
+        b[n - 1] = 1.0;
+        let solution = solve_linear_system(a, b)?;
+        Ok(self
+            .states
+            .iter()
+            .cloned()
+            .zip(solution.into_iter().map(|p| p.max(0.0)))
+            .collect())
+    }
+
+    /// # Mixing Profile
+    ///
+    /// `d(t) = max_s TV(P^t(s, ·), π)` for `t = 0..=steps`, the worst-case total variation
+    /// distance to the stationary distribution after `t` steps (with `π` from
+    /// `stationary_distribution` at tolerance `1e-12`).
+    pub fn mixing_profile(&self, steps: usize) -> Vec<f64> {
+        let n = self.states.len();
+        if n == 0 {
+            return vec![0.0; steps + 1];
+        }
+        let stationary = self.lazy_power_iteration(1e-12, 100_000);
+        let mut distributions: Vec<Vec<f64>> = (0..n)
+            .map(|i| {
+                let mut d = vec![0.0; n];
+                d[i] = 1.0;
+                d
+            })
+            .collect();
+        let mut profile = Vec::with_capacity(steps + 1);
+        for t in 0..=steps {
+            if t > 0 {
+                distributions = distributions.iter().map(|d| self.step(d)).collect();
+            }
+            let worst = distributions
+                .iter()
+                .map(|d| total_variation(d, &stationary))
+                .fold(0.0, f64::max);
+            profile.push(worst);
+        }
+        profile
+    }
+
+    /// # Mixing Time
+    ///
+    /// The first `t ≤ max_steps` with `d(t) ≤ epsilon` (see `mixing_profile`); `None` if the
+    /// chain has not mixed by then, which is always the case for periodic or reducible chains
+    /// and small `epsilon`. The conventional choice is `epsilon = 1/4`.
+    pub fn mixing_time(&self, epsilon: f64, max_steps: usize) -> Option<usize> {
+        self.mixing_profile(max_steps)
+            .into_iter()
+            .position(|distance| distance <= epsilon)
+    }
+
+    fn lazy_power_iteration(&self, tolerance: f64, max_iterations: usize) -> Vec<f64> {
+        let n = self.states.len();
+        if n == 0 {
+            return Vec::new();
+        }
+        let mut distribution = vec![1.0 / n as f64; n];
+        for _ in 0..max_iterations {
+            let stepped = self.step(&distribution);
+            let next: Vec<f64> = distribution
+                .iter()
+                .zip(&stepped)
+                .map(|(stay, moved)| 0.5 * (stay + moved))
+                .collect();
+            let change = next
+                .iter()
+                .zip(&distribution)
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0, f64::max);
+            distribution = next;
+            if change <= tolerance {
+                break;
+            }
+        }
+        distribution
+    }
+}
+
+/// Drops zero entries and rescales the row to sum to one (a row with no mass becomes a self-loop)
+fn normalized(row: HashMap<usize, f64>, state: usize) -> Vec<(usize, f64)> {
+    let total: f64 = row.values().sum();
+    if total <= 0.0 {
+        return vec![(state, 1.0)];
+    }
+    row.into_iter()
+        .filter(|(_, p)| *p > 0.0)
+        .map(|(j, p)| (j, p / total))
+        .collect()
+}
+
+/// `½ Σ |p - q|`
+fn total_variation(p: &[f64], q: &[f64]) -> f64 {
+    0.5 * p.iter().zip(q).map(|(a, b)| (a - b).abs()).sum::<f64>()
+}
@@ -0,0 +1,653 @@
+//! # MAXQ Hierarchies
+//!
+//! Hierarchical task decomposition after Dietterich's MAXQ (2000). A `Hierarchy` is a DAG of
+//! tasks: primitive tasks are MDP actions, composite tasks choose among child tasks until their
+//! termination predicate holds. Every composite task is itself an MDP over the primitive actions
+//! below it (`SubtaskMDP`), so any learner in the crate can solve it in isolation.
+//!
+//! Values decompose along the hierarchy,
+//!
+//! `Q(i, s, a) = V(a, s) + C(i, s, a)`, `V(i, s) = max_a Q(i, s, a)`,
+//!
+//! where `V(a, s)` of a primitive is its expected reward and the completion `C(i, s, a)` is the
+//! discounted value of finishing task `i` after child `a` returns. `maxq_q_learning` learns the
+//! decomposition (MAXQ-0). For products, `Hierarchy::from_product` splits the task at the
+//! component boundary: one subtask per component, done when that component is final.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use madepro::models::Sampler;
+use rand::Rng;
+use rand::seq::IndexedRandom;
+
+use crate::config::TrainingConfig;
+use crate::error::Error;
+use crate::factored::FactoredProduct;
+use crate::mdp::MDP;
+use crate::measure::Measure;
+use crate::policy::DeterministicPolicy;
+use crate::products::Product;
+use crate::q_learning::random_start;
+
+/// A task of a `Hierarchy`, numbered in the order tasks were added
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, PartialOrd, Ord)]
+pub struct TaskId(pub usize);
+
+/// Decides whether a composite task is done in a state
+type Terminated<S> = Box<dyn Fn(&S) -> bool>;
+
+enum TaskKind<S, A> {
+    Primitive(A),
+    Composite {
+        children: Vec<TaskId>,
+        terminated: Terminated<S>,
+    },
+}
+
+struct Task<S, A> {
+    name: String,
+    kind: TaskKind<S, A>,
+}
+
+/// A task hierarchy over an MDP with states `S` and actions `A`
+pub struct Hierarchy<S, A> {
+    tasks: Vec<Task<S, A>>,
+    root: Option<TaskId>,
+}
+
+impl<S, A> Default for Hierarchy<S, A> {
+    fn default() -> Self {
+        Hierarchy {
+            tasks: Vec::new(),
+            root: None,
+        }
+    }
+}
+
+impl<S, A> Hierarchy<S, A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the primitive task that takes `action`
+    pub fn add_primitive(&mut self, name: impl Into<String>, action: A) -> TaskId {
+        self.tasks.push(Task {
+            name: name.into(),
+            kind: TaskKind::Primitive(action),
+        });
+        TaskId(self.tasks.len() - 1)
+    }
+
+    /// Adds a composite task choosing among `children` until `terminated` holds. Children must
+    /// already be in the hierarchy (which keeps it acyclic), otherwise this fails with
+    /// `Error::Specification`. The most recently added composite task is the root unless
+    /// `set_root` says otherwise.
+    pub fn add_subtask(
+        &mut self,
+        name: impl Into<String>,
+        children: Vec<TaskId>,
+        terminated: impl Fn(&S) -> bool + 'static,
+    ) -> Result<TaskId, Error> {
+        let name = name.into();
+        if children.is_empty() {
+            return Err(Error::Specification(format!(
+                "subtask {name} has no children"
+            )));
+        }
+        if let Some(child) = children.iter().find(|c| c.0 >= self.tasks.len()) {
+            return Err(Error::Specification(format!(
+                "subtask {name} refers to unknown task {}",
+                child.0
+            )));
+        }
+        self.tasks.push(Task {
+            name,
+            kind: TaskKind::Composite {
+                children,
+                terminated: Box::new(terminated),
+            },
+        });
+        let id = TaskId(self.tasks.len() - 1);
+        self.root = Some(id);
+        Ok(id)
+    }
+
+    pub fn set_root(&mut self, task: TaskId) -> Result<(), Error> {
+        if self.is_primitive(task) || task.0 >= self.tasks.len() {
+            return Err(Error::Specification(format!(
+                "task {} is not a composite task",
+                task.0
+            )));
+        }
+        self.root = Some(task);
+        Ok(())
+    }
+
+    pub fn root(&self) -> Option<TaskId> {
+        self.root
+    }
+
+    pub fn num_tasks(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn name(&self, task: TaskId) -> &str {
+        &self.tasks[task.0].name
+    }
+
+    pub fn is_primitive(&self, task: TaskId) -> bool {
+        matches!(
+            self.tasks.get(task.0).map(|t| &t.kind),
+            Some(TaskKind::Primitive(_))
+        )
+    }
+
+    /// The action of a primitive task
+    pub fn action(&self, task: TaskId) -> Option<&A> {
+        match &self.tasks.get(task.0)?.kind {
+            TaskKind::Primitive(action) => Some(action),
+            TaskKind::Composite { .. } => None,
+        }
+    }
+
+    /// The children of a composite task (empty for primitives)
+    pub fn children(&self, task: TaskId) -> &[TaskId] {
+        match self.tasks.get(task.0).map(|t| &t.kind) {
+            Some(TaskKind::Composite { children, .. }) => children,
+            _ => &[],
+        }
+    }
+
+    /// Whether a composite task is done in `state`. Primitives are never "done" before they run.
+    pub fn is_terminated(&self, task: TaskId, state: &S) -> bool {
+        match self.tasks.get(task.0).map(|t| &t.kind) {
+            Some(TaskKind::Composite { terminated, .. }) => terminated(state),
+            _ => false,
+        }
+    }
+
+    /// The actions of the primitive tasks below `task`, without duplicates
+    pub fn primitive_actions(&self, task: TaskId) -> Vec<A>
+    where
+        A: PartialEq + Clone,
+    {
+        let mut actions = Vec::new();
+        let mut stack = vec![task];
+        let mut seen = HashSet::new();
+        while let Some(current) = stack.pop() {
+            if !seen.insert(current) {
+                continue;
+            }
+            match &self.tasks[current.0].kind {
+                TaskKind::Primitive(action) => {
+                    if !actions.contains(action) {
+                        actions.push(action.clone());
+                    }
+                }
+                TaskKind::Composite { children, .. } => stack.extend(children.iter().rev()),
+            }
+        }
+        actions
+    }
+
+    /// The children of `task` that can run in `state`: primitives whose action is available,
+    /// composites that are not yet done and have an available primitive below them
+    fn available_children<M>(&self, mdp: &M, task: TaskId, state: &S) -> Vec<TaskId>
+    where
+        M: MDP<State = S, Action = A>,
+        A: PartialEq + Clone,
+    {
+        let actions = mdp.actions_at(state);
+        self.children(task)
+            .iter()
+            .copied()
+            .filter(|&child| match &self.tasks[child.0].kind {
+                TaskKind::Primitive(action) => actions.contains(action),
+                TaskKind::Composite { .. } => {
+                    !self.is_terminated(child, state)
+                        && self
+                            .primitive_actions(child)
+                            .iter()
+                            .any(|a| actions.contains(a))
+                }
+            })
+            .collect()
+    }
+
+    /// # Subtask MDP
+    ///
+    /// The MDP a composite task solves: the dynamics and rewards of `mdp` restricted to the
+    /// primitive actions below `task`, with the states where the task is done made final.
+    pub fn subtask_mdp<'a, M>(&'a self, mdp: &'a M, task: TaskId) -> SubtaskMDP<'a, M>
+    where
+        M: MDP<State = S, Action = A>,
+        A: PartialEq + Clone,
+    {
+        SubtaskMDP {
+            mdp,
+            hierarchy: self,
+            task,
+            actions: self.primitive_actions(task),
+        }
+    }
+}
+
+impl<S1, S2, A> Hierarchy<Product<S1, S2>, A>
+where
+    S1: Eq + Hash + Clone + 'static,
+    S2: Eq + Hash + Clone + 'static,
+    A: PartialEq + Clone + Debug,
+{
+    /// # Product Hierarchy
+    ///
+    /// The two-level hierarchy given by a product's component boundary: a `left` subtask over the
+    /// product actions that move the first component, done once that component is in a final
+    /// state, the matching `right` subtask, and a `root` choosing between them. For a
+    /// `BoxProduct` the two subtasks own disjoint actions; for a `CartesianProduct` every joint
+    /// action moves both components, so both subtasks see all of them and differ only in their
+    /// goal.
+    pub fn from_product<P>(product: &P) -> Self
+    where
+        P: FactoredProduct<Action = A>,
+        P::First: MDP<State = S1>,
+        P::Second: MDP<State = S2>,
+    {
+        let (first, second) = product.factors();
+        let final_first: HashSet<_> = first
+            .all_states()
+            .iter()
+            .filter(|s| first.is_final_state(s))
+            .cloned()
+            .collect();
+        let final_second: HashSet<_> = second
+            .all_states()
+            .iter()
+            .filter(|s| second.is_final_state(s))
+            .cloned()
+            .collect();
+
+        let mut hierarchy = Hierarchy::new();
+        let mut left = Vec::new();
+        let mut right = Vec::new();
+        for action in product.all_actions() {
+            let (a1, a2) = P::split_action(&action);
+            let (moves_first, moves_second) = (a1.is_some(), a2.is_some());
+            let task = hierarchy.add_primitive(format!("{action:?}"), action);
+            if moves_first {
+                left.push(task);
+            }
+            if moves_second {
+                right.push(task);
+            }
+        }
+        let mut subtasks = Vec::new();
+        if !left.is_empty() {
+            subtasks.push(
+                hierarchy
+                    .add_subtask("left", left, move |s: &Product<S1, S2>| {
+                        final_first.contains(s.first())
+                    })
+                    .expect("children were just added"),
+            );
+        }
+        if !right.is_empty() {
+            subtasks.push(
+                hierarchy
+                    .add_subtask("right", right, move |s: &Product<S1, S2>| {
+                        final_second.contains(s.second())
+                    })
+                    .expect("children were just added"),
+            );
+        }
+        if !subtasks.is_empty() {
+            hierarchy
+                .add_subtask("root", subtasks, |_| false)
+                .expect("children were just added");
+        }
+        hierarchy
+    }
+}
+
+/// A composite task seen as an MDP: the primitive actions below it, final where it is done
+pub struct SubtaskMDP<'a, M: MDP> {
+    mdp: &'a M,
+    hierarchy: &'a Hierarchy<M::State, M::Action>,
+    task: TaskId,
+    actions: Vec<M::Action>,
+}
+
+impl<M: MDP> SubtaskMDP<'_, M> {
+    pub fn task(&self) -> TaskId {
+        self.task
+    }
+
+    pub fn inner(&self) -> &M {
+        self.mdp
+    }
+}
+
+impl<M> MDP for SubtaskMDP<'_, M>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    type State = M::State;
+    type Action = M::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        self.mdp.all_states()
+    }
+
+    fn all_actions(&self) -> Vec<Self::Action> {
+        self.actions.clone()
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        self.mdp
+            .actions_at(state)
+            .into_iter()
+            .filter(|a| self.actions.contains(a))
+            .collect()
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.hierarchy.is_terminated(self.task, state) || self.mdp.is_final_state(state)
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.hierarchy.is_terminated(self.task, state)
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        if !self.actions.contains(action) {
+            return Err(Error::UndefinedTransition);
+        }
+        self.mdp.stochastic_transition(state, action)
+    }
+}
+
+/// The MAXQ value decomposition: primitive values `V(a, s)` and completions `C(i, s, a)`
+pub struct MaxqValues<S> {
+    primitive: HashMap<(TaskId, S), f64>,
+    completion: HashMap<(TaskId, S, TaskId), f64>,
+}
+
+impl<S: Eq + Hash + Clone> MaxqValues<S> {
+    fn new() -> Self {
+        MaxqValues {
+            primitive: HashMap::new(),
+            completion: HashMap::new(),
+        }
+    }
+
+    /// `C(i, s, a)`; zero for triples never updated
+    pub fn completion(&self, task: TaskId, state: &S, child: TaskId) -> f64 {
+        self.completion
+            .get(&(task, state.clone(), child))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// `V(i, s)`: the expected reward of a primitive, the best `Q(i, s, a)` of a composite (zero
+    /// where the task is done or nothing can run)
+    pub fn value<M>(
+        &self,
+        hierarchy: &Hierarchy<S, M::Action>,
+        mdp: &M,
+        task: TaskId,
+        state: &S,
+    ) -> f64
+    where
+        M: MDP<State = S>,
+        M::Action: Clone,
+    {
+        if hierarchy.is_primitive(task) {
+            return self
+                .primitive
+                .get(&(task, state.clone()))
+                .copied()
+                .unwrap_or(0.0);
+        }
+        self.greedy_child(hierarchy, mdp, task, state)
+            .map_or(0.0, |(_, q)| q)
+    }
+
+    /// `Q(i, s, a) = V(a, s) + C(i, s, a)`
+    pub fn q_value<M>(
+        &self,
+        hierarchy: &Hierarchy<S, M::Action>,
+        mdp: &M,
+        task: TaskId,
+        state: &S,
+        child: TaskId,
+    ) -> f64
+    where
+        M: MDP<State = S>,
+        M::Action: Clone,
+    {
+        self.value(hierarchy, mdp, child, state) + self.completion(task, state, child)
+    }
+
+    /// The available child of `task` with the highest `Q(i, s, a)`, with that value (first child
+    /// wins ties)
+    pub fn greedy_child<M>(
+        &self,
+        hierarchy: &Hierarchy<S, M::Action>,
+        mdp: &M,
+        task: TaskId,
+        state: &S,
+    ) -> Option<(TaskId, f64)>
+    where
+        M: MDP<State = S>,
+        M::Action: Clone,
+    {
+        if mdp.is_final_state(state) || hierarchy.is_terminated(task, state) {
+            return None;
+        }
+        let mut best: Option<(TaskId, f64)> = None;
+        for child in hierarchy.available_children(mdp, task, state) {
+            let q = self.q_value(hierarchy, mdp, task, state, child);
+            if best.is_none_or(|(_, best_q)| q > best_q) {
+                best = Some((child, q));
+            }
+        }
+        best
+    }
+
+    /// # Decompose
+    ///
+    /// Follows greedy children from the root down to a primitive and reports the value terms on
+    /// the way: each composite task with its completion term `C(i, s, a)`, then the primitive
+    /// with `V(a, s)`. The terms sum to `V(root, s)`.
+    pub fn decompose<M>(
+        &self,
+        hierarchy: &Hierarchy<S, M::Action>,
+        mdp: &M,
+        state: &S,
+    ) -> Vec<(TaskId, f64)>
+    where
+        M: MDP<State = S>,
+        M::Action: Clone,
+    {
+        let mut terms = Vec::new();
+        let mut task = match hierarchy.root() {
+            Some(root) => root,
+            None => return terms,
+        };
+        while let Some((child, _)) = self.greedy_child(hierarchy, mdp, task, state) {
+            terms.push((task, self.completion(task, state, child)));
+            task = child;
+            if hierarchy.is_primitive(task) {
+                terms.push((task, self.value(hierarchy, mdp, task, state)));
+                break;
+            }
+        }
+        terms
+    }
+
+    /// The recursively greedy policy: in every non-final state, descend greedy children from
+    /// the root and take the primitive action reached
+    pub fn greedy_policy<M>(
+        &self,
+        hierarchy: &Hierarchy<S, M::Action>,
+        mdp: &M,
+    ) -> DeterministicPolicy<S, M::Action>
+    where
+        M: MDP<State = S>,
+        M::Action: Clone,
+    {
+        let mut policy = HashMap::new();
+        for state in mdp.all_states().iter() {
+            if mdp.is_final_state(state) {
+                continue;
+            }
+            let primitive = self
+                .decompose(hierarchy, mdp, state)
+                .last()
+                .and_then(|(task, _)| hierarchy.action(*task).cloned());
+            if let Some(action) = primitive {
+                policy.insert(state.clone(), action);
+            }
+        }
+        policy
+    }
+}
+
+/// MAXQ-0 state shared by the recursive calls of one training run
+struct Maxq0<'a, M: MDP, R> {
+    mdp: &'a M,
+    hierarchy: &'a Hierarchy<M::State, M::Action>,
+    config: &'a TrainingConfig,
+    values: MaxqValues<M::State>,
+    rng: R,
+    steps_left: usize,
+}
+
+impl<M, R> Maxq0<'_, M, R>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+    R: Rng,
+{
+    /// Runs `task` from `state`; returns the primitive steps taken and the state it ended in
+    fn run(&mut self, task: TaskId, state: M::State) -> Result<(usize, M::State), Error> {
+        if let Some(action) = self.hierarchy.action(task) {
+            let (measure, reward) = self.mdp.stochastic_transition(&state, action)?;
+            let next = measure
+                .sample_with(&mut self.rng)
+                .cloned()
+                .unwrap_or_else(|| state.clone());
+            let key = (task, state);
+            let current = self.values.primitive.get(&key).copied().unwrap_or(0.0);
+            self.values.primitive.insert(
+                key,
+                current + self.config.learning_rate * (reward - current),
+            );
+            self.steps_left = self.steps_left.saturating_sub(1);
+            return Ok((1, next));
+        }
+
+        let mut state = state;
+        let mut total = 0;
+        while self.steps_left > 0 {
+            let Some(child) = self.choose_child(task, &state) else {
+                break;
+            };
+            let (steps, next) = self.run(child, state.clone())?;
+            let done = self.mdp.is_final_state(&next) || self.hierarchy.is_terminated(task, &next);
+            let continuation = if done {
+                0.0
+            } else {
+                self.values
+                    .greedy_child(self.hierarchy, self.mdp, task, &next)
+                    .map_or(0.0, |(_, q)| q)
+            };
+            let target = self.config.discount_factor.powi(steps as i32) * continuation;
+            let current = self.values.completion(task, &state, child);
+            self.values.completion.insert(
+                (task, state, child),
+                current + self.config.learning_rate * (target - current),
+            );
+            total += steps;
+            state = next;
+        }
+        Ok((total, state))
+    }
+
+    fn choose_child(&mut self, task: TaskId, state: &M::State) -> Option<TaskId> {
+        if self.mdp.is_final_state(state) || self.hierarchy.is_terminated(task, state) {
+            return None;
+        }
+        if self.rng.random::<f64>() < self.config.exploration_rate {
+            self.hierarchy
+                .available_children(self.mdp, task, state)
+                .choose(&mut self.rng)
+                .copied()
+        } else {
+            self.values
+                .greedy_child(self.hierarchy, self.mdp, task, state)
+                .map(|(child, _)| child)
+        }
+    }
+}
+
+/// # MAXQ Q-Learning
+///
+/// Learns the MAXQ value decomposition (MAXQ-0). Every episode runs the root task from a random
+/// state; a composite task repeatedly picks an available child epsilon-greedily, runs it to
+/// completion (`N` primitive steps, ending in `s'`) and updates
+///
+/// `C(i, s, a) ← C(i, s, a) + α [γ^N max_a' Q(i, s', a') - C(i, s, a)]`
+///
+/// while a primitive updates `V(a, s) ← V(a, s) + α [r - V(a, s)]`. Episodes last at most
+/// `config.max_num_steps` primitive steps.
+///
+/// # Arguments
+/// * `mdp` - The MDP to learn from
+/// * `hierarchy` - The task hierarchy (its root is run every episode)
+/// * `config` - Configuration parameters (learning rate, discount factor, exploration rate, etc.)
+///
+/// # Returns
+/// The learned value decomposition
+pub fn maxq_q_learning<M>(
+    mdp: &M,
+    hierarchy: &Hierarchy<M::State, M::Action>,
+    config: &TrainingConfig,
+) -> Result<MaxqValues<M::State>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let states = mdp.all_states();
+    if states.iter().next().is_none() {
+        return Err(Error::EmptyStateSpace);
+    }
+    let root = hierarchy
+        .root()
+        .ok_or_else(|| Error::Specification("hierarchy has no root task".to_string()))?;
+
+    let mut learner = Maxq0 {
+        mdp,
+        hierarchy,
+        config,
+        values: MaxqValues::new(),
+        rng: config.rng(),
+        steps_left: 0,
+    };
+    for _ in 0..config.num_episodes {
+        learner.steps_left = config.max_num_steps as usize;
+        let state = random_start(states, &mut learner.rng)?.clone();
+        learner.run(root, state)?;
+    }
+
+    Ok(learner.values)
+}
@@ -0,0 +1,138 @@
+//! # Maze Generation
+//!
+//! Random perfect mazes (exactly one path between any two open cells) as `GridworldWithGoals`,
+//! so experiments can average over a distribution of environments instead of a single grid.
+//! Rooms sit on the even rows and columns of the grid and the cells between neighbouring rooms
+//! are walls until the generator carves a passage through them. The start is the top-left room
+//! and the goal the bottom-right one; as the maze is connected, every maze is solvable.
+
+use madepro::environments::gridworld::Cell;
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::error::Error;
+use crate::gridworld::{GridPosition, GridworldWithGoals};
+
+/// How `generate_maze` carves passages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MazeAlgorithm {
+    /// Depth-first search with backtracking: long, winding corridors with few branches
+    RecursiveBacktracker,
+    /// Randomized Prim: grows the maze from a random frontier, giving many short dead ends
+    Prim,
+}
+
+/// # Generate Maze
+///
+/// Carves a random maze into a `rows × cols` grid of walls. With even dimensions the last row
+/// or column stays solid wall.
+///
+/// # Arguments
+/// * `rows` - Number of grid rows
+/// * `cols` - Number of grid columns
+/// * `seed` - Seed of the random generator; the same seed gives the same maze
+/// * `algorithm` - The carving algorithm
+///
+/// # Returns
+/// A gridworld with start `(0, 0)` and a goal in the bottom-right room, or
+/// `Error::Specification` if the grid is too small to hold two rooms
+pub fn generate_maze(
+    rows: usize,
+    cols: usize,
+    seed: u64,
+    algorithm: MazeAlgorithm,
+) -> Result<GridworldWithGoals, Error> {
+    let (room_rows, room_cols) = (rows.div_ceil(2), cols.div_ceil(2));
+    if room_rows * room_cols < 2 {
+        return Err(Error::Specification(format!(
+            "a {rows}x{cols} grid has room for fewer than two maze cells"
+        )));
+    }
+    let mut maze = Maze {
+        cells: vec![vec![Cell::Wall; cols]; rows],
+        visited: vec![vec![false; room_cols]; room_rows],
+    };
+    let mut rng = StdRng::seed_from_u64(seed);
+    match algorithm {
+        MazeAlgorithm::RecursiveBacktracker => maze.backtrack(&mut rng),
+        MazeAlgorithm::Prim => maze.prim(&mut rng),
+    }
+    let goal = GridPosition::new(2 * (room_rows - 1), 2 * (room_cols - 1));
+    maze.cells[goal.row()][goal.col()] = Cell::End;
+    GridworldWithGoals::from_layout(maze.cells, vec![goal], Some(GridPosition::new(0, 0)))
+}
+
+/// A maze being carved; rooms are addressed by `(room row, room column)`
+struct Maze {
+    cells: Vec<Vec<Cell>>,
+    visited: Vec<Vec<bool>>,
+}
+
+impl Maze {
+    fn visit(&mut self, (row, col): (usize, usize)) {
+        self.visited[row][col] = true;
+        self.cells[2 * row][2 * col] = Cell::Air;
+    }
+
+    /// Opens the wall between two adjacent rooms
+    fn carve(&mut self, from: (usize, usize), to: (usize, usize)) {
+        self.cells[from.0 + to.0][from.1 + to.1] = Cell::Air;
+        self.visit(to);
+    }
+
+    fn unvisited_neighbours(&self, (row, col): (usize, usize)) -> Vec<(usize, usize)> {
+        let mut neighbours = Vec::with_capacity(4);
+        if row > 0 {
+            neighbours.push((row - 1, col));
+        }
+        if row + 1 < self.visited.len() {
+            neighbours.push((row + 1, col));
+        }
+        if col > 0 {
+            neighbours.push((row, col - 1));
+        }
+        if col + 1 < self.visited[0].len() {
+            neighbours.push((row, col + 1));
+        }
+        neighbours.retain(|&(r, c)| !self.visited[r][c]);
+        neighbours
+    }
+
+    fn backtrack<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.visit((0, 0));
+        let mut stack = vec![(0, 0)];
+        while let Some(&room) = stack.last() {
+            match self.unvisited_neighbours(room).choose(rng) {
+                Some(&next) => {
+                    self.carve(room, next);
+                    stack.push(next);
+                }
+                None => {
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    fn prim<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.visit((0, 0));
+        let mut frontier: Vec<_> = self
+            .unvisited_neighbours((0, 0))
+            .into_iter()
+            .map(|next| ((0, 0), next))
+            .collect();
+        while !frontier.is_empty() {
+            let (from, to) = frontier.swap_remove(rng.random_range(0..frontier.len()));
+            if self.visited[to.0][to.1] {
+                continue;
+            }
+            self.carve(from, to);
+            frontier.extend(
+                self.unvisited_neighbours(to)
+                    .into_iter()
+                    .map(|next| (to, next)),
+            );
+        }
+    }
+}
@@ -1,14 +1,35 @@
 use crate::measure::Measure;
 use crate::error::Error;
 use madepro::models::{Action, Sampler, State};
+use std::collections::HashSet;
+use std::sync::Arc;
 
+/// A Markov decision process with fallible stochastic transitions.
+///
+/// `all_actions` and `actions_at` default to each other: MDPs whose actions don't depend on the
+/// state implement `all_actions`, the others implement `actions_at`. Implementing neither recurses
+/// forever.
 pub trait MDP {
     type State: State;
     type Action: Action;
 
     fn all_states(&self) -> &Sampler<Self::State>;
 
-    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action>;
+    /// Every action available in some state, without duplicates. Defaults to the union of
+    /// `actions_at` over `all_states`, in order of first appearance.
+    fn all_actions(&self) -> Vec<Self::Action> {
+        let mut seen = HashSet::new();
+        self.all_states()
+            .iter()
+            .flat_map(|s| self.actions_at(s))
+            .filter(|a| seen.insert(a.clone()))
+            .collect()
+    }
+
+    /// The actions available in `state`. Defaults to `all_actions`.
+    fn actions_at(&self, _state: &Self::State) -> Vec<Self::Action> {
+        self.all_actions()
+    }
 
     fn is_final_state(&self, st: &Self::State) -> bool;
 
@@ -16,6 +37,12 @@ pub trait MDP {
         Self::is_final_state(self, st)
     }
 
+    /// Streams the states without requiring them to be materialized; implementations that build
+    /// `all_states` lazily override this so composites can enumerate them without a copy per level.
+    fn states(&self) -> Box<dyn Iterator<Item = Self::State> + '_> {
+        Box::new(self.all_states().iter().cloned())
+    }
+
     fn all_state_action_pairs(&self) -> Vec<(Self::State, Self::Action)> {
         self.all_states()
             .iter()
@@ -29,3 +56,79 @@ pub trait MDP {
         action: &Self::Action,
     ) -> Result<(Measure<Self::State>, f64), Error>;
 }
+
+/// Shared references are MDPs too, so one component can back several products at once.
+impl<M: MDP + ?Sized> MDP for &M {
+    type State = M::State;
+    type Action = M::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        (**self).all_states()
+    }
+
+    fn states(&self) -> Box<dyn Iterator<Item = Self::State> + '_> {
+        (**self).states()
+    }
+
+    fn all_actions(&self) -> Vec<Self::Action> {
+        (**self).all_actions()
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        (**self).actions_at(state)
+    }
+
+    fn is_final_state(&self, st: &Self::State) -> bool {
+        (**self).is_final_state(st)
+    }
+
+    fn is_goal(&self, st: &Self::State) -> bool {
+        (**self).is_goal(st)
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        (**self).stochastic_transition(state, action)
+    }
+}
+
+/// Like `&M`, for products that need to own a handle to a shared component.
+impl<M: MDP + ?Sized> MDP for Arc<M> {
+    type State = M::State;
+    type Action = M::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        (**self).all_states()
+    }
+
+    fn states(&self) -> Box<dyn Iterator<Item = Self::State> + '_> {
+        (**self).states()
+    }
+
+    fn all_actions(&self) -> Vec<Self::Action> {
+        (**self).all_actions()
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        (**self).actions_at(state)
+    }
+
+    fn is_final_state(&self, st: &Self::State) -> bool {
+        (**self).is_final_state(st)
+    }
+
+    fn is_goal(&self, st: &Self::State) -> bool {
+        (**self).is_goal(st)
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        (**self).stochastic_transition(state, action)
+    }
+}
@@ -1,9 +1,15 @@
-use std::collections::HashMap;
 use std::hash::Hash;
 
+use indexmap::IndexMap;
+
 use crate::error::Error;
 
 #[derive(Debug, PartialEq, PartialOrd, Copy, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "f64", into = "f64")
+)]
 pub struct Probability(f64);
 impl Probability {
     pub const ZERO: Self = Probability(0.);
@@ -31,14 +37,43 @@ impl Probability {
     }
 }
 
+impl TryFrom<f64> for Probability {
+    type Error = Error;
+
+    fn try_from(value: f64) -> Result<Self, Error> {
+        Probability::new(value)
+    }
+}
+
+impl From<Probability> for f64 {
+    fn from(probability: Probability) -> f64 {
+        probability.0
+    }
+}
+
+/// A finite probability measure. Outcomes keep the order they were first added in, and
+/// sampling walks them in that order, so a seeded generator draws the same outcomes in every
+/// process as long as the measure is built the same way.
+#[derive(Debug, Clone)]
 pub struct Measure<T> {
-    dist: HashMap<T, Probability>,
+    dist: IndexMap<T, Probability>,
 }
 
+/// How far the probabilities of a measure may sum from one by default
+pub const DEFAULT_TOLERANCE: f64 = 1e-10;
+
 impl<T: Eq + Hash> Measure<T> {
-    pub fn from_distribution(dist: HashMap<T, Probability>) -> Result<Measure<T>, Error> {
+    pub fn from_distribution(dist: IndexMap<T, Probability>) -> Result<Measure<T>, Error> {
+        Self::from_distribution_with_tolerance(dist, DEFAULT_TOLERANCE)
+    }
+
+    /// Like `from_distribution`, accepting probabilities whose sum is within `tolerance` of one
+    pub fn from_distribution_with_tolerance(
+        dist: IndexMap<T, Probability>,
+        tolerance: f64,
+    ) -> Result<Measure<T>, Error> {
         let sum: f64 = dist.values().map(|p| p.0).sum();
-        if (sum - 1.0).abs() > 1e-10 {
+        if sum.is_nan() || (sum - 1.0).abs() > tolerance {
             return Err(Error::InvalidMeasure);
         } else {
             Ok(Measure { dist })
@@ -46,12 +81,43 @@ impl<T: Eq + Hash> Measure<T> {
     }
 
     pub fn deterministic(key: T) -> Measure<T> {
-        let mut init = HashMap::new();
+        let mut init = IndexMap::new();
         init.insert(key, Probability::ONE);
         Self::from_distribution(init).unwrap()
     }
 
-    pub fn dist(&self) -> &HashMap<T, Probability> {
+    /// Equal probability on every item; fails with `Error::InvalidMeasure` if there
+    /// are none. Repeated items count once per occurrence, so `[a, a, b]` gives `a` two thirds.
+    pub fn uniform(items: impl IntoIterator<Item = T>) -> Result<Measure<T>, Error> {
+        items
+            .into_iter()
+            .map(|x| (x, 1.0))
+            .collect::<MeasureBuilder<T>>()
+            .normalize()
+    }
+
+    /// `a` with probability `p` and `b` with probability `1 - p` (all of it on `a` if the two are
+    /// equal). Fails with `Error::ProbabilityOutOfRange` unless `0 ≤ p ≤ 1`.
+    pub fn bernoulli(a: T, b: T, p: f64) -> Result<Measure<T>, Error> {
+        let p = Probability::new(p)?;
+        MeasureBuilder::new()
+            .add(a, p.0)
+            .add(b, p.complement().0)
+            .build()
+    }
+
+    /// The categorical distribution with the given `(outcome, probability)` pairs; probabilities
+    /// of repeated outcomes add up. Fails with `Error::ProbabilityOutOfRange` if a probability
+    /// is outside `[0, 1]` and with `Error::InvalidMeasure` if they do not sum to one.
+    pub fn categorical(pairs: impl IntoIterator<Item = (T, f64)>) -> Result<Measure<T>, Error> {
+        let mut builder = MeasureBuilder::new();
+        for (x, p) in pairs {
+            builder = builder.add(x, Probability::new(p)?.0);
+        }
+        builder.build()
+    }
+
+    pub fn dist(&self) -> &IndexMap<T, Probability> {
         &self.dist
     }
     pub fn get_prob(&self, key: &T) -> Option<&Probability> {
@@ -78,33 +144,385 @@ impl<T: Eq + Hash> Measure<T> {
         Measure::from_distribution(dist)
     }
 
+    /// `Σ_x p(x) f(x)`, the expected value of `f`
+    pub fn expectation(&self, f: impl Fn(&T) -> f64) -> f64 {
+        self.dist.iter().map(|(x, p)| p.0 * f(x)).sum()
+    }
+
+    /// The pushforward of this measure along `f`: outcome `f(x)` gets probability `p(x)`, and
+    /// the probabilities of outcomes that `f` maps to the same key are added
+    pub fn map<U: Eq + Hash>(self, f: impl Fn(T) -> U) -> Measure<U> {
+        let mut dist: IndexMap<U, Probability> = IndexMap::with_capacity(self.dist.len());
+        for (x, p) in self.dist {
+            let merged = dist.entry(f(x)).or_insert(Probability::ZERO);
+            *merged = Probability((merged.0 + p.0).min(1.0));
+        }
+        Measure { dist }
+    }
+
+    /// # Mixture
+    ///
+    /// `Σ_i w_i μ_i`: draws component `i` with probability proportional to `w_i`, then an outcome
+    /// from `μ_i`. Weights are normalized by their sum, so they need not add up to one, but they
+    /// must be non-negative with a positive total (otherwise `Error::InvalidMeasure`).
+    pub fn mixture(components: &[(f64, Measure<T>)]) -> Result<Measure<T>, Error>
+    where
+        T: Clone,
+    {
+        let total: f64 = components.iter().map(|(w, _)| *w).sum();
+        let negative = components.iter().any(|(w, _)| w.is_nan() || *w < 0.0);
+        if negative || !(total > 0.0 && total.is_finite()) {
+            return Err(Error::InvalidMeasure);
+        }
+        let mut mass: IndexMap<T, f64> = IndexMap::new();
+        for (weight, measure) in components {
+            for (x, p) in &measure.dist {
+                *mass.entry(x.clone()).or_insert(0.0) += weight / total * p.0;
+            }
+        }
+        let dist = mass
+            .into_iter()
+            .filter(|(_, p)| *p > 0.0)
+            .map(|(x, p)| (x, Probability(p.min(1.0))))
+            .collect();
+        Measure::from_distribution(dist)
+    }
+
+    /// # Conditioning
+    ///
+    /// `μ(· | A)` for the event `A = { x : predicate(x) }`: the satisfying outcomes keep their
+    /// relative probabilities, renormalized by `μ(A)`. Fails with `Error::InvalidMeasure` if the
+    /// event has probability zero.
+    pub fn condition(&self, predicate: impl Fn(&T) -> bool) -> Result<Measure<T>, Error>
+    where
+        T: Clone,
+    {
+        let event: Vec<(&T, f64)> = self
+            .dist
+            .iter()
+            .filter(|(x, p)| p.0 > 0.0 && predicate(x))
+            .map(|(x, p)| (x, p.0))
+            .collect();
+        let mass: f64 = event.iter().map(|(_, p)| p).sum();
+        if mass <= 0.0 {
+            return Err(Error::InvalidMeasure);
+        }
+        let dist = event
+            .into_iter()
+            .map(|(x, p)| (x.clone(), Probability((p / mass).min(1.0))))
+            .collect();
+        Measure::from_distribution(dist)
+    }
+
+    /// `Σ_x |p(x) - q(x)|`, over the union of both supports
+    pub fn l1_distance(&self, other: &Measure<T>) -> f64 {
+        let shared: f64 = self
+            .dist
+            .iter()
+            .map(|(x, p)| (p.0 - other.dist.get(x).map_or(0.0, |q| q.0)).abs())
+            .sum();
+        let only_other: f64 = other
+            .dist
+            .iter()
+            .filter(|(x, _)| !self.dist.contains_key(*x))
+            .map(|(_, q)| q.0)
+            .sum();
+        shared + only_other
+    }
+
+    /// `½ Σ_x |p(x) - q(x)|`, the largest difference between the probabilities the two measures
+    /// assign to the same event
+    pub fn total_variation(&self, other: &Measure<T>) -> f64 {
+        0.5 * self.l1_distance(other)
+    }
+
+    /// Shannon entropy `-Σ_x p(x) ln p(x)` in nats; zero for a deterministic measure
+    pub fn entropy(&self) -> f64 {
+        self.dist
+            .values()
+            .filter(|p| p.0 > 0.0)
+            .map(|p| p.0 * (1.0 / p.0).ln())
+            .sum()
+    }
+
+    /// `KL(p ‖ q) = Σ_x p(x) ln(p(x) / q(x))` in nats, with `self` as `p` and `other` as `q`;
+    /// infinite if `p` puts mass where `q` has none
+    pub fn kl_divergence(&self, other: &Measure<T>) -> f64 {
+        let mut divergence = 0.0;
+        for (x, p) in self.dist.iter().filter(|(_, p)| p.0 > 0.0) {
+            let q = other.dist.get(x).map_or(0.0, |q| q.0);
+            if q <= 0.0 {
+                return f64::INFINITY;
+            }
+            divergence += p.0 * (p.0 / q).ln();
+        }
+        divergence.max(0.0)
+    }
+
+    /// The measure on the same support within L1 distance `radius` of this one that minimizes the
+    /// expectation of `value`: up to `radius / 2` of probability mass is moved from the
+    /// highest-valued outcomes onto the lowest-valued one.
+    pub fn worst_case_l1(&self, value: impl Fn(&T) -> f64, radius: f64) -> Result<Measure<T>, Error>
+    where
+        T: Clone,
+    {
+        let mut outcomes: Vec<(&T, f64, f64)> =
+            self.dist.iter().map(|(x, p)| (x, value(x), p.0)).collect();
+        if outcomes.is_empty() {
+            return Err(Error::InvalidMeasure);
+        }
+        outcomes.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let mut budget = (radius.max(0.0) / 2.0).min(1.0 - outcomes[0].2);
+        outcomes[0].2 += budget;
+        for outcome in outcomes.iter_mut().skip(1).rev() {
+            if budget <= 0.0 {
+                break;
+            }
+            let moved = outcome.2.min(budget);
+            outcome.2 -= moved;
+            budget -= moved;
+        }
+        let dist = outcomes
+            .into_iter()
+            .filter(|(_, _, p)| *p > 0.0)
+            .map(|(x, _, p)| Ok((x.clone(), Probability::new(p.min(1.0))?)))
+            .collect::<Result<IndexMap<_, _>, Error>>()?;
+        Measure::from_distribution(dist)
+    }
+
     /// Sample a state from the measure according to its probability distribution
     pub fn sample(&self) -> Option<&T>
     where
         T: Clone,
     {
-        if self.dist.is_empty() {
-            return None;
-        }
-        
-        // Convert to vectors for weighted sampling
-        let states: Vec<&T> = self.dist.keys().collect();
-        let weights: Vec<f64> = self.dist.values().map(|p| p.value()).collect();
-        
-        // Use weighted random choice
-        use rand::Rng;
-        let mut rng = rand::rng();
+        self.sample_with(&mut rand::rng())
+    }
+
+    /// Like `sample`, drawing from the given random number generator. A measure with a single
+    /// outcome returns it without consuming randomness; otherwise the outcomes are walked in
+    /// order until their cumulative probability reaches one uniform draw.
+    pub fn sample_with<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Option<&T> {
+        if self.dist.len() <= 1 {
+            return self.dist.keys().next();
+        }
+
         let random_value: f64 = rng.random();
-        
         let mut cumulative = 0.0;
-        for (i, weight) in weights.iter().enumerate() {
-            cumulative += weight;
+        for (x, p) in &self.dist {
+            cumulative += p.0;
             if random_value <= cumulative {
-                return Some(states[i]);
+                return Some(x);
             }
         }
-        
-        // Fallback: return the last state (shouldn't happen if distribution is valid)
-        states.last().copied()
+        // Rounding left the cumulative sum just short of one
+        self.dist.keys().last()
+    }
+
+    /// Precomputes an alias table for drawing many samples from this measure in constant time each
+    pub fn sampler(&self) -> AliasSampler<'_, T> {
+        AliasSampler::new(self.dist.iter().map(|(x, p)| (x, p.0)).unzip())
+    }
+}
+
+/// Serialized as a list of `(outcome, probability)` pairs, so that outcomes need not be valid map
+/// keys (JSON object keys must be strings); deserialization checks that the pairs form a measure
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for Measure<T> {
+    fn serialize<Ser: serde::Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        serializer.collect_seq(self.dist.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for Measure<T>
+where
+    T: Eq + Hash + serde::Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pairs: Vec<(T, Probability)> = serde::Deserialize::deserialize(deserializer)?;
+        let mut builder = MeasureBuilder::new();
+        for (x, p) in pairs {
+            builder = builder.add(x, p.0);
+        }
+        builder.build().map_err(serde::de::Error::custom)
+    }
+}
+
+/// # Measure Builder
+///
+/// Accumulates non-negative weights outcome by outcome (repeated outcomes add up) and turns them
+/// into a `Measure`, either by rescaling them with `normalize` or, when they already are
+/// probabilities, by checking their sum with `build`.
+#[derive(Debug, Clone)]
+pub struct MeasureBuilder<T> {
+    weights: IndexMap<T, f64>,
+    tolerance: f64,
+}
+
+impl<T: Eq + Hash> Default for MeasureBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Eq + Hash> MeasureBuilder<T> {
+    pub fn new() -> Self {
+        MeasureBuilder {
+            weights: IndexMap::new(),
+            tolerance: DEFAULT_TOLERANCE,
+        }
+    }
+
+    /// Adds `weight` to the weight of `state`
+    pub fn add(mut self, state: T, weight: f64) -> Self {
+        *self.weights.entry(state).or_insert(0.0) += weight;
+        self
+    }
+
+    /// How far the weights may sum from one in `build` (`DEFAULT_TOLERANCE` unless set)
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sum of the weights added so far
+    pub fn total_weight(&self) -> f64 {
+        self.weights.values().sum()
+    }
+
+    /// # Normalize
+    ///
+    /// Divides every weight by the total, dropping outcomes of weight zero. Fails with
+    /// `Error::InvalidMeasure` if a weight is negative or not a number, or the total is not
+    /// positive and finite.
+    pub fn normalize(self) -> Result<Measure<T>, Error> {
+        self.check_weights()?;
+        let total = self.total_weight();
+        if !(total > 0.0 && total.is_finite()) {
+            return Err(Error::InvalidMeasure);
+        }
+        let dist = self
+            .weights
+            .into_iter()
+            .filter(|(_, w)| *w > 0.0)
+            .map(|(x, w)| (x, Probability((w / total).min(1.0))))
+            .collect();
+        Measure::from_distribution(dist)
+    }
+
+    /// Uses the weights as probabilities as they are; fails with `Error::InvalidMeasure` if one
+    /// is negative or their sum is further than the tolerance from one
+    pub fn build(self) -> Result<Measure<T>, Error> {
+        self.check_weights()?;
+        let tolerance = self.tolerance;
+        let dist = self
+            .weights
+            .into_iter()
+            .filter(|(_, w)| *w > 0.0)
+            .map(|(x, w)| (x, Probability(w.min(1.0))))
+            .collect();
+        Measure::from_distribution_with_tolerance(dist, tolerance)
+    }
+
+    fn check_weights(&self) -> Result<(), Error> {
+        if self.weights.values().any(|w| w.is_nan() || *w < 0.0) {
+            return Err(Error::InvalidMeasure);
+        }
+        Ok(())
+    }
+}
+
+impl<T: Eq + Hash> Extend<(T, f64)> for MeasureBuilder<T> {
+    fn extend<I: IntoIterator<Item = (T, f64)>>(&mut self, iter: I) {
+        for (state, weight) in iter {
+            *self.weights.entry(state).or_insert(0.0) += weight;
+        }
+    }
+}
+
+impl<T: Eq + Hash> FromIterator<(T, f64)> for MeasureBuilder<T> {
+    fn from_iter<I: IntoIterator<Item = (T, f64)>>(iter: I) -> Self {
+        let mut builder = Self::new();
+        builder.extend(iter);
+        builder
+    }
+}
+
+/// # Alias Sampler
+///
+/// Walker's alias method (in Vose's formulation) over the support of a `Measure`. Building the
+/// table takes linear time; every draw afterwards costs one uniform index and one coin flip, so
+/// it pays off when the same measure is sampled repeatedly, e.g. a fixed transition in a hot
+/// simulation loop. Single-outcome measures skip the table entirely.
+#[derive(Debug, Clone)]
+pub struct AliasSampler<'a, T> {
+    outcomes: Vec<&'a T>,
+    /// Probability of keeping column `i` rather than jumping to `alias[i]`
+    keep: Vec<f64>,
+    alias: Vec<usize>,
+}
+
+impl<'a, T> AliasSampler<'a, T> {
+    /// The table of `outcomes` with the given weights, in the order they are given
+    fn new((outcomes, weights): (Vec<&'a T>, Vec<f64>)) -> Self {
+        let n = outcomes.len();
+        if n <= 1 {
+            return AliasSampler {
+                outcomes,
+                keep: vec![1.0; n],
+                alias: (0..n).collect(),
+            };
+        }
+
+        let total: f64 = weights.iter().sum();
+        let mut keep: Vec<f64> = weights.iter().map(|w| w * n as f64 / total).collect();
+        let mut alias: Vec<usize> = (0..n).collect();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) =
+            (0..n).partition(|&i| keep[i] < 1.0);
+        while let (Some(&s), Some(&l)) = (small.last(), large.last()) {
+            small.pop();
+            alias[s] = l;
+            keep[l] -= 1.0 - keep[s];
+            if keep[l] < 1.0 {
+                large.pop();
+                small.push(l);
+            }
+        }
+        // Whatever is left over is full up to rounding
+        for i in small.into_iter().chain(large) {
+            keep[i] = 1.0;
+        }
+
+        AliasSampler {
+            outcomes,
+            keep,
+            alias,
+        }
+    }
+
+    /// Draws one outcome; `None` only for an empty measure
+    pub fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Option<&'a T> {
+        match self.outcomes.len() {
+            0 => None,
+            1 => Some(self.outcomes[0]),
+            n => {
+                let column = rng.random_range(0..n);
+                let index = if rng.random::<f64>() < self.keep[column] {
+                    column
+                } else {
+                    self.alias[column]
+                };
+                Some(self.outcomes[index])
+            }
+        }
+    }
+
+    /// Number of outcomes in the support
+    pub fn len(&self) -> usize {
+        self.outcomes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outcomes.is_empty()
     }
 }
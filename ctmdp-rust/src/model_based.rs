@@ -0,0 +1,528 @@
+//! # Model-Based Learning
+//!
+//! Learners that estimate a tabular model of the environment from experience and act by planning
+//! on it with value iteration.
+
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+use madepro::models::{Action, State};
+use rand::Rng;
+use rand_distr::{Distribution, Gamma, Normal};
+
+use crate::config::TrainingConfig;
+use crate::constructors::TabularMDP;
+use crate::counts::VisitCounts;
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::measure::{Measure, Probability};
+use crate::planning::value_iteration;
+use crate::policy::DeterministicPolicy;
+use crate::q_learning::random_start;
+
+/// The policy and values a model-based learner ends with, plus the return of every training episode
+pub struct ModelBasedResult<S, A> {
+    pub policy: DeterministicPolicy<S, A>,
+    pub values: HashMap<S, f64>,
+    pub episode_returns: Vec<f64>,
+}
+
+/// How PSRL treats rewards
+#[derive(Debug, Clone, PartialEq)]
+pub enum RewardModel {
+    /// Rewards are known: they are read from the MDP and only transitions are learned
+    Known,
+    /// Rewards have a Gaussian posterior with known observation noise, starting from a single
+    /// pseudo-observation at `prior_mean`
+    Gaussian { prior_mean: f64, noise_std: f64 },
+}
+
+/// Prior for posterior sampling
+#[derive(Debug, Clone, PartialEq)]
+pub struct PsrlPrior {
+    /// Dirichlet concentration placed on every possible next state of every state-action pair
+    pub transition_concentration: f64,
+    pub rewards: RewardModel,
+}
+
+impl Default for PsrlPrior {
+    fn default() -> Self {
+        PsrlPrior {
+            transition_concentration: 1.0,
+            rewards: RewardModel::Known,
+        }
+    }
+}
+
+/// Every non-final state paired with its available actions
+type StateActions<S, A> = Vec<(S, Vec<A>)>;
+
+/// All states together with the actions of the non-final ones
+type Enumeration<S, A> = (Vec<S>, StateActions<S, A>);
+
+/// Sufficient statistics of the posterior over an MDP with the same states and actions as `M`
+struct Posterior<S, A> {
+    states: Vec<S>,
+    actions: StateActions<S, A>,
+    counts: VisitCounts<S, A>,
+    known_rewards: HashMap<(S, A), f64>,
+}
+
+impl<S: State, A: Action> Posterior<S, A> {
+    fn new<M>(mdp: &M, prior: &PsrlPrior) -> Result<Self, Error>
+    where
+        M: MDP<State = S, Action = A>,
+    {
+        let (states, actions) = enumerate(mdp);
+        let mut known_rewards = HashMap::new();
+        if prior.rewards == RewardModel::Known {
+            for (state, state_actions) in &actions {
+                for action in state_actions {
+                    let (_, reward) = mdp.stochastic_transition(state, action)?;
+                    known_rewards.insert((state.clone(), action.clone()), reward);
+                }
+            }
+        }
+        Ok(Posterior {
+            states,
+            actions,
+            counts: VisitCounts::new(),
+            known_rewards,
+        })
+    }
+
+    /// Draws an MDP from the posterior, or builds the posterior-mean MDP when `mean` is set
+    fn model<M, R>(
+        &self,
+        mdp: &M,
+        prior: &PsrlPrior,
+        mean: bool,
+        rng: &mut R,
+    ) -> Result<TabularMDP<S, A>, Error>
+    where
+        M: MDP<State = S, Action = A>,
+        R: Rng,
+    {
+        let mut model = TabularMDP::new(self.states.clone());
+        for state in &self.states {
+            if mdp.is_final_state(state) {
+                model.set_final(state.clone());
+            }
+        }
+
+        for (state, state_actions) in &self.actions {
+            for action in state_actions {
+                let counts = self.counts.next_state_counts(state, action);
+                let mut weights = Vec::with_capacity(self.states.len());
+                for next in &self.states {
+                    let alpha = prior.transition_concentration
+                        + counts.and_then(|c| c.get(next)).copied().unwrap_or(0) as f64;
+                    let weight = if mean {
+                        alpha
+                    } else {
+                        Gamma::new(alpha, 1.0)
+                            .map_err(|_| Error::InvalidMeasure)?
+                            .sample(rng)
+                    };
+                    weights.push(weight);
+                }
+                let measure = normalized_measure(&self.states, &weights, state)?;
+
+                let reward = match &prior.rewards {
+                    RewardModel::Known => self
+                        .known_rewards
+                        .get(&(state.clone(), action.clone()))
+                        .copied()
+                        .unwrap_or(0.0),
+                    RewardModel::Gaussian {
+                        prior_mean,
+                        noise_std,
+                    } => {
+                        let n = self.counts.visits(state, action);
+                        let sum = self.counts.reward_sum(state, action);
+                        let posterior_mean = (prior_mean + sum) / (n + 1) as f64;
+                        if mean {
+                            posterior_mean
+                        } else {
+                            let std = noise_std / ((n + 1) as f64).sqrt();
+                            Normal::new(posterior_mean, std)
+                                .map_err(|_| Error::InvalidMeasure)?
+                                .sample(rng)
+                        }
+                    }
+                };
+                model.add_transition(state.clone(), action.clone(), measure, reward);
+            }
+        }
+        Ok(model)
+    }
+}
+
+/// All states, and the actions of every non-final state
+fn enumerate<M>(mdp: &M) -> Enumeration<M::State, M::Action>
+where
+    M: MDP,
+    M::State: Clone,
+{
+    let states: Vec<M::State> = mdp.all_states().iter().cloned().collect();
+    let actions = states
+        .iter()
+        .filter(|s| !mdp.is_final_state(s))
+        .map(|s| (s.clone(), mdp.actions_at(s)))
+        .collect();
+    (states, actions)
+}
+
+/// Normalizes nonnegative `weights` over `states` into a measure, dropping negligible mass.
+/// Falls back to staying at `fallback` if every weight underflowed to zero.
+fn normalized_measure<S: State>(
+    states: &[S],
+    weights: &[f64],
+    fallback: &S,
+) -> Result<Measure<S>, Error> {
+    const NEGLIGIBLE: f64 = 1e-12;
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+        return Ok(Measure::deterministic(fallback.clone()));
+    }
+    let kept: Vec<(&S, f64)> = states
+        .iter()
+        .zip(weights)
+        .filter(|(_, w)| **w / total > NEGLIGIBLE)
+        .map(|(s, w)| (s, *w))
+        .collect();
+    let kept_total: f64 = kept.iter().map(|(_, w)| w).sum();
+    let dist = kept
+        .into_iter()
+        .map(|(s, w)| Ok((s.clone(), Probability::new((w / kept_total).min(1.0))?)))
+        .collect::<Result<IndexMap<_, _>, Error>>()?;
+    Measure::from_distribution(dist)
+}
+
+/// Runs one episode of `policy` from a random state, reporting every transition to `observe`.
+/// Returns the undiscounted episode return.
+fn run_episode<M, F, R>(
+    mdp: &M,
+    policy: &DeterministicPolicy<M::State, M::Action>,
+    max_steps: u32,
+    rng: &mut R,
+    mut observe: F,
+) -> Result<f64, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+    F: FnMut(&M::State, &M::Action, f64, &M::State),
+    R: Rng + ?Sized,
+{
+    let mut state = random_start(mdp.all_states(), rng)?.clone();
+    let mut episode_return = 0.0;
+    for _ in 0..max_steps {
+        if mdp.is_final_state(&state) {
+            break;
+        }
+        let action = match policy.get(&state) {
+            Some(a) => a.clone(),
+            None => match mdp.actions_at(&state).into_iter().next() {
+                Some(a) => a,
+                None => break,
+            },
+        };
+        let (measure, reward) = mdp.stochastic_transition(&state, &action)?;
+        let next_state = measure.sample_with(rng).cloned().unwrap_or_else(|| state.clone());
+        observe(&state, &action, reward, &next_state);
+        episode_return += reward;
+        state = next_state;
+    }
+    Ok(episode_return)
+}
+
+/// # Posterior Sampling RL
+///
+/// This function implements PSRL with Dirichlet transition posteriors.
+/// Every episode it:
+/// 1. Samples an MDP from the posterior (Dirichlet over next states for every state-action pair,
+///    plus a Gaussian reward posterior unless rewards are known)
+/// 2. Solves the sample with value iteration
+/// 3. Acts greedily with respect to the sampled MDP for up to `max_num_steps` steps,
+///    updating the posterior with every observed transition
+///
+/// Sampled models have full support over `all_states()`, so each planning step costs
+/// `O(|S|² |A|)` per sweep: PSRL is meant for small tabular MDPs.
+///
+/// # Arguments
+/// * `mdp` - The MDP to learn from
+/// * `config` - Configuration parameters (episodes, steps, discount factor, planning tolerance, etc.)
+/// * `prior` - Transition concentration and reward model
+///
+/// # Returns
+/// The greedy policy and values of the posterior-mean MDP, and the per-episode returns
+pub fn psrl<M>(
+    mdp: &M,
+    config: &TrainingConfig,
+    prior: &PsrlPrior,
+) -> Result<ModelBasedResult<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let mut rng = config.rng();
+    let mut posterior = Posterior::new(mdp, prior)?;
+    let mut episode_returns = Vec::with_capacity(config.num_episodes as usize);
+
+    for _ in 0..config.num_episodes {
+        let sample = posterior.model(mdp, prior, false, &mut rng)?;
+        let plan = value_iteration(
+            &sample,
+            config.discount_factor,
+            config.planning_tolerance,
+            config.max_planning_iterations,
+        )?;
+        let episode_return =
+            run_episode(mdp, &plan.policy, config.max_num_steps, &mut rng, |s, a, r, n| {
+                posterior.counts.record(s, a, r, n);
+            })?;
+        episode_returns.push(episode_return);
+    }
+
+    let mean_model = posterior.model(mdp, prior, true, &mut rng)?;
+    let plan = value_iteration(
+        &mean_model,
+        config.discount_factor,
+        config.planning_tolerance,
+        config.max_planning_iterations,
+    )?;
+    Ok(ModelBasedResult {
+        policy: plan.policy,
+        values: plan.values,
+        episode_returns,
+    })
+}
+
+/// The R-MAX model: empirical dynamics for known pairs, and an optimistic self-loop paying
+/// `r_max` for every pair visited fewer than `known_threshold` times. Planning on the self-loop
+/// values an unknown pair at `r_max / (1 - γ)`, as if it led to a maximally rewarding absorbing state.
+fn rmax_model<S: State, A: Action>(
+    states: &[S],
+    actions: &StateActions<S, A>,
+    final_states: &[S],
+    counts: &VisitCounts<S, A>,
+    known_threshold: usize,
+    r_max: f64,
+) -> Result<TabularMDP<S, A>, Error> {
+    let mut model = TabularMDP::new(states.to_vec());
+    for state in final_states {
+        model.set_final(state.clone());
+    }
+    for (state, state_actions) in actions {
+        for action in state_actions {
+            let known = counts
+                .is_known(state, action, known_threshold.max(1))
+                .then(|| counts.empirical_measure(state, action))
+                .flatten();
+            match known {
+                Some(measure) => {
+                    let reward = counts.mean_reward(state, action).unwrap_or(0.0);
+                    model.add_transition(state.clone(), action.clone(), measure?, reward);
+                }
+                None => model.add_transition(
+                    state.clone(),
+                    action.clone(),
+                    Measure::deterministic(state.clone()),
+                    r_max,
+                ),
+            }
+        }
+    }
+    Ok(model)
+}
+
+/// # R-MAX
+///
+/// This function implements the R-MAX PAC-MDP learner.
+/// State-action pairs visited fewer than `config.known_threshold` times are assumed to yield the
+/// maximal reward `r_max` forever; known pairs use their empirical transition measure and mean
+/// reward. The agent acts greedily on this optimistic model and re-plans with value iteration
+/// whenever a pair becomes known, so exploration is driven entirely by optimism.
+///
+/// # Arguments
+/// * `mdp` - The MDP to learn from
+/// * `config` - Configuration parameters (episodes, steps, discount factor, known threshold, etc.)
+/// * `r_max` - An upper bound on the one-step reward of `mdp`
+///
+/// # Returns
+/// The greedy policy and values of the final optimistic model, and the per-episode returns
+pub fn rmax<M>(
+    mdp: &M,
+    config: &TrainingConfig,
+    r_max: f64,
+) -> Result<ModelBasedResult<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let (states, actions) = enumerate(mdp);
+    let final_states: Vec<M::State> = states
+        .iter()
+        .filter(|s| mdp.is_final_state(s))
+        .cloned()
+        .collect();
+    let mut counts = VisitCounts::new();
+    let plan_model = |counts: &VisitCounts<M::State, M::Action>| {
+        let model = rmax_model(
+            &states,
+            &actions,
+            &final_states,
+            counts,
+            config.known_threshold,
+            r_max,
+        )?;
+        value_iteration(
+            &model,
+            config.discount_factor,
+            config.planning_tolerance,
+            config.max_planning_iterations,
+        )
+    };
+
+    let mut plan = plan_model(&counts)?;
+    let mut episode_returns = Vec::with_capacity(config.num_episodes as usize);
+    let mut rng = config.rng();
+
+    for _ in 0..config.num_episodes {
+        let mut state = random_start(mdp.all_states(), &mut rng)?.clone();
+        let mut episode_return = 0.0;
+        for _ in 0..config.max_num_steps {
+            if mdp.is_final_state(&state) {
+                break;
+            }
+            let action = match plan.policy.get(&state) {
+                Some(a) => a.clone(),
+                None => break,
+            };
+            let (measure, reward) = mdp.stochastic_transition(&state, &action)?;
+            let next_state = measure
+                .sample_with(&mut rng)
+                .cloned()
+                .unwrap_or_else(|| state.clone());
+            episode_return += reward;
+
+            // Re-plan exactly when this pair crosses the threshold and becomes known
+            if counts.record(&state, &action, reward, &next_state) == config.known_threshold.max(1) {
+                plan = plan_model(&counts)?;
+            }
+            state = next_state;
+        }
+        episode_returns.push(episode_return);
+    }
+
+    Ok(ModelBasedResult {
+        policy: plan.policy,
+        values: plan.values,
+        episode_returns,
+    })
+}
+
+/// The UCB-VI model: empirical dynamics with mean rewards plus the bonus
+/// `bonus_coefficient / sqrt(N(s, a))`. Unvisited pairs are a self-loop paying the largest
+/// observed mean reward plus the full coefficient, an upper bound on every visited pair's reward,
+/// so planning values them at `(r_max + c) / (1 - γ)`, no less than any explored alternative.
+fn optimistic_model<S: State, A: Action>(
+    states: &[S],
+    actions: &StateActions<S, A>,
+    final_states: &[S],
+    counts: &VisitCounts<S, A>,
+    bonus_coefficient: f64,
+) -> Result<TabularMDP<S, A>, Error> {
+    let optimistic_reward = counts.max_mean_reward().unwrap_or(0.0) + bonus_coefficient;
+    let mut model = TabularMDP::new(states.to_vec());
+    for state in final_states {
+        model.set_final(state.clone());
+    }
+    for (state, state_actions) in actions {
+        for action in state_actions {
+            match counts.empirical_measure(state, action) {
+                Some(measure) => {
+                    let visits = counts.visits(state, action) as f64;
+                    let reward = counts.mean_reward(state, action).unwrap_or(0.0)
+                        + bonus_coefficient / visits.sqrt();
+                    model.add_transition(state.clone(), action.clone(), measure?, reward);
+                }
+                None => model.add_transition(
+                    state.clone(),
+                    action.clone(),
+                    Measure::deterministic(state.clone()),
+                    optimistic_reward,
+                ),
+            }
+        }
+    }
+    Ok(model)
+}
+
+/// # UCB-VI
+///
+/// This function implements an episodic optimistic model-based learner in the style of UCB-VI.
+/// Before every episode it plans with value iteration on the empirical model, whose rewards are
+/// inflated by the count-based bonus `config.bonus_coefficient / sqrt(N(s, a))`, and then acts
+/// greedily on that plan for the whole episode. Larger coefficients explore more; with a
+/// coefficient of zero the learner is a certainty-equivalent planner.
+///
+/// # Arguments
+/// * `mdp` - The MDP to learn from
+/// * `config` - Configuration parameters (episodes, steps, discount factor, bonus coefficient, etc.)
+///
+/// # Returns
+/// The greedy policy and values of the final optimistic model, and the per-episode returns
+pub fn ucb_vi<M>(
+    mdp: &M,
+    config: &TrainingConfig,
+) -> Result<ModelBasedResult<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let (states, actions) = enumerate(mdp);
+    let final_states: Vec<M::State> = states
+        .iter()
+        .filter(|s| mdp.is_final_state(s))
+        .cloned()
+        .collect();
+    let mut counts = VisitCounts::new();
+    let plan_model = |counts: &VisitCounts<M::State, M::Action>| {
+        let model = optimistic_model(
+            &states,
+            &actions,
+            &final_states,
+            counts,
+            config.bonus_coefficient,
+        )?;
+        value_iteration(
+            &model,
+            config.discount_factor,
+            config.planning_tolerance,
+            config.max_planning_iterations,
+        )
+    };
+
+    let mut episode_returns = Vec::with_capacity(config.num_episodes as usize);
+    let mut rng = config.rng();
+    for _ in 0..config.num_episodes {
+        let plan = plan_model(&counts)?;
+        let episode_return =
+            run_episode(mdp, &plan.policy, config.max_num_steps, &mut rng, |s, a, r, n| {
+                counts.record(s, a, r, n);
+            })?;
+        episode_returns.push(episode_return);
+    }
+
+    let plan = plan_model(&counts)?;
+    Ok(ModelBasedResult {
+        policy: plan.policy,
+        values: plan.values,
+        episode_returns,
+    })
+}
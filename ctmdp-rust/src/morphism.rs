@@ -0,0 +1,187 @@
+//! # Morphisms
+//!
+//! Structure-preserving maps between MDPs. A homomorphism from `M1` to `M2` maps states
+//! `s ↦ f(s)` and, per state, actions `a ↦ g_s(a)` such that rewards agree and transition
+//! measures commute with the state map:
+//!
+//! `r1(s, a) = r2(f(s), g_s(a))` and `f_*(P1(· | s, a)) = P2(· | f(s), g_s(a))`.
+//!
+//! Solving the (usually smaller) target and pulling its policy back solves the source.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::measure::Measure;
+use crate::policy::{DeterministicPolicy, Policy};
+
+/// Maps states of the source to states of the target
+type StateMap<S1, S2> = Box<dyn Fn(&S1) -> S2>;
+
+/// Maps actions of the source, in a given source state, to actions of the target
+type ActionMap<S1, A1, A2> = Box<dyn Fn(&S1, &A1) -> A2>;
+
+/// A way in which a candidate morphism fails to commute with the dynamics
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation<S, A> {
+    /// `s` and `f(s)` disagree on being final
+    FinalState { state: S },
+    /// `g_s(a)` is not available at `f(s)`
+    UnavailableAction { state: S, action: A },
+    /// `|r1(s, a) - r2(f(s), g_s(a))|` exceeds the tolerance
+    Reward {
+        state: S,
+        action: A,
+        source: f64,
+        target: f64,
+    },
+    /// The total-variation distance between `f_*(P1(· | s, a))` and `P2(· | f(s), g_s(a))`
+    /// exceeds the tolerance
+    Transition { state: S, action: A, distance: f64 },
+}
+
+/// Every violation found for a source MDP `M`
+pub type Violations<M> = Vec<Violation<<M as MDP>::State, <M as MDP>::Action>>;
+
+/// A candidate homomorphism from `M1` to `M2`, given by its state and action maps.
+/// Use `violations` (or `is_valid`) to check that it actually commutes with the dynamics.
+pub struct Morphism<M1: MDP, M2: MDP> {
+    state_map: StateMap<M1::State, M2::State>,
+    action_map: ActionMap<M1::State, M1::Action, M2::Action>,
+}
+
+/// Total-variation distance `½ Σ_x |p(x) - q(x)|` between two measures
+fn total_variation<T: Eq + Hash>(p: &HashMap<T, f64>, q: &Measure<T>) -> f64 {
+    let mut distance: f64 = p
+        .iter()
+        .map(|(x, px)| (px - q.get_prob(x).map_or(0.0, |qx| qx.value())).abs())
+        .sum();
+    distance += q
+        .dist()
+        .iter()
+        .filter(|(x, _)| !p.contains_key(x))
+        .map(|(_, qx)| qx.value())
+        .sum::<f64>();
+    distance / 2.0
+}
+
+impl<M1, M2> Morphism<M1, M2>
+where
+    M1: MDP,
+    M2: MDP,
+    M1::State: Clone,
+    M1::Action: Clone,
+{
+    pub fn new(
+        state_map: impl Fn(&M1::State) -> M2::State + 'static,
+        action_map: impl Fn(&M1::State, &M1::Action) -> M2::Action + 'static,
+    ) -> Self {
+        Morphism {
+            state_map: Box::new(state_map),
+            action_map: Box::new(action_map),
+        }
+    }
+
+    pub fn map_state(&self, state: &M1::State) -> M2::State {
+        (self.state_map)(state)
+    }
+
+    pub fn map_action(&self, state: &M1::State, action: &M1::Action) -> M2::Action {
+        (self.action_map)(state, action)
+    }
+
+    /// Checks every state-action pair of `source` against `target`. Rewards and transition
+    /// measures must agree to within `tolerance` (absolute difference and total-variation
+    /// distance respectively). An empty result means the maps form a homomorphism.
+    pub fn violations(
+        &self,
+        source: &M1,
+        target: &M2,
+        tolerance: f64,
+    ) -> Result<Violations<M1>, Error> {
+        let mut violations = Vec::new();
+        for state in source.all_states().iter() {
+            let image = self.map_state(state);
+            if source.is_final_state(state) != target.is_final_state(&image) {
+                violations.push(Violation::FinalState {
+                    state: state.clone(),
+                });
+            }
+            if source.is_final_state(state) {
+                continue;
+            }
+
+            let target_actions = target.actions_at(&image);
+            for action in source.actions_at(state) {
+                let image_action = self.map_action(state, &action);
+                if !target_actions.contains(&image_action) {
+                    violations.push(Violation::UnavailableAction {
+                        state: state.clone(),
+                        action,
+                    });
+                    continue;
+                }
+
+                let (measure1, reward1) = source.stochastic_transition(state, &action)?;
+                let (measure2, reward2) = target.stochastic_transition(&image, &image_action)?;
+                if (reward1 - reward2).abs() > tolerance {
+                    violations.push(Violation::Reward {
+                        state: state.clone(),
+                        action: action.clone(),
+                        source: reward1,
+                        target: reward2,
+                    });
+                }
+
+                let mut pushforward: HashMap<M2::State, f64> = HashMap::new();
+                for (s, p) in measure1.dist() {
+                    *pushforward.entry(self.map_state(s)).or_insert(0.0) += p.value();
+                }
+                let distance = total_variation(&pushforward, &measure2);
+                if distance > tolerance {
+                    violations.push(Violation::Transition {
+                        state: state.clone(),
+                        action,
+                        distance,
+                    });
+                }
+            }
+        }
+        Ok(violations)
+    }
+
+    pub fn is_valid(&self, source: &M1, target: &M2, tolerance: f64) -> Result<bool, Error> {
+        Ok(self.violations(source, target, tolerance)?.is_empty())
+    }
+
+    /// Pulls a policy on the target back to the source: at each non-final source state `s`, picks
+    /// the first available action `a` with `g_s(a)` equal to the target policy's action at `f(s)`.
+    /// States where the target policy is undefined or no action maps onto its choice are left out.
+    pub fn transport_policy<P>(
+        &self,
+        source: &M1,
+        policy: &P,
+    ) -> DeterministicPolicy<M1::State, M1::Action>
+    where
+        P: Policy<M2::State, M2::Action>,
+    {
+        let mut transported = HashMap::new();
+        for state in source.all_states().iter() {
+            if source.is_final_state(state) {
+                continue;
+            }
+            let Some(target_action) = policy.action(&self.map_state(state)) else {
+                continue;
+            };
+            if let Some(action) = source
+                .actions_at(state)
+                .into_iter()
+                .find(|a| self.map_action(state, a) == target_action)
+            {
+                transported.insert(state.clone(), action);
+            }
+        }
+        transported
+    }
+}
@@ -0,0 +1,436 @@
+//! # Multi-Objective MDPs
+//!
+//! MDPs whose transitions pay a vector of rewards, one per objective, and the Pareto set of
+//! deterministic policies found by linear scalarization. Products are naturally multi-objective:
+//! a `CartesianProduct` or `BoxProduct` pays each component's reward in its own coordinate
+//! instead of squashing them into one scalar.
+
+use std::collections::HashMap;
+
+use madepro::models::Sampler;
+
+use crate::config::TrainingConfig;
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::measure::Measure;
+use crate::planning::value_iteration;
+use crate::policy::DeterministicPolicy;
+use crate::products::{BoxAction, BoxProduct, CartesianProduct, Product};
+
+/// An MDP with a reward vector of length `num_objectives()` per transition. The scalar reward of
+/// `stochastic_transition` is whatever the implementation chooses (typically a sum); multi-objective
+/// algorithms only look at `vector_transition`.
+pub trait MultiObjectiveMDP: MDP {
+    fn num_objectives(&self) -> usize;
+
+    fn vector_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, Vec<f64>), Error>;
+}
+
+impl<M: MultiObjectiveMDP + ?Sized> MultiObjectiveMDP for &M {
+    fn num_objectives(&self) -> usize {
+        (**self).num_objectives()
+    }
+
+    fn vector_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, Vec<f64>), Error> {
+        (**self).vector_transition(state, action)
+    }
+}
+
+/// Objective 0 is the first component's reward and objective 1 the second's
+impl<M1, M2> MultiObjectiveMDP for CartesianProduct<M1, M2>
+where
+    M1: MDP,
+    M2: MDP,
+    M1::State: Clone,
+    M2::State: Clone,
+    M1::Action: Clone,
+    M2::Action: Clone,
+{
+    fn num_objectives(&self) -> usize {
+        2
+    }
+
+    fn vector_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, Vec<f64>), Error> {
+        let (mdp1, mdp2) = self.components();
+        let (m1, r1) = mdp1.stochastic_transition(state.first(), action.first())?;
+        let (m2, r2) = mdp2.stochastic_transition(state.second(), action.second())?;
+        let measure = m1.product(&m2)?.map(|(s1, s2)| Product::new(s1, s2));
+        Ok((measure, vec![r1, r2]))
+    }
+}
+
+/// Objective 0 is the first component's reward and objective 1 the second's; the component that
+/// does not move earns nothing
+impl<M1, M2> MultiObjectiveMDP for BoxProduct<M1, M2>
+where
+    M1: MDP,
+    M2: MDP,
+    M1::State: Clone,
+    M2::State: Clone,
+    M1::Action: Clone,
+    M2::Action: Clone,
+{
+    fn num_objectives(&self) -> usize {
+        2
+    }
+
+    fn vector_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, Vec<f64>), Error> {
+        let (measure, reward) = self.stochastic_transition(state, action)?;
+        let rewards = match action {
+            BoxAction::Left(_) => vec![reward, 0.0],
+            BoxAction::Right(_) => vec![0.0, reward],
+        };
+        Ok((measure, rewards))
+    }
+}
+
+/// Reward of a transition `(s, a, s')` for one objective
+type ObjectiveFn<S, A> = Box<dyn Fn(&S, &A, &S) -> f64>;
+
+/// Turns any MDP into a multi-objective one with explicit per-objective reward functions. Each
+/// objective is the expectation of its function over the next state; the scalar reward stays the
+/// wrapped MDP's.
+pub struct VectorReward<M: MDP> {
+    mdp: M,
+    objectives: Vec<ObjectiveFn<M::State, M::Action>>,
+}
+
+impl<M: MDP> VectorReward<M> {
+    pub fn new(mdp: M) -> Self {
+        VectorReward {
+            mdp,
+            objectives: Vec::new(),
+        }
+    }
+
+    pub fn with_objective(
+        mut self,
+        objective: impl Fn(&M::State, &M::Action, &M::State) -> f64 + 'static,
+    ) -> Self {
+        self.objectives.push(Box::new(objective));
+        self
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mdp
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mdp
+    }
+}
+
+impl<M> MDP for VectorReward<M>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    type State = M::State;
+    type Action = M::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        self.mdp.all_states()
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        self.mdp.actions_at(state)
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.mdp.is_final_state(state)
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.mdp.is_goal(state)
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        self.mdp.stochastic_transition(state, action)
+    }
+}
+
+impl<M> MultiObjectiveMDP for VectorReward<M>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    fn num_objectives(&self) -> usize {
+        self.objectives.len()
+    }
+
+    fn vector_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, Vec<f64>), Error> {
+        let (measure, _) = self.mdp.stochastic_transition(state, action)?;
+        let rewards = self
+            .objectives
+            .iter()
+            .map(|objective| {
+                measure
+                    .dist()
+                    .iter()
+                    .map(|(next, p)| p.value() * objective(state, action, next))
+                    .sum()
+            })
+            .collect();
+        Ok((measure, rewards))
+    }
+}
+
+/// The scalar MDP with reward `w · r(s, a)`
+pub struct Scalarized<M> {
+    mdp: M,
+    weights: Vec<f64>,
+}
+
+impl<M: MultiObjectiveMDP> Scalarized<M> {
+    pub fn new(mdp: M, weights: Vec<f64>) -> Self {
+        Scalarized { mdp, weights }
+    }
+
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mdp
+    }
+}
+
+impl<M: MultiObjectiveMDP> MDP for Scalarized<M> {
+    type State = M::State;
+    type Action = M::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        self.mdp.all_states()
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        self.mdp.actions_at(state)
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.mdp.is_final_state(state)
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.mdp.is_goal(state)
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        let (measure, rewards) = self.mdp.vector_transition(state, action)?;
+        let reward = rewards.iter().zip(&self.weights).map(|(r, w)| r * w).sum();
+        Ok((measure, reward))
+    }
+}
+
+/// A deterministic policy on the Pareto front
+pub struct ParetoPolicy<S, A> {
+    /// The scalarization weights it is optimal for (the first found, if several)
+    pub weights: Vec<f64>,
+    pub policy: DeterministicPolicy<S, A>,
+    /// Expected discounted return per objective from the initial distribution
+    pub value: Vec<f64>,
+}
+
+/// The non-dominated policies returned by `pareto_front`
+pub type ParetoFront<S, A> = Vec<ParetoPolicy<S, A>>;
+
+/// Reward vector and next-state distribution `[(index, probability)]` of a policy's action
+type PolicyStep = (Vec<f64>, Vec<(usize, f64)>);
+
+/// The weight vectors with non-negative entries summing to one whose entries are multiples of
+/// `1 / divisions`
+fn simplex_weights(num_objectives: usize, divisions: usize) -> Vec<Vec<f64>> {
+    fn compositions(
+        parts: usize,
+        total: usize,
+        prefix: &mut Vec<usize>,
+        out: &mut Vec<Vec<usize>>,
+    ) {
+        if parts == 1 {
+            prefix.push(total);
+            out.push(prefix.clone());
+            prefix.pop();
+            return;
+        }
+        for first in 0..=total {
+            prefix.push(first);
+            compositions(parts - 1, total - first, prefix, out);
+            prefix.pop();
+        }
+    }
+    if num_objectives == 0 {
+        return Vec::new();
+    }
+    let divisions = divisions.max(1);
+    let mut out = Vec::new();
+    compositions(num_objectives, divisions, &mut Vec::new(), &mut out);
+    out.into_iter()
+        .map(|c| c.into_iter().map(|k| k as f64 / divisions as f64).collect())
+        .collect()
+}
+
+/// # Vector Policy Evaluation
+///
+/// Expected discounted return of a deterministic policy for every objective, from `initial`.
+/// States where the policy is undefined, final states and next states outside `all_states()`
+/// are worth zero. Iterates until no value changes by more than `config.planning_tolerance` or
+/// `config.max_planning_iterations` sweeps have run.
+pub fn evaluate_vector<M>(
+    mdp: &M,
+    policy: &DeterministicPolicy<M::State, M::Action>,
+    initial: &Measure<M::State>,
+    config: &TrainingConfig,
+) -> Result<Vec<f64>, Error>
+where
+    M: MultiObjectiveMDP,
+    M::State: Clone,
+{
+    let num_objectives = mdp.num_objectives();
+    let states: Vec<M::State> = mdp.all_states().iter().cloned().collect();
+    let index: HashMap<&M::State, usize> = states.iter().enumerate().map(|(i, s)| (s, i)).collect();
+
+    // Per state under the policy: reward vector and next-state distribution
+    let mut table: Vec<Option<PolicyStep>> = Vec::with_capacity(states.len());
+    for state in &states {
+        let entry = match policy.get(state) {
+            Some(action) if !mdp.is_final_state(state) => {
+                let (measure, rewards) = mdp.vector_transition(state, action)?;
+                let next = measure
+                    .dist()
+                    .iter()
+                    .filter_map(|(s, p)| index.get(s).map(|&j| (j, p.value())))
+                    .collect();
+                Some((rewards, next))
+            }
+            _ => None,
+        };
+        table.push(entry);
+    }
+
+    let mut values = vec![vec![0.0; num_objectives]; states.len()];
+    for _ in 0..config.max_planning_iterations {
+        let mut max_change: f64 = 0.0;
+        let mut updated = vec![vec![0.0; num_objectives]; states.len()];
+        for (i, entry) in table.iter().enumerate() {
+            let Some((rewards, next)) = entry else {
+                continue;
+            };
+            for k in 0..num_objectives {
+                let expected: f64 = next.iter().map(|&(j, p)| p * values[j][k]).sum();
+                updated[i][k] =
+                    rewards.get(k).copied().unwrap_or(0.0) + config.discount_factor * expected;
+                max_change = max_change.max((updated[i][k] - values[i][k]).abs());
+            }
+        }
+        values = updated;
+        if max_change <= config.planning_tolerance {
+            break;
+        }
+    }
+
+    let mut value = vec![0.0; num_objectives];
+    for (state, p) in initial.dist() {
+        if let Some(&i) = index.get(state) {
+            for (total, v) in value.iter_mut().zip(&values[i]) {
+                *total += p.value() * v;
+            }
+        }
+    }
+    Ok(value)
+}
+
+/// `a` is at least `b` everywhere and better somewhere (up to `tolerance`)
+fn dominates(a: &[f64], b: &[f64], tolerance: f64) -> bool {
+    a.iter().zip(b).all(|(x, y)| *x >= y - tolerance)
+        && a.iter().zip(b).any(|(x, y)| *x > y + tolerance)
+}
+
+/// # Pareto Front
+///
+/// Linear-scalarization sweep: for every weight vector on a simplex grid with `divisions` steps
+/// per objective, solves the scalarized MDP `w · r` with value iteration, evaluates the greedy
+/// policy on every objective from `initial`, and keeps the non-dominated policies. Policies with
+/// the same value vector (within `config.planning_tolerance`) are kept once.
+///
+/// Scalarization only finds policies on the convex hull of the front; deterministic policies in
+/// its concave parts are missed.
+///
+/// # Arguments
+/// * `mdp` - The multi-objective MDP
+/// * `initial` - The start distribution values are measured from
+/// * `divisions` - Grid resolution: weights are multiples of `1 / divisions`
+/// * `config` - Configuration parameters (discount factor, planning tolerance and iterations)
+///
+/// # Returns
+/// The Pareto-optimal policies found, in the order their weights were visited
+pub fn pareto_front<M>(
+    mdp: &M,
+    initial: &Measure<M::State>,
+    divisions: usize,
+    config: &TrainingConfig,
+) -> Result<ParetoFront<M::State, M::Action>, Error>
+where
+    M: MultiObjectiveMDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let tolerance = config.planning_tolerance.max(1e-9);
+    let mut front: ParetoFront<M::State, M::Action> = Vec::new();
+    for weights in simplex_weights(mdp.num_objectives(), divisions) {
+        let scalarized = Scalarized::new(mdp, weights.clone());
+        let solution = value_iteration(
+            &scalarized,
+            config.discount_factor,
+            config.planning_tolerance,
+            config.max_planning_iterations,
+        )?;
+        let value = evaluate_vector(mdp, &solution.policy, initial, config)?;
+
+        let duplicate = front.iter().any(|p| {
+            p.value
+                .iter()
+                .zip(&value)
+                .all(|(a, b)| (a - b).abs() <= tolerance)
+        });
+        if duplicate || front.iter().any(|p| dominates(&p.value, &value, tolerance)) {
+            continue;
+        }
+        front.retain(|p| !dominates(&value, &p.value, tolerance));
+        front.push(ParetoPolicy {
+            weights,
+            policy: solution.policy,
+            value,
+        });
+    }
+    Ok(front)
+}
@@ -0,0 +1,390 @@
+//! # Occupancy Measures
+//!
+//! The discounted state-action occupancy of a policy started from an initial distribution `μ0`,
+//!
+//! `d(s, a) = Σ_t γ^t P(s_t = s, a_t = a)`,
+//!
+//! and the dual linear program over occupancies, whose solution is an optimal policy:
+//!
+//! `max Σ r(s, a) d(s, a)` subject to `Σ_a d(s', a) - γ Σ_(s, a) p(s' | s, a) d(s, a) = μ0(s')`
+//! for every non-final `s'`, and `d ≥ 0`.
+
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+use madepro::models::{Action, State};
+
+use crate::config::TrainingConfig;
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::measure::{Measure, Probability};
+use crate::policy::{DeterministicPolicy, StochasticPolicy};
+
+/// Pivot and feasibility tolerance of the simplex solver
+const LP_TOLERANCE: f64 = 1e-9;
+
+/// Per state, each action the policy takes with its probability and next-state distribution
+type PolicyKernel<S, A> = HashMap<S, Vec<(A, f64, Measure<S>)>>;
+
+/// A discounted state-action occupancy measure
+#[derive(Debug, Clone)]
+pub struct OccupancyMeasure<S, A> {
+    pub values: HashMap<(S, A), f64>,
+}
+
+impl<S: State, A: Action> OccupancyMeasure<S, A> {
+    pub fn get(&self, state: &S, action: &A) -> f64 {
+        self.values
+            .get(&(state.clone(), action.clone()))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// `d(s) = Σ_a d(s, a)`
+    pub fn state_occupancy(&self) -> HashMap<S, f64> {
+        let mut occupancy = HashMap::new();
+        for ((s, _), d) in &self.values {
+            *occupancy.entry(s.clone()).or_insert(0.0) += d;
+        }
+        occupancy
+    }
+
+    /// `Σ d(s, a)`, which is `1 / (1 - γ)` when no final state is ever reached
+    pub fn total(&self) -> f64 {
+        self.values.values().sum()
+    }
+
+    /// `π(a|s) = d(s, a) / d(s)` on every state with positive occupancy
+    pub fn policy(&self) -> Result<StochasticPolicy<S, A>, Error> {
+        let occupancy = self.state_occupancy();
+        let mut dists: HashMap<S, IndexMap<A, Probability>> = HashMap::new();
+        for ((s, a), d) in &self.values {
+            let total = occupancy[s];
+            if total > 0.0 && *d > 0.0 {
+                dists
+                    .entry(s.clone())
+                    .or_default()
+                    .insert(a.clone(), Probability::new((d / total).min(1.0))?);
+            }
+        }
+        dists
+            .into_iter()
+            .map(|(s, dist)| Ok((s, Measure::from_distribution(dist)?)))
+            .collect()
+    }
+
+    /// The most-occupied action of every state with positive occupancy
+    pub fn greedy_policy(&self) -> DeterministicPolicy<S, A> {
+        let mut best: HashMap<S, (A, f64)> = HashMap::new();
+        for ((s, a), d) in &self.values {
+            if *d <= 0.0 {
+                continue;
+            }
+            match best.get(s) {
+                Some((_, best_d)) if best_d >= d => {}
+                _ => {
+                    best.insert(s.clone(), (a.clone(), *d));
+                }
+            }
+        }
+        best.into_iter().map(|(s, (a, _))| (s, a)).collect()
+    }
+
+    /// `Σ r(s, a) d(s, a)`, the expected discounted return of the policy behind the measure
+    pub fn expected_return<M>(&self, mdp: &M) -> Result<f64, Error>
+    where
+        M: MDP<State = S, Action = A>,
+    {
+        let mut total = 0.0;
+        for ((s, a), d) in &self.values {
+            let (_, reward) = mdp.stochastic_transition(s, a)?;
+            total += reward * d;
+        }
+        Ok(total)
+    }
+}
+
+/// # Occupancy Measure
+///
+/// Computes the discounted occupancy of `policy` from `initial` by iterating
+/// `d(s') ← μ0(s') + γ Σ_(s, a) π(a|s) p(s' | s, a) d(s)` on state occupancies until no value
+/// changes by more than `config.planning_tolerance` or `config.max_planning_iterations` sweeps have
+/// run. Final states and states the policy leaves undefined end the trajectory.
+///
+/// # Returns
+///
+/// The occupancy measure, or `Error::NumericalError` if `config.discount_factor` is not below 1
+/// (the occupancy of a non-terminating policy is then infinite).
+pub fn occupancy_measure<M>(
+    mdp: &M,
+    policy: &StochasticPolicy<M::State, M::Action>,
+    initial: &Measure<M::State>,
+    config: &TrainingConfig,
+) -> Result<OccupancyMeasure<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let discount_factor = config.discount_factor;
+    if !(0.0..1.0).contains(&discount_factor) {
+        return Err(Error::NumericalError(format!(
+            "occupancy measures need a discount factor in [0, 1), got {discount_factor}"
+        )));
+    }
+
+    let mut kernel: PolicyKernel<M::State, M::Action> = HashMap::new();
+    for state in mdp.all_states().iter() {
+        if mdp.is_final_state(state) {
+            continue;
+        }
+        let Some(measure) = policy.get(state) else {
+            continue;
+        };
+        let mut outcomes = Vec::new();
+        for (action, p) in measure.dist() {
+            if p.value() > 0.0 {
+                let (next, _) = mdp.stochastic_transition(state, action)?;
+                outcomes.push((action.clone(), p.value(), next));
+            }
+        }
+        kernel.insert(state.clone(), outcomes);
+    }
+
+    let start: HashMap<M::State, f64> = initial
+        .dist()
+        .iter()
+        .map(|(s, p)| (s.clone(), p.value()))
+        .collect();
+    let mut occupancy = start.clone();
+    for _ in 0..config.max_planning_iterations {
+        let mut updated = start.clone();
+        for (state, d) in &occupancy {
+            for (_, pi, next) in kernel.get(state).into_iter().flatten() {
+                for (s, p) in next.dist() {
+                    *updated.entry(s.clone()).or_insert(0.0) +=
+                        discount_factor * pi * p.value() * d;
+                }
+            }
+        }
+        let max_change = updated
+            .iter()
+            .map(|(s, d)| (d - occupancy.get(s).copied().unwrap_or(0.0)).abs())
+            .fold(0.0, f64::max);
+        occupancy = updated;
+        if max_change <= config.planning_tolerance {
+            break;
+        }
+    }
+
+    let mut values = HashMap::new();
+    for (state, d) in &occupancy {
+        for (action, pi, _) in kernel.get(state).into_iter().flatten() {
+            values.insert((state.clone(), action.clone()), pi * d);
+        }
+    }
+    Ok(OccupancyMeasure { values })
+}
+
+/// The optimal occupancy found by `solve_dual_lp`
+pub struct DualLpResult<S, A> {
+    pub occupancy: OccupancyMeasure<S, A>,
+    /// The most-occupied action of every reachable state
+    pub policy: DeterministicPolicy<S, A>,
+    /// The optimal objective `Σ r(s, a) d(s, a)`: the optimal expected discounted return from `μ0`
+    pub value: f64,
+}
+
+/// # Dual LP
+///
+/// Solves the dual linear program over occupancy measures (see the module docs) with a dense
+/// two-phase simplex method. Final states and non-final states without actions have no
+/// occupancy variables and contribute nothing; next states outside `all_states()` are ignored.
+/// The tableau has one row per state and one column per state-action pair, so this is meant for
+/// small MDPs and as a ground truth for the iterative solvers.
+///
+/// # Returns
+///
+/// The optimal occupancy, a deterministic optimal policy on the reachable states and the optimal
+/// value, or `Error::NumericalError` if `discount_factor` is not below 1 or the solver fails.
+pub fn solve_dual_lp<M>(
+    mdp: &M,
+    initial: &Measure<M::State>,
+    discount_factor: f64,
+) -> Result<DualLpResult<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    if !(0.0..1.0).contains(&discount_factor) {
+        return Err(Error::NumericalError(format!(
+            "the dual LP needs a discount factor in [0, 1), got {discount_factor}"
+        )));
+    }
+
+    // Rows: states with actions. Columns: their state-action pairs.
+    let mut rows: HashMap<M::State, usize> = HashMap::new();
+    let mut pairs: Vec<(M::State, M::Action)> = Vec::new();
+    for state in mdp.all_states().iter() {
+        if mdp.is_final_state(state) {
+            continue;
+        }
+        let actions = mdp.actions_at(state);
+        if actions.is_empty() {
+            continue;
+        }
+        rows.insert(state.clone(), rows.len());
+        pairs.extend(actions.into_iter().map(|a| (state.clone(), a)));
+    }
+
+    let mut a = vec![vec![0.0; pairs.len()]; rows.len()];
+    let mut c = vec![0.0; pairs.len()];
+    for (j, (state, action)) in pairs.iter().enumerate() {
+        let (measure, reward) = mdp.stochastic_transition(state, action)?;
+        c[j] = reward;
+        a[rows[state]][j] += 1.0;
+        for (next, p) in measure.dist() {
+            if let Some(&i) = rows.get(next) {
+                a[i][j] -= discount_factor * p.value();
+            }
+        }
+    }
+    let mut b = vec![0.0; rows.len()];
+    for (state, p) in initial.dist() {
+        if let Some(&i) = rows.get(state) {
+            b[i] += p.value();
+        }
+    }
+
+    let x = simplex(&a, &b, &c)?;
+    let value = x.iter().zip(&c).map(|(x, c)| x * c).sum();
+    let values = pairs
+        .into_iter()
+        .zip(x)
+        .filter(|(_, d)| *d > LP_TOLERANCE)
+        .collect();
+    let occupancy = OccupancyMeasure { values };
+    Ok(DualLpResult {
+        policy: occupancy.greedy_policy(),
+        occupancy,
+        value,
+    })
+}
+
+/// Maximizes `c·x` subject to `A x = b` and `x ≥ 0` (with `b ≥ 0`) by the two-phase tableau
+/// simplex method, using Bland's rule so degenerate problems cannot cycle
+fn simplex(a: &[Vec<f64>], b: &[f64], c: &[f64]) -> Result<Vec<f64>, Error> {
+    let m = b.len();
+    let n = c.len();
+    // Columns: n originals, m artificials, then the right-hand side
+    let mut tableau: Vec<Vec<f64>> = (0..m)
+        .map(|i| {
+            let mut row = a[i].clone();
+            row.extend((0..m).map(|k| if k == i { 1.0 } else { 0.0 }));
+            row.push(b[i]);
+            row
+        })
+        .collect();
+    let mut basis: Vec<usize> = (n..n + m).collect();
+
+    // Phase 1: drive the artificials to zero
+    let phase_one: Vec<f64> = (0..n + m).map(|j| if j < n { 0.0 } else { -1.0 }).collect();
+    run_simplex(&mut tableau, &mut basis, &phase_one, n + m)?;
+    let infeasibility: f64 = (0..m)
+        .filter(|&i| basis[i] >= n)
+        .map(|i| tableau[i][n + m])
+        .sum();
+    if infeasibility > LP_TOLERANCE.sqrt() {
+        return Err(Error::NumericalError(
+            "the linear program is infeasible".into(),
+        ));
+    }
+    for i in 0..m {
+        if basis[i] >= n
+            && let Some(j) = (0..n).find(|&j| tableau[i][j].abs() > LP_TOLERANCE)
+        {
+            pivot(&mut tableau, &mut basis, i, j);
+        }
+    }
+
+    // Phase 2: optimize the real objective over the original columns
+    let mut phase_two = c.to_vec();
+    phase_two.extend(std::iter::repeat_n(0.0, m));
+    run_simplex(&mut tableau, &mut basis, &phase_two, n)?;
+
+    let mut x = vec![0.0; n];
+    for (i, &j) in basis.iter().enumerate() {
+        if j < n {
+            x[j] = tableau[i][n + m].max(0.0);
+        }
+    }
+    Ok(x)
+}
+
+/// Pivots until no column below `entering_limit` has a positive reduced cost
+fn run_simplex(
+    tableau: &mut [Vec<f64>],
+    basis: &mut [usize],
+    cost: &[f64],
+    entering_limit: usize,
+) -> Result<(), Error> {
+    let rhs = cost.len();
+    loop {
+        let entering = (0..entering_limit).find(|&j| {
+            let reduced: f64 = cost[j]
+                - basis
+                    .iter()
+                    .zip(tableau.iter())
+                    .map(|(&k, row)| cost[k] * row[j])
+                    .sum::<f64>();
+            reduced > LP_TOLERANCE
+        });
+        let Some(j) = entering else {
+            return Ok(());
+        };
+
+        let mut leaving: Option<(usize, f64)> = None;
+        for (i, row) in tableau.iter().enumerate() {
+            if row[j] > LP_TOLERANCE {
+                let ratio = row[rhs] / row[j];
+                let better = match leaving {
+                    None => true,
+                    Some((l, best)) => {
+                        ratio < best - LP_TOLERANCE
+                            || (ratio <= best + LP_TOLERANCE && basis[i] < basis[l])
+                    }
+                };
+                if better {
+                    leaving = Some((i, ratio));
+                }
+            }
+        }
+        let Some((i, _)) = leaving else {
+            return Err(Error::NumericalError(
+                "the linear program is unbounded".into(),
+            ));
+        };
+        pivot(tableau, basis, i, j);
+    }
+}
+
+fn pivot(tableau: &mut [Vec<f64>], basis: &mut [usize], row: usize, column: usize) {
+    let scale = tableau[row][column];
+    for value in tableau[row].iter_mut() {
+        *value /= scale;
+    }
+    let pivot_row = tableau[row].clone();
+    for (i, other) in tableau.iter_mut().enumerate() {
+        if i == row {
+            continue;
+        }
+        let factor = other[column];
+        if factor != 0.0 {
+            for (value, p) in other.iter_mut().zip(&pivot_row) {
+                *value -= factor * p;
+            }
+        }
+    }
+    basis[row] = column;
+}
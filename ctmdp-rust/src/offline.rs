@@ -0,0 +1,127 @@
+//! # Offline Learning
+//!
+//! Learners that only read a logged `Dataset` and never touch the environment.
+
+use std::collections::HashMap;
+
+use madepro::models::{Action, ActionValue, Sampler, State};
+
+use crate::config::TrainingConfig;
+use crate::dataset::Dataset;
+use crate::error::Error;
+
+/// The logged outcomes of one state-action pair: `(reward, index of the next state or None if
+/// it was final)`
+type Outcomes = Vec<(f64, Option<usize>)>;
+
+/// # Fitted Q-Iteration
+///
+/// Tabular fitted Q-iteration: repeats the batch Bellman backup
+///
+/// `Q(s, a) ← mean over logged (s, a, r, s') of [ r + γ max_a' Q(s', a') ]`
+///
+/// over every logged state-action pair until no Q-value changes by more than
+/// `config.planning_tolerance` or `config.max_planning_iterations` sweeps have run. Final next
+/// states bootstrap zero, and the maximum only ranges over actions logged at `s'` (a next state
+/// with no logged actions is worth zero), so values never rest on pairs the data says nothing about.
+///
+/// # Arguments
+/// * `dataset` - The logged trajectories
+/// * `config` - Configuration parameters (discount factor, planning tolerance and iterations)
+///
+/// # Returns
+/// An `ActionValue` table over the logged states and actions; unlogged pairs keep the table's
+/// initial value. Fails with `Error::EmptyStateSpace` if the dataset has no transitions.
+pub fn fitted_q_iteration<S, A>(
+    dataset: &Dataset<S, A>,
+    config: &TrainingConfig,
+) -> Result<ActionValue<S, A>, Error>
+where
+    S: State,
+    A: Action,
+{
+    let transitions = dataset.transitions();
+    if transitions.is_empty() {
+        return Err(Error::EmptyStateSpace);
+    }
+
+    let mut states: Vec<S> = Vec::new();
+    let mut index: HashMap<S, usize> = HashMap::new();
+    let mut intern = |s: &S| -> usize {
+        *index.entry(s.clone()).or_insert_with(|| {
+            states.push(s.clone());
+            states.len() - 1
+        })
+    };
+    let mut pairs: HashMap<(usize, A), Outcomes> = HashMap::new();
+    for transition in &transitions {
+        let state = intern(&transition.state);
+        let next_state = intern(&transition.next_state);
+        pairs
+            .entry((state, transition.action.clone()))
+            .or_default()
+            .push((transition.reward, (!transition.done).then_some(next_state)));
+    }
+    let pairs: Vec<((usize, A), Outcomes)> = pairs.into_iter().collect();
+
+    // Logged pairs per state, as indices into `pairs`
+    let mut logged: Vec<Vec<usize>> = vec![Vec::new(); states.len()];
+    for (i, ((state, _), _)) in pairs.iter().enumerate() {
+        logged[*state].push(i);
+    }
+
+    let mut q = vec![0.0; pairs.len()];
+    for _ in 0..config.max_planning_iterations {
+        let state_values: Vec<f64> = logged
+            .iter()
+            .map(|indices| {
+                indices
+                    .iter()
+                    .map(|&i| q[i])
+                    .fold(None, |best: Option<f64>, v| {
+                        Some(best.map_or(v, |b| b.max(v)))
+                    })
+                    .unwrap_or(0.0)
+            })
+            .collect();
+
+        let mut max_change: f64 = 0.0;
+        let updated: Vec<f64> = pairs
+            .iter()
+            .zip(&q)
+            .map(|((_, outcomes), old)| {
+                let total: f64 = outcomes
+                    .iter()
+                    .map(|(reward, next)| {
+                        reward + next.map_or(0.0, |s| config.discount_factor * state_values[s])
+                    })
+                    .sum();
+                let value = total / outcomes.len() as f64;
+                max_change = max_change.max((value - old).abs());
+                value
+            })
+            .collect();
+        q = updated;
+        if !max_change.is_finite() {
+            return Err(Error::NumericalError(format!(
+                "fitted Q-iteration diverged (change {max_change})"
+            )));
+        }
+        if max_change <= config.planning_tolerance {
+            break;
+        }
+    }
+
+    let mut actions: Vec<A> = Vec::new();
+    for ((_, action), _) in &pairs {
+        if !actions.contains(action) {
+            actions.push(action.clone());
+        }
+    }
+    let state_sampler: Sampler<S> = states.clone().into();
+    let mut action_value = ActionValue::new(&state_sampler, &actions.into());
+    for (((state, action), _), value) in pairs.iter().zip(q) {
+        action_value.insert(&states[*state], action, value);
+    }
+    Ok(action_value)
+}
@@ -0,0 +1,509 @@
+//! # Options
+//!
+//! Temporally extended actions (Sutton, Precup & Singh, 1999). An option has an initiation set,
+//! an internal policy and a termination condition; an MDP with a set of options is a semi-MDP
+//! whose decisions take a random number of steps. `smdp_q_learning` learns option values from
+//! whole executions, `intra_option_q_learning` updates every option consistent with each primitive
+//! step. The type is `MdpOption` to stay clear of `std::option::Option`.
+//!
+//! Policies learned on a component MDP can be packaged as options inside a `BoxProduct` with
+//! `box_left_option`/`box_right_option`.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::rc::Rc;
+
+use rand::Rng;
+use rand::seq::IndexedRandom;
+
+use crate::config::TrainingConfig;
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::policy::{DeterministicPolicy, Policy};
+use crate::products::{BoxAction, Product};
+use crate::q_learning::random_start;
+
+type Initiation<S> = Box<dyn Fn(&S) -> bool>;
+type InternalPolicy<S, A> = Box<dyn Fn(&S) -> Option<A>>;
+type Termination<S> = Box<dyn Fn(&S) -> f64>;
+
+/// An option: where it may start, what it does, and how likely it is to stop in each state
+pub struct MdpOption<S, A> {
+    name: String,
+    initiation: Initiation<S>,
+    policy: InternalPolicy<S, A>,
+    termination: Termination<S>,
+}
+
+impl<S: 'static, A: Clone + 'static> MdpOption<S, A> {
+    /// # Arguments
+    /// * `name` - A label for reports
+    /// * `initiation` - The states the option may be started in
+    /// * `policy` - The internal policy (`None` ends the option)
+    /// * `termination` - `β(s)`, the probability of stopping on arrival in `s`
+    pub fn new(
+        name: impl Into<String>,
+        initiation: impl Fn(&S) -> bool + 'static,
+        policy: impl Fn(&S) -> Option<A> + 'static,
+        termination: impl Fn(&S) -> f64 + 'static,
+    ) -> Self {
+        MdpOption {
+            name: name.into(),
+            initiation: Box::new(initiation),
+            policy: Box::new(policy),
+            termination: Box::new(termination),
+        }
+    }
+
+    /// The one-step option that takes `action` wherever it may start
+    pub fn primitive(name: impl Into<String>, action: A) -> Self {
+        Self::new(name, |_| true, move |_| Some(action.clone()), |_| 1.0)
+    }
+
+    /// Packages a policy as an option that may start anywhere the policy is defined and stops in
+    /// the states satisfying `stop`
+    pub fn from_policy(
+        name: impl Into<String>,
+        policy: impl Policy<S, A> + 'static,
+        stop: impl Fn(&S) -> bool + 'static,
+    ) -> Self {
+        let policy = Rc::new(policy);
+        let defined = policy.clone();
+        Self::new(
+            name,
+            move |s| defined.action(s).is_some(),
+            move |s| policy.action(s),
+            move |s| if stop(s) { 1.0 } else { 0.0 },
+        )
+    }
+}
+
+impl<S, A> MdpOption<S, A> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn can_start(&self, state: &S) -> bool {
+        (self.initiation)(state)
+    }
+
+    pub fn action(&self, state: &S) -> Option<A> {
+        (self.policy)(state)
+    }
+
+    pub fn termination_probability(&self, state: &S) -> f64 {
+        (self.termination)(state).clamp(0.0, 1.0)
+    }
+}
+
+/// An option of a `BoxProduct` that runs a left-component policy on the left coordinate,
+/// stopping when `stop` holds there
+pub fn box_left_option<S1, S2, A1, A2>(
+    name: impl Into<String>,
+    policy: DeterministicPolicy<S1, A1>,
+    stop: impl Fn(&S1) -> bool + 'static,
+) -> MdpOption<Product<S1, S2>, BoxAction<A1, A2>>
+where
+    S1: Eq + Hash + 'static,
+    S2: 'static,
+    A1: Clone + 'static,
+    A2: Clone + 'static,
+{
+    let policy = Rc::new(policy);
+    let defined = policy.clone();
+    MdpOption::new(
+        name,
+        move |s: &Product<S1, S2>| defined.contains_key(s.first()),
+        move |s: &Product<S1, S2>| policy.get(s.first()).cloned().map(BoxAction::Left),
+        move |s: &Product<S1, S2>| if stop(s.first()) { 1.0 } else { 0.0 },
+    )
+}
+
+/// The right-component counterpart of `box_left_option`
+pub fn box_right_option<S1, S2, A1, A2>(
+    name: impl Into<String>,
+    policy: DeterministicPolicy<S2, A2>,
+    stop: impl Fn(&S2) -> bool + 'static,
+) -> MdpOption<Product<S1, S2>, BoxAction<A1, A2>>
+where
+    S1: 'static,
+    S2: Eq + Hash + 'static,
+    A1: Clone + 'static,
+    A2: Clone + 'static,
+{
+    let policy = Rc::new(policy);
+    let defined = policy.clone();
+    MdpOption::new(
+        name,
+        move |s: &Product<S1, S2>| defined.contains_key(s.second()),
+        move |s: &Product<S1, S2>| policy.get(s.second()).cloned().map(BoxAction::Right),
+        move |s: &Product<S1, S2>| if stop(s.second()) { 1.0 } else { 0.0 },
+    )
+}
+
+/// Values of options, indexed by their position in the option list
+pub struct OptionValues<S> {
+    table: HashMap<(S, usize), f64>,
+}
+
+impl<S: Eq + Hash + Clone> OptionValues<S> {
+    fn new() -> Self {
+        OptionValues {
+            table: HashMap::new(),
+        }
+    }
+
+    /// `Q(s, o)`; zero for pairs never updated
+    pub fn get(&self, state: &S, option: usize) -> f64 {
+        self.table
+            .get(&(state.clone(), option))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    fn insert(&mut self, state: &S, option: usize, value: f64) {
+        self.table.insert((state.clone(), option), value);
+    }
+
+    /// The available option with the highest value in `state` (lowest index wins ties)
+    pub fn greedy_option<M>(
+        &self,
+        mdp: &M,
+        options: &[MdpOption<S, M::Action>],
+        state: &S,
+    ) -> Option<usize>
+    where
+        M: MDP<State = S>,
+    {
+        let mut best: Option<(usize, f64)> = None;
+        for index in available_options(mdp, options, state) {
+            let value = self.get(state, index);
+            if best.is_none_or(|(_, best_value)| value > best_value) {
+                best = Some((index, value));
+            }
+        }
+        best.map(|(index, _)| index)
+    }
+
+    /// `max_o Q(s, o)` over the available options; zero if none is available
+    pub fn max_value<M>(&self, mdp: &M, options: &[MdpOption<S, M::Action>], state: &S) -> f64
+    where
+        M: MDP<State = S>,
+    {
+        self.greedy_option(mdp, options, state)
+            .map_or(0.0, |o| self.get(state, o))
+    }
+
+    /// The greedy option in every non-final state
+    pub fn greedy_policy<M>(
+        &self,
+        mdp: &M,
+        options: &[MdpOption<S, M::Action>],
+    ) -> DeterministicPolicy<S, usize>
+    where
+        M: MDP<State = S>,
+    {
+        mdp.all_states()
+            .iter()
+            .filter(|s| !mdp.is_final_state(s))
+            .filter_map(|s| self.greedy_option(mdp, options, s).map(|o| (s.clone(), o)))
+            .collect()
+    }
+}
+
+/// Options that may start in `state` and whose first action is available there
+fn available_options<M>(
+    mdp: &M,
+    options: &[MdpOption<M::State, M::Action>],
+    state: &M::State,
+) -> Vec<usize>
+where
+    M: MDP,
+{
+    if mdp.is_final_state(state) {
+        return Vec::new();
+    }
+    let actions = mdp.actions_at(state);
+    options
+        .iter()
+        .enumerate()
+        .filter(|(_, option)| option.can_start(state))
+        .filter(|(_, option)| option.action(state).is_some_and(|a| actions.contains(&a)))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// What running an option produced
+pub struct OptionOutcome<S> {
+    pub state: S,
+    /// `Σ_k γ^k r_k` over the option's steps
+    pub discounted_reward: f64,
+    pub steps: usize,
+}
+
+/// # Execute Option
+///
+/// Runs `option` from `state` until it terminates (sampled from `β` after every step), its policy
+/// has no available action, a final state is reached or `max_steps` primitive steps have been
+/// taken.
+pub fn execute_option<M, R>(
+    mdp: &M,
+    option: &MdpOption<M::State, M::Action>,
+    state: &M::State,
+    discount_factor: f64,
+    max_steps: usize,
+    rng: &mut R,
+) -> Result<OptionOutcome<M::State>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    R: Rng + ?Sized,
+{
+    let mut current = state.clone();
+    let mut discounted_reward = 0.0;
+    let mut discount = 1.0;
+    let mut steps = 0;
+    while steps < max_steps && !mdp.is_final_state(&current) {
+        let Some(action) = option.action(&current) else {
+            break;
+        };
+        if !mdp.actions_at(&current).contains(&action) {
+            break;
+        }
+        let (measure, reward) = mdp.stochastic_transition(&current, &action)?;
+        current = measure
+            .sample_with(rng)
+            .cloned()
+            .unwrap_or_else(|| current.clone());
+        discounted_reward += discount * reward;
+        discount *= discount_factor;
+        steps += 1;
+        if rng.random::<f64>() < option.termination_probability(&current) {
+            break;
+        }
+    }
+    Ok(OptionOutcome {
+        state: current,
+        discounted_reward,
+        steps,
+    })
+}
+
+/// Picks an available option epsilon-greedily
+fn choose_option<M, R>(
+    mdp: &M,
+    options: &[MdpOption<M::State, M::Action>],
+    values: &OptionValues<M::State>,
+    state: &M::State,
+    exploration_rate: f64,
+    rng: &mut R,
+) -> Option<usize>
+where
+    M: MDP,
+    M::State: Clone,
+    R: Rng + ?Sized,
+{
+    if rng.random::<f64>() < exploration_rate {
+        available_options(mdp, options, state).choose(rng).copied()
+    } else {
+        values.greedy_option(mdp, options, state)
+    }
+}
+
+/// # SMDP Q-Learning
+///
+/// Q-learning over options. Each decision runs an option to completion and applies
+///
+/// `Q(s, o) ← Q(s, o) + α [R + γ^k max_o' Q(s', o') - Q(s, o)]`
+///
+/// where `R` is the discounted reward collected over the option's `k` steps. Options are chosen
+/// epsilon-greedily among those available; episodes start in random states and last at most
+/// `config.max_num_steps` primitive steps.
+///
+/// # Arguments
+/// * `mdp` - The MDP to learn from
+/// * `options` - The options to choose between
+/// * `config` - Configuration parameters (learning rate, discount factor, exploration rate, etc.)
+///
+/// # Returns
+/// The learned option values
+pub fn smdp_q_learning<M>(
+    mdp: &M,
+    options: &[MdpOption<M::State, M::Action>],
+    config: &TrainingConfig,
+) -> Result<OptionValues<M::State>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+{
+    smdp_q_learning_with_discount(mdp, options, config, config.discount_factor)
+}
+
+/// SMDP Q-learning discounting every primitive step by `step_discount` instead of
+/// `config.discount_factor`
+pub(crate) fn smdp_q_learning_with_discount<M>(
+    mdp: &M,
+    options: &[MdpOption<M::State, M::Action>],
+    config: &TrainingConfig,
+    step_discount: f64,
+) -> Result<OptionValues<M::State>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+{
+    let states = mdp.all_states();
+    if states.iter().next().is_none() {
+        return Err(Error::EmptyStateSpace);
+    }
+    let mut values = OptionValues::new();
+    let mut rng = config.rng();
+
+    for _ in 0..config.num_episodes {
+        let mut state = random_start(states, &mut rng)?.clone();
+        let mut steps_left = config.max_num_steps as usize;
+        while steps_left > 0 {
+            let Some(index) = choose_option(
+                mdp,
+                options,
+                &values,
+                &state,
+                config.exploration_rate,
+                &mut rng,
+            ) else {
+                break;
+            };
+            let outcome = execute_option(
+                mdp,
+                &options[index],
+                &state,
+                step_discount,
+                steps_left,
+                &mut rng,
+            )?;
+            if outcome.steps == 0 {
+                break;
+            }
+            let target = outcome.discounted_reward
+                + step_discount.powi(outcome.steps as i32)
+                    * values.max_value(mdp, options, &outcome.state);
+            let current = values.get(&state, index);
+            values.insert(
+                &state,
+                index,
+                current + config.learning_rate * (target - current),
+            );
+            steps_left -= outcome.steps;
+            state = outcome.state;
+        }
+    }
+
+    Ok(values)
+}
+
+/// # Intra-Option Q-Learning
+///
+/// Off-policy learning of option values from every primitive step. After taking `a` in `s` and
+/// landing in `s'` with reward `r`, every option `o` whose policy would also have taken `a` in `s`
+/// is updated toward
+///
+/// `r + γ [(1 - β_o(s')) Q(s', o) + β_o(s') max_o' Q(s', o')]`
+///
+/// so all options consistent with the behavior learn at once, not only the one being executed.
+/// Behavior picks options epsilon-greedily and follows them until they terminate.
+///
+/// # Arguments
+/// * `mdp` - The MDP to learn from
+/// * `options` - The options to learn values for (their internal policies are deterministic)
+/// * `config` - Configuration parameters (learning rate, discount factor, exploration rate, etc.)
+///
+/// # Returns
+/// The learned option values
+pub fn intra_option_q_learning<M>(
+    mdp: &M,
+    options: &[MdpOption<M::State, M::Action>],
+    config: &TrainingConfig,
+) -> Result<OptionValues<M::State>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+{
+    let states = mdp.all_states();
+    if states.iter().next().is_none() {
+        return Err(Error::EmptyStateSpace);
+    }
+    let mut values = OptionValues::new();
+    let mut rng = config.rng();
+
+    for _ in 0..config.num_episodes {
+        let mut state = random_start(states, &mut rng)?.clone();
+        let mut running: Option<usize> = None;
+
+        for _ in 0..config.max_num_steps {
+            if mdp.is_final_state(&state) {
+                break;
+            }
+            let index = match running {
+                Some(index) => index,
+                None => match choose_option(
+                    mdp,
+                    options,
+                    &values,
+                    &state,
+                    config.exploration_rate,
+                    &mut rng,
+                ) {
+                    Some(index) => index,
+                    None => break,
+                },
+            };
+            let available = mdp.actions_at(&state);
+            let Some(action) = options[index]
+                .action(&state)
+                .filter(|a| available.contains(a))
+            else {
+                running = None;
+                continue;
+            };
+
+            let (measure, reward) = mdp.stochastic_transition(&state, &action)?;
+            let next_state = measure
+                .sample_with(&mut rng)
+                .cloned()
+                .unwrap_or_else(|| state.clone());
+            let done = mdp.is_final_state(&next_state);
+            let best_next = if done {
+                0.0
+            } else {
+                values.max_value(mdp, options, &next_state)
+            };
+
+            for (o, option) in options.iter().enumerate() {
+                if option.action(&state).as_ref() != Some(&action) {
+                    continue;
+                }
+                let continuation = if done {
+                    0.0
+                } else {
+                    let beta = option.termination_probability(&next_state);
+                    (1.0 - beta) * values.get(&next_state, o) + beta * best_next
+                };
+                let target = reward + config.discount_factor * continuation;
+                let current = values.get(&state, o);
+                values.insert(
+                    &state,
+                    o,
+                    current + config.learning_rate * (target - current),
+                );
+            }
+
+            running = if rng.random::<f64>() < options[index].termination_probability(&next_state) {
+                None
+            } else {
+                Some(index)
+            };
+            state = next_state;
+        }
+    }
+
+    Ok(values)
+}
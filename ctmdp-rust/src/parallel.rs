@@ -0,0 +1,92 @@
+//! # Parallel Training and Planning
+//!
+//! Multi-threaded versions of the two loops the experiments spend their time in, behind the
+//! `parallel` feature. Independent learning trials are embarrassingly parallel, and so is one
+//! synchronous value-iteration sweep: every state's backup reads the previous values only. Both
+//! run on rayon's global thread pool (sized by `RAYON_NUM_THREADS`).
+
+use madepro::models::ActionValue;
+use rayon::prelude::*;
+
+use crate::compiled::compile;
+use crate::config::TrainingConfig;
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::planning::{ValueIterationResult, iterate_values, optimal_backup};
+use crate::q_learning::q_learning;
+
+/// The outcome of one Q-learning trial on `M`
+pub type TrialResult<M> = Result<ActionValue<<M as MDP>::State, <M as MDP>::Action>, Error>;
+
+/// Runs `trial(0)`, ..., `trial(num_trials - 1)` in parallel and returns the results in trial
+/// order
+pub fn parallel_trials<T, F>(num_trials: usize, trial: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize) -> T + Send + Sync,
+{
+    (0..num_trials).into_par_iter().map(trial).collect()
+}
+
+/// # Parallel Q-Learning Trials
+///
+/// Trains `num_trials` independent Q-learning agents on `mdp` with the same configuration, one
+/// per worker thread at a time. If `config` is seeded with `s`, trial `i` runs with seed `s + i`,
+/// so the trials differ from each other while the whole batch repeats exactly; unseeded trials
+/// each draw their seed from the thread RNG.
+///
+/// # Arguments
+/// * `mdp` - The MDP to learn from, shared by every trial
+/// * `config` - Configuration parameters (learning rate, discount factor, exploration rate, etc.)
+/// * `num_trials` - Number of independent trials
+///
+/// # Returns
+/// The learned `ActionValue` table (or error) of every trial, in trial order
+pub fn run_trials_parallel<M>(
+    mdp: &M,
+    config: &TrainingConfig,
+    num_trials: usize,
+) -> Vec<TrialResult<M>>
+where
+    M: MDP + Sync,
+    M::State: Clone + Send,
+    M::Action: Clone + Send,
+{
+    parallel_trials(num_trials, |trial| {
+        let mut config = config.clone();
+        config.seed = config.seed.map(|seed| seed.wrapping_add(trial as u64));
+        q_learning(mdp, &config)
+    })
+}
+
+/// # Parallel Value Iteration
+///
+/// `value_iteration` with every sweep split across threads. The iterates, and hence the
+/// result, are identical to the sequential solver's.
+pub fn par_value_iteration<M>(
+    mdp: &M,
+    discount_factor: f64,
+    tolerance: f64,
+    max_iterations: usize,
+) -> Result<ValueIterationResult<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone + Send + Sync,
+    M::Action: Clone + Send + Sync,
+{
+    let model = compile(mdp)?;
+    Ok(iterate_values(
+        &model,
+        discount_factor,
+        tolerance,
+        max_iterations,
+        |values, updated| {
+            updated
+                .par_iter_mut()
+                .enumerate()
+                .for_each(|(state, value)| {
+                    *value = optimal_backup(&model, state, values, discount_factor);
+                });
+        },
+    ))
+}
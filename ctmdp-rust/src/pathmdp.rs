@@ -1,10 +1,10 @@
-use super::{END_TRANSITION_REWARD, NO_OP_TRANSITION_REWARD};
-use crate::measure::Measure;
+use crate::measure::{Measure, MeasureBuilder};
 use crate::mdp::MDP;
 use crate::error::Error;
 use madepro::models::{Action, Sampler, State};
 
 #[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PathAction {
     Next,
     Prev,
@@ -13,6 +13,7 @@ pub enum PathAction {
 impl Action for PathAction {}
 
 #[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PathState(pub(crate) usize);
 
 impl PathState {
@@ -27,25 +28,151 @@ impl PathState {
 
 impl State for PathState {}
 
+/// Rewards of a `PathWorld`. The defaults are tuned to match the Python chain experiments.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PathRewards {
+    /// Reward of a `Next` move
+    pub step: f64,
+    /// Reward of a `Prev` move
+    pub back_step: f64,
+    /// Reward of a move off the end of a line, which leaves the state unchanged
+    pub no_op: f64,
+    /// Bonus added to the move reward when arriving at a goal
+    pub goal: f64,
+}
+
+impl Default for PathRewards {
+    fn default() -> Self {
+        PathRewards {
+            step: 0.1,
+            back_step: -0.5,
+            no_op: -1.0,
+            goal: 10.0,
+        }
+    }
+}
+
 pub struct PathWorld {
     states: Sampler<PathState>,
     actions: Sampler<PathAction>,
-    // cyclic: bool
+    /// `Next` from the last state wraps to the first and `Prev` from the first to the last
+    cyclic: bool,
+    /// Indices of the final (goal) states
+    goals: Vec<usize>,
+    rewards: PathRewards,
+    /// Probability that `Next` moves like `Prev` instead
+    slip_back: f64,
+    /// Probability that `Next` leaves the state unchanged
+    slip_stay: f64,
 }
 
 impl PathWorld {
+    /// A line whose last state is the goal
     pub fn new(states: Vec<PathState>, actions: Vec<PathAction>) -> Self {
+        let goals = states.len().checked_sub(1).into_iter().collect();
         PathWorld {
             states: states.into(),
             actions: actions.into(),
+            cyclic: false,
+            goals,
+            rewards: PathRewards::default(),
+            slip_back: 0.0,
+            slip_stay: 0.0,
+        }
+    }
+
+    /// A ring of `length` states with both actions, where `Next` from the last state wraps to
+    /// `0` and `Prev` from `0` to the last state. The states at `goals` are final; with no goals
+    /// the ring is a continuing task. Indices at or beyond `length` are never reached.
+    pub fn cyclic_new(length: usize, goals: Vec<usize>) -> Self {
+        PathWorld {
+            states: (0..length).map(PathState).collect::<Vec<_>>().into(),
+            actions: vec![PathAction::Next, PathAction::Prev].into(),
+            cyclic: true,
+            goals,
+            rewards: PathRewards::default(),
+            slip_back: 0.0,
+            slip_stay: 0.0,
+        }
+    }
+
+    /// Makes the states at `goals` the final ones, replacing the default (the last state of a
+    /// line, none on a ring). Arriving at any of them earns `PathRewards::goal`; indices at or
+    /// beyond `length()` are never reached.
+    pub fn with_goals(mut self, goals: impl IntoIterator<Item = usize>) -> Self {
+        self.goals = goals.into_iter().collect();
+        self.goals.sort_unstable();
+        self.goals.dedup();
+        self
+    }
+
+    /// Replaces the default rewards
+    pub fn with_rewards(mut self, rewards: PathRewards) -> Self {
+        self.rewards = rewards;
+        self
+    }
+
+    /// `Next` slips: with probability `back` it moves like `Prev`, with probability `stay` it
+    /// leaves the state unchanged, and it only advances with the remaining probability. Each
+    /// outcome earns the reward of the move actually made (`PathRewards::no_op` for staying).
+    /// Fails with `Error::ProbabilityOutOfRange` unless both are in `[0, 1]` and sum to at most 1.
+    pub fn with_slip(mut self, back: f64, stay: f64) -> Result<Self, Error> {
+        let in_range = |p: f64| (0.0..=1.0).contains(&p);
+        if !in_range(back) || !in_range(stay) || back + stay > 1.0 {
+            return Err(Error::ProbabilityOutOfRange);
         }
+        self.slip_back = back;
+        self.slip_stay = stay;
+        Ok(self)
+    }
+
+    /// The `(back, stay)` slip probabilities of `Next`
+    pub fn slip(&self) -> (f64, f64) {
+        (self.slip_back, self.slip_stay)
+    }
+
+    pub fn rewards(&self) -> &PathRewards {
+        &self.rewards
     }
-    // pub fn cyclic_new(length: usize) -> Self {
-    //     PathWorld { length, actions: vec![PathAction::Next, PathAction::Prev].into(), cyclic: true }
-    // }
+
     pub fn length(&self) -> usize {
         self.states.iter().count() // Compute from states
     }
+
+    pub fn is_cyclic(&self) -> bool {
+        self.cyclic
+    }
+
+    /// Indices of the goal states
+    pub fn goals(&self) -> &[usize] {
+        &self.goals
+    }
+
+    /// Where `action` leads from `current` without slipping, and its reward
+    fn step(&self, current: usize, action: &PathAction) -> (usize, f64) {
+        let length = self.length();
+        // Tentative position
+        let next = match (action, self.cyclic) {
+            (PathAction::Next, false) => current + 1,
+            (PathAction::Prev, false) => current.saturating_sub(1),
+            (PathAction::Next, true) => (current + 1) % length,
+            (PathAction::Prev, true) => (current + length - 1) % length,
+        };
+        // Out of bounds check
+        if next >= length || next == current {
+            return (current, self.rewards.no_op);
+        }
+        let reward = match action {
+            PathAction::Next => self.rewards.step,
+            PathAction::Prev => self.rewards.back_step,
+        };
+        if self.goals.contains(&next) {
+            (next, self.rewards.goal + reward)
+        } else {
+            (next, reward)
+        }
+    }
 }
 
 impl MDP for PathWorld {
@@ -57,10 +184,10 @@ impl MDP for PathWorld {
     }
 
     fn is_final_state(&self, state: &PathState) -> bool {
-        state.0 == self.length() - 1
+        self.goals.contains(&state.0)
     }
 
-    fn actions_at(&self, _state: &Self::State) -> Vec<Self::Action> {
+    fn all_actions(&self) -> Vec<Self::Action> {
         self.actions.iter().cloned().collect()
     }
 
@@ -71,25 +198,30 @@ impl MDP for PathWorld {
     ) -> Result<(Measure<Self::State>, f64), Error> {
         let current = state.0;
         let length = self.length();
-        // Tentative position
-        let next = match action {
-            Self::Action::Next => current + 1,
-            Self::Action::Prev => current.saturating_sub(1),
-        };
-        // Out of bounds check
-        if next >= length || next == current {
-            let measure = Measure::deterministic(state.clone());
-            return Ok((measure, NO_OP_TRANSITION_REWARD));
+        if current >= length {
+            return Err(Error::StateNotFound);
         }
-        let reward = match action {
-            Self::Action::Next => 0.1,
-            Self::Action::Prev => -0.5,
-        };
-        let measure = Measure::deterministic(PathState(next));
-        if next == length - 1 {
-            Ok((measure, END_TRANSITION_REWARD + reward))
-        } else {
-            Ok((measure, reward))
+        if !self.actions.iter().any(|a| a == action) {
+            return Err(Error::action_not_available(state, action));
+        }
+        if *action == PathAction::Prev || self.slip_back + self.slip_stay == 0.0 {
+            let (next, reward) = self.step(current, action);
+            return Ok((Measure::deterministic(PathState(next)), reward));
+        }
+        let outcomes = [
+            (
+                self.step(current, &PathAction::Next),
+                1.0 - self.slip_back - self.slip_stay,
+            ),
+            (self.step(current, &PathAction::Prev), self.slip_back),
+            ((current, self.rewards.no_op), self.slip_stay),
+        ];
+        let mut builder = MeasureBuilder::new();
+        let mut reward = 0.0;
+        for ((next, r), p) in outcomes {
+            builder = builder.add(PathState(next), p);
+            reward += p * r;
         }
+        Ok((builder.normalize()?, reward))
     }
 }
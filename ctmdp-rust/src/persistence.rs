@@ -0,0 +1,185 @@
+//! # Saving and Loading
+//!
+//! Learned artifacts written to and read back from disk, so components can be trained once and
+//! reused in many products. Anything `Serialize` can be stored as JSON (readable, diffable) or in
+//! a compact binary encoding; `QTable` is the serializable snapshot of a `madepro` `ActionValue`,
+//! and deterministic policies are stored as `(state, action)` pairs since product states are not
+//! valid JSON object keys.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::hash::Hash;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+use madepro::models::{Action, ActionValue, Sampler, State};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::policy::DeterministicPolicy;
+
+/// On-disk encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    /// `bincode`: smaller and faster, but not self-describing
+    Binary,
+}
+
+impl Format {
+    /// `Json` for paths ending in `.json`, `Binary` otherwise
+    pub fn from_path(path: impl AsRef<Path>) -> Format {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Format::Json,
+            _ => Format::Binary,
+        }
+    }
+}
+
+fn io_error(path: &Path, error: impl std::fmt::Display) -> Error {
+    Error::Io(format!("{}: {error}", path.display()))
+}
+
+/// Writes `value` to `path` in the given format, replacing any existing file
+pub fn save<T: Serialize + ?Sized>(
+    value: &T,
+    path: impl AsRef<Path>,
+    format: Format,
+) -> Result<(), Error> {
+    let path = path.as_ref();
+    let mut writer = BufWriter::new(File::create(path).map_err(|e| io_error(path, e))?);
+    match format {
+        Format::Json => serde_json::to_writer(&mut writer, value)
+            .map_err(|e| Error::Serialization(e.to_string()))?,
+        Format::Binary => bincode::serialize_into(&mut writer, value)
+            .map_err(|e| Error::Serialization(e.to_string()))?,
+    }
+    writer.flush().map_err(|e| io_error(path, e))
+}
+
+/// Reads a value written by `save` in the same format
+pub fn load<T: DeserializeOwned>(path: impl AsRef<Path>, format: Format) -> Result<T, Error> {
+    let path = path.as_ref();
+    let reader = BufReader::new(File::open(path).map_err(|e| io_error(path, e))?);
+    match format {
+        Format::Json => {
+            serde_json::from_reader(reader).map_err(|e| Error::Serialization(e.to_string()))
+        }
+        Format::Binary => {
+            bincode::deserialize_from(reader).map_err(|e| Error::Serialization(e.to_string()))
+        }
+    }
+}
+
+/// A serializable copy of an `ActionValue` table: one `(state, action, value)` entry per pair
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QTable<S, A> {
+    pub entries: Vec<(S, A, f64)>,
+}
+
+impl<S: State, A: Action> QTable<S, A> {
+    /// Reads `Q(s, a)` for every state in `states` and action in `actions`
+    pub fn from_action_value(
+        action_value: &ActionValue<S, A>,
+        states: &Sampler<S>,
+        actions: &Sampler<A>,
+    ) -> Self {
+        let entries = states
+            .iter()
+            .flat_map(|s| {
+                actions
+                    .iter()
+                    .map(move |a| (s.clone(), a.clone(), action_value.get(s, a)))
+            })
+            .collect();
+        QTable { entries }
+    }
+
+    /// # Restore
+    ///
+    /// A fresh `ActionValue` over `states` and `actions` (zero everywhere) overwritten with the
+    /// stored entries. Fails with `Error::StateNotFound` if an entry's state is not in `states`,
+    /// and with `Error::Serialization` if its action is not in `actions`.
+    pub fn to_action_value(
+        &self,
+        states: &Sampler<S>,
+        actions: &Sampler<A>,
+    ) -> Result<ActionValue<S, A>, Error> {
+        let known_states: HashSet<&S> = states.iter().collect();
+        let known_actions: HashSet<&A> = actions.iter().collect();
+        let mut action_value = ActionValue::new(states, actions);
+        for (state, action, value) in &self.entries {
+            if !known_states.contains(state) {
+                return Err(Error::StateNotFound);
+            }
+            if !known_actions.contains(action) {
+                return Err(Error::Serialization(
+                    "stored action is not in the action set".to_string(),
+                ));
+            }
+            action_value.insert(state, action, *value);
+        }
+        Ok(action_value)
+    }
+}
+
+/// Saves `Q(s, a)` for every state in `states` and action in `actions` (see `QTable`)
+pub fn save_action_value<S, A>(
+    action_value: &ActionValue<S, A>,
+    states: &Sampler<S>,
+    actions: &Sampler<A>,
+    path: impl AsRef<Path>,
+    format: Format,
+) -> Result<(), Error>
+where
+    S: State + Serialize,
+    A: Action + Serialize,
+{
+    save(
+        &QTable::from_action_value(action_value, states, actions),
+        path,
+        format,
+    )
+}
+
+/// Loads a table written by `save_action_value` into an `ActionValue` over `states` and `actions`
+pub fn load_action_value<S, A>(
+    path: impl AsRef<Path>,
+    format: Format,
+    states: &Sampler<S>,
+    actions: &Sampler<A>,
+) -> Result<ActionValue<S, A>, Error>
+where
+    S: State + DeserializeOwned,
+    A: Action + DeserializeOwned,
+{
+    load::<QTable<S, A>>(path, format)?.to_action_value(states, actions)
+}
+
+/// Saves a deterministic policy as a list of `(state, action)` pairs
+pub fn save_policy<S, A>(
+    policy: &DeterministicPolicy<S, A>,
+    path: impl AsRef<Path>,
+    format: Format,
+) -> Result<(), Error>
+where
+    S: Serialize,
+    A: Serialize,
+{
+    let pairs: Vec<(&S, &A)> = policy.iter().collect();
+    save(&pairs, path, format)
+}
+
+/// Loads a policy written by `save_policy`
+pub fn load_policy<S, A>(
+    path: impl AsRef<Path>,
+    format: Format,
+) -> Result<DeterministicPolicy<S, A>, Error>
+where
+    S: Eq + Hash + DeserializeOwned,
+    A: DeserializeOwned,
+{
+    let pairs: Vec<(S, A)> = load(path, format)?;
+    Ok(pairs.into_iter().collect())
+}
@@ -0,0 +1,146 @@
+//! # Planning
+//!
+//! Exact dynamic-programming solvers for MDPs whose transition measures are known.
+
+use std::collections::HashMap;
+
+use madepro::models::{Action, State};
+
+use crate::compiled::{CompiledMDP, compile};
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::policy::DeterministicPolicy;
+
+/// Optimal values and a greedy policy computed by `value_iteration`
+pub struct ValueIterationResult<S, A> {
+    pub values: HashMap<S, f64>,
+    pub policy: DeterministicPolicy<S, A>,
+    pub iterations: usize,
+    /// Whether the last sweep changed no value by more than the tolerance
+    pub converged: bool,
+}
+
+/// # Value Iteration
+///
+/// Computes optimal state values by synchronous Bellman optimality backups
+///
+/// `V(s) ← max_a [ r(s, a) + γ Σ_s' p(s' | s, a) V(s') ]`
+///
+/// until no value changes by more than `tolerance` or `max_iterations` sweeps have run.
+/// Final states and states without actions are worth zero. The returned policy is greedy with
+/// respect to the final values (first listed action wins ties).
+///
+/// Sweeps run on the `compile`d model, so a transition that leaves `all_states()` fails with
+/// `Error::StateNotFound`.
+pub fn value_iteration<M>(
+    mdp: &M,
+    discount_factor: f64,
+    tolerance: f64,
+    max_iterations: usize,
+) -> Result<ValueIterationResult<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let model = compile(mdp)?;
+    Ok(iterate_values(
+        &model,
+        discount_factor,
+        tolerance,
+        max_iterations,
+        |values, updated| {
+            for (state, value) in updated.iter_mut().enumerate() {
+                *value = optimal_backup(&model, state, values, discount_factor);
+            }
+        },
+    ))
+}
+
+/// `max_a [r(s, a) + γ Σ_s' p(s' | s, a) values[s']]` for the state with index `state`; zero for
+/// final states and states without actions
+pub(crate) fn optimal_backup<S, A>(
+    model: &CompiledMDP<S, A>,
+    state: usize,
+    values: &[f64],
+    discount_factor: f64,
+) -> f64 {
+    if model.is_final_index(state) {
+        return 0.0;
+    }
+    model
+        .choices(state)
+        .map(|choice| model.backup(choice, values, discount_factor))
+        .fold(None, |best: Option<f64>, v| Some(best.map_or(v, |b| b.max(v))))
+        .unwrap_or(0.0)
+}
+
+/// Runs value iteration on a compiled model, with `sweep(values, updated)` filling `updated` with
+/// one synchronous Bellman backup of every state
+pub(crate) fn iterate_values<S, A>(
+    model: &CompiledMDP<S, A>,
+    discount_factor: f64,
+    tolerance: f64,
+    max_iterations: usize,
+    sweep: impl Fn(&[f64], &mut [f64]),
+) -> ValueIterationResult<S, A>
+where
+    S: State,
+    A: Action,
+{
+    let num_states = model.num_states();
+    let mut values = vec![0.0; num_states];
+    let mut updated = vec![0.0; num_states];
+    let mut iterations = 0;
+    let mut converged = false;
+
+    while iterations < max_iterations {
+        iterations += 1;
+        sweep(&values, &mut updated);
+        let max_change = values
+            .iter()
+            .zip(&updated)
+            .map(|(old, new)| (new - old).abs())
+            .fold(0.0, f64::max);
+        std::mem::swap(&mut values, &mut updated);
+        if max_change <= tolerance {
+            converged = true;
+            break;
+        }
+    }
+
+    let policy = greedy_from_values(model, &values, discount_factor);
+    let states = model.state_index().items();
+    ValueIterationResult {
+        values: states.iter().cloned().zip(values).collect(),
+        policy,
+        iterations,
+        converged,
+    }
+}
+
+fn greedy_from_values<S: State, A: Action>(
+    model: &CompiledMDP<S, A>,
+    values: &[f64],
+    discount_factor: f64,
+) -> DeterministicPolicy<S, A> {
+    let states = model.state_index().items();
+    let actions = model.action_index().items();
+    let mut policy = HashMap::new();
+    for (state, name) in states.iter().enumerate() {
+        if model.is_final_index(state) {
+            continue;
+        }
+        let mut best: Option<(usize, f64)> = None;
+        for choice in model.choices(state) {
+            let value = model.backup(choice, values, discount_factor);
+            if best.is_none_or(|(_, best_value)| value > best_value) {
+                best = Some((choice, value));
+            }
+        }
+        if let Some((choice, _)) = best {
+            policy.insert(name.clone(), actions[model.choice_action(choice)].clone());
+        }
+    }
+    policy
+}
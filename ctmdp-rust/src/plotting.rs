@@ -0,0 +1,241 @@
+//! # Plotting
+//!
+//! Image exports on `plotters`, behind the `plotting` feature: value-function heatmaps of
+//! anything that implements `GridView`, and learning curves from the per-episode returns in
+//! `TrainingResult`. The format follows the file extension, `.svg` or `.png`.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::path::Path;
+
+use madepro::models::{Action, State};
+use plotters::coord::Shift;
+use plotters::prelude::*;
+
+use crate::error::Error;
+use crate::gridworld::GridPosition;
+use crate::q_learning::TrainingResult;
+use crate::render::GridView;
+
+/// Pixels per grid cell in heatmaps
+const CELL_PIXELS: u32 = 40;
+
+/// Size of learning-curve images
+const CURVE_SIZE: (u32, u32) = (800, 500);
+
+const WALL_COLOR: RGBColor = RGBColor(60, 60, 60);
+const LOW_COLOR: RGBColor = RGBColor(49, 54, 149);
+const HIGH_COLOR: RGBColor = RGBColor(215, 48, 39);
+
+/// Draws with the backend the extension of `path` asks for
+fn with_backend(
+    path: &Path,
+    size: (u32, u32),
+    draw_svg: impl FnOnce(DrawingArea<SVGBackend, Shift>) -> Result<(), String>,
+    draw_png: impl FnOnce(DrawingArea<BitMapBackend, Shift>) -> Result<(), String>,
+) -> Result<(), Error> {
+    let result = match path.extension().and_then(|e| e.to_str()) {
+        Some("svg") => draw_svg(SVGBackend::new(path, size).into_drawing_area()),
+        Some("png") => draw_png(BitMapBackend::new(path, size).into_drawing_area()),
+        _ => {
+            return Err(Error::Specification(format!(
+                "{}: expected a .svg or .png file",
+                path.display()
+            )));
+        }
+    };
+    result.map_err(|e| Error::Io(format!("{}: {e}", path.display())))
+}
+
+/// `LOW_COLOR` at `t = 0` to `HIGH_COLOR` at `t = 1`
+fn interpolate(t: f64) -> RGBColor {
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t.clamp(0.0, 1.0)).round() as u8;
+    RGBColor(
+        mix(LOW_COLOR.0, HIGH_COLOR.0),
+        mix(LOW_COLOR.1, HIGH_COLOR.1),
+        mix(LOW_COLOR.2, HIGH_COLOR.2),
+    )
+}
+
+/// # Plot Value Heatmap
+///
+/// One square per grid cell, colored from blue (lowest value) to red (highest). Walls are dark
+/// grey, cells without a value white; the caption gives the value range.
+///
+/// # Arguments
+/// * `view` - The grid, e.g. a `GridworldWithGoals` built from a map
+/// * `values` - State values, e.g. `ValueIterationResult::values`
+/// * `path` - Output file ending in `.svg` or `.png`
+/// * `title` - Caption above the grid
+///
+/// # Returns
+/// `Ok(())`, `Error::Specification` for another extension, or `Error::Io` if drawing fails
+pub fn plot_value_heatmap<V>(
+    view: &V,
+    values: &HashMap<V::State, f64>,
+    path: impl AsRef<Path>,
+    title: &str,
+) -> Result<(), Error>
+where
+    V: GridView,
+    V::State: Eq + Hash,
+{
+    let (rows, cols) = view.size();
+    if rows == 0 || cols == 0 {
+        return Err(Error::Specification(
+            "the grid has no known layout to plot".to_string(),
+        ));
+    }
+    let (min, max) = values
+        .values()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| {
+            (lo.min(v), hi.max(v))
+        });
+    let mut colors: HashMap<GridPosition, RGBColor> = HashMap::new();
+    for (state, &value) in values {
+        if let [position] = view.cells_of(state)[..] {
+            let t = if max > min {
+                (value - min) / (max - min)
+            } else {
+                1.0
+            };
+            colors.insert(position, interpolate(t));
+        }
+    }
+    let caption = if values.is_empty() {
+        title.to_string()
+    } else {
+        format!("{title} ({min:.2} .. {max:.2})")
+    };
+    let color_at = |row: usize, col: usize| {
+        let position = GridPosition::new(row, col);
+        match colors.get(&position) {
+            Some(color) => *color,
+            None if view.background(position) == '#' => WALL_COLOR,
+            None => WHITE,
+        }
+    };
+    let size = (cols as u32 * CELL_PIXELS, rows as u32 * CELL_PIXELS + 40);
+    with_backend(
+        path.as_ref(),
+        size,
+        |root| draw_heatmap(root, rows, cols, &caption, color_at),
+        |root| draw_heatmap(root, rows, cols, &caption, color_at),
+    )
+}
+
+fn draw_heatmap<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    rows: usize,
+    cols: usize,
+    caption: &str,
+    color_at: impl Fn(usize, usize) -> RGBColor,
+) -> Result<(), String> {
+    root.fill(&WHITE).map_err(|e| e.to_string())?;
+    let grid = root
+        .titled(caption, ("sans-serif", 20))
+        .map_err(|e| e.to_string())?;
+    for (i, cell) in grid.split_evenly((rows, cols)).iter().enumerate() {
+        cell.fill(&color_at(i / cols, i % cols))
+            .map_err(|e| e.to_string())?;
+    }
+    root.present().map_err(|e| e.to_string())
+}
+
+/// The mean of each window of `window` consecutive values, ending at each index (shorter at
+/// the start)
+pub fn moving_average(values: &[f64], window: usize) -> Vec<f64> {
+    let window = window.max(1);
+    let mut sum = 0.0;
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| {
+            sum += v;
+            if i >= window {
+                sum -= values[i - window];
+            }
+            sum / (i + 1).min(window) as f64
+        })
+        .collect()
+}
+
+/// # Plot Learning Curves
+///
+/// The return of every training episode, smoothed by a moving average over `window` episodes,
+/// with one labelled line per result (e.g. SARSA against Q-Learning on the same task).
+///
+/// # Arguments
+/// * `results` - `(label, result)` pairs from `sarsa_with_history`/`q_learning_with_history`
+/// * `window` - Episodes per moving-average window (`1` plots raw returns)
+/// * `path` - Output file ending in `.svg` or `.png`
+/// * `title` - Caption above the chart
+///
+/// # Returns
+/// `Ok(())`, `Error::Specification` for another extension or no episodes, or `Error::Io` if
+/// drawing fails
+pub fn plot_learning_curves<S: State, A: Action>(
+    results: &[(&str, &TrainingResult<S, A>)],
+    window: usize,
+    path: impl AsRef<Path>,
+    title: &str,
+) -> Result<(), Error> {
+    let curves: Vec<(&str, Vec<f64>)> = results
+        .iter()
+        .map(|(label, result)| (*label, moving_average(&result.episode_returns, window)))
+        .collect();
+    let episodes = curves.iter().map(|(_, c)| c.len()).max().unwrap_or(0);
+    if episodes == 0 {
+        return Err(Error::Specification("no episodes to plot".to_string()));
+    }
+    with_backend(
+        path.as_ref(),
+        CURVE_SIZE,
+        |root| draw_curves(root, &curves, episodes, title),
+        |root| draw_curves(root, &curves, episodes, title),
+    )
+}
+
+fn draw_curves<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    curves: &[(&str, Vec<f64>)],
+    episodes: usize,
+    title: &str,
+) -> Result<(), String> {
+    let (lo, hi) = curves
+        .iter()
+        .flat_map(|(_, c)| c.iter().copied())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+            (lo.min(v), hi.max(v))
+        });
+    let margin = ((hi - lo) * 0.05).max(1e-6);
+    root.fill(&WHITE).map_err(|e| e.to_string())?;
+    let mut chart = ChartBuilder::on(&root)
+        .caption(title, ("sans-serif", 24))
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(0..episodes, (lo - margin)..(hi + margin))
+        .map_err(|e| e.to_string())?;
+    chart
+        .configure_mesh()
+        .x_desc("episode")
+        .y_desc("return")
+        .draw()
+        .map_err(|e| e.to_string())?;
+    for (i, (label, curve)) in curves.iter().enumerate() {
+        let color = Palette99::pick(i).to_rgba();
+        chart
+            .draw_series(LineSeries::new(curve.iter().copied().enumerate(), color))
+            .map_err(|e| e.to_string())?
+            .label(*label)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()
+        .map_err(|e| e.to_string())?;
+    root.present().map_err(|e| e.to_string())
+}
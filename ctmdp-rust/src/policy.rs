@@ -0,0 +1,164 @@
+//! # Policies
+//!
+//! Policy representations shared by the learners and the experiments, and helpers for extracting
+//! them from learned `ActionValue` tables.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use madepro::models::ActionValue;
+
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::measure::{Measure, MeasureBuilder};
+
+/// A policy choosing a single action per state
+pub type DeterministicPolicy<S, A> = HashMap<S, A>;
+
+/// A policy choosing actions according to a probability measure per state
+pub type StochasticPolicy<S, A> = HashMap<S, Measure<A>>;
+
+/// Anything that picks an action in a state. Returns `None` where the policy is undefined
+/// (e.g. states a tabular policy never saw).
+pub trait Policy<S, A> {
+    fn action(&self, state: &S) -> Option<A>;
+}
+
+impl<S: Eq + Hash, A: Clone> Policy<S, A> for DeterministicPolicy<S, A> {
+    fn action(&self, state: &S) -> Option<A> {
+        self.get(state).cloned()
+    }
+}
+
+/// Samples from the state's measure
+impl<S: Eq + Hash, A: Eq + Hash + Clone> Policy<S, A> for StochasticPolicy<S, A> {
+    fn action(&self, state: &S) -> Option<A> {
+        self.get(state).and_then(|measure| measure.sample().cloned())
+    }
+}
+
+impl<S, A, P: Policy<S, A> + ?Sized> Policy<S, A> for &P {
+    fn action(&self, state: &S) -> Option<A> {
+        (**self).action(state)
+    }
+}
+
+/// The table of `policy`'s choices at every non-final state of `mdp` where it picks an action,
+/// e.g. to compare a lifted product policy with a learned one
+pub fn tabulate<M, P>(mdp: &M, policy: &P) -> DeterministicPolicy<M::State, M::Action>
+where
+    M: MDP,
+    M::State: Clone,
+    P: Policy<M::State, M::Action> + ?Sized,
+{
+    mdp.all_states()
+        .iter()
+        .filter(|state| !mdp.is_final_state(state))
+        .filter_map(|state| Some((state.clone(), policy.action(state)?)))
+        .collect()
+}
+
+/// The stochastic policy that puts all its mass on the deterministic policy's action
+pub fn to_stochastic<S, A>(policy: &DeterministicPolicy<S, A>) -> StochasticPolicy<S, A>
+where
+    S: Eq + Hash + Clone,
+    A: Eq + Hash + Clone,
+{
+    policy
+        .iter()
+        .map(|(s, a)| (s.clone(), Measure::deterministic(a.clone())))
+        .collect()
+}
+
+/// Extracts the greedy policy over the actions available at each state.
+/// Ties are broken in favor of the action listed first by `actions_at`.
+pub fn greedy_policy<M>(
+    mdp: &M,
+    q_values: &ActionValue<M::State, M::Action>,
+) -> DeterministicPolicy<M::State, M::Action>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let mut policy = HashMap::new();
+
+    for state in mdp.all_states().iter() {
+        let actions = mdp.actions_at(state);
+        if actions.is_empty() {
+            continue;
+        }
+
+        let mut best_action = actions[0].clone();
+        let mut best_value = q_values.get(state, &best_action);
+
+        for action in actions.into_iter().skip(1) {
+            let value = q_values.get(state, &action);
+            if value > best_value {
+                best_value = value;
+                best_action = action;
+            }
+        }
+
+        policy.insert(state.clone(), best_action);
+    }
+
+    policy
+}
+
+/// Extracts the Boltzmann (softmax) policy `π(a|s) ∝ exp(Q(s, a) / temperature)` over the
+/// actions available at each state.
+pub fn boltzmann_policy<M>(
+    mdp: &M,
+    q_values: &ActionValue<M::State, M::Action>,
+    temperature: f64,
+) -> Result<StochasticPolicy<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let mut policy = HashMap::new();
+
+    for state in mdp.all_states().iter() {
+        let actions = mdp.actions_at(state);
+        if actions.is_empty() {
+            continue;
+        }
+        let measure = boltzmann_measure(actions, |a| q_values.get(state, a), temperature)?;
+        policy.insert(state.clone(), measure);
+    }
+
+    Ok(policy)
+}
+
+/// `temperature * log Σ_a exp(value(a) / temperature)`, computed stably by factoring out the maximum.
+/// Returns `0.0` for an empty action set.
+pub(crate) fn soft_maximum<A>(actions: &[A], value: impl Fn(&A) -> f64, temperature: f64) -> f64 {
+    let values: Vec<f64> = actions.iter().map(|a| value(a) / temperature).collect();
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY {
+        return 0.0;
+    }
+    let sum: f64 = values.iter().map(|v| (v - max).exp()).sum();
+    temperature * (max + sum.ln())
+}
+
+/// Builds the softmax measure over `actions` with weights `exp(value(a) / temperature)`
+pub(crate) fn boltzmann_measure<A>(
+    actions: Vec<A>,
+    value: impl Fn(&A) -> f64,
+    temperature: f64,
+) -> Result<Measure<A>, Error>
+where
+    A: Eq + Hash,
+{
+    let values: Vec<f64> = actions.iter().map(|a| value(a) / temperature).collect();
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let weights: Vec<f64> = values.iter().map(|v| (v - max).exp()).collect();
+    actions
+        .into_iter()
+        .zip(weights)
+        .collect::<MeasureBuilder<A>>()
+        .normalize()
+}
@@ -1,8 +1,11 @@
 use crate::measure::Measure;
 use crate::{mdp::MDP, measure::Probability};
 use crate::error::Error;
+use crate::policy::{DeterministicPolicy, Policy};
 use madepro::models::{Action, Sampler, State};
-use std::{collections::HashMap, hash::Hash};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::{cell::{OnceCell, RefCell}, collections::HashMap, hash::Hash};
 
 #[derive(Debug)]
 pub struct BoxProduct<M1: MDP, M2: MDP>
@@ -16,18 +19,21 @@ where
 }
 
 #[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BoxAction<A1, A2> {
     Left(A1),
     Right(A2),
 }
 
 #[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CartesianAction<A1, A2> {
     action1: A1,
     action2: A2,
 }
 
 #[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Product<S1, S2> {
     fst: S1,
     snd: S2,
@@ -45,6 +51,61 @@ impl<S1, S2> Product<S1, S2> {
     pub fn second(&self) -> &S2 {
         &self.snd
     }
+
+    pub fn into_parts(self) -> (S1, S2) {
+        (self.fst, self.snd)
+    }
+}
+
+impl<A1, A2> BoxAction<A1, A2> {
+    /// The first component's action, if this action moves the first component
+    pub fn project_left(&self) -> Option<&A1> {
+        match self {
+            BoxAction::Left(a1) => Some(a1),
+            BoxAction::Right(_) => None,
+        }
+    }
+
+    /// The second component's action, if this action moves the second component
+    pub fn project_right(&self) -> Option<&A2> {
+        match self {
+            BoxAction::Left(_) => None,
+            BoxAction::Right(a2) => Some(a2),
+        }
+    }
+}
+
+/// One step of a `BoxProduct` trajectory: the state and the action taken in it
+pub type BoxStep<S1, S2, A1, A2> = (Product<S1, S2>, BoxAction<A1, A2>);
+
+/// The first component of every state in a trajectory
+pub fn project_left<S1: Clone, S2>(states: &[Product<S1, S2>]) -> Vec<S1> {
+    states.iter().map(|s| s.fst.clone()).collect()
+}
+
+/// The second component of every state in a trajectory
+pub fn project_right<S1, S2: Clone>(states: &[Product<S1, S2>]) -> Vec<S2> {
+    states.iter().map(|s| s.snd.clone()).collect()
+}
+
+/// The steps of a `BoxProduct` trajectory that moved the first component, as that component saw them
+pub fn left_moves<S1: Clone, S2, A1: Clone, A2>(
+    trajectory: &[BoxStep<S1, S2, A1, A2>],
+) -> Vec<(S1, A1)> {
+    trajectory
+        .iter()
+        .filter_map(|(s, a)| a.project_left().map(|a1| (s.fst.clone(), a1.clone())))
+        .collect()
+}
+
+/// The steps of a `BoxProduct` trajectory that moved the second component, as that component saw them
+pub fn right_moves<S1, S2: Clone, A1, A2: Clone>(
+    trajectory: &[BoxStep<S1, S2, A1, A2>],
+) -> Vec<(S2, A2)> {
+    trajectory
+        .iter()
+        .filter_map(|(s, a)| a.project_right().map(|a2| (s.snd.clone(), a2.clone())))
+        .collect()
 }
 
 impl<S1: State, S2: State> State for Product<S1, S2> {}
@@ -69,12 +130,21 @@ where
 
         BoxProduct { mdp1, mdp2, states }
     }
+
+    pub fn components(&self) -> (&M1, &M2) {
+        (&self.mdp1, &self.mdp2)
+    }
+
+    pub fn into_components(self) -> (M1, M2) {
+        (self.mdp1, self.mdp2)
+    }
 }
 
 #[derive(Debug)]
 pub struct CartesianProduct<M1:MDP, M2:MDP> {
     mdp1: M1,
     mdp2: M2,
+    combiner: RewardCombiner,
     states: Sampler<Product<M1::State, M2::State>>,
 }
 
@@ -112,6 +182,12 @@ where
         [left_actions, right_actions].concat()
     }
 
+    fn all_actions(&self) -> Vec<Self::Action> {
+        self.mdp1.all_actions().into_iter().map(BoxAction::Left)
+            .chain(self.mdp2.all_actions().into_iter().map(BoxAction::Right))
+            .collect()
+    }
+
     fn is_final_state(&self, state: &Self::State) -> bool {
         self.mdp1.is_final_state(&state.fst) && self.mdp2.is_final_state(&state.snd)
     }
@@ -125,25 +201,17 @@ where
             BoxAction::Left(a1) => {
                 let (measure1, prob1) = self.mdp1.stochastic_transition(&state.fst, a1)?;
                 let measure2 = Measure::deterministic(state.snd.clone());
-                let product_dist: HashMap<Product<M1::State, M2::State>, Probability> = measure1.product(&measure2)
-                    .unwrap()
-                    .dist()
-                    .iter()
-                    .map(|((s1, s2), prob)| (Product::new(s1.clone(), s2.clone()), *prob))
-                    .collect();
-                let product_measure = Measure::from_distribution(product_dist)?;
+                let product_measure = measure1
+                    .product(&measure2)?
+                    .map(|(s1, s2)| Product::new(s1, s2));
                 Ok((product_measure, prob1))
             }
             BoxAction::Right(a2) => {
                 let (measure2, prob2) = self.mdp2.stochastic_transition(&state.snd, a2)?;
                 let measure1 = Measure::deterministic(state.fst.clone());
-                let product_dist: HashMap<Product<M1::State, M2::State>, Probability> = measure1.product(&measure2)
-                    .unwrap()
-                    .dist()
-                    .iter()
-                    .map(|((s1, s2), prob)| (Product::new(s1.clone(), s2.clone()), *prob))
-                    .collect();
-                let product_measure = Measure::from_distribution(product_dist)?;
+                let product_measure = measure1
+                    .product(&measure2)?
+                    .map(|(s1, s2)| Product::new(s1, s2));
                 Ok((product_measure, prob2))
             }
         }
@@ -156,7 +224,12 @@ where
     M1::State: Clone,
     M2::State: Clone,
 {
+    /// Sums the component rewards
     pub fn new(mdp1: M1, mdp2: M2) -> Self {
+        Self::with_combiner(mdp1, mdp2, RewardCombiner::Sum)
+    }
+
+    pub fn with_combiner(mdp1: M1, mdp2: M2, combiner: RewardCombiner) -> Self {
         let mut states = Vec::new();
 
         for s1 in mdp1.all_states().iter() {
@@ -166,8 +239,31 @@ where
         }
         let states = Sampler::new(states);
 
-        CartesianProduct { mdp1, mdp2, states }
+        CartesianProduct { mdp1, mdp2, combiner, states }
+    }
+
+    pub fn combiner(&self) -> RewardCombiner {
+        self.combiner
+    }
+
+    pub fn components(&self) -> (&M1, &M2) {
+        (&self.mdp1, &self.mdp2)
+    }
+
+    pub fn into_components(self) -> (M1, M2) {
+        (self.mdp1, self.mdp2)
+    }
+}
+
+/// Every pair of an action from `actions1` with one from `actions2`
+fn joint_actions<A1: Clone, A2: Clone>(actions1: Vec<A1>, actions2: &[A2]) -> Vec<Product<A1, A2>> {
+    let mut out = Vec::with_capacity(actions1.len() * actions2.len());
+    for a1 in actions1 {
+        for a2 in actions2 {
+            out.push(Product::new(a1.clone(), a2.clone()));
+        }
     }
+    out
 }
 
 impl<M1, M2> MDP for CartesianProduct<M1, M2>
@@ -186,16 +282,524 @@ where
     }
 
     fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
-        let actions1 = self.mdp1.actions_at(&state.fst);
+        joint_actions(
+            self.mdp1.actions_at(&state.fst),
+            &self.mdp2.actions_at(&state.snd),
+        )
+    }
+
+    fn all_actions(&self) -> Vec<Self::Action> {
+        joint_actions(self.mdp1.all_actions(), &self.mdp2.all_actions())
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.mdp1.is_final_state(&state.fst) && self.mdp2.is_final_state(&state.snd)
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        // product field is `.fst` and `.snd`
+        let (m1, r1) = self.mdp1.stochastic_transition(&state.fst, &action.fst)?;
+        let (m2, r2) = self.mdp2.stochastic_transition(&state.snd, &action.snd)?;
+
+        let joint = m1.product(&m2)?.map(|(s1, s2)| Product::new(s1, s2));
+
+        Ok((joint, self.combiner.combine(r1, r2)))
+    }
+
+}
+
+/// A value from one of two sides, used for the states and actions of a `Coproduct`
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Either<L, R> {
+    Left(L),
+    Right(R),
+}
+
+impl<L: State, R: State> State for Either<L, R> {}
+
+impl<L: Action, R: Action> Action for Either<L, R> {}
+
+/// Disjoint union of two MDPs: every state belongs to exactly one component, which supplies its
+/// actions and dynamics. The two components never interact.
+#[derive(Debug)]
+pub struct Coproduct<M1: MDP, M2: MDP> {
+    mdp1: M1,
+    mdp2: M2,
+    states: Sampler<Either<M1::State, M2::State>>,
+}
+
+impl<M1, M2> Coproduct<M1, M2>
+where
+    M1: MDP,
+    M2: MDP,
+    M1::State: Clone,
+    M2::State: Clone,
+{
+    pub fn new(mdp1: M1, mdp2: M2) -> Self {
+        let mut states = Vec::new();
+        for s1 in mdp1.all_states().iter() {
+            states.push(Either::Left(s1.clone()));
+        }
+        for s2 in mdp2.all_states().iter() {
+            states.push(Either::Right(s2.clone()));
+        }
+        let states = Sampler::new(states);
+
+        Coproduct { mdp1, mdp2, states }
+    }
+
+    pub fn left(&self) -> &M1 {
+        &self.mdp1
+    }
+
+    pub fn right(&self) -> &M2 {
+        &self.mdp2
+    }
+
+    pub fn components(&self) -> (&M1, &M2) {
+        (&self.mdp1, &self.mdp2)
+    }
+
+    pub fn into_components(self) -> (M1, M2) {
+        (self.mdp1, self.mdp2)
+    }
+}
+
+impl<M1, M2> MDP for Coproduct<M1, M2>
+where
+    M1: MDP,
+    M2: MDP,
+    M1::State: Clone,
+    M2::State: Clone,
+    M1::Action: Clone,
+    M2::Action: Clone,
+{
+    type State = Either<M1::State, M2::State>;
+    type Action = Either<M1::Action, M2::Action>;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        &self.states
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        match state {
+            Either::Left(s1) => self.mdp1.actions_at(s1).into_iter().map(Either::Left).collect(),
+            Either::Right(s2) => self.mdp2.actions_at(s2).into_iter().map(Either::Right).collect(),
+        }
+    }
+
+    fn all_actions(&self) -> Vec<Self::Action> {
+        self.mdp1.all_actions().into_iter().map(Either::Left)
+            .chain(self.mdp2.all_actions().into_iter().map(Either::Right))
+            .collect()
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        match state {
+            Either::Left(s1) => self.mdp1.is_final_state(s1),
+            Either::Right(s2) => self.mdp2.is_final_state(s2),
+        }
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        match state {
+            Either::Left(s1) => self.mdp1.is_goal(s1),
+            Either::Right(s2) => self.mdp2.is_goal(s2),
+        }
+    }
+
+    /// Actions from the other component are not available and yield `Error::UndefinedTransition`
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        match (state, action) {
+            (Either::Left(s1), Either::Left(a1)) => {
+                let (measure, reward) = self.mdp1.stochastic_transition(s1, a1)?;
+                Ok((measure.map(Either::Left), reward))
+            }
+            (Either::Right(s2), Either::Right(a2)) => {
+                let (measure, reward) = self.mdp2.stochastic_transition(s2, a2)?;
+                Ok((measure.map(Either::Right), reward))
+            }
+            _ => Err(Error::UndefinedTransition),
+        }
+    }
+}
+
+/// Weight of the first reward under `RewardCombiner::Lexicographic`
+pub const LEXICOGRAPHIC_SCALE: f64 = 1e6;
+
+/// How the rewards of two simultaneously moving components are combined into one
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RewardCombiner {
+    #[default]
+    Sum,
+    WeightedSum(f64, f64),
+    Max,
+    Min,
+    /// `LEXICOGRAPHIC_SCALE * r1 + r2`: the second reward only breaks ties in the first, as long
+    /// as its magnitude stays well below the scale
+    Lexicographic,
+}
+
+impl RewardCombiner {
+    pub fn combine(&self, r1: f64, r2: f64) -> f64 {
+        match *self {
+            RewardCombiner::Sum => r1 + r2,
+            RewardCombiner::WeightedSum(w1, w2) => w1 * r1 + w2 * r2,
+            RewardCombiner::Max => r1.max(r2),
+            RewardCombiner::Min => r1.min(r2),
+            RewardCombiner::Lexicographic => LEXICOGRAPHIC_SCALE * r1 + r2,
+        }
+    }
+}
+
+/// Synchronous product of two MDPs over a shared action type: one action moves both components
+/// at once, so only actions available in both component states are available. Next states are
+/// independent and rewards are merged by a `RewardCombiner`.
+#[derive(Debug)]
+pub struct SyncProduct<M1: MDP, M2: MDP> {
+    mdp1: M1,
+    mdp2: M2,
+    combiner: RewardCombiner,
+    states: Sampler<Product<M1::State, M2::State>>,
+}
+
+impl<M1, M2> SyncProduct<M1, M2>
+where
+    M1: MDP,
+    M2: MDP<Action = M1::Action>,
+    M1::State: Clone,
+    M2::State: Clone,
+{
+    /// Sums the component rewards
+    pub fn new(mdp1: M1, mdp2: M2) -> Self {
+        Self::with_combiner(mdp1, mdp2, RewardCombiner::Sum)
+    }
+
+    pub fn with_combiner(mdp1: M1, mdp2: M2, combiner: RewardCombiner) -> Self {
+        let mut states = Vec::new();
+        for s1 in mdp1.all_states().iter() {
+            for s2 in mdp2.all_states().iter() {
+                states.push(Product::new(s1.clone(), s2.clone()));
+            }
+        }
+        let states = Sampler::new(states);
+
+        SyncProduct { mdp1, mdp2, combiner, states }
+    }
+
+    pub fn combiner(&self) -> RewardCombiner {
+        self.combiner
+    }
+
+    pub fn components(&self) -> (&M1, &M2) {
+        (&self.mdp1, &self.mdp2)
+    }
+
+    pub fn into_components(self) -> (M1, M2) {
+        (self.mdp1, self.mdp2)
+    }
+}
+
+impl<M1, M2> MDP for SyncProduct<M1, M2>
+where
+    M1: MDP,
+    M2: MDP<Action = M1::Action>,
+    M1::State: Clone,
+    M2::State: Clone,
+    M1::Action: Clone,
+{
+    type State = Product<M1::State, M2::State>;
+    type Action = M1::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        &self.states
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
         let actions2 = self.mdp2.actions_at(&state.snd);
+        self.mdp1
+            .actions_at(&state.fst)
+            .into_iter()
+            .filter(|a| actions2.contains(a))
+            .collect()
+    }
+
+    fn all_actions(&self) -> Vec<Self::Action> {
+        let actions2 = self.mdp2.all_actions();
+        self.mdp1
+            .all_actions()
+            .into_iter()
+            .filter(|a| actions2.contains(a))
+            .collect()
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.mdp1.is_final_state(&state.fst) && self.mdp2.is_final_state(&state.snd)
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        let (m1, r1) = self.mdp1.stochastic_transition(&state.fst, action)?;
+        let (m2, r2) = self.mdp2.stochastic_transition(&state.snd, action)?;
+
+        let joint = m1.product(&m2)?.map(|(s1, s2)| Product::new(s1, s2));
+
+        Ok((joint, self.combiner.combine(r1, r2)))
+    }
+}
+
+/// Which component of a `ScheduledBoxProduct` (or a round-robin lifted policy) moves next
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Turn {
+    First,
+    Second,
+}
+
+impl Turn {
+    pub fn other(&self) -> Turn {
+        match self {
+            Turn::First => Turn::Second,
+            Turn::Second => Turn::First,
+        }
+    }
+}
+
+/// How turns are assigned in a `ScheduledBoxProduct`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Schedule {
+    /// The components take turns, one step each
+    Alternating,
+    /// After every step a coin with the given probability of `Turn::First` picks the next mover
+    RandomTurn(Probability),
+}
+
+/// A product state together with the component whose turn it is
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScheduledState<S1, S2> {
+    state: Product<S1, S2>,
+    turn: Turn,
+}
+
+impl<S1, S2> ScheduledState<S1, S2> {
+    pub fn new(state: Product<S1, S2>, turn: Turn) -> Self {
+        ScheduledState { state, turn }
+    }
+
+    pub fn state(&self) -> &Product<S1, S2> {
+        &self.state
+    }
 
-        let mut out = Vec::with_capacity(actions1.len() * actions2.len());
-        for a1 in actions1 {
-            for a2 in actions2.iter() {
-                out.push(Product::new(a1.clone(), a2.clone()));
+    pub fn turn(&self) -> Turn {
+        self.turn
+    }
+}
+
+impl<S1: State, S2: State> State for ScheduledState<S1, S2> {}
+
+/// A `BoxProduct` where the scheduler, not the agent, decides which component advances: only the
+/// active component's actions are available, and the turn is part of the state. Once a component
+/// has reached a final state the other one moves regardless of the turn.
+#[derive(Debug)]
+pub struct ScheduledBoxProduct<M1: MDP, M2: MDP> {
+    mdp1: M1,
+    mdp2: M2,
+    schedule: Schedule,
+    states: Sampler<ScheduledState<M1::State, M2::State>>,
+}
+
+impl<M1, M2> ScheduledBoxProduct<M1, M2>
+where
+    M1: MDP,
+    M2: MDP,
+    M1::State: Clone,
+    M2::State: Clone,
+{
+    pub fn new(mdp1: M1, mdp2: M2, schedule: Schedule) -> Self {
+        let mut states = Vec::new();
+        for s1 in mdp1.all_states().iter() {
+            for s2 in mdp2.all_states().iter() {
+                for turn in [Turn::First, Turn::Second] {
+                    states.push(ScheduledState::new(Product::new(s1.clone(), s2.clone()), turn));
+                }
             }
         }
-        out
+        let states = Sampler::new(states);
+
+        ScheduledBoxProduct { mdp1, mdp2, schedule, states }
+    }
+
+    pub fn schedule(&self) -> Schedule {
+        self.schedule
+    }
+
+    pub fn components(&self) -> (&M1, &M2) {
+        (&self.mdp1, &self.mdp2)
+    }
+
+    pub fn into_components(self) -> (M1, M2) {
+        (self.mdp1, self.mdp2)
+    }
+
+    /// The component that actually moves in `state`
+    fn mover(&self, state: &ScheduledState<M1::State, M2::State>) -> Turn {
+        let finished = match state.turn {
+            Turn::First => self.mdp1.is_final_state(&state.state.fst),
+            Turn::Second => self.mdp2.is_final_state(&state.state.snd),
+        };
+        if finished { state.turn.other() } else { state.turn }
+    }
+
+    fn next_turns(&self, mover: Turn) -> Result<Measure<Turn>, Error> {
+        match self.schedule {
+            Schedule::Alternating => Ok(Measure::deterministic(mover.other())),
+            Schedule::RandomTurn(p) => Measure::bernoulli(Turn::First, Turn::Second, p.value()),
+        }
+    }
+}
+
+impl<M1, M2> MDP for ScheduledBoxProduct<M1, M2>
+where
+    M1: MDP,
+    M2: MDP,
+    M1::State: Clone,
+    M2::State: Clone,
+    M1::Action: Clone,
+    M2::Action: Clone,
+{
+    type State = ScheduledState<M1::State, M2::State>;
+    type Action = BoxAction<M1::Action, M2::Action>;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        &self.states
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        match self.mover(state) {
+            Turn::First => self.mdp1.actions_at(&state.state.fst).into_iter().map(BoxAction::Left).collect(),
+            Turn::Second => self.mdp2.actions_at(&state.state.snd).into_iter().map(BoxAction::Right).collect(),
+        }
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.mdp1.is_final_state(&state.state.fst) && self.mdp2.is_final_state(&state.state.snd)
+    }
+
+    /// Actions of the component that is not moving yield `Error::UndefinedTransition`
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        let mover = self.mover(state);
+        let (moved, reward) = match (mover, action) {
+            (Turn::First, BoxAction::Left(a1)) => {
+                let (measure1, reward) = self.mdp1.stochastic_transition(&state.state.fst, a1)?;
+                (measure1.map(|s1| Product::new(s1, state.state.snd.clone())), reward)
+            }
+            (Turn::Second, BoxAction::Right(a2)) => {
+                let (measure2, reward) = self.mdp2.stochastic_transition(&state.state.snd, a2)?;
+                (measure2.map(|s2| Product::new(state.state.fst.clone(), s2)), reward)
+            }
+            _ => return Err(Error::UndefinedTransition),
+        };
+
+        let next = moved
+            .product(&self.next_turns(mover)?)?
+            .map(|(s, turn)| ScheduledState::new(s, turn));
+        Ok((next, reward))
+    }
+}
+
+/// A `CartesianProduct` that never enumerates its state space up front. `states()` streams the
+/// product of the component streams, and `all_states()` only materializes it on first use, so
+/// nesting `LazyProduct`s over many components stores at most the outermost state space rather
+/// than one copy per level.
+#[derive(Debug)]
+pub struct LazyProduct<M1: MDP, M2: MDP> {
+    mdp1: M1,
+    mdp2: M2,
+    combiner: RewardCombiner,
+    states: OnceCell<Sampler<Product<M1::State, M2::State>>>,
+}
+
+impl<M1, M2> LazyProduct<M1, M2>
+where
+    M1: MDP,
+    M2: MDP,
+{
+    /// Sums the component rewards
+    pub fn new(mdp1: M1, mdp2: M2) -> Self {
+        Self::with_combiner(mdp1, mdp2, RewardCombiner::Sum)
+    }
+
+    pub fn with_combiner(mdp1: M1, mdp2: M2, combiner: RewardCombiner) -> Self {
+        LazyProduct { mdp1, mdp2, combiner, states: OnceCell::new() }
+    }
+
+    pub fn combiner(&self) -> RewardCombiner {
+        self.combiner
+    }
+
+    /// Whether `all_states()` has been materialized yet
+    pub fn is_materialized(&self) -> bool {
+        self.states.get().is_some()
+    }
+
+    pub fn components(&self) -> (&M1, &M2) {
+        (&self.mdp1, &self.mdp2)
+    }
+
+    pub fn into_components(self) -> (M1, M2) {
+        (self.mdp1, self.mdp2)
+    }
+}
+
+impl<M1, M2> MDP for LazyProduct<M1, M2>
+where
+    M1: MDP,
+    M2: MDP,
+    M1::State: Clone,
+    M2::State: Clone,
+    M1::Action: Clone,
+    M2::Action: Clone,
+{
+    type State = Product<M1::State, M2::State>;
+    type Action = Product<M1::Action, M2::Action>;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        self.states
+            .get_or_init(|| Sampler::new(self.states().collect()))
+    }
+
+    fn states(&self) -> Box<dyn Iterator<Item = Self::State> + '_> {
+        Box::new(self.mdp1.states().flat_map(move |s1| {
+            self.mdp2.states().map(move |s2| Product::new(s1.clone(), s2))
+        }))
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        joint_actions(
+            self.mdp1.actions_at(&state.fst),
+            &self.mdp2.actions_at(&state.snd),
+        )
+    }
+
+    fn all_actions(&self) -> Vec<Self::Action> {
+        joint_actions(self.mdp1.all_actions(), &self.mdp2.all_actions())
     }
 
     fn is_final_state(&self, state: &Self::State) -> bool {
@@ -207,18 +811,323 @@ where
         state: &Self::State,
         action: &Self::Action,
     ) -> Result<(Measure<Self::State>, f64), Error> {
-        // product field is `.fst` and `.snd`
         let (m1, r1) = self.mdp1.stochastic_transition(&state.fst, &action.fst)?;
         let (m2, r2) = self.mdp2.stochastic_transition(&state.snd, &action.snd)?;
 
-        let joint = m1.product(&m2)?;
-        let dist = joint
-            .dist()
-            .iter()
-            .map(|((s1, s2), p)| (Product::new(s1.clone(), s2.clone()), *p))
-            .collect();
+        let joint = m1.product(&m2)?.map(|(s1, s2)| Product::new(s1, s2));
+
+        Ok((joint, self.combiner.combine(r1, r2)))
+    }
+}
+
+/// How a lifted `BoxProduct` policy decides which component to advance
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scheduler {
+    /// Alternate between the components, skipping one that is done. The turn is threaded by the
+    /// caller through `LiftedBoxPolicy::action_on_turn`
+    RoundRobin,
+    /// Advance the first component until it is done, then the second
+    FinishFirst,
+    /// Pick a component at random with probability proportional to its weight
+    PriorityWeighted(f64, f64),
+}
+
+/// A `BoxProduct` action together with the round-robin turn that follows it
+type TurnMove<M1, M2> = (BoxAction<<M1 as MDP>::Action, <M2 as MDP>::Action>, Turn);
+
+/// Product policy for a `BoxProduct` built from one policy per component and a `Scheduler`.
+///
+/// A component is done once it sits in a final state or its policy has no action for it.
+/// The policy keeps no turn of its own: round-robin callers pass the turn to `action_on_turn` and
+/// carry the one it hands back to the next step, while `Policy::action` gives the first component
+/// the right of way. Priority weighting keeps its RNG in a `RefCell` so the policy stays usable
+/// through `&self`; use `reseed` for reproducible priority draws.
+pub struct LiftedBoxPolicy<'a, M1: MDP, M2: MDP, P1, P2> {
+    mdp1: &'a M1,
+    mdp2: &'a M2,
+    first: P1,
+    second: P2,
+    scheduler: Scheduler,
+    rng: RefCell<StdRng>,
+}
+
+impl<M1, M2, P1, P2> LiftedBoxPolicy<'_, M1, M2, P1, P2>
+where
+    M1: MDP,
+    M2: MDP,
+    P1: Policy<M1::State, M1::Action>,
+    P2: Policy<M2::State, M2::Action>,
+{
+    /// Restarts the random draws of `Scheduler::PriorityWeighted` from `seed`
+    pub fn reseed(&self, seed: u64) {
+        *self.rng.borrow_mut() = StdRng::seed_from_u64(seed);
+    }
+
+    /// The action for `state` when `turn` is the round-robin turn, together with the turn for the
+    /// next step. Schedulers other than `Scheduler::RoundRobin` ignore `turn`.
+    pub fn action_on_turn(
+        &self,
+        state: &Product<M1::State, M2::State>,
+        turn: Turn,
+    ) -> Option<TurnMove<M1, M2>> {
+        let left = if self.mdp1.is_final_state(&state.fst) {
+            None
+        } else {
+            self.first.action(&state.fst)
+        };
+        let right = if self.mdp2.is_final_state(&state.snd) {
+            None
+        } else {
+            self.second.action(&state.snd)
+        };
+
+        let preferred = match self.scheduler {
+            Scheduler::RoundRobin => turn,
+            Scheduler::FinishFirst => Turn::First,
+            Scheduler::PriorityWeighted(w1, w2) => {
+                if self.rng.borrow_mut().random::<f64>() * (w1 + w2) < w1 {
+                    Turn::First
+                } else {
+                    Turn::Second
+                }
+            }
+        };
+
+        match (preferred, left, right) {
+            (Turn::First, Some(a1), _) | (Turn::Second, Some(a1), None) => {
+                Some((BoxAction::Left(a1), Turn::Second))
+            }
+            (_, _, Some(a2)) => Some((BoxAction::Right(a2), Turn::First)),
+            (_, None, None) => None,
+        }
+    }
+}
+
+impl<M1, M2, P1, P2> Policy<Product<M1::State, M2::State>, BoxAction<M1::Action, M2::Action>>
+    for LiftedBoxPolicy<'_, M1, M2, P1, P2>
+where
+    M1: MDP,
+    M2: MDP,
+    P1: Policy<M1::State, M1::Action>,
+    P2: Policy<M2::State, M2::Action>,
+{
+    fn action(
+        &self,
+        state: &Product<M1::State, M2::State>,
+    ) -> Option<BoxAction<M1::Action, M2::Action>> {
+        self.action_on_turn(state, Turn::First).map(|(action, _)| action)
+    }
+}
+
+impl<M1, M2> BoxProduct<M1, M2>
+where
+    M1: MDP,
+    M2: MDP,
+    M1::State: Clone,
+    M2::State: Clone,
+{
+    /// Combines component policies into a policy on the product, advancing one component per step
+    /// as chosen by `scheduler`
+    pub fn lift_policies<P1, P2>(
+        &self,
+        first: P1,
+        second: P2,
+        scheduler: Scheduler,
+    ) -> LiftedBoxPolicy<'_, M1, M2, P1, P2>
+    where
+        P1: Policy<M1::State, M1::Action>,
+        P2: Policy<M2::State, M2::Action>,
+    {
+        LiftedBoxPolicy {
+            mdp1: &self.mdp1,
+            mdp2: &self.mdp2,
+            first,
+            second,
+            scheduler,
+            rng: RefCell::new(StdRng::from_rng(&mut rand::rng())),
+        }
+    }
+}
+
+/// Product policy for a `CartesianProduct`: both components act on their own state at once
+pub struct LiftedCartesianPolicy<P1, P2> {
+    first: P1,
+    second: P2,
+}
+
+impl<S1, S2, A1, A2, P1, P2> Policy<Product<S1, S2>, Product<A1, A2>>
+    for LiftedCartesianPolicy<P1, P2>
+where
+    P1: Policy<S1, A1>,
+    P2: Policy<S2, A2>,
+{
+    fn action(&self, state: &Product<S1, S2>) -> Option<Product<A1, A2>> {
+        Some(Product::new(
+            self.first.action(&state.fst)?,
+            self.second.action(&state.snd)?,
+        ))
+    }
+}
 
-        Ok((Measure::from_distribution(dist)?, r1 + r2))
+impl<M1, M2> CartesianProduct<M1, M2>
+where
+    M1: MDP,
+    M2: MDP,
+    M1::State: Clone,
+    M2::State: Clone,
+{
+    /// Combines component policies into the policy that runs both of them side by side
+    pub fn lift_policies<P1, P2>(&self, first: P1, second: P2) -> LiftedCartesianPolicy<P1, P2>
+    where
+        P1: Policy<M1::State, M1::Action>,
+        P2: Policy<M2::State, M2::Action>,
+    {
+        LiftedCartesianPolicy { first, second }
     }
+}
+
+/// How product states are weighted when projecting a product policy onto its components
+#[derive(Debug, Clone, Copy)]
+pub enum ProjectionWeights<'a, S> {
+    /// Every product state casts one vote
+    MajorityVote,
+    /// Each product state votes with its weight, e.g. a state occupancy; missing states get zero
+    Occupancy(&'a HashMap<S, f64>),
+}
+
+impl<S: Eq + Hash> ProjectionWeights<'_, S> {
+    fn weight(&self, state: &S) -> f64 {
+        match self {
+            ProjectionWeights::MajorityVote => 1.0,
+            ProjectionWeights::Occupancy(weights) => weights.get(state).copied().unwrap_or(0.0),
+        }
+    }
+}
 
+/// A component policy recovered from a product policy
+#[derive(Debug, Clone)]
+pub struct MarginalPolicy<S, A> {
+    pub policy: DeterministicPolicy<S, A>,
+    /// Share of the (weighted) votes at each component state that went to the chosen action;
+    /// 1.0 means the product policy acts identically wherever that component is in this state
+    pub agreement: HashMap<S, f64>,
+}
+
+impl<S, A> MarginalPolicy<S, A> {
+    /// Average agreement over component states, or `None` if there are none
+    pub fn mean_agreement(&self) -> Option<f64> {
+        if self.agreement.is_empty() {
+            None
+        } else {
+            Some(self.agreement.values().sum::<f64>() / self.agreement.len() as f64)
+        }
+    }
+}
+
+/// A deterministic policy on the product of `M1` and `M2` with product actions `A`
+type ProductPolicy<M1, M2, A> =
+    DeterministicPolicy<Product<<M1 as MDP>::State, <M2 as MDP>::State>, A>;
+
+/// The component policies recovered from a policy on the product of `M1` and `M2`
+pub type Marginals<M1, M2> = (
+    MarginalPolicy<<M1 as MDP>::State, <M1 as MDP>::Action>,
+    MarginalPolicy<<M2 as MDP>::State, <M2 as MDP>::Action>,
+);
+
+/// Picks the most-voted action per state, ignoring votes without weight. Ties go to the action
+/// listed first in `order` (the component's `all_actions()`), so the projection does not depend on
+/// the order the votes come in.
+fn tally<S, A>(votes: impl Iterator<Item = (S, A, f64)>, order: &[A]) -> MarginalPolicy<S, A>
+where
+    S: Eq + Hash + Clone,
+    A: Eq + Hash,
+{
+    let rank = |action: &A| order.iter().position(|a| a == action).unwrap_or(order.len());
+    let mut ballots: HashMap<S, HashMap<A, f64>> = HashMap::new();
+    for (state, action, weight) in votes.filter(|(_, _, weight)| *weight > 0.0) {
+        *ballots.entry(state).or_default().entry(action).or_insert(0.0) += weight;
+    }
+
+    let mut policy = HashMap::new();
+    let mut agreement = HashMap::new();
+    for (state, counts) in ballots {
+        let total: f64 = counts.values().sum();
+        let winner = counts.into_iter().max_by(|(a, x), (b, y)| {
+            x.total_cmp(y).then_with(|| rank(b).cmp(&rank(a)))
+        });
+        if let Some((action, votes)) = winner {
+            agreement.insert(state.clone(), votes / total);
+            policy.insert(state, action);
+        }
+    }
+    MarginalPolicy { policy, agreement }
+}
+
+impl<M1, M2> CartesianProduct<M1, M2>
+where
+    M1: MDP,
+    M2: MDP,
+    M1::State: Clone,
+    M2::State: Clone,
+    M1::Action: Clone,
+    M2::Action: Clone,
+{
+    /// Projects a policy on this product onto its components: each component state gets the
+    /// component action chosen most often (by weight) across the product states containing it,
+    /// ties going to the action the component lists first.
+    pub fn project_policy(
+        &self,
+        policy: &ProductPolicy<M1, M2, Product<M1::Action, M2::Action>>,
+        weights: ProjectionWeights<'_, Product<M1::State, M2::State>>,
+    ) -> Marginals<M1, M2> {
+        let first = tally(
+            policy
+                .iter()
+                .map(|(s, a)| (s.fst.clone(), a.fst.clone(), weights.weight(s))),
+            &self.mdp1.all_actions(),
+        );
+        let second = tally(
+            policy
+                .iter()
+                .map(|(s, a)| (s.snd.clone(), a.snd.clone(), weights.weight(s))),
+            &self.mdp2.all_actions(),
+        );
+        (first, second)
+    }
+}
+
+impl<M1, M2> BoxProduct<M1, M2>
+where
+    M1: MDP,
+    M2: MDP,
+    M1::State: Clone,
+    M2::State: Clone,
+    M1::Action: Clone,
+    M2::Action: Clone,
+{
+    /// Projects a policy on this product onto its components. Only product states where the
+    /// policy moves a component vote for that component, so a component the policy never
+    /// advances from some state has no action there; ties go to the action the component lists
+    /// first.
+    pub fn project_policy(
+        &self,
+        policy: &ProductPolicy<M1, M2, BoxAction<M1::Action, M2::Action>>,
+        weights: ProjectionWeights<'_, Product<M1::State, M2::State>>,
+    ) -> Marginals<M1, M2> {
+        let first = tally(
+            policy.iter().filter_map(|(s, a)| {
+                a.project_left()
+                    .map(|a1| (s.fst.clone(), a1.clone(), weights.weight(s)))
+            }),
+            &self.mdp1.all_actions(),
+        );
+        let second = tally(
+            policy.iter().filter_map(|(s, a)| {
+                a.project_right()
+                    .map(|a2| (s.snd.clone(), a2.clone(), weights.weight(s)))
+            }),
+            &self.mdp2.all_actions(),
+        );
+        (first, second)
+    }
 }
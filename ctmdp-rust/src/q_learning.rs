@@ -3,105 +3,224 @@
 //! The `q_learning` module contains implementations of temporal difference learning algorithms
 //! (SARSA and Q-Learning) for MDPs.
 
-use madepro::models::{Sampler, Config, ActionValue};
+use madepro::models::{Action, ActionValue, Sampler, State};
+use rand::Rng;
+use rand::seq::IteratorRandom;
+use crate::compiled::compile;
+use crate::config::TrainingConfig;
+use crate::dense::{ActionCache, DenseQTable};
 use crate::mdp::MDP;
 use crate::error::Error;
+use crate::policy::{boltzmann_measure, soft_maximum};
+use crate::replay::{ReplayBuffer, Transition};
+use std::collections::VecDeque;
+
+/// The Q-values a tabular learner ends with, plus the undiscounted return and length of every
+/// training episode (zero for episodes that started in a state without actions)
+pub struct TrainingResult<S: State, A: Action> {
+    pub q_values: ActionValue<S, A>,
+    pub episode_returns: Vec<f64>,
+    pub episode_lengths: Vec<usize>,
+}
+
+/// Collects all unique actions across all states, which is the action set the Q-table is keyed on
+pub(crate) fn action_union<M>(mdp: &M) -> Sampler<M::Action>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    mdp.all_actions().into()
+}
+
+/// The action union, or an error if it or the state space is empty (there would be nothing to
+/// start episodes from or to key the Q-table on)
+pub(crate) fn checked_action_union<M>(mdp: &M) -> Result<Sampler<M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    if mdp.all_states().iter().next().is_none() {
+        return Err(Error::EmptyStateSpace);
+    }
+    let actions = action_union(mdp);
+    if actions.iter().next().is_none() {
+        return Err(Error::EmptyActionSet);
+    }
+    Ok(actions)
+}
+
+/// A start state drawn uniformly from `states` with `rng`, so seeded learners start their
+/// episodes reproducibly (`Sampler::get_random` draws from the thread RNG)
+pub(crate) fn random_start<'a, S, R>(states: &'a Sampler<S>, rng: &mut R) -> Result<&'a S, Error>
+where
+    R: Rng + ?Sized,
+{
+    states.iter().choose(rng).ok_or(Error::EmptyStateSpace)
+}
+
+/// Rejects a NaN or infinite Q-value before it spreads through the table
+pub(crate) fn checked_q_value(q: f64) -> Result<f64, Error> {
+    if q.is_finite() {
+        Ok(q)
+    } else {
+        Err(Error::NumericalError(format!("Q-value update produced {q}")))
+    }
+}
+
+/// Pops the oldest pending step and moves its Q-value toward the n-step return
+/// `r_0 + γ r_1 + ... + γ^(k-1) r_(k-1) + γ^k tail_q`, where `k` is the number of pending steps.
+fn update_oldest(
+    table: &mut DenseQTable,
+    pending: &mut VecDeque<(usize, usize, f64)>,
+    tail_q: f64,
+    config: &TrainingConfig,
+) -> Result<(), Error> {
+    let discounted_rewards: f64 = pending
+        .iter()
+        .rev()
+        .fold(0.0, |acc, (_, _, reward)| reward + config.discount_factor * acc);
+    let target = discounted_rewards + config.discount_factor.powi(pending.len() as i32) * tail_q;
+
+    if let Some((state, action, _)) = pending.pop_front() {
+        let current_q = table.value(state, action);
+        let new_q = checked_q_value(current_q + config.learning_rate * (target - current_q))?;
+        table.set_value(state, action, new_q);
+    }
+    Ok(())
+}
 
 /// Internal helper function that implements both SARSA and Q-Learning
 /// The `q_learning` parameter determines which algorithm to use:
 /// - `true` for Q-Learning (off-policy)
 /// - `false` for SARSA (on-policy)
+///
+/// Targets are n-step returns with `n = config.n_step`. Steps still pending when the episode
+/// ends (terminal state, dead end or step limit) are flushed with shorter returns that bootstrap
+/// from the last state-action pair reached.
+///
+/// When `config.replay_updates > 0`, every observed transition is also stored in a
+/// `ReplayBuffer` and that many sampled transitions are replayed with one-step Q-Learning
+/// targets after each environment step (for SARSA too, since replayed actions are off-policy).
+///
+/// Learning runs on a `DenseQTable` over the indexed states and actions. The MDP is `compile`d
+/// once up front, so every step reads its reward and samples its successor from flat arrays
+/// instead of rebuilding the transition measure; a transition that leaves `all_states()` fails
+/// with `Error::StateNotFound`.
 fn sarsa_q_learning<M>(
     mdp: &M,
-    config: &Config,
+    config: &TrainingConfig,
     q_learning: bool,
-) -> Result<ActionValue<M::State, M::Action>, Error>
+) -> Result<TrainingResult<M::State, M::Action>, Error>
 where
     M: MDP,
     M::State: Clone,
     M::Action: Clone,
 {
-    let states = mdp.all_states();
-    let state_action_pairs = mdp.all_state_action_pairs();
-    
-    // Collect all unique actions across all states
-    // Use a HashSet to deduplicate without requiring Ord
-    use std::collections::HashSet;
-    let all_actions: Vec<M::Action> = state_action_pairs
-        .iter()
-        .map(|(_, a)| a.clone())
-        .collect::<HashSet<_>>()
-        .into_iter()
-        .collect();
-    let actions: Sampler<M::Action> = all_actions.into();
-    
-    let mut action_value = ActionValue::new(states, &actions);
+    checked_action_union(mdp)?;
+    let model = compile(mdp)?;
+    let (states, actions) = (model.state_index(), model.action_index());
+    let available = ActionCache::new(&model, states, actions);
+
+    let mut table = DenseQTable::new(states.len(), actions.len());
+    let n_step = config.n_step.max(1);
+    let mut replay = ReplayBuffer::new(if config.replay_updates > 0 {
+        config.replay_capacity
+    } else {
+        0
+    });
     
+    let mut rng = config.rng();
+    let mut episode_returns = Vec::with_capacity(config.num_episodes as usize);
+    let mut episode_lengths = Vec::with_capacity(config.num_episodes as usize);
+
     for _ in 0..config.num_episodes {
         // Start from a random state
-        let mut state = states.get_random().clone();
-        
-        // Get available actions at this state
-        let available_actions = mdp.actions_at(&state);
-        if available_actions.is_empty() {
+        let mut state = rng.random_range(0..states.len());
+        // Select initial action using epsilon-greedy among the actions available there
+        let Some(mut action) = table.epsilon_greedy(
+            state,
+            available.action_indices_at(state),
+            config.exploration_rate,
+            &mut rng,
+        ) else {
+            episode_returns.push(0.0);
+            episode_lengths.push(0);
             continue;
-        }
-        
-        // Select initial action using epsilon-greedy
-        let mut action = action_value
-            .epsilon_greedy(&actions, &state, config.exploration_rate)
-            .clone();
+        };
+        let mut episode_return = 0.0;
+        let mut episode_length = 0;
+
+        // Steps whose n-step return is still being accumulated, oldest first
+        let mut pending: VecDeque<(usize, usize, f64)> = VecDeque::with_capacity(n_step);
         
         for _ in 0..config.max_num_steps {
-            // Transition: get measure over next states and reward
-            let (measure, reward) = mdp.stochastic_transition(&state, &action)?;
-            
-            // Sample next state from the measure
-            let next_state = match measure.sample() {
-                Some(s) => s.clone(),
-                None => {
-                    // If measure is empty, stay in current state
-                    state.clone()
-                }
-            };
-            
-            // Get available actions at next state
-            let next_available_actions = mdp.actions_at(&next_state);
-            if next_available_actions.is_empty() {
+            // Transition: read the reward and sample the next state from the compiled model
+            let Some(choice) = model.find_choice(state, action) else {
                 break;
-            }
+            };
+            let reward = model.reward(choice);
+            episode_return += reward;
+            episode_length += 1;
+            let next_state = model.sample_next(choice, &mut rng);
             
-            // Select next action (for SARSA) or greedy action (for Q-Learning)
+            // Select next action (for SARSA) or greedy action (for Q-Learning); stop at dead ends
+            let next_actions = available.action_indices_at(next_state);
             let next_action = if q_learning {
                 // Q-Learning: use greedy action for target
-                action_value.greedy(&next_state).clone()
+                table.greedy(next_state, next_actions)
             } else {
                 // SARSA: use epsilon-greedy action
-                action_value
-                    .epsilon_greedy(&actions, &next_state, config.exploration_rate)
-                    .clone()
+                table.epsilon_greedy(next_state, next_actions, config.exploration_rate, &mut rng)
+            };
+            let Some(next_action) = next_action else {
+                break;
             };
             
-            // Update Q-value using Bellman equation
-            let current_q = action_value.get(&state, &action);
-            let next_q = action_value.get(&next_state, &next_action);
-            let target = reward + config.discount_factor * next_q;
-            let new_q = current_q + config.learning_rate * (target - current_q);
-            
-            action_value.insert(&state, &action, new_q);
+            if config.replay_updates > 0 {
+                replay.push(Transition {
+                    state,
+                    action,
+                    reward,
+                    next_state,
+                    done: model.is_final_index(next_state),
+                });
+            }
+
+            // Update the oldest pending Q-value once its n rewards are known
+            pending.push_back((state, action, reward));
+            if pending.len() == n_step {
+                let next_q = table.value(next_state, next_action);
+                update_oldest(&mut table, &mut pending, next_q, config)?;
+            }
+
+            let batch = replay.sample_with(config.replay_updates, &mut rng);
+            table.replay(&batch, config.learning_rate, config.discount_factor)?;
             
             // Move to next state
             state = next_state;
             action = next_action;
             
             // Check if we've reached a terminal state
-            if mdp.is_final_state(&state) {
+            if model.is_final_index(state) {
                 break;
             }
         }
+
+        while !pending.is_empty() {
+            let tail_q = table.value(state, action);
+            update_oldest(&mut table, &mut pending, tail_q, config)?;
+        }
+        episode_returns.push(episode_return);
+        episode_lengths.push(episode_length);
     }
     
-    Ok(action_value)
+    Ok(TrainingResult {
+        q_values: table.to_action_value(states, actions),
+        episode_returns,
+        episode_lengths,
+    })
 }
 
 /// # SARSA
@@ -117,11 +236,26 @@ where
 ///
 /// # Arguments
 /// * `mdp` - The MDP to learn from
-/// * `config` - Configuration parameters (learning rate, discount factor, exploration rate, etc.)
+/// * `config` - Configuration parameters (learning rate, discount factor, exploration rate, n-step horizon, etc.)
 ///
 /// # Returns
 /// An `ActionValue` table containing the learned Q-values for all state-action pairs
-pub fn sarsa<M>(mdp: &M, config: &Config) -> Result<ActionValue<M::State, M::Action>, Error>
+pub fn sarsa<M>(mdp: &M, config: &TrainingConfig) -> Result<ActionValue<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    sarsa_with_history(mdp, config).map(|result| result.q_values)
+}
+
+/// # SARSA With History
+///
+/// `sarsa`, also returning the return and length of every training episode (a learning curve).
+pub fn sarsa_with_history<M>(
+    mdp: &M,
+    config: &TrainingConfig,
+) -> Result<TrainingResult<M::State, M::Action>, Error>
 where
     M: MDP,
     M::State: Clone,
@@ -146,11 +280,27 @@ where
 ///
 /// # Arguments
 /// * `mdp` - The MDP to learn from
-/// * `config` - Configuration parameters (learning rate, discount factor, exploration rate, etc.)
+/// * `config` - Configuration parameters (learning rate, discount factor, exploration rate, n-step horizon, etc.)
 ///
 /// # Returns
 /// An `ActionValue` table containing the learned Q-values for all state-action pairs
-pub fn q_learning<M>(mdp: &M, config: &Config) -> Result<ActionValue<M::State, M::Action>, Error>
+pub fn q_learning<M>(mdp: &M, config: &TrainingConfig) -> Result<ActionValue<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    q_learning_with_history(mdp, config).map(|result| result.q_values)
+}
+
+/// # Q-Learning With History
+///
+/// `q_learning`, also returning the return and length of every training episode (a learning
+/// curve).
+pub fn q_learning_with_history<M>(
+    mdp: &M,
+    config: &TrainingConfig,
+) -> Result<TrainingResult<M::State, M::Action>, Error>
 where
     M: MDP,
     M::State: Clone,
@@ -158,3 +308,82 @@ where
 {
     sarsa_q_learning(mdp, config, true)
 }
+
+/// # Soft Q-Learning
+///
+/// This function implements entropy-regularized (soft) Q-Learning.
+/// Actions are sampled from the Boltzmann policy `π(a|s) ∝ exp(Q(s, a) / τ)` and the target
+/// replaces the hard maximum by the log-sum-exp soft value:
+///
+/// `r + γ τ log Σ_a' exp(Q(s', a') / τ)`
+///
+/// with `τ = config.temperature`. As `τ → 0` this recovers Q-Learning; larger temperatures favor
+/// stochastic policies, which can be extracted with `policy::boltzmann_policy`.
+///
+/// # Arguments
+/// * `mdp` - The MDP to learn from
+/// * `config` - Configuration parameters (learning rate, discount factor, temperature, etc.)
+///
+/// # Returns
+/// An `ActionValue` table containing the learned soft Q-values for all state-action pairs
+pub fn soft_q_learning<M>(
+    mdp: &M,
+    config: &TrainingConfig,
+) -> Result<ActionValue<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    checked_action_union(mdp)?;
+    let model = compile(mdp)?;
+    let (states, actions) = (model.state_index(), model.action_index());
+    let available = ActionCache::new(&model, states, actions);
+    let temperature = config.temperature;
+
+    let mut table = DenseQTable::new(states.len(), actions.len());
+    let mut rng = config.rng();
+
+    for _ in 0..config.num_episodes {
+        let mut state = rng.random_range(0..states.len());
+
+        for _ in 0..config.max_num_steps {
+            let available_actions = available.action_indices_at(state);
+            if available_actions.is_empty() {
+                break;
+            }
+            let behavior = boltzmann_measure(
+                available_actions.to_vec(),
+                |&a| table.value(state, a),
+                temperature,
+            )?;
+            let action = match behavior.sample_with(&mut rng) {
+                Some(&a) => a,
+                None => break,
+            };
+
+            let Some(choice) = model.find_choice(state, action) else {
+                break;
+            };
+            let reward = model.reward(choice);
+            let next_state = model.sample_next(choice, &mut rng);
+
+            let soft_value = soft_maximum(
+                available.action_indices_at(next_state),
+                |&a| table.value(next_state, a),
+                temperature,
+            );
+            let current_q = table.value(state, action);
+            let target = reward + config.discount_factor * soft_value;
+            let new_q = checked_q_value(current_q + config.learning_rate * (target - current_q))?;
+            table.set_value(state, action, new_q);
+
+            state = next_state;
+            if model.is_final_index(state) {
+                break;
+            }
+        }
+    }
+
+    Ok(table.to_action_value(states, actions))
+}
@@ -0,0 +1,275 @@
+//! # Quotients
+//!
+//! Collapsing an MDP by an equivalence on its states. `QuotientMDP` has one state per block of a
+//! partition, with transition measures and rewards averaged over the block's members, and
+//! `bisimulation_partition` computes the coarsest partition under which that averaging is exact.
+
+use std::collections::{BTreeMap, HashMap};
+
+use indexmap::IndexMap;
+use madepro::models::{Sampler, State};
+
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::measure::{Measure, Probability};
+use crate::policy::DeterministicPolicy;
+
+/// A state of a `QuotientMDP`: the index of a block of the partition
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub struct Block(usize);
+
+impl Block {
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl State for Block {}
+
+/// An MDP whose states are the blocks of a partition of another MDP's states.
+///
+/// Actions available at a block are those available at every member. Taking an action from a
+/// block averages, uniformly over the members, the members' rewards and the probability of
+/// landing in each block. A block is final if all of its members are. When the partition is a
+/// bisimulation the averages are exact and the quotient has the same optimal values.
+///
+/// Actions are matched by label. States that are only symmetric up to renaming actions, like
+/// `(s, t)` and `(t, s)` in a `BoxProduct` of two copies of one MDP, can still be grouped, but the
+/// averaged dynamics are then an approximation; `morphism::Morphism` captures such symmetries
+/// exactly.
+pub struct QuotientMDP<M: MDP> {
+    mdp: M,
+    blocks: Vec<Vec<M::State>>,
+    block_of: HashMap<M::State, Block>,
+    states: Sampler<Block>,
+}
+
+impl<M> QuotientMDP<M>
+where
+    M: MDP,
+    M::State: Clone,
+{
+    /// States missing from `partition` get blocks of their own. A state listed in several blocks
+    /// belongs to the first.
+    pub fn from_partition(mdp: M, partition: Vec<Vec<M::State>>) -> Self {
+        let mut blocks: Vec<Vec<M::State>> = Vec::new();
+        let mut block_of = HashMap::new();
+        for members in partition {
+            let members: Vec<M::State> = members
+                .into_iter()
+                .filter(|s| !block_of.contains_key(s))
+                .collect();
+            if members.is_empty() {
+                continue;
+            }
+            for s in &members {
+                block_of.insert(s.clone(), Block(blocks.len()));
+            }
+            blocks.push(members);
+        }
+        for s in mdp.all_states().iter() {
+            if !block_of.contains_key(s) {
+                block_of.insert(s.clone(), Block(blocks.len()));
+                blocks.push(vec![s.clone()]);
+            }
+        }
+        let states = (0..blocks.len()).map(Block).collect::<Vec<_>>().into();
+
+        QuotientMDP {
+            mdp,
+            blocks,
+            block_of,
+            states,
+        }
+    }
+
+    /// Groups states by an equivalence relation, which must be reflexive, symmetric and
+    /// transitive; each state is compared against the first member of each existing block.
+    pub fn from_equivalence(mdp: M, equivalent: impl Fn(&M::State, &M::State) -> bool) -> Self {
+        let mut partition: Vec<Vec<M::State>> = Vec::new();
+        for s in mdp.all_states().iter() {
+            match partition.iter_mut().find(|block| equivalent(&block[0], s)) {
+                Some(block) => block.push(s.clone()),
+                None => partition.push(vec![s.clone()]),
+            }
+        }
+        Self::from_partition(mdp, partition)
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mdp
+    }
+
+    pub fn num_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Number of original states per block; above 1 means the quotient saved states
+    pub fn compression_ratio(&self) -> f64 {
+        self.block_of.len() as f64 / self.blocks.len().max(1) as f64
+    }
+
+    /// The block containing `state`, if it is a state of the wrapped MDP
+    pub fn block_of(&self, state: &M::State) -> Option<Block> {
+        self.block_of.get(state).copied()
+    }
+
+    pub fn members(&self, block: Block) -> &[M::State] {
+        self.blocks
+            .get(block.0)
+            .map_or(&[], |members| members.as_slice())
+    }
+
+    /// Gives every member of a block the action the quotient policy picks for the block
+    pub fn lift_policy<A: Clone>(
+        &self,
+        policy: &DeterministicPolicy<Block, A>,
+    ) -> DeterministicPolicy<M::State, A> {
+        let mut lifted = HashMap::new();
+        for (block, action) in policy {
+            for s in self.members(*block) {
+                lifted.insert(s.clone(), action.clone());
+            }
+        }
+        lifted
+    }
+}
+
+impl<M> MDP for QuotientMDP<M>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    type State = Block;
+    type Action = M::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        &self.states
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        let members = self.members(*state);
+        let Some((first, rest)) = members.split_first() else {
+            return Vec::new();
+        };
+        let others: Vec<Vec<M::Action>> = rest.iter().map(|s| self.mdp.actions_at(s)).collect();
+        self.mdp
+            .actions_at(first)
+            .into_iter()
+            .filter(|a| others.iter().all(|actions| actions.contains(a)))
+            .collect()
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.members(*state)
+            .iter()
+            .all(|s| self.mdp.is_final_state(s))
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.members(*state).iter().any(|s| self.mdp.is_goal(s))
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        let members = self.members(*state);
+        if members.is_empty() {
+            return Err(Error::StateNotFound);
+        }
+        let weight = 1.0 / members.len() as f64;
+        let mut next_blocks: IndexMap<Block, f64> = IndexMap::new();
+        let mut reward = 0.0;
+        for s in members {
+            let (measure, r) = self.mdp.stochastic_transition(s, action)?;
+            reward += weight * r;
+            for (next, p) in measure.dist() {
+                let block = self.block_of(next).ok_or(Error::StateNotFound)?;
+                *next_blocks.entry(block).or_insert(0.0) += weight * p.value();
+            }
+        }
+        let dist = next_blocks
+            .into_iter()
+            .map(|(block, p)| Ok((block, Probability::new(p.min(1.0))?)))
+            .collect::<Result<IndexMap<_, _>, Error>>()?;
+        Ok((Measure::from_distribution(dist)?, reward))
+    }
+}
+
+/// What a state looks like from the current partition: whether it is final and, per action, its
+/// (quantized) reward and probability of reaching each block
+type Signature<A> = (bool, HashMap<A, (i64, BTreeMap<usize, i64>)>);
+
+fn quantize(x: f64, tolerance: f64) -> i64 {
+    (x / tolerance).round() as i64
+}
+
+/// # Bisimulation Partition
+///
+/// Computes the coarsest partition of the states into blocks that agree on being final, on
+/// their available actions, on the reward of every action and on the probability of moving into
+/// every block, by iterated partition refinement.
+///
+/// Rewards and probabilities are compared after rounding to multiples of `tolerance`, so values
+/// within `tolerance` usually but not always land in the same block.
+pub fn bisimulation_partition<M>(mdp: &M, tolerance: f64) -> Result<Vec<Vec<M::State>>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let tolerance = tolerance.max(f64::EPSILON);
+    let states: Vec<M::State> = mdp.all_states().iter().cloned().collect();
+    let mut partition: Vec<Vec<M::State>> = vec![states];
+
+    loop {
+        let block_of: HashMap<M::State, usize> = partition
+            .iter()
+            .enumerate()
+            .flat_map(|(i, block)| block.iter().map(move |s| (s.clone(), i)))
+            .collect();
+
+        let mut refined: Vec<Vec<M::State>> = Vec::new();
+        for block in &partition {
+            let mut signatures: Vec<Signature<M::Action>> = Vec::new();
+            let mut groups: Vec<Vec<M::State>> = Vec::new();
+            for s in block {
+                let mut signature = HashMap::new();
+                let is_final = mdp.is_final_state(s);
+                if !is_final {
+                    for action in mdp.actions_at(s) {
+                        let (measure, reward) = mdp.stochastic_transition(s, &action)?;
+                        let mut reach: BTreeMap<usize, f64> = BTreeMap::new();
+                        for (next, p) in measure.dist() {
+                            let block = block_of.get(next).ok_or(Error::StateNotFound)?;
+                            *reach.entry(*block).or_insert(0.0) += p.value();
+                        }
+                        let reach = reach
+                            .into_iter()
+                            .map(|(block, p)| (block, quantize(p, tolerance)))
+                            .filter(|(_, p)| *p != 0)
+                            .collect();
+                        signature.insert(action, (quantize(reward, tolerance), reach));
+                    }
+                }
+                let key = (is_final, signature);
+                match signatures.iter().position(|k| *k == key) {
+                    Some(i) => groups[i].push(s.clone()),
+                    None => {
+                        signatures.push(key);
+                        groups.push(vec![s.clone()]);
+                    }
+                }
+            }
+            refined.extend(groups);
+        }
+
+        if refined.len() == partition.len() {
+            return Ok(refined);
+        }
+        partition = refined;
+    }
+}
@@ -0,0 +1,210 @@
+//! # Race Composition
+//!
+//! The continuous-time counterpart of `BoxProduct`. Two CTMDPs run side by side, each under its
+//! own chosen action, and their exponential clocks race: the joint process leaves `(s1, s2)` at
+//! rate `E1 + E2`, and the component whose transition fires first moves while the other stays
+//! put (the left one wins with probability `E1 / (E1 + E2)`). Interleaving is therefore decided
+//! by the rates instead of by an explicit choice of which component to move, and by
+//! memorylessness the loser's clock needs no bookkeeping.
+
+use std::collections::HashMap;
+
+use madepro::models::{Action, Sampler};
+
+use crate::ctmdp::{CTMDP, RateMeasure};
+use crate::error::Error;
+use crate::products::Product;
+
+/// A joint action of a `RaceProduct`: one action per component, `None` for a component that has
+/// no action available (it is final and frozen)
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RaceAction<A1, A2> {
+    left: Option<A1>,
+    right: Option<A2>,
+}
+
+impl<A1, A2> RaceAction<A1, A2> {
+    pub fn new(left: Option<A1>, right: Option<A2>) -> Self {
+        RaceAction { left, right }
+    }
+
+    pub fn left(&self) -> Option<&A1> {
+        self.left.as_ref()
+    }
+
+    pub fn right(&self) -> Option<&A2> {
+        self.right.as_ref()
+    }
+}
+
+impl<A1: Action, A2: Action> Action for RaceAction<A1, A2> {}
+
+/// Two CTMDPs running concurrently; whichever component's transition fires first moves
+pub struct RaceProduct<M1: CTMDP, M2: CTMDP> {
+    ctmdp1: M1,
+    ctmdp2: M2,
+    states: Sampler<Product<M1::State, M2::State>>,
+}
+
+impl<M1, M2> RaceProduct<M1, M2>
+where
+    M1: CTMDP,
+    M2: CTMDP,
+    M1::State: Clone,
+    M2::State: Clone,
+{
+    pub fn new(ctmdp1: M1, ctmdp2: M2) -> Self {
+        let mut states = Vec::new();
+        for s1 in ctmdp1.all_states().iter() {
+            for s2 in ctmdp2.all_states().iter() {
+                states.push(Product::new(s1.clone(), s2.clone()));
+            }
+        }
+        RaceProduct {
+            ctmdp1,
+            ctmdp2,
+            states: Sampler::new(states),
+        }
+    }
+
+    /// The probabilities that the left and the right component, respectively, fire first from
+    /// `state` under `action` (both zero if neither can move)
+    pub fn first_to_fire(
+        &self,
+        state: &Product<M1::State, M2::State>,
+        action: &RaceAction<M1::Action, M2::Action>,
+    ) -> Result<(f64, f64), Error> {
+        let (exit1, exit2) = self.component_exit_rates(state, action)?;
+        let total = exit1 + exit2;
+        if total <= 0.0 {
+            return Ok((0.0, 0.0));
+        }
+        Ok((exit1 / total, exit2 / total))
+    }
+
+    fn component_exit_rates(
+        &self,
+        state: &Product<M1::State, M2::State>,
+        action: &RaceAction<M1::Action, M2::Action>,
+    ) -> Result<(f64, f64), Error> {
+        let exit1 = match action.left() {
+            Some(a1) => self.ctmdp1.exit_rate(state.first(), a1)?,
+            None => 0.0,
+        };
+        let exit2 = match action.right() {
+            Some(a2) => self.ctmdp2.exit_rate(state.second(), a2)?,
+            None => 0.0,
+        };
+        Ok((exit1, exit2))
+    }
+
+    pub fn components(&self) -> (&M1, &M2) {
+        (&self.ctmdp1, &self.ctmdp2)
+    }
+
+    pub fn into_components(self) -> (M1, M2) {
+        (self.ctmdp1, self.ctmdp2)
+    }
+}
+
+/// The actions of a component that is still running; final components contribute none
+fn running_actions<M: CTMDP>(ctmdp: &M, state: &M::State) -> Vec<M::Action> {
+    if ctmdp.is_final_state(state) {
+        Vec::new()
+    } else {
+        ctmdp.actions_at(state)
+    }
+}
+
+impl<M1, M2> CTMDP for RaceProduct<M1, M2>
+where
+    M1: CTMDP,
+    M2: CTMDP,
+    M1::State: Clone,
+    M2::State: Clone,
+    M1::Action: Clone,
+    M2::Action: Clone,
+{
+    type State = Product<M1::State, M2::State>;
+    type Action = RaceAction<M1::Action, M2::Action>;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        &self.states
+    }
+
+    /// Every pair of running-component actions; a component that is final or has no actions
+    /// contributes `None`
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        let actions1 = running_actions(&self.ctmdp1, state.first());
+        let actions2 = running_actions(&self.ctmdp2, state.second());
+        match (actions1.is_empty(), actions2.is_empty()) {
+            (true, true) => Vec::new(),
+            (false, true) => actions1
+                .into_iter()
+                .map(|a1| RaceAction::new(Some(a1), None))
+                .collect(),
+            (true, false) => actions2
+                .into_iter()
+                .map(|a2| RaceAction::new(None, Some(a2)))
+                .collect(),
+            (false, false) => {
+                let mut out = Vec::with_capacity(actions1.len() * actions2.len());
+                for a1 in &actions1 {
+                    for a2 in &actions2 {
+                        out.push(RaceAction::new(Some(a1.clone()), Some(a2.clone())));
+                    }
+                }
+                out
+            }
+        }
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.ctmdp1.is_final_state(state.first()) && self.ctmdp2.is_final_state(state.second())
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.ctmdp1.is_goal(state.first()) && self.ctmdp2.is_goal(state.second())
+    }
+
+    /// `q((s1', s2) | ·) = q1(s1' | s1, a1)` and `q((s1, s2') | ·) = q2(s2' | s2, a2)`
+    fn rates(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<RateMeasure<Self::State>, Error> {
+        let mut rates: HashMap<Self::State, f64> = HashMap::new();
+        if let Some(a1) = action.left() {
+            for (s1, rate) in self.ctmdp1.rates(state.first(), a1)?.rates() {
+                *rates
+                    .entry(Product::new(s1.clone(), state.second().clone()))
+                    .or_insert(0.0) += rate;
+            }
+        }
+        if let Some(a2) = action.right() {
+            for (s2, rate) in self.ctmdp2.rates(state.second(), a2)?.rates() {
+                *rates
+                    .entry(Product::new(state.first().clone(), s2.clone()))
+                    .or_insert(0.0) += rate;
+            }
+        }
+        RateMeasure::new(rates)
+    }
+
+    /// Both components earn their reward rates while the race is on
+    fn reward_rate(&self, state: &Self::State, action: &Self::Action) -> f64 {
+        let r1 = action
+            .left()
+            .map_or(0.0, |a1| self.ctmdp1.reward_rate(state.first(), a1));
+        let r2 = action
+            .right()
+            .map_or(0.0, |a2| self.ctmdp2.reward_rate(state.second(), a2));
+        r1 + r2
+    }
+
+    fn exit_rate(&self, state: &Self::State, action: &Self::Action) -> Result<f64, Error> {
+        let (exit1, exit2) = self.component_exit_rates(state, action)?;
+        Ok(exit1 + exit2)
+    }
+}
@@ -0,0 +1,446 @@
+//! # Reachability
+//!
+//! Which states an MDP can actually get to, and how likely it is to get there. Products in
+//! particular enumerate many joint states that no sensible start ever visits; pruning them keeps
+//! tables and sweeps small. `reach_probability` and `max_reach_probability` compute exact
+//! probabilities of eventually reaching goal states (probabilistic model checking of reachability),
+//! and `expected_hitting_time` the expected number of steps it takes.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::combinators::Restricted;
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::planning::ValueIterationResult;
+use crate::policy::{DeterministicPolicy, StochasticPolicy};
+
+/// Pivots smaller than this make `solve_linear_system` report a singular system
+const PIVOT_TOLERANCE: f64 = 1e-12;
+
+/// Policy-chain rows that lose more probability than this (to states outside the MDP) leak
+const LEAK_TOLERANCE: f64 = 1e-9;
+
+/// # Reachable States
+///
+/// Breadth-first search from `start` over the supports of the transition measures of every
+/// available action. Final states are reached but not expanded, and zero-probability outcomes are
+/// ignored.
+///
+/// # Returns
+///
+/// The reachable states (including `start`) in the order they were discovered.
+pub fn reachable_states<M>(mdp: &M, start: &M::State) -> Result<Vec<M::State>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+{
+    let mut seen = HashSet::new();
+    let mut order = Vec::new();
+    let mut frontier = VecDeque::new();
+    seen.insert(start.clone());
+    frontier.push_back(start.clone());
+
+    while let Some(state) = frontier.pop_front() {
+        if !mdp.is_final_state(&state) {
+            for action in mdp.actions_at(&state) {
+                let (measure, _) = mdp.stochastic_transition(&state, &action)?;
+                for (next, p) in measure.dist() {
+                    if p.value() > 0.0 && seen.insert(next.clone()) {
+                        frontier.push_back(next.clone());
+                    }
+                }
+            }
+        }
+        order.push(state);
+    }
+
+    Ok(order)
+}
+
+/// Restricts `mdp` to the states reachable from `start`. Nothing reachable leaves the subset, so
+/// the `Restricted` wrapper's absorbing state is itself unreachable from `start`.
+pub fn prune_unreachable<M>(mdp: M, start: &M::State) -> Result<Restricted<M>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+{
+    let reachable = reachable_states(&mdp, start)?;
+    Ok(Restricted::new(mdp, reachable))
+}
+
+/// Solves `a x = b` by Gaussian elimination with partial pivoting
+pub(crate) fn solve_linear_system(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Result<Vec<f64>, Error> {
+    let n = b.len();
+    for column in 0..n {
+        let pivot = (column..n)
+            .max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs()))
+            .unwrap_or(column);
+        if a[pivot][column].abs() < PIVOT_TOLERANCE {
+            return Err(Error::NumericalError(
+                "linear system is singular".to_string(),
+            ));
+        }
+        a.swap(column, pivot);
+        b.swap(column, pivot);
+        for row in column + 1..n {
+            let factor = a[row][column] / a[column][column];
+            if factor == 0.0 {
+                continue;
+            }
+            let (upper, lower) = a.split_at_mut(row);
+            for (entry, pivot_entry) in lower[0][column..].iter_mut().zip(&upper[column][column..])
+            {
+                *entry -= factor * pivot_entry;
+            }
+            b[row] -= factor * b[column];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let tail: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - tail) / a[row][row];
+    }
+    Ok(x)
+}
+
+/// Sparse rows `[(j, p(j | i))]` of a policy-induced chain with the predecessor lists of every
+/// state
+type ChainRows = (Vec<Vec<(usize, f64)>>, Vec<Vec<usize>>);
+
+/// The Markov chain `policy` induces on `states`, with `stop` states (and final states and
+/// states where the policy is undefined) left without outgoing transitions. Next states outside
+/// `states` are dropped.
+fn policy_chain<M>(
+    mdp: &M,
+    policy: &StochasticPolicy<M::State, M::Action>,
+    states: &[M::State],
+    stop: &[bool],
+) -> Result<ChainRows, Error>
+where
+    M: MDP,
+{
+    let index: HashMap<&M::State, usize> = states.iter().enumerate().map(|(i, s)| (s, i)).collect();
+    let n = states.len();
+    let mut chain: Vec<Vec<(usize, f64)>> = vec![Vec::new(); n];
+    let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for (i, state) in states.iter().enumerate() {
+        if stop[i] || mdp.is_final_state(state) {
+            continue;
+        }
+        let Some(measure) = policy.get(state) else {
+            continue;
+        };
+        let mut row: HashMap<usize, f64> = HashMap::new();
+        for (action, p_action) in measure.dist() {
+            if p_action.value() <= 0.0 {
+                continue;
+            }
+            let (next, _) = mdp.stochastic_transition(state, action)?;
+            for (s, p) in next.dist() {
+                if let Some(&j) = index.get(s) {
+                    *row.entry(j).or_insert(0.0) += p_action.value() * p.value();
+                }
+            }
+        }
+        for (&j, &p) in &row {
+            if p > 0.0 {
+                predecessors[j].push(i);
+            }
+        }
+        chain[i] = row.into_iter().collect();
+    }
+    Ok((chain, predecessors))
+}
+
+/// Every state with a path to a `targets` state, found by backward search over `predecessors`
+/// (optionally only through states that are not `blocked`)
+fn backward_closure(targets: &[bool], predecessors: &[Vec<usize>], blocked: &[bool]) -> Vec<bool> {
+    let mut reached = targets.to_vec();
+    let mut frontier: VecDeque<usize> = (0..targets.len()).filter(|&i| targets[i]).collect();
+    while let Some(j) = frontier.pop_front() {
+        for &i in &predecessors[j] {
+            if !reached[i] && !blocked[i] {
+                reached[i] = true;
+                frontier.push_back(i);
+            }
+        }
+    }
+    reached
+}
+
+/// # Reach Probability
+///
+/// The probability of eventually reaching a state satisfying `goal` from every state, when
+/// actions are drawn from `policy`. Goal states have probability one. Final states that are not
+/// goals, states where the policy is undefined, and states from which no goal is reachable under
+/// the policy have probability zero. The rest solve the linear system
+///
+/// `x(s) = Σ_a π(a | s) Σ_s' p(s' | s, a) x(s')`
+///
+/// exactly (next states outside `all_states()` count as zero).
+///
+/// # Arguments
+/// * `mdp` - The MDP
+/// * `policy` - The policy to check (see `policy::to_stochastic` for deterministic ones)
+/// * `goal` - Which states count as reached
+///
+/// # Returns
+/// The reach probability of every state
+pub fn reach_probability<M>(
+    mdp: &M,
+    policy: &StochasticPolicy<M::State, M::Action>,
+    goal: impl Fn(&M::State) -> bool,
+) -> Result<HashMap<M::State, f64>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let states: Vec<M::State> = mdp.all_states().iter().cloned().collect();
+    let n = states.len();
+    let is_goal: Vec<bool> = states.iter().map(&goal).collect();
+    let (chain, predecessors) = policy_chain(mdp, policy, &states, &is_goal)?;
+
+    // States that reach a goal with positive probability
+    let can_reach = backward_closure(&is_goal, &predecessors, &vec![false; n]);
+
+    // Unknowns: states that can reach a goal but are not goals themselves
+    let unknowns: Vec<usize> = (0..n).filter(|&i| can_reach[i] && !is_goal[i]).collect();
+    let position: HashMap<usize, usize> =
+        unknowns.iter().enumerate().map(|(k, &i)| (i, k)).collect();
+    let mut a = vec![vec![0.0; unknowns.len()]; unknowns.len()];
+    let mut b = vec![0.0; unknowns.len()];
+    for (k, &i) in unknowns.iter().enumerate() {
+        a[k][k] += 1.0;
+        for &(j, p) in &chain[i] {
+            if is_goal[j] {
+                b[k] += p;
+            } else if let Some(&l) = position.get(&j) {
+                a[k][l] -= p;
+            }
+        }
+    }
+    let solution = solve_linear_system(a, b)?;
+
+    let mut probabilities = vec![0.0; n];
+    for (i, probability) in probabilities.iter_mut().enumerate() {
+        if is_goal[i] {
+            *probability = 1.0;
+        }
+    }
+    for (&i, x) in unknowns.iter().zip(solution) {
+        probabilities[i] = x.clamp(0.0, 1.0);
+    }
+    Ok(states.into_iter().zip(probabilities).collect())
+}
+
+/// # Expected Hitting Time
+///
+/// The expected number of steps until a state satisfying `target` is first visited, from every
+/// state, when actions are drawn from `policy`. Target states take zero steps. States from which
+/// the target is missed with positive probability (a final state, a state where the policy is
+/// undefined, a next state outside `all_states()` or a region the target is unreachable from can
+/// be hit first) take `f64::INFINITY`. The rest solve the linear system
+///
+/// `h(s) = 1 + Σ_a π(a | s) Σ_s' p(s' | s, a) h(s')`
+///
+/// exactly.
+///
+/// # Arguments
+/// * `mdp` - The MDP
+/// * `policy` - The policy to evaluate (see `policy::to_stochastic` for deterministic ones)
+/// * `target` - Which states end the count
+///
+/// # Returns
+/// The expected hitting time of every state
+pub fn expected_hitting_time<M>(
+    mdp: &M,
+    policy: &StochasticPolicy<M::State, M::Action>,
+    target: impl Fn(&M::State) -> bool,
+) -> Result<HashMap<M::State, f64>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let states: Vec<M::State> = mdp.all_states().iter().cloned().collect();
+    let n = states.len();
+    let is_target: Vec<bool> = states.iter().map(&target).collect();
+    let (chain, predecessors) = policy_chain(mdp, policy, &states, &is_target)?;
+
+    // Where the walk can stop or leak before the target, and everything that can get there first
+    let can_reach = backward_closure(&is_target, &predecessors, &vec![false; n]);
+    let missed: Vec<bool> = (0..n)
+        .map(|i| {
+            let mass: f64 = chain[i].iter().map(|(_, p)| p).sum();
+            !is_target[i] && (!can_reach[i] || mass < 1.0 - LEAK_TOLERANCE)
+        })
+        .collect();
+    let infinite = backward_closure(&missed, &predecessors, &is_target);
+
+    let unknowns: Vec<usize> = (0..n).filter(|&i| !is_target[i] && !infinite[i]).collect();
+    let position: HashMap<usize, usize> =
+        unknowns.iter().enumerate().map(|(k, &i)| (i, k)).collect();
+    let mut a = vec![vec![0.0; unknowns.len()]; unknowns.len()];
+    let b = vec![1.0; unknowns.len()];
+    for (k, &i) in unknowns.iter().enumerate() {
+        a[k][k] += 1.0;
+        for &(j, p) in &chain[i] {
+            if let Some(&l) = position.get(&j) {
+                a[k][l] -= p;
+            }
+        }
+    }
+    let solution = solve_linear_system(a, b)?;
+
+    let mut times: Vec<f64> = infinite
+        .iter()
+        .zip(&is_target)
+        .map(|(&inf, &hit)| if inf && !hit { f64::INFINITY } else { 0.0 })
+        .collect();
+    for (&i, h) in unknowns.iter().zip(solution) {
+        times[i] = h.max(0.0);
+    }
+    Ok(states.into_iter().zip(times).collect())
+}
+
+/// # Maximal Reach Probability
+///
+/// The highest probability of eventually reaching a state satisfying `goal` that any policy
+/// achieves from each state, by value iteration on probabilities
+///
+/// `x(s) ← max_a Σ_s' p(s' | s, a) x(s')`, with `x = 1` on goals,
+///
+/// started from zero so it converges to the least fixed point. Stops once no probability changes
+/// by more than `tolerance` or after `max_iterations` sweeps. Non-goal final states have
+/// probability zero.
+///
+/// # Returns
+/// The probabilities (as `values`) and an optimal policy. Among actions with the maximal
+/// probability the policy takes one that makes progress toward a goal, so it does not dither
+/// between equally good actions forever.
+pub fn max_reach_probability<M>(
+    mdp: &M,
+    goal: impl Fn(&M::State) -> bool,
+    tolerance: f64,
+    max_iterations: usize,
+) -> Result<ValueIterationResult<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let mut table = Vec::new();
+    for state in mdp.all_states().iter() {
+        let mut backups = Vec::new();
+        let is_goal = goal(state);
+        if !is_goal && !mdp.is_final_state(state) {
+            for action in mdp.actions_at(state) {
+                let (measure, _) = mdp.stochastic_transition(state, &action)?;
+                let outcomes: Vec<(M::State, f64)> = measure
+                    .dist()
+                    .iter()
+                    .map(|(s, p)| (s.clone(), p.value()))
+                    .collect();
+                backups.push((action, outcomes));
+            }
+        }
+        table.push((state.clone(), is_goal, backups));
+    }
+
+    let backup = |outcomes: &[(M::State, f64)], values: &HashMap<M::State, f64>| -> f64 {
+        outcomes
+            .iter()
+            .map(|(s, p)| p * values.get(s).copied().unwrap_or(0.0))
+            .sum()
+    };
+
+    let mut values: HashMap<M::State, f64> = table
+        .iter()
+        .map(|(s, is_goal, _)| (s.clone(), if *is_goal { 1.0 } else { 0.0 }))
+        .collect();
+    let mut iterations = 0;
+    let mut converged = false;
+    while iterations < max_iterations {
+        iterations += 1;
+        let mut max_change: f64 = 0.0;
+        let mut updated = HashMap::with_capacity(values.len());
+        for (state, is_goal, backups) in &table {
+            let value = if *is_goal {
+                1.0
+            } else {
+                backups
+                    .iter()
+                    .map(|(_, outcomes)| backup(outcomes, &values))
+                    .fold(0.0, f64::max)
+            };
+            max_change = max_change.max((value - values[state]).abs());
+            updated.insert(state.clone(), value);
+        }
+        values = updated;
+        if max_change <= tolerance {
+            converged = true;
+            break;
+        }
+    }
+
+    // Among optimal actions, pick one that moves closer to a goal: states are ranked by rounds of
+    // a backward search from the goals, and each state takes an optimal action with a successor of
+    // an earlier round, so following the policy cannot dither forever between optimal actions.
+    let slack = tolerance.max(1e-12);
+    let mut rank: HashMap<&M::State, usize> = table
+        .iter()
+        .filter(|(_, is_goal, _)| *is_goal)
+        .map(|(s, _, _)| (s, 0))
+        .collect();
+    let mut policy = DeterministicPolicy::new();
+    let mut round = 0;
+    loop {
+        round += 1;
+        let mut ranked = Vec::new();
+        for (state, _, backups) in &table {
+            if rank.contains_key(state) || values[state] <= 0.0 {
+                continue;
+            }
+            let progressing = backups.iter().find(|(_, outcomes)| {
+                backup(outcomes, &values) >= values[state] - slack
+                    && outcomes
+                        .iter()
+                        .any(|(s, p)| *p > 0.0 && rank.get(s).is_some_and(|&r| r < round))
+            });
+            if let Some((action, _)) = progressing {
+                policy.insert(state.clone(), action.clone());
+                ranked.push(state);
+            }
+        }
+        if ranked.is_empty() {
+            break;
+        }
+        for state in ranked {
+            rank.insert(state, round);
+        }
+    }
+    // States that cannot reach a goal (or were not ranked) keep any greedy action
+    for (state, _, backups) in &table {
+        if policy.contains_key(state) {
+            continue;
+        }
+        let mut best: Option<(&M::Action, f64)> = None;
+        for (action, outcomes) in backups {
+            let value = backup(outcomes, &values);
+            if best.is_none_or(|(_, best_value)| value > best_value) {
+                best = Some((action, value));
+            }
+        }
+        if let Some((action, _)) = best {
+            policy.insert(state.clone(), action.clone());
+        }
+    }
+
+    Ok(ValueIterationResult {
+        values,
+        policy,
+        iterations,
+        converged,
+    })
+}
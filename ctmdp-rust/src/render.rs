@@ -0,0 +1,450 @@
+//! # Text Rendering
+//!
+//! Plain-text pictures of grid environments for the terminal: the layout itself, a shaded
+//! heatmap of state values, the arrows of a policy, and the frames of a rollout with a
+//! step-through viewer. Anything implementing `GridView` can be drawn; `SideBySide` places two
+//! views next to each other, so rollouts of a product of gridworlds show both components at
+//! once.
+//!
+//! Background characters are `#` for walls, `.` for open ground, `S` for the start and `G` for
+//! goals, plus environment-specific ones (`C` for a cliff, `H` for a hole).
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::io::{BufRead, Write};
+
+use madepro::environments::gridworld::{Cell, GridworldAction, GridworldState};
+
+use crate::dataset::Trajectory;
+use crate::error::Error;
+use crate::gridworld::{
+    CliffWalking, FourRooms, FrozenLake, GridMove, GridPosition, GridworldWithGoals,
+    GridworldWithMoves, Tile, WindyGridworld,
+};
+use crate::policy::Policy;
+use crate::products::Product;
+
+/// Shades from lowest to highest value
+const SHADES: [char; 5] = [' ', '░', '▒', '▓', '█'];
+
+/// Columns between the two halves of a `SideBySide`
+const SIDE_BY_SIDE_GAP: usize = 3;
+
+/// An environment that can be drawn as a grid of characters
+pub trait GridView {
+    type State;
+
+    /// `(rows, cols)` of the picture
+    fn size(&self) -> (usize, usize);
+
+    /// The character drawn at `position` when nothing is on it
+    fn background(&self, position: GridPosition) -> char;
+
+    /// The cells `state` occupies: one for a grid, one per component for `SideBySide`
+    fn cells_of(&self, state: &Self::State) -> Vec<GridPosition>;
+}
+
+/// The cell `state` occupies, if it occupies exactly one
+fn single_cell<V: GridView>(view: &V, state: &V::State) -> Option<GridPosition> {
+    match view.cells_of(state)[..] {
+        [position] => Some(position),
+        _ => None,
+    }
+}
+
+/// Draws the picture row by row, asking `overlay` for a character before falling back to the
+/// background
+fn draw<V: GridView>(view: &V, overlay: impl Fn(GridPosition) -> Option<char>) -> String {
+    let (rows, cols) = view.size();
+    let mut out = String::with_capacity(rows * (cols + 1));
+    for row in 0..rows {
+        for col in 0..cols {
+            let position = GridPosition::new(row, col);
+            out.push(overlay(position).unwrap_or_else(|| view.background(position)));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// # Render Grid
+///
+/// The layout of `view`: walls, open ground, start and goals.
+pub fn render_grid<V: GridView>(view: &V) -> String {
+    draw(view, |_| None)
+}
+
+/// # Render Values
+///
+/// A heatmap of `values` (for example `ValueIterationResult::values`): every state that
+/// occupies a single cell is shaded from blank (lowest value) to `█` (highest), walls and
+/// states without a value keep their background. A legend with the range follows the grid.
+pub fn render_values<V>(view: &V, values: &HashMap<V::State, f64>) -> String
+where
+    V: GridView,
+    V::State: Eq + Hash,
+{
+    let (min, max) = values
+        .values()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| {
+            (lo.min(v), hi.max(v))
+        });
+    let shades: HashMap<GridPosition, char> = values
+        .iter()
+        .filter_map(|(state, &value)| {
+            let position = single_cell(view, state)?;
+            let level = if max > min {
+                ((value - min) / (max - min) * (SHADES.len() - 1) as f64).round() as usize
+            } else {
+                SHADES.len() - 1
+            };
+            Some((position, SHADES[level.min(SHADES.len() - 1)]))
+        })
+        .collect();
+    let mut out = draw(view, |position| shades.get(&position).copied());
+    if values.is_empty() {
+        out.push_str("(no values)\n");
+    } else {
+        out.push_str(&format!(
+            "'{}' = {min:.3} .. '{}' = {max:.3}\n",
+            SHADES[0],
+            SHADES[SHADES.len() - 1]
+        ));
+    }
+    out
+}
+
+/// # Render Policy
+///
+/// The action `policy` takes in every state of `states`, drawn with `arrow`. Goals keep their
+/// `G`; states where the policy has no action keep their background.
+///
+/// # Arguments
+/// * `view` - The grid to draw on
+/// * `states` - The states to ask the policy about, e.g. `mdp.all_states().iter()`
+/// * `policy` - The policy
+/// * `arrow` - The character for an action (`gridworld_arrow` for compass moves)
+pub fn render_policy<'a, V, A, P>(
+    view: &V,
+    states: impl IntoIterator<Item = &'a V::State>,
+    policy: &P,
+    arrow: impl Fn(&A) -> char,
+) -> String
+where
+    V: GridView,
+    V::State: 'a,
+    P: Policy<V::State, A>,
+{
+    let arrows: HashMap<GridPosition, char> = states
+        .into_iter()
+        .filter_map(|state| {
+            let position = single_cell(view, state)?;
+            let action = policy.action(state)?;
+            Some((position, arrow(&action)))
+        })
+        .collect();
+    draw(view, |position| {
+        if view.background(position) == 'G' {
+            None
+        } else {
+            arrows.get(&position).copied()
+        }
+    })
+}
+
+/// The arrow of a compass move
+pub fn gridworld_arrow(action: &GridworldAction) -> char {
+    match action {
+        GridworldAction::Up => '↑',
+        GridworldAction::Down => '↓',
+        GridworldAction::Left => '←',
+        GridworldAction::Right => '→',
+    }
+}
+
+/// The arrow of a move with diagonals (`·` for `Stay`)
+pub fn grid_move_arrow(action: &GridMove) -> char {
+    match action {
+        GridMove::Up => '↑',
+        GridMove::Down => '↓',
+        GridMove::Left => '←',
+        GridMove::Right => '→',
+        GridMove::UpLeft => '↖',
+        GridMove::UpRight => '↗',
+        GridMove::DownLeft => '↙',
+        GridMove::DownRight => '↘',
+        GridMove::Stay => '·',
+    }
+}
+
+/// # Render Trajectory
+///
+/// One frame per state visited by `trajectory`, with the agent drawn as `@` and a caption giving
+/// the step, the action taken from that state, its reward and the return so far. The last frame
+/// shows where the episode ended and whether it terminated or was truncated.
+pub fn render_trajectory<V, A>(view: &V, trajectory: &Trajectory<V::State, A>) -> Vec<String>
+where
+    V: GridView,
+    A: Debug,
+{
+    let frame = |state: &V::State| {
+        let agent = view.cells_of(state);
+        draw(view, |position| agent.contains(&position).then_some('@'))
+    };
+    let mut frames = Vec::with_capacity(trajectory.len() + 1);
+    let mut total = 0.0;
+    for (t, step) in trajectory.steps.iter().enumerate() {
+        total += step.reward;
+        frames.push(format!(
+            "{}step {t}: {:?}, reward {:.2}, return {total:.2}\n",
+            frame(&step.state),
+            step.action,
+            step.reward
+        ));
+    }
+    let outcome = if trajectory.terminal {
+        "terminated"
+    } else {
+        "truncated"
+    };
+    frames.push(format!(
+        "{}step {}: {outcome}, return {total:.2}\n",
+        frame(&trajectory.last_state),
+        trajectory.len()
+    ));
+    frames
+}
+
+/// # Step Through
+///
+/// Shows the frames of `render_trajectory` one at a time on `output`, advancing on every line
+/// read from `input` (Enter in a terminal) and stopping early on `q`.
+///
+/// # Returns
+/// `Ok(())`, or `Error::Io` if reading or writing fails
+pub fn step_through<V, A>(
+    view: &V,
+    trajectory: &Trajectory<V::State, A>,
+    mut input: impl BufRead,
+    mut output: impl Write,
+) -> Result<(), Error>
+where
+    V: GridView,
+    A: Debug,
+{
+    let io_error = |e: std::io::Error| Error::Io(format!("rollout viewer: {e}"));
+    let frames = render_trajectory(view, trajectory);
+    let count = frames.len();
+    for (i, frame) in frames.into_iter().enumerate() {
+        write!(output, "{frame}").map_err(io_error)?;
+        if i + 1 == count {
+            break;
+        }
+        write!(output, "[Enter: next, q: quit] ").map_err(io_error)?;
+        output.flush().map_err(io_error)?;
+        let mut line = String::new();
+        let read = input.read_line(&mut line).map_err(io_error)?;
+        if read == 0 || line.trim() == "q" {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Two views drawn next to each other; the states are products of theirs
+pub struct SideBySide<'a, V1, V2> {
+    left: &'a V1,
+    right: &'a V2,
+}
+
+impl<'a, V1, V2> SideBySide<'a, V1, V2> {
+    pub fn new(left: &'a V1, right: &'a V2) -> Self {
+        SideBySide { left, right }
+    }
+}
+
+impl<V1: GridView, V2: GridView> SideBySide<'_, V1, V2> {
+    fn right_offset(&self) -> usize {
+        self.left.size().1 + SIDE_BY_SIDE_GAP
+    }
+}
+
+impl<V1: GridView, V2: GridView> GridView for SideBySide<'_, V1, V2> {
+    type State = Product<V1::State, V2::State>;
+
+    fn size(&self) -> (usize, usize) {
+        let (left_rows, _) = self.left.size();
+        let (right_rows, right_cols) = self.right.size();
+        (left_rows.max(right_rows), self.right_offset() + right_cols)
+    }
+
+    fn background(&self, position: GridPosition) -> char {
+        let offset = self.right_offset();
+        let (left_rows, left_cols) = self.left.size();
+        let (right_rows, _) = self.right.size();
+        if position.col() < left_cols {
+            if position.row() < left_rows {
+                self.left.background(position)
+            } else {
+                ' '
+            }
+        } else if position.col() >= offset && position.row() < right_rows {
+            self.right
+                .background(GridPosition::new(position.row(), position.col() - offset))
+        } else {
+            ' '
+        }
+    }
+
+    fn cells_of(&self, state: &Self::State) -> Vec<GridPosition> {
+        let offset = self.right_offset();
+        let mut cells = self.left.cells_of(state.first());
+        cells.extend(
+            self.right
+                .cells_of(state.second())
+                .into_iter()
+                .map(|p| GridPosition::new(p.row(), p.col() + offset)),
+        );
+        cells
+    }
+}
+
+/// Gridworlds built from a map (`from_ascii`, `generate_maze`); others have no known layout
+/// and draw as an empty picture
+impl GridView for GridworldWithGoals {
+    type State = GridworldState;
+
+    fn size(&self) -> (usize, usize) {
+        self.cells().map_or((0, 0), |cells| {
+            (cells.len(), cells.first().map_or(0, Vec::len))
+        })
+    }
+
+    fn background(&self, position: GridPosition) -> char {
+        let state = GridworldState::new(position.row(), position.col());
+        if self.get_start() == Some(&state) {
+            return 'S';
+        }
+        let cell = self
+            .cells()
+            .and_then(|cells| cells.get(position.row())?.get(position.col()));
+        match cell {
+            Some(Cell::Wall) => '#',
+            Some(Cell::End) => 'G',
+            Some(Cell::Air) if self.get_goals().contains(&state) => 'G',
+            Some(Cell::Air) => '.',
+            None => ' ',
+        }
+    }
+
+    fn cells_of(&self, state: &Self::State) -> Vec<GridPosition> {
+        self.position(state).into_iter().collect()
+    }
+}
+
+impl GridView for GridworldWithMoves {
+    type State = GridworldState;
+
+    fn size(&self) -> (usize, usize) {
+        (**self).size()
+    }
+
+    fn background(&self, position: GridPosition) -> char {
+        (**self).background(position)
+    }
+
+    fn cells_of(&self, state: &Self::State) -> Vec<GridPosition> {
+        (**self).cells_of(state)
+    }
+}
+
+impl GridView for WindyGridworld {
+    type State = GridPosition;
+
+    fn size(&self) -> (usize, usize) {
+        self.dimensions()
+    }
+
+    fn background(&self, position: GridPosition) -> char {
+        if position == self.start() {
+            'S'
+        } else if position == self.goal() {
+            'G'
+        } else {
+            '.'
+        }
+    }
+
+    fn cells_of(&self, state: &Self::State) -> Vec<GridPosition> {
+        vec![*state]
+    }
+}
+
+impl GridView for CliffWalking {
+    type State = GridPosition;
+
+    fn size(&self) -> (usize, usize) {
+        self.dimensions()
+    }
+
+    fn background(&self, position: GridPosition) -> char {
+        if position == self.start() {
+            'S'
+        } else if position == self.goal() {
+            'G'
+        } else if self.is_cliff(&position) {
+            'C'
+        } else {
+            '.'
+        }
+    }
+
+    fn cells_of(&self, state: &Self::State) -> Vec<GridPosition> {
+        vec![*state]
+    }
+}
+
+impl GridView for FourRooms {
+    type State = GridPosition;
+
+    fn size(&self) -> (usize, usize) {
+        (FourRooms::SIZE, FourRooms::SIZE)
+    }
+
+    fn background(&self, position: GridPosition) -> char {
+        if position == self.goal() {
+            'G'
+        } else if self.is_wall(&position) {
+            '#'
+        } else {
+            '.'
+        }
+    }
+
+    fn cells_of(&self, state: &Self::State) -> Vec<GridPosition> {
+        vec![*state]
+    }
+}
+
+impl GridView for FrozenLake {
+    type State = GridPosition;
+
+    fn size(&self) -> (usize, usize) {
+        self.dimensions()
+    }
+
+    fn background(&self, position: GridPosition) -> char {
+        match self.tile(&position) {
+            Some(Tile::Start) => 'S',
+            Some(Tile::Frozen) => '.',
+            Some(Tile::Hole) => 'H',
+            Some(Tile::Goal) => 'G',
+            None => ' ',
+        }
+    }
+
+    fn cells_of(&self, state: &Self::State) -> Vec<GridPosition> {
+        vec![*state]
+    }
+}
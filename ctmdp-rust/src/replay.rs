@@ -0,0 +1,81 @@
+//! # Experience Replay
+//!
+//! A bounded buffer of observed transitions that learners can revisit with extra updates between
+//! environment steps, and which offline methods can consume directly.
+
+use std::collections::VecDeque;
+
+use rand::Rng;
+
+/// A single observed transition `(s, a, r, s', done)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition<S, A> {
+    pub state: S,
+    pub action: A,
+    pub reward: f64,
+    pub next_state: S,
+    /// Whether `next_state` is final, in which case targets do not bootstrap from it
+    pub done: bool,
+}
+
+/// A FIFO buffer of transitions that evicts the oldest entry once `capacity` is reached
+#[derive(Debug, Clone)]
+pub struct ReplayBuffer<S, A> {
+    transitions: VecDeque<Transition<S, A>>,
+    capacity: usize,
+}
+
+impl<S, A> ReplayBuffer<S, A> {
+    pub fn new(capacity: usize) -> Self {
+        ReplayBuffer {
+            transitions: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.transitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transitions.is_empty()
+    }
+
+    pub fn push(&mut self, transition: Transition<S, A>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.transitions.len() == self.capacity {
+            self.transitions.pop_front();
+        }
+        self.transitions.push_back(transition);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Transition<S, A>> {
+        self.transitions.iter()
+    }
+
+    /// Samples `batch_size` transitions uniformly at random (with replacement).
+    /// Returns an empty batch if the buffer is empty.
+    pub fn sample(&self, batch_size: usize) -> Vec<&Transition<S, A>> {
+        self.sample_with(batch_size, &mut rand::rng())
+    }
+
+    /// `sample` drawing from `rng`
+    pub fn sample_with<R: Rng + ?Sized>(
+        &self,
+        batch_size: usize,
+        rng: &mut R,
+    ) -> Vec<&Transition<S, A>> {
+        if self.transitions.is_empty() {
+            return Vec::new();
+        }
+        (0..batch_size)
+            .map(|_| &self.transitions[rng.random_range(0..self.transitions.len())])
+            .collect()
+    }
+}
@@ -0,0 +1,271 @@
+//! # Experiment Reports
+//!
+//! A `Report` collects what every trial of an experiment measured (policy distance, average
+//! return, goal-hit rates, wall-clock time) together with the metadata needed to reproduce it:
+//! the experiment, the learner, the base seed, the git commit and each trial's hyperparameters.
+//! Reports are written as JSON (one document with the metadata and a list of trials) or as CSV
+//! (one row per trial, the metadata repeated in every row), so runs can be aggregated and
+//! plotted downstream instead of read off the terminal.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::TrainingConfig;
+use crate::error::Error;
+
+/// On-disk encoding of a `Report`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    /// One row per trial
+    Csv,
+}
+
+impl ReportFormat {
+    /// `Csv` for paths ending in `.csv`, `Json` otherwise
+    pub fn from_path(path: impl AsRef<Path>) -> ReportFormat {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => ReportFormat::Csv,
+            _ => ReportFormat::Json,
+        }
+    }
+}
+
+/// The hyperparameters a trial was trained with
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TrialConfig {
+    pub episodes: u32,
+    pub max_steps: u32,
+    pub learning_rate: f64,
+    pub discount_factor: f64,
+    pub exploration_rate: f64,
+}
+
+impl From<&TrainingConfig> for TrialConfig {
+    fn from(config: &TrainingConfig) -> Self {
+        TrialConfig {
+            episodes: config.num_episodes,
+            max_steps: config.max_num_steps,
+            learning_rate: config.learning_rate,
+            discount_factor: config.discount_factor,
+            exploration_rate: config.exploration_rate,
+        }
+    }
+}
+
+/// What one training run of one model measured. Metrics an experiment does not compute are
+/// left out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trial {
+    /// The experiment's setting, e.g. `length 6`
+    pub setting: String,
+    /// The model that was trained, e.g. `BP` or `CP`
+    pub product: String,
+    /// Index of the run within its setting, counting from 0
+    pub run: usize,
+    pub seed: Option<u64>,
+    pub config: TrialConfig,
+    /// Fraction of states where the learned policy differs from the reference one
+    pub policy_distance: Option<f64>,
+    /// Average return of the learned policy over evaluation rollouts
+    pub mean_return: Option<f64>,
+    /// Fraction of evaluation rollouts reaching each goal, by goal name
+    pub goal_hit_rates: BTreeMap<String, f64>,
+    /// Time spent training and evaluating, in seconds
+    pub wall_clock_secs: f64,
+}
+
+impl Trial {
+    pub fn new(
+        setting: impl Into<String>,
+        product: impl Into<String>,
+        run: usize,
+        config: &TrainingConfig,
+    ) -> Self {
+        Trial {
+            setting: setting.into(),
+            product: product.into(),
+            run,
+            seed: config.seed,
+            config: TrialConfig::from(config),
+            policy_distance: None,
+            mean_return: None,
+            goal_hit_rates: BTreeMap::new(),
+            wall_clock_secs: 0.0,
+        }
+    }
+
+    pub fn with_policy_distance(mut self, distance: f64) -> Self {
+        self.policy_distance = Some(distance);
+        self
+    }
+
+    pub fn with_mean_return(mut self, mean_return: f64) -> Self {
+        self.mean_return = Some(mean_return);
+        self
+    }
+
+    pub fn with_goal_hit_rate(mut self, goal: impl Into<String>, rate: f64) -> Self {
+        self.goal_hit_rates.insert(goal.into(), rate);
+        self
+    }
+
+    pub fn with_wall_clock(mut self, elapsed: Duration) -> Self {
+        self.wall_clock_secs = elapsed.as_secs_f64();
+        self
+    }
+}
+
+/// The trials of one experiment and the metadata to reproduce them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Report {
+    pub experiment: String,
+    pub algorithm: String,
+    /// Seed of the first run, if seeded
+    pub seed: Option<u64>,
+    /// Commit the experiment ran on, if known
+    pub git_hash: Option<String>,
+    pub trials: Vec<Trial>,
+}
+
+/// Columns of every CSV row before the goal-hit rates
+const CSV_COLUMNS: [&str; 14] = [
+    "experiment",
+    "algorithm",
+    "git_hash",
+    "setting",
+    "product",
+    "run",
+    "seed",
+    "episodes",
+    "max_steps",
+    "learning_rate",
+    "discount_factor",
+    "exploration_rate",
+    "policy_distance",
+    "mean_return",
+];
+
+/// `fields` joined into one CSV line, each quoted if it contains a separator, a quote or a line
+/// break
+fn csv_line(fields: &[String]) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+impl Report {
+    pub fn new(experiment: impl Into<String>, algorithm: impl Into<String>) -> Self {
+        Report {
+            experiment: experiment.into(),
+            algorithm: algorithm.into(),
+            seed: None,
+            git_hash: None,
+            trials: Vec::new(),
+        }
+    }
+
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_git_hash(mut self, git_hash: Option<String>) -> Self {
+        self.git_hash = git_hash;
+        self
+    }
+
+    pub fn push(&mut self, trial: Trial) {
+        self.trials.push(trial);
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::Serialization(e.to_string()))
+    }
+
+    /// # CSV
+    ///
+    /// A header and one row per trial. Goal-hit rates get a `goal_hit_rate:<goal>` column per
+    /// goal any trial reports; missing metrics are empty fields.
+    pub fn to_csv(&self) -> String {
+        let goals: BTreeSet<&String> = self
+            .trials
+            .iter()
+            .flat_map(|trial| trial.goal_hit_rates.keys())
+            .collect();
+
+        let mut header: Vec<String> = CSV_COLUMNS.iter().map(|c| c.to_string()).collect();
+        header.extend(goals.iter().map(|goal| format!("goal_hit_rate:{goal}")));
+        header.push("wall_clock_secs".to_string());
+
+        let mut lines = vec![csv_line(&header)];
+        for trial in &self.trials {
+            let mut row = vec![
+                self.experiment.clone(),
+                self.algorithm.clone(),
+                self.git_hash.clone().unwrap_or_default(),
+                trial.setting.clone(),
+                trial.product.clone(),
+                trial.run.to_string(),
+                optional(trial.seed),
+                trial.config.episodes.to_string(),
+                trial.config.max_steps.to_string(),
+                trial.config.learning_rate.to_string(),
+                trial.config.discount_factor.to_string(),
+                trial.config.exploration_rate.to_string(),
+                optional(trial.policy_distance),
+                optional(trial.mean_return),
+            ];
+            row.extend(
+                goals
+                    .iter()
+                    .map(|goal| optional(trial.goal_hit_rates.get(*goal))),
+            );
+            row.push(trial.wall_clock_secs.to_string());
+            lines.push(csv_line(&row));
+        }
+
+        lines.join("\n") + "\n"
+    }
+
+    /// Writes the report to `path` in the given format, replacing any existing file
+    pub fn save(&self, path: impl AsRef<Path>, format: ReportFormat) -> Result<(), Error> {
+        let path = path.as_ref();
+        let contents = match format {
+            ReportFormat::Json => self.to_json()?,
+            ReportFormat::Csv => self.to_csv(),
+        };
+        fs::write(path, contents).map_err(|e| Error::Io(format!("{}: {e}", path.display())))
+    }
+}
+
+/// The commit checked out in the current directory, if it is inside a git repository and
+/// `git` is on the path
+pub fn git_hash() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let hash = String::from_utf8(output.stdout).ok()?;
+    Some(hash.trim().to_string()).filter(|hash| !hash.is_empty())
+}
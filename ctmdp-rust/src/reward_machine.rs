@@ -0,0 +1,246 @@
+//! # Reward Machines
+//!
+//! Finite automata that read a label of every visited MDP state and pay a reward on each edge
+//! they take (Toro Icarte et al., 2018). They express temporally extended tasks such as "visit A,
+//! then B" that a reward on single states cannot. `RewardMachineProduct` runs a machine alongside
+//! an MDP, so the product state `(s, u)` is Markov again and every learner in the crate applies.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use indexmap::IndexMap;
+use madepro::models::{Sampler, State};
+
+use crate::error::Error;
+use crate::labeling::{Labeled, Labels};
+use crate::mdp::MDP;
+use crate::measure::{Measure, Probability};
+use crate::products::Product;
+
+/// A state of a `RewardMachine`, numbered from zero
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub struct MachineState(pub usize);
+
+impl State for MachineState {}
+
+/// A deterministic finite automaton over labels `L`. Reading a label without an outgoing edge
+/// leaves the machine where it is and pays nothing.
+#[derive(Debug, Clone)]
+pub struct RewardMachine<L> {
+    num_states: usize,
+    initial: MachineState,
+    terminal: HashSet<MachineState>,
+    edges: HashMap<(MachineState, L), (MachineState, f64)>,
+}
+
+impl<L: Eq + Hash> RewardMachine<L> {
+    /// A machine with states `0..num_states`, starting in state `0`, with no edges
+    pub fn new(num_states: usize) -> Self {
+        RewardMachine {
+            num_states,
+            initial: MachineState(0),
+            terminal: HashSet::new(),
+            edges: HashMap::new(),
+        }
+    }
+
+    pub fn num_states(&self) -> usize {
+        self.num_states
+    }
+
+    pub fn initial_state(&self) -> MachineState {
+        self.initial
+    }
+
+    pub fn states(&self) -> impl Iterator<Item = MachineState> + '_ {
+        (0..self.num_states).map(MachineState)
+    }
+
+    /// Adds the edge `from --label / reward--> to`, replacing any edge `from` already had on
+    /// `label`. Fails with `Error::StateNotFound` if either state is out of range.
+    pub fn add_edge(&mut self, from: usize, label: L, to: usize, reward: f64) -> Result<(), Error> {
+        if from >= self.num_states || to >= self.num_states {
+            return Err(Error::StateNotFound);
+        }
+        self.edges
+            .insert((MachineState(from), label), (MachineState(to), reward));
+        Ok(())
+    }
+
+    /// Marks `state` as accepting: the task is done once the machine reaches it
+    pub fn set_terminal(&mut self, state: usize) -> Result<(), Error> {
+        if state >= self.num_states {
+            return Err(Error::StateNotFound);
+        }
+        self.terminal.insert(MachineState(state));
+        Ok(())
+    }
+
+    pub fn is_terminal(&self, state: MachineState) -> bool {
+        self.terminal.contains(&state)
+    }
+
+    /// The machine state after reading `label` in `state`, with the reward of the edge taken
+    pub fn step(&self, state: MachineState, label: &L) -> (MachineState, f64)
+    where
+        L: Clone,
+    {
+        self.edges
+            .get(&(state, label.clone()))
+            .copied()
+            .unwrap_or((state, 0.0))
+    }
+}
+
+/// Maps an MDP state to the label a reward machine reads
+type Labeler<S, L> = Box<dyn Fn(&S) -> L>;
+
+/// The product of an MDP with a reward machine. A transition moves the MDP as usual, then feeds
+/// the label of the next state to the machine; the reward is the expected edge reward (plus the
+/// MDP's own reward if `with_mdp_reward` is set). A product state is final once the machine
+/// reaches a terminal state or the MDP reaches a final state.
+pub struct RewardMachineProduct<M: MDP, L> {
+    mdp: M,
+    machine: RewardMachine<L>,
+    labeler: Labeler<M::State, L>,
+    include_mdp_reward: bool,
+    states: Sampler<Product<M::State, MachineState>>,
+}
+
+impl<M, L> RewardMachineProduct<M, L>
+where
+    M: MDP,
+    M::State: Clone,
+    L: Eq + Hash + Clone,
+{
+    pub fn new(
+        mdp: M,
+        machine: RewardMachine<L>,
+        labeler: impl Fn(&M::State) -> L + 'static,
+    ) -> Self {
+        let mut states = Vec::new();
+        for s in mdp.all_states().iter() {
+            for u in machine.states() {
+                states.push(Product::new(s.clone(), u));
+            }
+        }
+        RewardMachineProduct {
+            mdp,
+            machine,
+            labeler: Box::new(labeler),
+            include_mdp_reward: false,
+            states: Sampler::new(states),
+        }
+    }
+
+    /// Adds the MDP's reward to the machine's
+    pub fn with_mdp_reward(mut self) -> Self {
+        self.include_mdp_reward = true;
+        self
+    }
+
+    pub fn machine(&self) -> &RewardMachine<L> {
+        &self.machine
+    }
+
+    pub fn label(&self, state: &M::State) -> L {
+        (self.labeler)(state)
+    }
+
+    /// The product state for MDP state `state` with the machine in its initial state
+    pub fn initial_state(&self, state: M::State) -> Product<M::State, MachineState> {
+        Product::new(state, self.machine.initial_state())
+    }
+
+    /// The product state for MDP state `state` with the machine having read the label of
+    /// `state` from its initial state (the reward of that first edge is not paid)
+    pub fn start_state(&self, state: M::State) -> Product<M::State, MachineState> {
+        let (machine_state, _) = self
+            .machine
+            .step(self.machine.initial_state(), &self.label(&state));
+        Product::new(state, machine_state)
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mdp
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mdp
+    }
+}
+
+impl<M> RewardMachineProduct<M, Labels>
+where
+    M: Labeled,
+    M::State: Clone + 'static,
+{
+    /// Runs `machine` on the proposition sets of a labeled MDP. Labels are computed once per
+    /// state up front.
+    pub fn from_labeled(mdp: M, machine: RewardMachine<Labels>) -> Self {
+        let labels: HashMap<M::State, Labels> = mdp
+            .all_states()
+            .iter()
+            .map(|s| (s.clone(), mdp.labels(s)))
+            .collect();
+        Self::new(mdp, machine, move |s| {
+            labels.get(s).cloned().unwrap_or_default()
+        })
+    }
+}
+
+impl<M, L> MDP for RewardMachineProduct<M, L>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+    L: Eq + Hash + Clone,
+{
+    type State = Product<M::State, MachineState>;
+    type Action = M::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        &self.states
+    }
+
+    fn all_actions(&self) -> Vec<Self::Action> {
+        self.mdp.all_actions()
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        self.mdp.actions_at(state.first())
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.machine.is_terminal(*state.second()) || self.mdp.is_final_state(state.first())
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.machine.is_terminal(*state.second())
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        let (measure, mdp_reward) = self.mdp.stochastic_transition(state.first(), action)?;
+        let mut dist: IndexMap<Self::State, f64> = IndexMap::new();
+        let mut machine_reward = 0.0;
+        for (next, p) in measure.dist() {
+            let (u, reward) = self.machine.step(*state.second(), &self.label(next));
+            machine_reward += p.value() * reward;
+            *dist.entry(Product::new(next.clone(), u)).or_insert(0.0) += p.value();
+        }
+        let dist = dist
+            .into_iter()
+            .map(|(s, p)| Ok((s, Probability::new(p.min(1.0))?)))
+            .collect::<Result<IndexMap<_, _>, Error>>()?;
+        let reward = if self.include_mdp_reward {
+            machine_reward + mdp_reward
+        } else {
+            machine_reward
+        };
+        Ok((Measure::from_distribution(dist)?, reward))
+    }
+}
@@ -0,0 +1,189 @@
+//! # Risk-Sensitive Learning
+//!
+//! Criteria that look at more of the return distribution than its mean. `risk_sensitive_q_learning`
+//! learns certainty equivalents under an exponential utility; `conditional_value_at_risk` and
+//! `value_at_risk` summarize the tail of sampled or categorical return distributions (e.g.
+//! `Dataset::returns` or `ReturnDistributions::probabilities`).
+
+use madepro::models::ActionValue;
+use rand::Rng;
+use rand::seq::IndexedRandom;
+
+use crate::config::TrainingConfig;
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::q_learning::{checked_action_union, checked_q_value, random_start};
+
+/// Moves the certainty equivalent `current` toward `target` in the exponential domain:
+/// `exp(βQ) ← (1 - α) exp(βQ) + α exp(β target)`, computed relative to `current` so the
+/// exponentials stay in range. `β = 0` is the ordinary average.
+fn exponential_update(current: f64, target: f64, rate: f64, beta: f64) -> f64 {
+    if beta == 0.0 {
+        return current + rate * (target - current);
+    }
+    let mixed = (1.0 - rate) + rate * (beta * (target - current)).exp();
+    current + mixed.ln() / beta
+}
+
+/// # Risk-Sensitive Q-Learning
+///
+/// Q-learning under the exponential utility (entropic risk) criterion. With sensitivity
+/// `β = config.risk_sensitivity` the learned values are certainty equivalents
+///
+/// `Q(s, a) = (1/β) log E[exp(β (r + γ max_a' Q(s', a')))]`
+///
+/// so `β < 0` penalizes the spread of returns (risk-averse), `β > 0` rewards it (risk-seeking) and
+/// `β = 0` is ordinary Q-learning. Each step moves `exp(βQ(s, a))` toward `exp(β target)` with
+/// weight `learning_rate`. Behavior is epsilon-greedy on the certainty equivalents.
+///
+/// # Arguments
+/// * `mdp` - The MDP to learn from
+/// * `config` - Configuration parameters (learning rate, discount factor, exploration rate, risk sensitivity, etc.)
+///
+/// # Returns
+/// An `ActionValue` table of certainty equivalents
+pub fn risk_sensitive_q_learning<M>(
+    mdp: &M,
+    config: &TrainingConfig,
+) -> Result<ActionValue<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let states = mdp.all_states();
+    let actions = checked_action_union(mdp)?;
+    let beta = config.risk_sensitivity;
+
+    let mut action_value = ActionValue::new(states, &actions);
+    let mut rng = config.rng();
+
+    for _ in 0..config.num_episodes {
+        let mut state = random_start(states, &mut rng)?.clone();
+
+        for _ in 0..config.max_num_steps {
+            if mdp.is_final_state(&state) {
+                break;
+            }
+            let available_actions = mdp.actions_at(&state);
+            let action = if rng.random::<f64>() < config.exploration_rate {
+                available_actions.choose(&mut rng).cloned()
+            } else {
+                best_action(&available_actions, |a| action_value.get(&state, a))
+            };
+            let Some(action) = action else {
+                break;
+            };
+
+            let (measure, reward) = mdp.stochastic_transition(&state, &action)?;
+            let next_state = measure
+                .sample_with(&mut rng)
+                .cloned()
+                .unwrap_or_else(|| state.clone());
+
+            let next_value = if mdp.is_final_state(&next_state) {
+                0.0
+            } else {
+                mdp.actions_at(&next_state)
+                    .iter()
+                    .map(|a| action_value.get(&next_state, a))
+                    .max_by(f64::total_cmp)
+                    .unwrap_or(0.0)
+            };
+            let target = reward + config.discount_factor * next_value;
+            let current_q = action_value.get(&state, &action);
+            let new_q = checked_q_value(exponential_update(
+                current_q,
+                target,
+                config.learning_rate,
+                beta,
+            ))?;
+            action_value.insert(&state, &action, new_q);
+
+            state = next_state;
+        }
+    }
+
+    Ok(action_value)
+}
+
+fn best_action<A: Clone>(actions: &[A], score: impl Fn(&A) -> f64) -> Option<A> {
+    let mut best: Option<(&A, f64)> = None;
+    for action in actions {
+        let value = score(action);
+        if best.is_none_or(|(_, best_value)| value > best_value) {
+            best = Some((action, value));
+        }
+    }
+    best.map(|(a, _)| a.clone())
+}
+
+/// Outcomes `(value, weight)` sorted worst first, with weights normalized to sum to one. `None` if
+/// there is no positive weight.
+fn sorted_outcomes(outcomes: &[(f64, f64)]) -> Option<Vec<(f64, f64)>> {
+    let total: f64 = outcomes.iter().map(|(_, w)| w.max(0.0)).sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let mut sorted: Vec<(f64, f64)> = outcomes
+        .iter()
+        .filter(|(_, w)| *w > 0.0)
+        .map(|&(v, w)| (v, w / total))
+        .collect();
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Some(sorted)
+}
+
+/// The lower `alpha`-quantile of a weighted distribution of returns `(value, weight)`: the
+/// smallest value whose cumulative weight reaches `alpha`. `None` if `alpha` is not in `(0, 1]`
+/// or there is no positive weight.
+pub fn value_at_risk(outcomes: &[(f64, f64)], alpha: f64) -> Option<f64> {
+    if !(alpha > 0.0 && alpha <= 1.0) {
+        return None;
+    }
+    let sorted = sorted_outcomes(outcomes)?;
+    let mut cumulative = 0.0;
+    for &(value, weight) in &sorted {
+        cumulative += weight;
+        if cumulative >= alpha - f64::EPSILON {
+            return Some(value);
+        }
+    }
+    sorted.last().map(|&(value, _)| value)
+}
+
+/// # Conditional Value at Risk
+///
+/// The expected return over the worst `alpha` fraction of a weighted distribution of returns
+/// `(value, weight)`, splitting the outcome that straddles the `alpha`-quantile. `alpha = 1` is the
+/// mean; small `alpha` approaches the worst case. Weights need not be normalized.
+///
+/// # Arguments
+/// * `outcomes` - Returns with their probabilities or counts
+/// * `alpha` - The tail fraction, in `(0, 1]`
+///
+/// # Returns
+/// `CVaR_α`, or `None` if `alpha` is out of range or there is no positive weight
+pub fn conditional_value_at_risk(outcomes: &[(f64, f64)], alpha: f64) -> Option<f64> {
+    if !(alpha > 0.0 && alpha <= 1.0) {
+        return None;
+    }
+    let sorted = sorted_outcomes(outcomes)?;
+    let mut remaining = alpha;
+    let mut total = 0.0;
+    for (value, weight) in sorted {
+        let taken = weight.min(remaining);
+        total += taken * value;
+        remaining -= taken;
+        if remaining <= 0.0 {
+            break;
+        }
+    }
+    Some(total / (alpha - remaining.max(0.0)))
+}
+
+/// `conditional_value_at_risk` of equally likely sampled returns
+pub fn empirical_cvar(returns: &[f64], alpha: f64) -> Option<f64> {
+    let outcomes: Vec<(f64, f64)> = returns.iter().map(|&r| (r, 1.0)).collect();
+    conditional_value_at_risk(&outcomes, alpha)
+}
@@ -0,0 +1,216 @@
+//! # Robust MDPs
+//!
+//! MDPs whose transition measures are only known up to an uncertainty set. `RobustMDP` attaches
+//! an L1 radius to every nominal transition measure; `robust_value_iteration` plans against the
+//! worst distribution in each set (adversarial nature, rectangular uncertainty).
+
+use std::collections::HashMap;
+
+use madepro::models::Sampler;
+
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::measure::Measure;
+use crate::planning::ValueIterationResult;
+use crate::policy::DeterministicPolicy;
+
+/// Uncertainty radius of the transition measure of `(s, a)`
+type RadiusFn<S, A> = Box<dyn Fn(&S, &A) -> f64>;
+
+/// An MDP whose true transition measure at `(s, a)` may be any measure on the nominal support
+/// within L1 distance `radius(s, a)` of the nominal one. As an `MDP` it behaves like the nominal
+/// model; the uncertainty only matters to robust solvers.
+pub struct RobustMDP<M: MDP> {
+    mdp: M,
+    radius: RadiusFn<M::State, M::Action>,
+}
+
+impl<M: MDP> RobustMDP<M> {
+    /// The same radius for every state-action pair
+    pub fn new(mdp: M, radius: f64) -> Self {
+        Self::with_radius_fn(mdp, move |_, _| radius)
+    }
+
+    /// A radius per state-action pair
+    pub fn with_radius_fn(mdp: M, radius: impl Fn(&M::State, &M::Action) -> f64 + 'static) -> Self {
+        RobustMDP {
+            mdp,
+            radius: Box::new(radius),
+        }
+    }
+
+    pub fn radius(&self, state: &M::State, action: &M::Action) -> f64 {
+        (self.radius)(state, action)
+    }
+
+    /// The transition measure in the uncertainty set of `(s, a)` minimizing the expectation of
+    /// `value`, with the nominal reward
+    pub fn worst_case_transition(
+        &self,
+        state: &M::State,
+        action: &M::Action,
+        value: impl Fn(&M::State) -> f64,
+    ) -> Result<(Measure<M::State>, f64), Error>
+    where
+        M::State: Clone,
+    {
+        let (measure, reward) = self.mdp.stochastic_transition(state, action)?;
+        let worst = measure.worst_case_l1(value, self.radius(state, action))?;
+        Ok((worst, reward))
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mdp
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mdp
+    }
+}
+
+impl<M> MDP for RobustMDP<M>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    type State = M::State;
+    type Action = M::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        self.mdp.all_states()
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        self.mdp.actions_at(state)
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.mdp.is_final_state(state)
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.mdp.is_goal(state)
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        self.mdp.stochastic_transition(state, action)
+    }
+}
+
+/// One state's available actions with their nominal measures, rewards and radii
+type RobustBackups<S, A> = Vec<(A, Measure<S>, f64, f64)>;
+
+/// Every state paired with its robust backups
+type RobustTable<S, A> = Vec<(S, RobustBackups<S, A>)>;
+
+/// `r + γ min_{q ∈ U} Σ_s' q(s') V(s')`, where states missing from `values` are worth zero
+fn robust_backup<S>(
+    measure: &Measure<S>,
+    reward: f64,
+    radius: f64,
+    values: &HashMap<S, f64>,
+    discount_factor: f64,
+) -> Result<f64, Error>
+where
+    S: Eq + std::hash::Hash + Clone,
+{
+    let value = |s: &S| values.get(s).copied().unwrap_or(0.0);
+    let worst = measure.worst_case_l1(value, radius)?;
+    let expected: f64 = worst.dist().iter().map(|(s, p)| p.value() * value(s)).sum();
+    Ok(reward + discount_factor * expected)
+}
+
+/// # Robust Value Iteration
+///
+/// Value iteration against an adversary that picks the transition measure from each
+/// state-action pair's uncertainty set:
+///
+/// `V(s) ← max_a [ r(s, a) + γ min_{q : ‖q - p(· | s, a)‖₁ ≤ radius(s, a)} Σ_s' q(s') V(s') ]`
+///
+/// The inner minimization is solved exactly by `Measure::worst_case_l1`. Runs until no value
+/// changes by more than `tolerance` or `max_iterations` sweeps have run. Final states and states
+/// without actions are worth zero. With every radius zero this is ordinary value iteration.
+///
+/// # Arguments
+/// * `rmdp` - The robust MDP
+/// * `discount_factor` - The discount factor `γ`
+/// * `tolerance` - Stopping threshold on the largest value change of a sweep
+/// * `max_iterations` - Maximum number of sweeps
+///
+/// # Returns
+/// The worst-case optimal values and a policy greedy with respect to them
+pub fn robust_value_iteration<M>(
+    rmdp: &RobustMDP<M>,
+    discount_factor: f64,
+    tolerance: f64,
+    max_iterations: usize,
+) -> Result<ValueIterationResult<M::State, M::Action>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let mut table: RobustTable<M::State, M::Action> = Vec::new();
+    for state in rmdp.all_states().iter() {
+        let mut backups = Vec::new();
+        if !rmdp.is_final_state(state) {
+            for action in rmdp.actions_at(state) {
+                let (measure, reward) = rmdp.stochastic_transition(state, &action)?;
+                let radius = rmdp.radius(state, &action);
+                backups.push((action, measure, reward, radius));
+            }
+        }
+        table.push((state.clone(), backups));
+    }
+
+    let mut values: HashMap<M::State, f64> = table.iter().map(|(s, _)| (s.clone(), 0.0)).collect();
+    let mut iterations = 0;
+    let mut converged = false;
+
+    while iterations < max_iterations {
+        iterations += 1;
+        let mut max_change: f64 = 0.0;
+        let mut updated = HashMap::with_capacity(values.len());
+        for (state, backups) in &table {
+            let mut best: Option<f64> = None;
+            for (_, measure, reward, radius) in backups {
+                let value = robust_backup(measure, *reward, *radius, &values, discount_factor)?;
+                best = Some(best.map_or(value, |b| b.max(value)));
+            }
+            let value = best.unwrap_or(0.0);
+            max_change = max_change.max((value - values[state]).abs());
+            updated.insert(state.clone(), value);
+        }
+        values = updated;
+        if max_change <= tolerance {
+            converged = true;
+            break;
+        }
+    }
+
+    let mut policy = DeterministicPolicy::new();
+    for (state, backups) in &table {
+        let mut best: Option<(&M::Action, f64)> = None;
+        for (action, measure, reward, radius) in backups {
+            let value = robust_backup(measure, *reward, *radius, &values, discount_factor)?;
+            if best.is_none_or(|(_, best_value)| value > best_value) {
+                best = Some((action, value));
+            }
+        }
+        if let Some((action, _)) = best {
+            policy.insert(state.clone(), action.clone());
+        }
+    }
+
+    Ok(ValueIterationResult {
+        values,
+        policy,
+        iterations,
+        converged,
+    })
+}
@@ -0,0 +1,204 @@
+//! # Shielding
+//!
+//! Safety filters for learned policies. A `Shield` is computed from the exact transition measures
+//! of a model: for every state it keeps the actions whose probability of eventually reaching an
+//! unsafe state, if the agent behaves as safely as possible afterwards, stays within a threshold.
+//! Wrapping a policy in a shield overrides its unsafe choices. Works on any `MDP`, including
+//! products.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::config::TrainingConfig;
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::policy::Policy;
+
+/// One state's available actions with their next-state distributions `[(index, probability)]`
+type StateActions<A> = Vec<(A, Vec<(usize, f64)>)>;
+
+/// Wraps a policy and replaces the actions it picks whenever they are too risky
+pub struct Shield<P, S, A> {
+    policy: P,
+    /// Per state, the permitted actions in `actions_at` order
+    allowed: HashMap<S, Vec<A>>,
+    /// Per state, the minimal probability of ever reaching an unsafe state
+    risk: HashMap<S, f64>,
+    threshold: f64,
+    interventions: Cell<usize>,
+}
+
+impl<P, S, A> Shield<P, S, A>
+where
+    S: Eq + Hash + Clone,
+    A: PartialEq + Clone,
+{
+    /// # Shield
+    ///
+    /// Builds the shield by backward reachability from the unsafe states. Value iteration
+    /// computes, for every state, the least probability `ρ(s)` of eventually reaching an unsafe
+    /// state that any policy achieves (`ρ = 1` on unsafe states, `0` on safe final states):
+    ///
+    /// `ρ(s) ← min_a Σ_s' p(s' | s, a) ρ(s')`
+    ///
+    /// until no value changes by more than `config.planning_tolerance` or
+    /// `config.max_planning_iterations` sweeps have run. Action `a` is allowed in `s` if
+    /// `Σ_s' p(s' | s, a) ρ(s') ≤ threshold`; where no action qualifies, the least risky ones are
+    /// allowed so the agent is never left without a move.
+    ///
+    /// # Arguments
+    /// * `mdp` - The model the policy acts in
+    /// * `policy` - The policy to shield
+    /// * `is_unsafe` - Which states must be avoided
+    /// * `threshold` - Highest tolerated probability of eventually reaching an unsafe state
+    /// * `config` - Configuration parameters (planning tolerance and iterations)
+    ///
+    /// # Returns
+    /// The shielded policy
+    pub fn new<M>(
+        mdp: &M,
+        policy: P,
+        is_unsafe: impl Fn(&S) -> bool,
+        threshold: f64,
+        config: &TrainingConfig,
+    ) -> Result<Self, Error>
+    where
+        M: MDP<State = S, Action = A>,
+    {
+        let states: Vec<S> = mdp.all_states().iter().cloned().collect();
+        let index: HashMap<&S, usize> = states.iter().enumerate().map(|(i, s)| (s, i)).collect();
+        let unsafe_states: Vec<bool> = states.iter().map(&is_unsafe).collect();
+
+        let mut table: Vec<StateActions<A>> = Vec::with_capacity(states.len());
+        for (state, &bad) in states.iter().zip(&unsafe_states) {
+            let mut actions = Vec::new();
+            if !bad && !mdp.is_final_state(state) {
+                for action in mdp.actions_at(state) {
+                    let (measure, _) = mdp.stochastic_transition(state, &action)?;
+                    let outcomes = measure
+                        .dist()
+                        .iter()
+                        .filter_map(|(s, p)| index.get(s).map(|&j| (j, p.value())))
+                        .collect();
+                    actions.push((action, outcomes));
+                }
+            }
+            table.push(actions);
+        }
+
+        let expected = |outcomes: &[(usize, f64)], risk: &[f64]| -> f64 {
+            outcomes.iter().map(|&(j, p)| p * risk[j]).sum()
+        };
+        let mut risk: Vec<f64> = unsafe_states
+            .iter()
+            .map(|&bad| if bad { 1.0 } else { 0.0 })
+            .collect();
+        for _ in 0..config.max_planning_iterations {
+            let mut max_change: f64 = 0.0;
+            let updated: Vec<f64> = table
+                .iter()
+                .zip(&risk)
+                .map(|(actions, &current)| {
+                    if actions.is_empty() {
+                        return current;
+                    }
+                    let value = actions
+                        .iter()
+                        .map(|(_, outcomes)| expected(outcomes, &risk))
+                        .fold(f64::INFINITY, f64::min);
+                    max_change = max_change.max((value - current).abs());
+                    value
+                })
+                .collect();
+            risk = updated;
+            if max_change <= config.planning_tolerance {
+                break;
+            }
+        }
+
+        let mut allowed = HashMap::with_capacity(states.len());
+        for (state, actions) in states.iter().zip(&table) {
+            let risks: Vec<f64> = actions
+                .iter()
+                .map(|(_, outcomes)| expected(outcomes, &risk))
+                .collect();
+            let safest = risks.iter().copied().fold(f64::INFINITY, f64::min);
+            let limit = if safest <= threshold {
+                threshold
+            } else {
+                safest
+            };
+            let permitted = actions
+                .iter()
+                .zip(&risks)
+                .filter(|(_, r)| **r <= limit + f64::EPSILON)
+                .map(|((a, _), _)| a.clone())
+                .collect();
+            allowed.insert(state.clone(), permitted);
+        }
+
+        Ok(Shield {
+            policy,
+            allowed,
+            risk: states.into_iter().zip(risk).collect(),
+            threshold,
+            interventions: Cell::new(0),
+        })
+    }
+
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    /// The minimal probability of eventually reaching an unsafe state from `state`
+    pub fn risk(&self, state: &S) -> Option<f64> {
+        self.risk.get(state).copied()
+    }
+
+    /// The actions the shield permits in `state` (empty for final, unsafe and unknown states)
+    pub fn allowed_actions(&self, state: &S) -> &[A] {
+        self.allowed.get(state).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn is_allowed(&self, state: &S, action: &A) -> bool {
+        self.allowed_actions(state).contains(action)
+    }
+
+    /// How many times the shield has overridden the wrapped policy
+    pub fn interventions(&self) -> usize {
+        self.interventions.get()
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.policy
+    }
+
+    pub fn into_inner(self) -> P {
+        self.policy
+    }
+}
+
+/// The wrapped policy's action if the shield allows it, otherwise the first allowed action. States
+/// without allowed actions pass the wrapped policy through.
+impl<P, S, A> Policy<S, A> for Shield<P, S, A>
+where
+    P: Policy<S, A>,
+    S: Eq + Hash + Clone,
+    A: PartialEq + Clone,
+{
+    fn action(&self, state: &S) -> Option<A> {
+        let proposed = self.policy.action(state);
+        let allowed = self.allowed_actions(state);
+        if allowed.is_empty() {
+            return proposed;
+        }
+        match proposed {
+            Some(action) if allowed.contains(&action) => Some(action),
+            _ => {
+                self.interventions.set(self.interventions.get() + 1);
+                allowed.first().cloned()
+            }
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! # Sojourn-Time Q-Learning
+//!
+//! Semi-Markov Q-learning with continuous-time discounting. A decision is followed by a sojourn
+//! of random length `τ`, during which reward accrues, and the next state's value is discounted by
+//! `exp(-β τ)` with `β = config.discount_rate`. The same target serves CTMDPs, where `τ` is the
+//! exponential holding time, and options, where `τ` is the number of primitive steps times their
+//! duration, so policies trained on either half of the crate are measured in the same units.
+
+use madepro::models::{ActionValue, Sampler};
+use rand::Rng;
+use rand::seq::IndexedRandom;
+
+use crate::config::TrainingConfig;
+use crate::ctmdp::{CTMDP, dwell_reward, sample_sojourn};
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::options::{MdpOption, OptionValues, smdp_q_learning_with_discount};
+use crate::q_learning::{checked_q_value, random_start};
+
+/// `max_a Q(s, a)` over the actions available in `state`, with the maximizing action
+fn best_available<M>(
+    ctmdp: &M,
+    action_value: &ActionValue<M::State, M::Action>,
+    state: &M::State,
+) -> Option<(M::Action, f64)>
+where
+    M: CTMDP,
+{
+    let mut best: Option<(M::Action, f64)> = None;
+    for action in ctmdp.actions_at(state) {
+        let q = action_value.get(state, &action);
+        if best.as_ref().is_none_or(|(_, best_q)| q > *best_q) {
+            best = Some((action, q));
+        }
+    }
+    best
+}
+
+/// # Sojourn-Time Q-Learning
+///
+/// Q-learning on a CTMDP. In `s` the agent picks `a` epsilon-greedily among the available
+/// actions, dwells for `τ ~ Exp(E(s, a))` while earning the reward rate `ρ(s, a)`, jumps to `s'`
+/// and updates
+///
+/// `Q(s, a) ← Q(s, a) + α [∫_0^τ e^{-βt} ρ dt + e^{-βτ} max_a' Q(s', a') - Q(s, a)]`.
+///
+/// Episodes start in random states and end in final or absorbing states, or after
+/// `config.max_num_steps` jumps.
+///
+/// # Arguments
+/// * `ctmdp` - The continuous-time model to learn from
+/// * `config` - Configuration parameters (learning rate, discount rate `β`, exploration rate, etc.)
+///
+/// # Returns
+/// An `ActionValue` table with the learned values of every state-action pair
+pub fn sojourn_q_learning<M>(
+    ctmdp: &M,
+    config: &TrainingConfig,
+) -> Result<ActionValue<M::State, M::Action>, Error>
+where
+    M: CTMDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let states = ctmdp.all_states();
+    if states.iter().next().is_none() {
+        return Err(Error::EmptyStateSpace);
+    }
+    let actions: Sampler<M::Action> = ctmdp.all_actions().into();
+    if actions.iter().next().is_none() {
+        return Err(Error::EmptyActionSet);
+    }
+    let mut action_value = ActionValue::new(states, &actions);
+    let mut rng = config.rng();
+
+    for _ in 0..config.num_episodes {
+        let mut state = random_start(states, &mut rng)?.clone();
+
+        for _ in 0..config.max_num_steps {
+            if ctmdp.is_final_state(&state) {
+                break;
+            }
+            let action = if rng.random::<f64>() < config.exploration_rate {
+                ctmdp.actions_at(&state).choose(&mut rng).cloned()
+            } else {
+                best_available(ctmdp, &action_value, &state).map(|(a, _)| a)
+            };
+            let Some(action) = action else {
+                break;
+            };
+
+            let rates = ctmdp.rates(&state, &action)?;
+            let sojourn = sample_sojourn(rates.exit_rate(), &mut rng);
+            let reward = dwell_reward(
+                ctmdp.reward_rate(&state, &action),
+                sojourn,
+                config.discount_rate,
+            );
+            let current_q = action_value.get(&state, &action);
+            if rates.is_absorbing() {
+                let new_q =
+                    checked_q_value(current_q + config.learning_rate * (reward - current_q))?;
+                action_value.insert(&state, &action, new_q);
+                break;
+            }
+
+            let next_state = rates
+                .jump_measure()?
+                .sample_with(&mut rng)
+                .cloned()
+                .unwrap_or_else(|| state.clone());
+            let next_value = if ctmdp.is_final_state(&next_state) {
+                0.0
+            } else {
+                best_available(ctmdp, &action_value, &next_state).map_or(0.0, |(_, q)| q)
+            };
+            let target = reward + (-config.discount_rate * sojourn).exp() * next_value;
+            let new_q = checked_q_value(current_q + config.learning_rate * (target - current_q))?;
+            action_value.insert(&state, &action, new_q);
+
+            state = next_state;
+        }
+    }
+
+    Ok(action_value)
+}
+
+/// # Sojourn-Time Option Learning
+///
+/// SMDP Q-learning over options where every primitive step lasts `step_duration` units of time:
+/// an option that runs for `k` steps is discounted by `exp(-β k Δt)`, and the rewards along the way
+/// by `exp(-β i Δt)`. With `step_duration = 1` and `β = -ln γ` this is `smdp_q_learning`.
+///
+/// # Arguments
+/// * `mdp` - The MDP to learn from
+/// * `options` - The options to choose between
+/// * `step_duration` - The time `Δt` one primitive step takes
+/// * `config` - Configuration parameters (learning rate, discount rate `β`, exploration rate, etc.)
+///
+/// # Returns
+/// The learned option values
+pub fn sojourn_option_q_learning<M>(
+    mdp: &M,
+    options: &[MdpOption<M::State, M::Action>],
+    step_duration: f64,
+    config: &TrainingConfig,
+) -> Result<OptionValues<M::State>, Error>
+where
+    M: MDP,
+    M::State: Clone,
+{
+    let step_discount = (-config.discount_rate * step_duration).exp();
+    smdp_q_learning_with_discount(mdp, options, config, step_discount)
+}
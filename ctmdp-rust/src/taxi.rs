@@ -0,0 +1,264 @@
+//! # Taxi Domain
+//!
+//! Dietterich's taxi domain (2000): a taxi on a 5×5 grid with interior walls must drive to a
+//! passenger waiting at one of four landmarks (`R`, `G`, `Y`, `B`), pick them up, and drop them
+//! off at their destination landmark.
+//!
+//! ```text
+//! +---------+
+//! |R: | : :G|
+//! | : | : : |
+//! | : : : : |
+//! | | : | : |
+//! |Y| : |B: |
+//! +---------+
+//! ```
+//!
+//! A state is the `Product` of the taxi's position and a `PassengerStatus` (where the
+//! passenger is and where they want to go), 25 × 20 = 500 states in all. The factorization is
+//! what makes the domain a good fit for the product constructions: the four moves only change
+//! the first factor, pickup and dropoff only the second, and the taxi's position merely guards
+//! which of the latter succeed.
+
+use madepro::environments::gridworld::GridworldAction;
+use madepro::models::{Action, Sampler, State};
+
+use crate::error::Error;
+use crate::gridworld::{GridPosition, move_within};
+use crate::mdp::MDP;
+use crate::measure::Measure;
+use crate::products::Product;
+
+/// Side length of the grid
+pub const TAXI_GRID_SIZE: usize = 5;
+
+/// The pickup and dropoff landmarks `R`, `G`, `Y` and `B`, in that order
+pub const LANDMARKS: [GridPosition; 4] = [
+    GridPosition::new(0, 0),
+    GridPosition::new(0, 4),
+    GridPosition::new(4, 0),
+    GridPosition::new(4, 3),
+];
+
+/// Reward for every move and for a dropoff at the wrong landmark
+pub const STEP_REWARD: f64 = -1.0;
+/// Reward for delivering the passenger
+pub const DELIVERY_REWARD: f64 = 20.0;
+/// Reward for a pickup or dropoff where none is possible
+pub const ILLEGAL_ACTION_REWARD: f64 = -10.0;
+
+/// Cells with a wall on their east side (and, equivalently, their east neighbours' west side)
+const WALLS_EAST_OF: [GridPosition; 6] = [
+    GridPosition::new(0, 1),
+    GridPosition::new(1, 1),
+    GridPosition::new(3, 0),
+    GridPosition::new(3, 2),
+    GridPosition::new(4, 0),
+    GridPosition::new(4, 2),
+];
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TaxiAction {
+    South,
+    North,
+    East,
+    West,
+    Pickup,
+    Dropoff,
+}
+
+impl Action for TaxiAction {}
+
+/// Where the passenger is: waiting at landmark `i` (an index into `LANDMARKS`) or in the taxi
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Passenger {
+    At(usize),
+    InTaxi,
+}
+
+/// The passenger's location and destination landmark
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PassengerStatus {
+    passenger: Passenger,
+    destination: usize,
+}
+
+impl PassengerStatus {
+    pub fn new(passenger: Passenger, destination: usize) -> Self {
+        PassengerStatus {
+            passenger,
+            destination,
+        }
+    }
+
+    pub fn passenger(&self) -> Passenger {
+        self.passenger
+    }
+
+    pub fn destination(&self) -> usize {
+        self.destination
+    }
+
+    /// Whether the passenger has been dropped off at their destination
+    pub fn is_delivered(&self) -> bool {
+        self.passenger == Passenger::At(self.destination)
+    }
+}
+
+impl State for PassengerStatus {}
+
+pub type TaxiState = Product<GridPosition, PassengerStatus>;
+
+/// The taxi domain with all 500 states
+pub struct Taxi {
+    states: Sampler<TaxiState>,
+}
+
+impl Default for Taxi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Taxi {
+    pub fn new() -> Self {
+        let passengers = (0..LANDMARKS.len())
+            .map(Passenger::At)
+            .chain([Passenger::InTaxi]);
+        let mut states = Vec::new();
+        for row in 0..TAXI_GRID_SIZE {
+            for col in 0..TAXI_GRID_SIZE {
+                for passenger in passengers.clone() {
+                    for destination in 0..LANDMARKS.len() {
+                        states.push(Product::new(
+                            GridPosition::new(row, col),
+                            PassengerStatus::new(passenger, destination),
+                        ));
+                    }
+                }
+            }
+        }
+        Taxi {
+            states: states.into(),
+        }
+    }
+
+    /// The 300 states an episode starts from: any taxi position, with the passenger waiting at
+    /// a landmark other than their destination
+    pub fn initial_states(&self) -> Vec<TaxiState> {
+        self.states
+            .iter()
+            .filter(|s| {
+                let status = s.second();
+                status.passenger() != Passenger::InTaxi && !status.is_delivered()
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Where a move takes the taxi: one cell in its direction unless a wall or the border is
+    /// in the way
+    pub fn drive(position: GridPosition, action: TaxiAction) -> GridPosition {
+        let direction = match action {
+            TaxiAction::South => GridworldAction::Down,
+            TaxiAction::North => GridworldAction::Up,
+            TaxiAction::East if WALLS_EAST_OF.contains(&position) => return position,
+            TaxiAction::East => GridworldAction::Right,
+            TaxiAction::West
+                if position.col() > 0
+                    && WALLS_EAST_OF
+                        .contains(&GridPosition::new(position.row(), position.col() - 1)) =>
+            {
+                return position;
+            }
+            TaxiAction::West => GridworldAction::Left,
+            TaxiAction::Pickup | TaxiAction::Dropoff => return position,
+        };
+        move_within(position, &direction, TAXI_GRID_SIZE, TAXI_GRID_SIZE)
+    }
+
+    /// The effect of `Pickup` or `Dropoff` on the passenger and its reward
+    fn serve(
+        &self,
+        position: GridPosition,
+        status: PassengerStatus,
+        action: TaxiAction,
+    ) -> (PassengerStatus, f64) {
+        let landmark = LANDMARKS.iter().position(|l| *l == position);
+        match (action, status.passenger, landmark) {
+            (TaxiAction::Pickup, Passenger::At(i), Some(j)) if i == j => (
+                PassengerStatus::new(Passenger::InTaxi, status.destination),
+                STEP_REWARD,
+            ),
+            (TaxiAction::Dropoff, Passenger::InTaxi, Some(j)) => {
+                let reward = if j == status.destination {
+                    DELIVERY_REWARD
+                } else {
+                    STEP_REWARD
+                };
+                (
+                    PassengerStatus::new(Passenger::At(j), status.destination),
+                    reward,
+                )
+            }
+            _ => (status, ILLEGAL_ACTION_REWARD),
+        }
+    }
+}
+
+impl MDP for Taxi {
+    type State = TaxiState;
+    type Action = TaxiAction;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        &self.states
+    }
+
+    fn all_actions(&self) -> Vec<Self::Action> {
+        vec![
+            TaxiAction::South,
+            TaxiAction::North,
+            TaxiAction::East,
+            TaxiAction::West,
+            TaxiAction::Pickup,
+            TaxiAction::Dropoff,
+        ]
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        state.second().is_delivered()
+    }
+
+    /// Deterministic; delivered states are absorbing with reward `0`
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        let (position, status) = (*state.first(), *state.second());
+        if position.row() >= TAXI_GRID_SIZE
+            || position.col() >= TAXI_GRID_SIZE
+            || status.destination >= LANDMARKS.len()
+            || matches!(status.passenger, Passenger::At(i) if i >= LANDMARKS.len())
+        {
+            return Err(Error::StateNotFound);
+        }
+        if status.is_delivered() {
+            return Ok((Measure::deterministic(state.clone()), 0.0));
+        }
+        let (next, reward) = match action {
+            TaxiAction::Pickup | TaxiAction::Dropoff => {
+                let (status, reward) = self.serve(position, status, *action);
+                (Product::new(position, status), reward)
+            }
+            _ => (
+                Product::new(Self::drive(position, *action), status),
+                STEP_REWARD,
+            ),
+        };
+        Ok((Measure::deterministic(next), reward))
+    }
+}
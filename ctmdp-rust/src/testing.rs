@@ -0,0 +1,220 @@
+//! # Property-Testing Utilities
+//!
+//! Random generators for measures, small tabular MDPs and policies, and checkers for the
+//! invariants every model should satisfy. The generators draw from a caller-provided RNG, so a
+//! property test only has to vary a seed (e.g. `any::<u64>()` in proptest or `u64` in
+//! quickcheck) and can replay failures exactly. Checkers return `Error::InvariantViolation`
+//! describing the first violation found.
+
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use madepro::models::{Action, State};
+use rand::Rng;
+use rand::seq::IndexedRandom;
+
+use crate::constructors::TabularMDP;
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::measure::{Measure, MeasureBuilder};
+use crate::policy::{DeterministicPolicy, StochasticPolicy};
+use crate::products::{CartesianProduct, Product};
+
+/// States of generated MDPs
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, PartialOrd, Ord)]
+pub struct TestState(pub usize);
+
+impl State for TestState {}
+
+/// Actions of generated MDPs
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, PartialOrd, Ord)]
+pub struct TestAction(pub usize);
+
+impl Action for TestAction {}
+
+/// A random subset of `items` that keeps each with probability one half and is non-empty unless
+/// `items` is, in the order of `items`
+fn random_subset<'a, T, R: Rng + ?Sized>(items: &'a [T], rng: &mut R) -> Vec<&'a T> {
+    let forced = rng.random_range(0..items.len().max(1));
+    items
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i == forced || rng.random_bool(0.5))
+        .map(|(_, x)| x)
+        .collect()
+}
+
+/// A random measure on a `random_subset` of `support` with independent uniform weights; `None`
+/// if `support` is empty
+pub fn random_measure<T, R>(support: &[T], rng: &mut R) -> Option<Measure<T>>
+where
+    T: Eq + Hash + Clone,
+    R: Rng + ?Sized,
+{
+    random_subset(support, rng)
+        .into_iter()
+        .fold(MeasureBuilder::new(), |builder, x| {
+            builder.add(x.clone(), rng.random_range(0.01..1.0))
+        })
+        .normalize()
+        .ok()
+}
+
+/// # Random Tabular MDP
+///
+/// An MDP over `TestState(0..num_states)` and `TestAction(0..num_actions)`. Every state except
+/// the first is final with probability one quarter; every other state gets a non-empty random
+/// subset of the actions, each leading to a `random_measure` over all states with a reward
+/// uniform in `[-1, 1]`.
+pub fn random_tabular_mdp<R: Rng + ?Sized>(
+    num_states: usize,
+    num_actions: usize,
+    rng: &mut R,
+) -> TabularMDP<TestState, TestAction> {
+    let states: Vec<TestState> = (0..num_states).map(TestState).collect();
+    let actions: Vec<TestAction> = (0..num_actions).map(TestAction).collect();
+    let mut mdp = TabularMDP::new(states.clone());
+    for state in &states {
+        if state.0 > 0 && rng.random_bool(0.25) {
+            mdp.set_final(*state);
+            continue;
+        }
+        for action in random_subset(&actions, rng) {
+            if let Some(measure) = random_measure(&states, rng) {
+                mdp.add_transition(*state, *action, measure, rng.random_range(-1.0..=1.0));
+            }
+        }
+    }
+    mdp
+}
+
+/// A deterministic policy choosing uniformly among the actions available in each non-final
+/// state
+pub fn random_policy<M, R>(mdp: &M, rng: &mut R) -> DeterministicPolicy<M::State, M::Action>
+where
+    M: MDP,
+    R: Rng + ?Sized,
+{
+    mdp.all_states()
+        .iter()
+        .filter(|s| !mdp.is_final_state(s))
+        .filter_map(|s| {
+            let action = mdp.actions_at(s).choose(rng).cloned()?;
+            Some((s.clone(), action))
+        })
+        .collect()
+}
+
+/// A stochastic policy with a `random_measure` over the available actions of each non-final
+/// state
+pub fn random_stochastic_policy<M, R>(mdp: &M, rng: &mut R) -> StochasticPolicy<M::State, M::Action>
+where
+    M: MDP,
+    R: Rng + ?Sized,
+{
+    mdp.all_states()
+        .iter()
+        .filter(|s| !mdp.is_final_state(s))
+        .filter_map(|s| Some((s.clone(), random_measure(&mdp.actions_at(s), rng)?)))
+        .collect()
+}
+
+fn violation(message: String) -> Error {
+    Error::InvariantViolation(message)
+}
+
+/// Checks that every probability of `measure` lies in `[0, 1]` and that they sum to one within
+/// `tolerance`
+pub fn check_measure<T>(measure: &Measure<T>, tolerance: f64) -> Result<(), Error>
+where
+    T: Eq + Hash + Debug,
+{
+    let mut total = 0.0;
+    for (x, p) in measure.dist() {
+        let p = p.value();
+        if !(0.0..=1.0).contains(&p) {
+            return Err(violation(format!(
+                "probability {p} of {x:?} is outside [0, 1]"
+            )));
+        }
+        total += p;
+    }
+    if (total - 1.0).abs() > tolerance {
+        return Err(violation(format!("probabilities sum to {total}")));
+    }
+    Ok(())
+}
+
+/// # Check Transitions
+///
+/// Checks every state-action pair of `mdp`: the transition is defined, its measure is valid
+/// (see `check_measure`), its reward is finite and every successor is in `all_states()`.
+pub fn check_transitions<M>(mdp: &M, tolerance: f64) -> Result<(), Error>
+where
+    M: MDP,
+    M::State: Debug,
+    M::Action: Debug,
+{
+    let states: HashSet<&M::State> = mdp.all_states().iter().collect();
+    for (state, action) in mdp.all_state_action_pairs() {
+        let (measure, reward) = mdp.stochastic_transition(&state, &action)?;
+        check_measure(&measure, tolerance)
+            .map_err(|e| violation(format!("{state:?}, {action:?}: {e}")))?;
+        if !reward.is_finite() {
+            return Err(violation(format!("{state:?}, {action:?}: reward {reward}")));
+        }
+        if let Some(next) = measure.dist().keys().find(|s| !states.contains(s)) {
+            return Err(violation(format!(
+                "{state:?}, {action:?}: successor {next:?} is not a state"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// # Check Cartesian Marginals
+///
+/// Checks that the transitions of a `CartesianProduct` are the independent products of the
+/// component transitions: for every product state and joint action, the marginal of the joint
+/// measure on each component equals that component's measure, and the joint measure equals the
+/// product of the marginals, all within `tolerance` in L1 distance.
+pub fn check_cartesian_marginals<M1, M2>(
+    product: &CartesianProduct<M1, M2>,
+    tolerance: f64,
+) -> Result<(), Error>
+where
+    M1: MDP,
+    M2: MDP,
+    M1::State: Debug,
+    M2::State: Debug,
+    M1::Action: Debug,
+    M2::Action: Debug,
+{
+    let (mdp1, mdp2) = product.components();
+    for (state, action) in product.all_state_action_pairs() {
+        let (joint, _) = product.stochastic_transition(&state, &action)?;
+        let (m1, _) = mdp1.stochastic_transition(state.first(), action.first())?;
+        let (m2, _) = mdp2.stochastic_transition(state.second(), action.second())?;
+
+        let first = joint
+            .clone()
+            .map(|s: Product<M1::State, M2::State>| s.into_parts().0);
+        let second = joint
+            .clone()
+            .map(|s: Product<M1::State, M2::State>| s.into_parts().1);
+        let independent = m1.product(&m2)?.map(|(s1, s2)| Product::new(s1, s2));
+        for (what, distance) in [
+            ("first marginal", first.l1_distance(&m1)),
+            ("second marginal", second.l1_distance(&m2)),
+            ("joint", joint.l1_distance(&independent)),
+        ] {
+            if distance > tolerance {
+                return Err(violation(format!(
+                    "{state:?}, {action:?}: {what} is {distance} away from the components'"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,203 @@
+//! # Uniformization
+//!
+//! Turns a CTMDP into a discrete-time MDP with the same optimal policies. Pick a rate `Λ` at
+//! least as large as every exit rate and let a clock tick at rate `Λ` in every state: each tick
+//! jumps to `s'` with probability `q(s' | s, a) / Λ` and stays put with the remaining
+//! probability `1 - E(s, a) / Λ`. Sampled at the ticks the process is a discrete-time chain, so
+//! the planners and learners written against `MDP` apply unchanged.
+//!
+//! With continuous discounting at rate `β`, one tick discounts by `γ = Λ / (Λ + β)` and the reward
+//! rate `ρ` pays `ρ / (Λ + β)` per tick; under those two choices the discrete values equal the
+//! continuous-time ones exactly.
+
+use indexmap::IndexMap;
+use madepro::models::Sampler;
+
+use crate::ctmdp::CTMDP;
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::measure::{Measure, Probability};
+
+/// The uniformized discrete-time MDP of a CTMDP
+pub struct Uniformized<M: CTMDP> {
+    ctmdp: M,
+    rate_bound: f64,
+    discount_rate: f64,
+}
+
+/// # Uniformize
+///
+/// Builds the uniformized MDP of `ctmdp` at rate `rate_bound`. Per tick, the reward is
+/// `ρ(s, a) / Λ` (the reward rate times the mean tick length), which keeps reward per unit of
+/// time intact; `with_discount_rate` switches to discounted rewards.
+///
+/// # Arguments
+/// * `ctmdp` - The continuous-time model
+/// * `rate_bound` - The uniformization rate `Λ`, at least every exit rate `E(s, a)`
+///
+/// # Returns
+/// The uniformized MDP, or `Error::NumericalError` if `rate_bound` is not positive or some exit
+/// rate exceeds it
+///
+/// # Example
+///
+/// Discounted values of a birth-death chain computed on the uniformized MDP satisfy the
+/// continuous-time Bellman equation `V(s) = [ρ(s) + Σ_s' q(s' | s) V(s')] / (E(s) + β)`, whichever
+/// valid rate bound is used:
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use ctmdp_rust::ctmdp::{CTMDP, RateMeasure, TabularCTMDP};
+/// use ctmdp_rust::pathmdp::{PathAction, PathState};
+/// use ctmdp_rust::planning::value_iteration;
+/// use ctmdp_rust::uniformization::uniformize;
+///
+/// // Births at rate 2, deaths at rate 1, reward rate equal to the population
+/// let n = 4;
+/// let mut chain = TabularCTMDP::new((0..n).map(PathState::new).collect());
+/// for i in 0..n {
+///     let mut rates = HashMap::new();
+///     if i + 1 < n {
+///         rates.insert(PathState::new(i + 1), 2.0);
+///     }
+///     if i > 0 {
+///         rates.insert(PathState::new(i - 1), 1.0);
+///     }
+///     let rates = RateMeasure::new(rates).unwrap();
+///     chain.add_transition(PathState::new(i), PathAction::Next, rates, i as f64);
+/// }
+///
+/// let beta = 0.5;
+/// let solve = |rate_bound: f64| {
+///     let mdp = uniformize(&chain, rate_bound).unwrap().with_discount_rate(beta);
+///     let gamma = mdp.discount_factor();
+///     value_iteration(&mdp, gamma, 1e-12, 100_000).unwrap().values
+/// };
+/// let values = solve(3.0);
+/// let coarse = solve(10.0);
+///
+/// for i in 0..n {
+///     let s = PathState::new(i);
+///     let rates = chain.rates(&s, &PathAction::Next).unwrap();
+///     let inflow: f64 = rates.rates().iter().map(|(t, q)| q * values[t]).sum();
+///     let bellman = (i as f64 + inflow) / (rates.exit_rate() + beta);
+///     assert!((values[&s] - bellman).abs() < 1e-8);
+///     assert!((values[&s] - coarse[&s]).abs() < 1e-8);
+/// }
+/// ```
+pub fn uniformize<M: CTMDP>(ctmdp: M, rate_bound: f64) -> Result<Uniformized<M>, Error> {
+    if !(rate_bound > 0.0 && rate_bound.is_finite()) {
+        return Err(Error::NumericalError(format!(
+            "uniformization rate {rate_bound} must be positive and finite"
+        )));
+    }
+    let max_rate = max_exit_rate(&ctmdp)?;
+    if max_rate > rate_bound {
+        return Err(Error::NumericalError(format!(
+            "uniformization rate {rate_bound} is below the exit rate {max_rate}"
+        )));
+    }
+    Ok(Uniformized {
+        ctmdp,
+        rate_bound,
+        discount_rate: 0.0,
+    })
+}
+
+/// The largest exit rate over all non-final states and their actions (zero if there is none)
+pub fn max_exit_rate<M: CTMDP>(ctmdp: &M) -> Result<f64, Error> {
+    let mut max_rate: f64 = 0.0;
+    for state in ctmdp.all_states().iter() {
+        if ctmdp.is_final_state(state) {
+            continue;
+        }
+        for action in ctmdp.actions_at(state) {
+            max_rate = max_rate.max(ctmdp.exit_rate(state, &action)?);
+        }
+    }
+    Ok(max_rate)
+}
+
+impl<M: CTMDP> Uniformized<M> {
+    /// Pays `ρ / (Λ + β)` per tick, so that value iteration with `discount_factor()` yields the
+    /// continuous-time values discounted at rate `discount_rate`
+    pub fn with_discount_rate(mut self, discount_rate: f64) -> Self {
+        self.discount_rate = discount_rate.max(0.0);
+        self
+    }
+
+    pub fn rate_bound(&self) -> f64 {
+        self.rate_bound
+    }
+
+    pub fn discount_rate(&self) -> f64 {
+        self.discount_rate
+    }
+
+    /// `Λ / (Λ + β)`, the per-tick discount matching the continuous discount rate
+    pub fn discount_factor(&self) -> f64 {
+        self.rate_bound / (self.rate_bound + self.discount_rate)
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.ctmdp
+    }
+
+    pub fn into_inner(self) -> M {
+        self.ctmdp
+    }
+}
+
+impl<M> MDP for Uniformized<M>
+where
+    M: CTMDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    type State = M::State;
+    type Action = M::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        self.ctmdp.all_states()
+    }
+
+    fn all_actions(&self) -> Vec<Self::Action> {
+        self.ctmdp.all_actions()
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        self.ctmdp.actions_at(state)
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.ctmdp.is_final_state(state)
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.ctmdp.is_goal(state)
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        let rates = self.ctmdp.rates(state, action)?;
+        let mut dist = IndexMap::new();
+        let mut stay = 1.0;
+        for (next, rate) in rates.rates() {
+            if next == state {
+                continue;
+            }
+            let p = rate / self.rate_bound;
+            stay -= p;
+            dist.insert(next.clone(), Probability::new(p.min(1.0))?);
+        }
+        if stay > 0.0 {
+            dist.insert(state.clone(), Probability::new(stay.min(1.0))?);
+        }
+        let reward = self.ctmdp.reward_rate(state, action) / (self.rate_bound + self.discount_rate);
+        Ok((Measure::from_distribution(dist)?, reward))
+    }
+}
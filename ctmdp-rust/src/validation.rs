@@ -0,0 +1,167 @@
+//! # Validation
+//!
+//! Sanity checks for hand-written MDPs. A malformed environment (transitions into states the
+//! learners don't know about, non-final states without actions, NaN rewards) doesn't fail
+//! loudly; it just produces garbage Q-values. `validate` finds those problems up front.
+
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+use crate::error::Error;
+use crate::mdp::MDP;
+
+/// Tolerance on `|Σ p - 1|` for transition measures
+const MEASURE_TOLERANCE: f64 = 1e-9;
+
+/// One problem found by `validate`
+#[derive(Debug)]
+pub enum Issue<S, A> {
+    /// `all_states()` is empty
+    EmptyStateSpace,
+    /// A state is listed more than once in `all_states()`
+    DuplicateState { state: S },
+    /// A non-final state has no available actions
+    NoActions { state: S },
+    /// `stochastic_transition` returned an error
+    TransitionFailed { state: S, action: A, error: Error },
+    /// The transition measure's probabilities do not sum to one
+    MeasureNotNormalized { state: S, action: A, total: f64 },
+    /// The transition measure puts positive probability on a state missing from `all_states()`
+    UnknownNextState { state: S, action: A, next_state: S },
+    /// The reward is NaN or infinite
+    NonFiniteReward { state: S, action: A, reward: f64 },
+}
+
+/// Everything `validate` checked and found
+#[derive(Debug)]
+pub struct ValidationReport<S, A> {
+    pub num_states: usize,
+    pub num_final_states: usize,
+    /// State-action pairs whose transitions were checked
+    pub num_transitions: usize,
+    pub issues: Vec<Issue<S, A>>,
+}
+
+impl<S: Debug, A: Debug> Issue<S, A> {
+    /// The `Error` a learner would hit (or should raise) because of this issue. Duplicate states
+    /// only skew uniform sampling of start states, so they have none.
+    pub fn into_error(self) -> Option<Error> {
+        match self {
+            Issue::EmptyStateSpace => Some(Error::EmptyStateSpace),
+            Issue::DuplicateState { .. } => None,
+            Issue::NoActions { .. } => Some(Error::EmptyActionSet),
+            Issue::TransitionFailed { error, .. } => Some(error),
+            Issue::MeasureNotNormalized { .. } => Some(Error::InvalidMeasure),
+            Issue::UnknownNextState { .. } => Some(Error::StateNotFound),
+            Issue::NonFiniteReward {
+                state,
+                action,
+                reward,
+            } => Some(Error::NumericalError(format!(
+                "reward {reward} for action {action:?} in state {state:?}"
+            ))),
+        }
+    }
+}
+
+impl<S, A> ValidationReport<S, A> {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl<S: Debug, A: Debug> ValidationReport<S, A> {
+    /// The first issue that has an `Error`, if any
+    pub fn into_result(self) -> Result<(), Error> {
+        match self.issues.into_iter().find_map(Issue::into_error) {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+/// # Validate
+///
+/// Checks that every non-final state has actions and that every available `(s, a)` yields a
+/// transition measure summing to one, supported on `all_states()`, with a finite reward.
+/// Problems are collected rather than returned as errors, so one report lists all of them.
+pub fn validate<M>(mdp: &M) -> ValidationReport<M::State, M::Action>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    let mut issues = Vec::new();
+    let mut known = HashSet::new();
+    for state in mdp.all_states().iter() {
+        if !known.insert(state.clone()) {
+            issues.push(Issue::DuplicateState {
+                state: state.clone(),
+            });
+        }
+    }
+    if known.is_empty() {
+        issues.push(Issue::EmptyStateSpace);
+    }
+
+    let mut num_final_states = 0;
+    let mut num_transitions = 0;
+    for state in mdp.all_states().iter() {
+        if mdp.is_final_state(state) {
+            num_final_states += 1;
+            continue;
+        }
+        let actions = mdp.actions_at(state);
+        if actions.is_empty() {
+            issues.push(Issue::NoActions {
+                state: state.clone(),
+            });
+        }
+        for action in actions {
+            num_transitions += 1;
+            let (measure, reward) = match mdp.stochastic_transition(state, &action) {
+                Ok(outcome) => outcome,
+                Err(error) => {
+                    issues.push(Issue::TransitionFailed {
+                        state: state.clone(),
+                        action,
+                        error,
+                    });
+                    continue;
+                }
+            };
+
+            let total: f64 = measure.dist().values().map(|p| p.value()).sum();
+            if (total - 1.0).abs() > MEASURE_TOLERANCE {
+                issues.push(Issue::MeasureNotNormalized {
+                    state: state.clone(),
+                    action: action.clone(),
+                    total,
+                });
+            }
+            for (next_state, p) in measure.dist() {
+                if p.value() > 0.0 && !known.contains(next_state) {
+                    issues.push(Issue::UnknownNextState {
+                        state: state.clone(),
+                        action: action.clone(),
+                        next_state: next_state.clone(),
+                    });
+                }
+            }
+            if !reward.is_finite() {
+                issues.push(Issue::NonFiniteReward {
+                    state: state.clone(),
+                    action,
+                    reward,
+                });
+            }
+        }
+    }
+
+    ValidationReport {
+        num_states: known.len(),
+        num_final_states,
+        num_transitions,
+        issues,
+    }
+}
@@ -0,0 +1,714 @@
+//! # Wrappers
+//!
+//! MDP wrappers that change one aspect of an environment (rewards, dynamics, horizon) and
+//! delegate everything else to the wrapped MDP. Every wrapper implements `MDP`, so wrappers nest
+//! and compose with `BoxProduct`/`CartesianProduct` in either order.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use indexmap::IndexMap;
+use madepro::models::{Action, Sampler, State};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand_distr::{Distribution, Normal, Uniform};
+
+use crate::error::Error;
+use crate::mdp::MDP;
+use crate::measure::{Measure, Probability};
+use crate::q_learning::action_union;
+
+/// Per state-action call counts, mutable through a shared reference
+type PairCounts<S, A> = RefCell<HashMap<(S, A), usize>>;
+
+/// Adds the count-based exploration bonus `beta / sqrt(N(s, a))` to every reward, where
+/// `N(s, a)` counts the calls to `stochastic_transition` for that pair (including the current one).
+///
+/// Counts live behind a `RefCell` so the wrapper can be used wherever an `&MDP` is expected.
+/// Planners that enumerate every transition also bump the counts; call `reset_counts` between
+/// runs that should not share them.
+pub struct ExplorationBonus<M: MDP> {
+    mdp: M,
+    beta: f64,
+    counts: PairCounts<M::State, M::Action>,
+}
+
+impl<M: MDP> ExplorationBonus<M> {
+    pub fn new(mdp: M, beta: f64) -> Self {
+        ExplorationBonus {
+            mdp,
+            beta,
+            counts: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mdp
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mdp
+    }
+
+    pub fn beta(&self) -> f64 {
+        self.beta
+    }
+
+    pub fn visits(&self, state: &M::State, action: &M::Action) -> usize
+    where
+        M::State: Clone,
+        M::Action: Clone,
+    {
+        self.counts
+            .borrow()
+            .get(&(state.clone(), action.clone()))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub fn reset_counts(&self) {
+        self.counts.borrow_mut().clear();
+    }
+}
+
+impl<M> MDP for ExplorationBonus<M>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    type State = M::State;
+    type Action = M::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        self.mdp.all_states()
+    }
+
+    fn states(&self) -> Box<dyn Iterator<Item = Self::State> + '_> {
+        self.mdp.states()
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        self.mdp.actions_at(state)
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.mdp.is_final_state(state)
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.mdp.is_goal(state)
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        let (measure, reward) = self.mdp.stochastic_transition(state, action)?;
+        let mut counts = self.counts.borrow_mut();
+        let visits = counts.entry((state.clone(), action.clone())).or_insert(0);
+        *visits += 1;
+        Ok((measure, reward + self.beta / (*visits as f64).sqrt()))
+    }
+}
+
+/// Reward bonus for arriving in a state
+type StateBonus<S> = Box<dyn Fn(&S) -> f64>;
+
+/// Transforms rewards as `clip(scale * r + offset + bonus)`, where `bonus` is the expected value,
+/// over the next-state measure, of `goal_bonus` on goal states (`MDP::is_goal`) plus any custom
+/// per-state bonus. Each part is optional and set with the builder-style methods.
+pub struct TransformReward<M: MDP> {
+    mdp: M,
+    scale: f64,
+    offset: f64,
+    clip: Option<(f64, f64)>,
+    goal_bonus: f64,
+    state_bonus: Option<StateBonus<M::State>>,
+}
+
+impl<M: MDP> TransformReward<M> {
+    /// Starts from the identity transform
+    pub fn new(mdp: M) -> Self {
+        TransformReward {
+            mdp,
+            scale: 1.0,
+            offset: 0.0,
+            clip: None,
+            goal_bonus: 0.0,
+            state_bonus: None,
+        }
+    }
+
+    pub fn scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn offset(mut self, offset: f64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Clamps transformed rewards to `[min, max]`
+    pub fn clip(mut self, min: f64, max: f64) -> Self {
+        self.clip = Some((min, max));
+        self
+    }
+
+    /// Bonus for transitions into goal states
+    pub fn goal_bonus(mut self, bonus: f64) -> Self {
+        self.goal_bonus = bonus;
+        self
+    }
+
+    /// Bonus for transitions into arbitrary states, e.g. a different reward per goal
+    pub fn state_bonus(mut self, bonus: impl Fn(&M::State) -> f64 + 'static) -> Self {
+        self.state_bonus = Some(Box::new(bonus));
+        self
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mdp
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mdp
+    }
+
+    fn transform(&self, reward: f64, measure: &Measure<M::State>) -> f64 {
+        let bonus = measure.expectation(|s| {
+            let goal = if self.mdp.is_goal(s) {
+                self.goal_bonus
+            } else {
+                0.0
+            };
+            goal + self.state_bonus.as_ref().map_or(0.0, |f| f(s))
+        });
+        let transformed = self.scale * reward + self.offset + bonus;
+        match self.clip {
+            Some((min, max)) => transformed.clamp(min, max),
+            None => transformed,
+        }
+    }
+}
+
+impl<M> MDP for TransformReward<M>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    type State = M::State;
+    type Action = M::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        self.mdp.all_states()
+    }
+
+    fn states(&self) -> Box<dyn Iterator<Item = Self::State> + '_> {
+        self.mdp.states()
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        self.mdp.actions_at(state)
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.mdp.is_final_state(state)
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.mdp.is_goal(state)
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        let (measure, reward) = self.mdp.stochastic_transition(state, action)?;
+        let reward = self.transform(reward, &measure);
+        Ok((measure, reward))
+    }
+}
+
+/// A state of a `TimeLimited` MDP: the wrapped state and the number of steps left in the episode
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct TimedState<S> {
+    state: S,
+    steps_remaining: usize,
+}
+
+impl<S> TimedState<S> {
+    pub fn new(state: S, steps_remaining: usize) -> Self {
+        TimedState {
+            state,
+            steps_remaining,
+        }
+    }
+
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    pub fn steps_remaining(&self) -> usize {
+        self.steps_remaining
+    }
+}
+
+impl<S: State> State for TimedState<S> {}
+
+/// Makes the horizon part of the state: every step decrements a counter, and states whose counter
+/// reached zero are final. Planners and evaluators see the truncation that learners otherwise
+/// only apply inside their episode loops.
+///
+/// `all_states` holds every wrapped state paired with every counter value `0..=horizon`.
+pub struct TimeLimited<M: MDP> {
+    mdp: M,
+    horizon: usize,
+    states: Sampler<TimedState<M::State>>,
+}
+
+impl<M> TimeLimited<M>
+where
+    M: MDP,
+    M::State: Clone,
+{
+    pub fn new(mdp: M, horizon: usize) -> Self {
+        let mut states = Vec::new();
+        for s in mdp.all_states().iter() {
+            for steps_remaining in 0..=horizon {
+                states.push(TimedState::new(s.clone(), steps_remaining));
+            }
+        }
+        let states = Sampler::new(states);
+        TimeLimited {
+            mdp,
+            horizon,
+            states,
+        }
+    }
+
+    pub fn horizon(&self) -> usize {
+        self.horizon
+    }
+
+    /// `state` with the full horizon remaining
+    pub fn initial_state(&self, state: M::State) -> TimedState<M::State> {
+        TimedState::new(state, self.horizon)
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mdp
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mdp
+    }
+}
+
+impl<M> MDP for TimeLimited<M>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    type State = TimedState<M::State>;
+    type Action = M::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        &self.states
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        self.mdp.actions_at(&state.state)
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        state.steps_remaining == 0 || self.mdp.is_final_state(&state.state)
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.mdp.is_goal(&state.state)
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        let (measure, reward) = self.mdp.stochastic_transition(&state.state, action)?;
+        let steps_remaining = state.steps_remaining.saturating_sub(1);
+        let dist: IndexMap<TimedState<M::State>, Probability> = measure
+            .dist()
+            .iter()
+            .map(|(s, p)| (TimedState::new(s.clone(), steps_remaining), *p))
+            .collect();
+        Ok((Measure::from_distribution(dist)?, reward))
+    }
+}
+
+/// Accumulates `weight * measure` into `acc`, relabelling outcomes with `relabel`
+fn add_weighted<T: Eq + Hash, U: Eq + Hash>(
+    acc: &mut IndexMap<U, f64>,
+    measure: &Measure<T>,
+    weight: f64,
+    relabel: impl Fn(&T) -> U,
+) {
+    for (t, p) in measure.dist() {
+        *acc.entry(relabel(t)).or_insert(0.0) += weight * p.value();
+    }
+}
+
+fn into_measure<T: Eq + Hash>(weights: IndexMap<T, f64>) -> Result<Measure<T>, Error> {
+    let dist = weights
+        .into_iter()
+        .map(|(t, w)| Ok((t, Probability::new(w.min(1.0))?)))
+        .collect::<Result<IndexMap<_, _>, Error>>()?;
+    Measure::from_distribution(dist)
+}
+
+/// With probability `noise` the chosen action is replaced by one drawn uniformly from the legal
+/// actions at the current state (possibly the chosen one again). Transition measures and rewards
+/// are the corresponding mixtures, so deterministic environments become genuinely stochastic.
+pub struct ActionNoise<M: MDP> {
+    mdp: M,
+    noise: Probability,
+}
+
+impl<M: MDP> ActionNoise<M> {
+    pub fn new(mdp: M, noise: Probability) -> Self {
+        ActionNoise { mdp, noise }
+    }
+
+    pub fn noise(&self) -> Probability {
+        self.noise
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mdp
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mdp
+    }
+}
+
+impl<M> MDP for ActionNoise<M>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    type State = M::State;
+    type Action = M::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        self.mdp.all_states()
+    }
+
+    fn states(&self) -> Box<dyn Iterator<Item = Self::State> + '_> {
+        self.mdp.states()
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        self.mdp.actions_at(state)
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.mdp.is_final_state(state)
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.mdp.is_goal(state)
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        let actions = self.mdp.actions_at(state);
+        if actions.is_empty() {
+            return self.mdp.stochastic_transition(state, action);
+        }
+        let substitute = self.noise.value() / actions.len() as f64;
+        let (measure, reward) = self.mdp.stochastic_transition(state, action)?;
+        let mut weights = IndexMap::new();
+        add_weighted(
+            &mut weights,
+            &measure,
+            self.noise.complement().value(),
+            |s| s.clone(),
+        );
+        let mut expected_reward = self.noise.complement().value() * reward;
+        for a in &actions {
+            let (measure, reward) = self.mdp.stochastic_transition(state, a)?;
+            add_weighted(&mut weights, &measure, substitute, |s| s.clone());
+            expected_reward += substitute * reward;
+        }
+        Ok((into_measure(weights)?, expected_reward))
+    }
+}
+
+/// A state of a `StickyActions` MDP: the wrapped state and the action executed to reach it
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct StickyState<S, A> {
+    state: S,
+    previous: Option<A>,
+}
+
+impl<S, A> StickyState<S, A> {
+    pub fn new(state: S, previous: Option<A>) -> Self {
+        StickyState { state, previous }
+    }
+
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    pub fn previous(&self) -> Option<&A> {
+        self.previous.as_ref()
+    }
+}
+
+impl<S: State, A: Action> State for StickyState<S, A> {}
+
+/// With probability `stickiness` the previously executed action is repeated instead of the chosen
+/// one, as long as it is still legal. The executed action is remembered in the state, so the
+/// wrapped MDP stays Markov; episodes should start from `initial_state`, which has no previous
+/// action.
+///
+/// `all_states` pairs every wrapped state with no previous action and with every action legal
+/// somewhere in the wrapped MDP.
+pub struct StickyActions<M: MDP> {
+    mdp: M,
+    stickiness: Probability,
+    states: Sampler<StickyState<M::State, M::Action>>,
+}
+
+impl<M> StickyActions<M>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    pub fn new(mdp: M, stickiness: Probability) -> Self {
+        let actions = action_union(&mdp);
+        let mut states = Vec::new();
+        for s in mdp.all_states().iter() {
+            states.push(StickyState::new(s.clone(), None));
+            for a in actions.iter() {
+                states.push(StickyState::new(s.clone(), Some(a.clone())));
+            }
+        }
+        StickyActions {
+            mdp,
+            stickiness,
+            states: states.into(),
+        }
+    }
+
+    pub fn stickiness(&self) -> Probability {
+        self.stickiness
+    }
+
+    /// `state` with no previous action
+    pub fn initial_state(&self, state: M::State) -> StickyState<M::State, M::Action> {
+        StickyState::new(state, None)
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mdp
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mdp
+    }
+}
+
+impl<M> MDP for StickyActions<M>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    type State = StickyState<M::State, M::Action>;
+    type Action = M::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        &self.states
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        self.mdp.actions_at(&state.state)
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.mdp.is_final_state(&state.state)
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.mdp.is_goal(&state.state)
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        let mut executed = vec![(action, 1.0)];
+        if let Some(previous) = &state.previous
+            && previous != action
+            && self.mdp.actions_at(&state.state).contains(previous)
+        {
+            executed = vec![
+                (action, self.stickiness.complement().value()),
+                (previous, self.stickiness.value()),
+            ];
+        }
+        let mut weights = IndexMap::new();
+        let mut expected_reward = 0.0;
+        for (a, weight) in executed {
+            let (measure, reward) = self.mdp.stochastic_transition(&state.state, a)?;
+            add_weighted(&mut weights, &measure, weight, |s| {
+                StickyState::new(s.clone(), Some(a.clone()))
+            });
+            expected_reward += weight * reward;
+        }
+        Ok((into_measure(weights)?, expected_reward))
+    }
+}
+
+/// Zero-mean noise distributions for `NoisyReward`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RewardNoise {
+    Gaussian {
+        std_dev: f64,
+    },
+    /// Uniform on `[-half_width, half_width]`
+    Uniform {
+        half_width: f64,
+    },
+}
+
+impl RewardNoise {
+    /// Fails with `Error::Specification` unless the spread is finite and non-negative
+    fn validate(&self) -> Result<(), Error> {
+        let (name, spread) = match *self {
+            RewardNoise::Gaussian { std_dev } => ("std_dev", std_dev),
+            RewardNoise::Uniform { half_width } => ("half_width", half_width),
+        };
+        if !spread.is_finite() || spread < 0.0 {
+            return Err(Error::Specification(format!(
+                "reward noise {name} must be finite and non-negative, got {spread}"
+            )));
+        }
+        Ok(())
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> Result<f64, Error> {
+        match *self {
+            RewardNoise::Gaussian { std_dev } => Ok(Normal::new(0.0, std_dev)
+                .map_err(|_| Error::InvalidMeasure)?
+                .sample(rng)),
+            RewardNoise::Uniform { half_width } => {
+                Ok(Uniform::new_inclusive(-half_width, half_width)
+                    .map_err(|_| Error::InvalidMeasure)?
+                    .sample(rng))
+            }
+        }
+    }
+}
+
+/// Adds independent zero-mean noise to every reward returned by `stochastic_transition`, so the
+/// expected reward is unchanged but learners only ever observe noisy samples. Planners that
+/// enumerate transitions see noisy rewards too and should run on the wrapped MDP instead.
+///
+/// The RNG lives behind a `RefCell`; use `with_seed` (or `reseed`) for reproducible runs.
+pub struct NoisyReward<M: MDP> {
+    mdp: M,
+    noise: RewardNoise,
+    rng: RefCell<StdRng>,
+}
+
+impl<M: MDP> NoisyReward<M> {
+    /// Seeds the noise from the thread RNG. Fails with `Error::Specification` if the noise's
+    /// spread is negative or not finite.
+    pub fn new(mdp: M, noise: RewardNoise) -> Result<Self, Error> {
+        noise.validate()?;
+        Ok(NoisyReward {
+            mdp,
+            noise,
+            rng: RefCell::new(StdRng::from_rng(&mut rand::rng())),
+        })
+    }
+
+    pub fn with_seed(mdp: M, noise: RewardNoise, seed: u64) -> Result<Self, Error> {
+        noise.validate()?;
+        Ok(NoisyReward {
+            mdp,
+            noise,
+            rng: RefCell::new(StdRng::seed_from_u64(seed)),
+        })
+    }
+
+    pub fn reseed(&self, seed: u64) {
+        *self.rng.borrow_mut() = StdRng::seed_from_u64(seed);
+    }
+
+    pub fn noise(&self) -> RewardNoise {
+        self.noise
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mdp
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mdp
+    }
+}
+
+impl<M> MDP for NoisyReward<M>
+where
+    M: MDP,
+    M::State: Clone,
+    M::Action: Clone,
+{
+    type State = M::State;
+    type Action = M::Action;
+
+    fn all_states(&self) -> &Sampler<Self::State> {
+        self.mdp.all_states()
+    }
+
+    fn states(&self) -> Box<dyn Iterator<Item = Self::State> + '_> {
+        self.mdp.states()
+    }
+
+    fn actions_at(&self, state: &Self::State) -> Vec<Self::Action> {
+        self.mdp.actions_at(state)
+    }
+
+    fn is_final_state(&self, state: &Self::State) -> bool {
+        self.mdp.is_final_state(state)
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.mdp.is_goal(state)
+    }
+
+    fn stochastic_transition(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Result<(Measure<Self::State>, f64), Error> {
+        let (measure, reward) = self.mdp.stochastic_transition(state, action)?;
+        let noise = self.noise.sample(&mut self.rng.borrow_mut())?;
+        Ok((measure, reward + noise))
+    }
+}
@@ -0,0 +1,35 @@
+use ctmdp_rust::pathmdp::{PathAction, PathState, PathWorld};
+use ctmdp_rust::policy::{DeterministicPolicy, Policy, tabulate};
+use ctmdp_rust::products::{BoxAction, BoxProduct, Product, Scheduler, Turn};
+
+fn path_world(length: usize) -> PathWorld {
+    PathWorld::new(
+        (0..length).map(PathState::new).collect(),
+        vec![PathAction::Next, PathAction::Prev],
+    )
+}
+
+fn always_next(length: usize) -> DeterministicPolicy<PathState, PathAction> {
+    (0..length)
+        .map(|i| (PathState::new(i), PathAction::Next))
+        .collect()
+}
+
+#[test]
+fn round_robin_alternates_on_the_turn_the_caller_passes() {
+    let (first, second) = (path_world(3), path_world(3));
+    let product = BoxProduct::new(&first, &second);
+    let lifted =
+        product.lift_policies(always_next(3), always_next(3), Scheduler::RoundRobin);
+    let start = Product::new(PathState::new(0), PathState::new(0));
+
+    let (action, turn) = lifted.action_on_turn(&start, Turn::First).unwrap();
+    assert_eq!(action, BoxAction::Left(PathAction::Next));
+    assert_eq!(turn, Turn::Second);
+    let (action, turn) = lifted.action_on_turn(&start, turn).unwrap();
+    assert_eq!(action, BoxAction::Right(PathAction::Next));
+    assert_eq!(turn, Turn::First);
+
+    assert_eq!(lifted.action(&start), lifted.action(&start));
+    assert_eq!(tabulate(&product, &lifted), tabulate(&product, &lifted));
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "parallel")]
+
+use ctmdp_rust::config::TrainingConfig;
+use ctmdp_rust::mdp::MDP;
+use ctmdp_rust::parallel::run_trials_parallel;
+use ctmdp_rust::pathmdp::{PathAction, PathState, PathWorld};
+
+fn path_world(length: usize) -> PathWorld {
+    PathWorld::new(
+        (0..length).map(PathState::new).collect(),
+        vec![PathAction::Next, PathAction::Prev],
+    )
+}
+
+/// Every trial's Q-values, listed over the MDP's state-action pairs
+fn trial_values(mdp: &PathWorld, config: &TrainingConfig, num_trials: usize) -> Vec<Vec<f64>> {
+    let pairs = mdp.all_state_action_pairs();
+    run_trials_parallel(mdp, config, num_trials)
+        .into_iter()
+        .map(|trial| {
+            let action_value = trial.unwrap();
+            pairs.iter().map(|(s, a)| action_value.get(s, a)).collect()
+        })
+        .collect()
+}
+
+#[test]
+fn seeded_trials_differ_but_the_batch_repeats() {
+    let mdp = path_world(6);
+    let mut config = TrainingConfig::default();
+    config.num_episodes = 20;
+    config.max_num_steps = 30;
+    config.exploration_rate = 0.5;
+    config.seed = Some(7);
+
+    let first = trial_values(&mdp, &config, 4);
+    assert_ne!(first[0], first[1]);
+    assert_eq!(first, trial_values(&mdp, &config, 4));
+}
@@ -0,0 +1,37 @@
+use ctmdp_rust::pathmdp::{PathAction, PathState, PathWorld};
+use ctmdp_rust::policy::DeterministicPolicy;
+use ctmdp_rust::products::{CartesianProduct, Product, ProjectionWeights};
+
+fn path_world(length: usize, actions: Vec<PathAction>) -> PathWorld {
+    PathWorld::new((0..length).map(PathState::new).collect(), actions)
+}
+
+type Joint<T> = Product<T, T>;
+
+/// At first-component state 0 the product policy votes once for `Next` and once for `Prev`
+fn tied_policy() -> DeterministicPolicy<Joint<PathState>, Joint<PathAction>> {
+    [(0, PathAction::Next), (1, PathAction::Prev)]
+        .into_iter()
+        .map(|(j, a1)| {
+            (
+                Product::new(PathState::new(0), PathState::new(j)),
+                Product::new(a1, PathAction::Next),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn tied_votes_go_to_the_action_listed_first() {
+    let second = path_world(2, vec![PathAction::Next, PathAction::Prev]);
+    for order in [
+        vec![PathAction::Next, PathAction::Prev],
+        vec![PathAction::Prev, PathAction::Next],
+    ] {
+        let first = path_world(2, order.clone());
+        let product = CartesianProduct::new(&first, &second);
+        let (marginal, _) = product.project_policy(&tied_policy(), ProjectionWeights::MajorityVote);
+        assert_eq!(marginal.policy[&PathState::new(0)], order[0]);
+        assert_eq!(marginal.agreement[&PathState::new(0)], 0.5);
+    }
+}